@@ -9,7 +9,7 @@ use super::utils::{get_program_build_args, get_rust_compiler_flags};
 
 /// Uses SP1_DOCKER_IMAGE environment variable if set, otherwise constructs the image to use based
 /// on the provided tag.
-fn get_docker_image(tag: &str) -> String {
+pub fn get_docker_image(tag: &str) -> String {
     std::env::var("SP1_DOCKER_IMAGE").unwrap_or_else(|_| {
         let image_base = "ghcr.io/succinctlabs/sp1";
         format!("{}:{}", image_base, tag)
@@ -3,6 +3,7 @@ mod command;
 mod utils;
 use build::build_program_internal;
 pub use build::execute_build_program;
+pub use command::docker::get_docker_image;
 
 use clap::Parser;
 
@@ -2,9 +2,9 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 use sp1_cli::{
     commands::{
-        build::BuildCmd, build_toolchain::BuildToolchainCmd,
-        install_toolchain::InstallToolchainCmd, new::NewCmd, prove::ProveCmd, trace::TraceCmd,
-        vkey::VkeyCmd,
+        build::BuildCmd, build_toolchain::BuildToolchainCmd, execute::ExecuteCmd,
+        inspect::InspectCmd, install_toolchain::InstallToolchainCmd, new::NewCmd, prove::ProveCmd,
+        trace::TraceCmd, verify::VerifyCmd, verify_build::VerifyBuildCmd, vkey::VkeyCmd,
     },
     SP1_VERSION_MESSAGE,
 };
@@ -34,6 +34,10 @@ pub enum ProveCliCommands {
     InstallToolchain(InstallToolchainCmd),
     Trace(TraceCmd),
     Vkey(VkeyCmd),
+    Inspect(InspectCmd),
+    Verify(VerifyCmd),
+    Execute(ExecuteCmd),
+    VerifyBuild(VerifyBuildCmd),
 }
 
 fn main() -> Result<()> {
@@ -47,5 +51,9 @@ fn main() -> Result<()> {
         ProveCliCommands::InstallToolchain(cmd) => cmd.run(),
         ProveCliCommands::Trace(cmd) => cmd.run(),
         ProveCliCommands::Vkey(cmd) => cmd.run(),
+        ProveCliCommands::Inspect(cmd) => cmd.run(),
+        ProveCliCommands::Verify(cmd) => cmd.run(),
+        ProveCliCommands::Execute(cmd) => cmd.run(),
+        ProveCliCommands::VerifyBuild(cmd) => cmd.run(),
     }
 }
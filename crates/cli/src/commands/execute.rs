@@ -0,0 +1,121 @@
+use std::{fs, time::Instant};
+
+use anstyle::*;
+use anyhow::Result;
+use clap::Parser;
+use serde_json::json;
+use sp1_build::{execute_build_program, BuildArgs};
+use sp1_core_machine::io::SP1Stdin;
+use sp1_sdk::{CostEstimator, ExecutionReport, ProverClient};
+use sp1_stark::SP1CoreOpts;
+
+use crate::{
+    commands::prove::Input,
+    util::{elapsed, write_status},
+};
+
+#[derive(Parser)]
+#[command(name = "execute", about = "Build and execute a program without proving it.")]
+pub struct ExecuteCmd {
+    #[clap(long, value_parser)]
+    input: Option<Input>,
+
+    /// Print the report as JSON.
+    #[clap(long, action)]
+    json: bool,
+
+    #[clap(flatten)]
+    build_args: BuildArgs,
+}
+
+impl ExecuteCmd {
+    pub fn run(&self) -> Result<()> {
+        let elf_paths = execute_build_program(&self.build_args, None)?;
+        let elf_path = if elf_paths.len() == 1 {
+            elf_paths[0].1.to_owned()
+        } else {
+            anyhow::bail!("the execute command does not work with multi-target builds");
+        };
+        let elf = fs::read(elf_path.as_std_path())?;
+
+        let mut stdin = SP1Stdin::new();
+        if let Some(ref input) = self.input {
+            input.write(&mut stdin)?;
+        }
+
+        let start_time = Instant::now();
+        let client = ProverClient::new();
+        let (public_values, report) = client.execute(&elf, stdin).run()?;
+        let elapsed = elapsed(start_time.elapsed());
+
+        let cycles = report.total_instruction_count();
+        let shard_size = SP1CoreOpts::default().shard_size as u64;
+        let shards = cycles.div_ceil(shard_size).max(1);
+        let gas = report.estimate_gas();
+
+        if self.json {
+            let json = json!({
+                "cycles": cycles,
+                "gas": gas,
+                "shard_size": shard_size,
+                "shards": shards,
+                "public_values": format!("0x{}", hex::encode(public_values.as_slice())),
+                "opcode_counts": report
+                    .opcode_counts
+                    .iter()
+                    .filter(|(_, count)| **count > 0)
+                    .map(|(opcode, count)| (opcode.to_string(), json!(count)))
+                    .collect::<serde_json::Map<_, _>>(),
+                "syscall_counts": report
+                    .syscall_counts
+                    .iter()
+                    .filter(|(_, count)| **count > 0)
+                    .map(|(syscall, count)| (syscall.to_string(), json!(count)))
+                    .collect::<serde_json::Map<_, _>>(),
+                "cycle_tracker": report
+                    .cycle_tracker
+                    .iter()
+                    .map(|(name, cycles)| (name.clone(), json!(cycles)))
+                    .collect::<serde_json::Map<_, _>>(),
+                "cycle_tracker_spans": report
+                    .cycle_tracker_spans
+                    .iter()
+                    .map(|span| json!({
+                        "name": span.name,
+                        "depth": span.depth,
+                        "count": span.count,
+                        "cycles": span.cycles,
+                    }))
+                    .collect::<Vec<_>>(),
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+            return Ok(());
+        }
+
+        print!("{report}");
+        print_cycle_tracker(&report);
+        println!("public values: 0x{}", hex::encode(public_values.as_slice()));
+        println!("estimated gas: {gas}");
+        println!("predicted shards: {shards} (shard size {shard_size})");
+
+        let green = AnsiColor::Green.on_default().effects(Effects::BOLD);
+        write_status(&green, "Finished", &format!("executing {cycles} cycles in {elapsed}"));
+        Ok(())
+    }
+}
+
+/// Print the cycle tracker spans as a tree, in the same layout as the executor's logs.
+fn print_cycle_tracker(report: &ExecutionReport) {
+    if report.cycle_tracker_spans.is_empty() {
+        return;
+    }
+    println!("cycle tracker:");
+    for span in report.cycle_tracker_spans.iter() {
+        let padding = "│ ".repeat(span.depth as usize);
+        match span.count {
+            0 => println!("  {padding}{}: not ended", span.name),
+            1 => println!("  {padding}{}: {} cycles", span.name, span.cycles),
+            count => println!("  {padding}{}: {} cycles in {count} spans", span.name, span.cycles),
+        }
+    }
+}
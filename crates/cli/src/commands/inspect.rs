@@ -0,0 +1,215 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use prettytable::{format, Cell, Row, Table};
+use serde::de::DeserializeOwned;
+use sp1_sdk::{ProverClient, SP1Proof, SP1ProofKind, SP1ProofWithPublicValues};
+
+#[derive(Parser)]
+#[command(name = "inspect", about = "Inspect a saved proof.")]
+pub struct InspectCmd {
+    /// Path to the proof.
+    proof: PathBuf,
+
+    /// Decode the public values as a comma-separated list of types, in the order the program
+    /// committed them.
+    #[arg(long, value_delimiter = ',')]
+    decode: Vec<PublicValueType>,
+
+    /// Verify the proof against the program with this ELF.
+    #[arg(long)]
+    elf: Option<PathBuf>,
+}
+
+/// The types the public values can be decoded as.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum PublicValueType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    String,
+    /// A length-prefixed byte vector.
+    Bytes,
+    /// A fixed 32 byte array, such as a hash.
+    Bytes32,
+}
+
+impl InspectCmd {
+    pub fn run(&self) -> Result<()> {
+        self.inspect(&mut io::stdout().lock())
+    }
+
+    /// Writes the description of the proof to `out`.
+    fn inspect(&self, out: &mut impl Write) -> Result<()> {
+        let proof = SP1ProofWithPublicValues::load(&self.proof)
+            .with_context(|| format!("failed to load {}", self.proof.display()))?;
+        let kind = SP1ProofKind::from(&proof.proof);
+
+        writeln!(out, "Proof kind:    {kind:?}")?;
+        writeln!(out, "SP1 version:   {}", proof.sp1_version)?;
+        match SP1ProofWithPublicValues::load_header(&self.proof) {
+            Ok(header) => writeln!(
+                out,
+                "File format:   version {}, {:?} compression",
+                header.format_version, header.compression
+            )?,
+            Err(_) => writeln!(out, "File format:   legacy, without a header")?,
+        }
+        match proof.vkey_hash() {
+            Some(vkey_hash) => writeln!(out, "Vkey hash:     0x{}", hex::encode(vkey_hash))?,
+            None => writeln!(out, "Vkey hash:     not committed to by {kind:?} proofs")?,
+        }
+
+        writeln!(out)?;
+        writeln!(out, "Sizes:")?;
+        writeln!(out, "  file           {:>10} bytes", fs::metadata(&self.proof)?.len())?;
+        writeln!(out, "  proof          {:>10} bytes", bincode::serialized_size(&proof.proof)?)?;
+        writeln!(out, "  stdin          {:>10} bytes", bincode::serialized_size(&proof.stdin)?)?;
+        writeln!(out, "  public values  {:>10} bytes", proof.public_values.as_slice().len())?;
+
+        let shards: Vec<_> = match &proof.proof {
+            SP1Proof::Core(shards) => shards.iter().collect(),
+            SP1Proof::Compressed(reduce_proof) => vec![&reduce_proof.proof],
+            SP1Proof::Plonk(_) | SP1Proof::Groth16(_) => vec![],
+        };
+        if !shards.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "Shards: {}", shards.len())?;
+            for (index, shard) in shards.iter().enumerate() {
+                let size = bincode::serialized_size(shard)?;
+                writeln!(out, "Shard {index}: {size} bytes")?;
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_LINESEP);
+                table.set_titles(Row::new(vec![Cell::new("Chip"), Cell::new("Height")]));
+                for (chip, log_degree) in shard.shape().chip_information.iter() {
+                    table.add_row(Row::new(vec![
+                        Cell::new(chip),
+                        Cell::new(&format!("2^{log_degree} ({})", 1usize << log_degree)),
+                    ]));
+                }
+                table.print(out)?;
+            }
+        }
+
+        writeln!(out)?;
+        writeln!(out, "Public values: 0x{}", hex::encode(proof.public_values.as_slice()))?;
+        if !self.decode.is_empty() {
+            let mut reader = proof.public_values.as_slice();
+            for (index, ty) in self.decode.iter().enumerate() {
+                let value = decode_public_value(*ty, &mut reader)
+                    .with_context(|| format!("failed to decode public value {index} as {ty:?}"))?;
+                writeln!(out, "  [{index}] {ty:?}: {value}")?;
+            }
+            if !reader.is_empty() {
+                writeln!(out, "  {} bytes left undecoded", reader.len())?;
+            }
+        }
+
+        if let Some(elf) = &self.elf {
+            let elf = fs::read(elf).with_context(|| format!("failed to read {}", elf.display()))?;
+            let client = ProverClient::new();
+            let (_, vk) = client.setup(&elf);
+            writeln!(out)?;
+            match client.verify(&proof, &vk) {
+                Ok(()) => writeln!(out, "Verification:  valid for the given ELF")?,
+                Err(e) => anyhow::bail!("the proof is invalid for the given ELF: {e}"),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Decodes the next public value of the given type, as written by `sp1_zkvm::io::commit`.
+fn decode_public_value(ty: PublicValueType, reader: &mut &[u8]) -> Result<String> {
+    fn next<T: DeserializeOwned + std::fmt::Debug>(reader: &mut &[u8]) -> Result<String> {
+        Ok(format!("{:?}", bincode::deserialize_from::<_, T>(reader)?))
+    }
+
+    match ty {
+        PublicValueType::Bool => next::<bool>(reader),
+        PublicValueType::U8 => next::<u8>(reader),
+        PublicValueType::U16 => next::<u16>(reader),
+        PublicValueType::U32 => next::<u32>(reader),
+        PublicValueType::U64 => next::<u64>(reader),
+        PublicValueType::U128 => next::<u128>(reader),
+        PublicValueType::I8 => next::<i8>(reader),
+        PublicValueType::I16 => next::<i16>(reader),
+        PublicValueType::I32 => next::<i32>(reader),
+        PublicValueType::I64 => next::<i64>(reader),
+        PublicValueType::I128 => next::<i128>(reader),
+        PublicValueType::String => next::<String>(reader),
+        PublicValueType::Bytes => {
+            Ok(format!("0x{}", hex::encode(bincode::deserialize_from::<_, Vec<u8>>(reader)?)))
+        }
+        PublicValueType::Bytes32 => {
+            Ok(format!("0x{}", hex::encode(bincode::deserialize_from::<_, [u8; 32]>(reader)?)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sp1_sdk::{utils, SP1Stdin};
+
+    use super::*;
+
+    fn inspect(proof: PathBuf, decode: Vec<PublicValueType>) -> String {
+        let cmd = InspectCmd { proof, decode, elf: None };
+        let mut out = Vec::new();
+        cmd.inspect(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_inspect_groth16() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../verifier/test_binaries/fibonacci-groth16.bin");
+        let output = inspect(path, vec![PublicValueType::U32; 3]);
+
+        assert!(output.contains("Proof kind:    Groth16\n"));
+        assert!(output.contains("File format:   legacy, without a header\n"));
+        assert!(output.contains(
+            "Vkey hash:     0x00e60860c07bfc6e4c480286c0ddbb879674eb47f84b4ef041cf858b17aa0ed1\n"
+        ));
+        assert!(output.contains("  file                 1449 bytes\n"));
+        assert!(!output.contains("Shards:"));
+        assert!(output.contains("Public values: 0xf4010000f404000086070000\n"));
+        assert!(output.contains("  [0] U32: 500\n  [1] U32: 1268\n  [2] U32: 1926\n"));
+    }
+
+    #[test]
+    fn test_inspect_core() {
+        utils::setup_logger();
+        let elf = include_bytes!("../../../../tests/fibonacci/elf/riscv32im-succinct-zkvm-elf");
+        let client = ProverClient::new();
+        let (pk, _) = client.setup(elf);
+        let proof = client.prove(&pk, SP1Stdin::new()).core().run().unwrap();
+        let path = std::env::temp_dir().join(format!("inspect-core-{}.bin", std::process::id()));
+        proof.save(&path).unwrap();
+        let output = inspect(path.clone(), vec![PublicValueType::U32; 2]);
+        fs::remove_file(path).unwrap();
+
+        let SP1Proof::Core(shards) = &proof.proof else { unreachable!() };
+        assert!(output.contains("Proof kind:    Core\n"));
+        assert!(output.contains(&format!("Shards: {}\n", shards.len())));
+        for (index, shard) in shards.iter().enumerate() {
+            let size = bincode::serialized_size(shard).unwrap();
+            assert!(output.contains(&format!("Shard {index}: {size} bytes\n")));
+        }
+        assert!(output.contains("  [0] U32: 55\n  [1] U32: 89\n"));
+    }
+}
@@ -1,7 +1,11 @@
 pub mod build;
 pub mod build_toolchain;
+pub mod execute;
+pub mod inspect;
 pub mod install_toolchain;
 pub mod new;
 pub mod prove;
 pub mod trace;
+pub mod verify;
+pub mod verify_build;
 pub mod vkey;
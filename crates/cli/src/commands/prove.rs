@@ -12,7 +12,7 @@ use std::{env, fs::File, io::Read, path::PathBuf, str::FromStr, time::Instant};
 use crate::util::{elapsed, write_status};
 
 #[derive(Debug, Clone)]
-enum Input {
+pub(crate) enum Input {
     FilePath(PathBuf),
     HexBytes(Vec<u8>),
 }
@@ -26,6 +26,24 @@ fn is_valid_hex_string(s: &str) -> bool {
         || s.chars().all(|c| c.is_ascii_hexdigit())
 }
 
+impl Input {
+    /// Write the input to the program's stdin.
+    pub(crate) fn write(&self, stdin: &mut SP1Stdin) -> Result<()> {
+        match self {
+            Input::FilePath(ref path) => {
+                let mut file = File::open(path).expect("failed to open input file");
+                let mut bytes = Vec::new();
+                file.read_to_end(&mut bytes)?;
+                stdin.write_slice(&bytes);
+            }
+            Input::HexBytes(ref bytes) => {
+                stdin.write_slice(bytes);
+            }
+        }
+        Ok(())
+    }
+}
+
 impl FromStr for Input {
     type Err = String;
 
@@ -102,17 +120,7 @@ impl ProveCmd {
 
         let mut stdin = SP1Stdin::new();
         if let Some(ref input) = self.input {
-            match input {
-                Input::FilePath(ref path) => {
-                    let mut file = File::open(path).expect("failed to open input file");
-                    let mut bytes = Vec::new();
-                    file.read_to_end(&mut bytes)?;
-                    stdin.write_slice(&bytes);
-                }
-                Input::HexBytes(ref bytes) => {
-                    stdin.write_slice(bytes);
-                }
-            }
+            input.write(&mut stdin)?;
         }
 
         let start_time = Instant::now();
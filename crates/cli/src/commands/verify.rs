@@ -0,0 +1,81 @@
+use std::{fs, path::PathBuf};
+
+use anstyle::*;
+use anyhow::{Context, Result};
+use clap::{ArgGroup, Parser};
+use sp1_sdk::{
+    ProverClient, SP1Proof, SP1ProofKind, SP1ProofWithPublicValues, SP1VerificationError,
+    SP1_CIRCUIT_VERSION,
+};
+use sp1_verifier::{Groth16Verifier, PlonkVerifier, GROTH16_VK_BYTES, PLONK_VK_BYTES};
+
+use crate::util::write_status;
+
+#[derive(Parser)]
+#[command(name = "verify", about = "Verify a saved proof.")]
+#[command(group(ArgGroup::new("key").required(true).args(["elf", "vkey"])))]
+pub struct VerifyCmd {
+    /// Path to the proof.
+    #[arg(long)]
+    proof: PathBuf,
+
+    /// Path to the ELF of the program the proof is for.
+    #[arg(long)]
+    elf: Option<PathBuf>,
+
+    /// The vkey hash of the program, as printed by `cargo prove vkey`. Only Plonk and Groth16
+    /// proofs can be verified without the ELF.
+    #[arg(long)]
+    vkey: Option<String>,
+}
+
+impl VerifyCmd {
+    pub fn run(&self) -> Result<()> {
+        let proof = SP1ProofWithPublicValues::load(&self.proof)
+            .with_context(|| format!("failed to load {}", self.proof.display()))?;
+
+        let result = match (&self.elf, &self.vkey) {
+            (Some(elf), _) => {
+                let elf =
+                    fs::read(elf).with_context(|| format!("failed to read {}", elf.display()))?;
+                let client = ProverClient::new();
+                let (_, vk) = client.setup(&elf);
+                client.verify(&proof, &vk)
+            }
+            (None, Some(vkey)) => verify_with_vkey_hash(&proof, vkey)?,
+            (None, None) => unreachable!("clap requires --elf or --vkey"),
+        };
+        result.context("the proof is invalid")?;
+
+        let green = AnsiColor::Green.on_default().effects(Effects::BOLD);
+        let kind = SP1ProofKind::from(&proof.proof);
+        write_status(&green, "Verified", &format!("{kind:?} proof {}", self.proof.display()));
+        Ok(())
+    }
+}
+
+/// Verifies a Plonk or Groth16 proof against a program's vkey hash, with the verifying keys of the
+/// circuits of this SP1 version.
+fn verify_with_vkey_hash(
+    proof: &SP1ProofWithPublicValues,
+    vkey: &str,
+) -> Result<Result<(), SP1VerificationError>> {
+    if proof.sp1_version != SP1_CIRCUIT_VERSION {
+        return Ok(Err(SP1VerificationError::VersionMismatch(proof.sp1_version.clone())));
+    }
+
+    let public_values = proof.public_values.as_slice();
+    Ok(match &proof.proof {
+        SP1Proof::Plonk(_) => {
+            PlonkVerifier::verify(&proof.bytes(), public_values, vkey, &PLONK_VK_BYTES)
+                .map_err(|e| SP1VerificationError::Plonk(e.into()))
+        }
+        SP1Proof::Groth16(_) => {
+            Groth16Verifier::verify(&proof.bytes(), public_values, vkey, &GROTH16_VK_BYTES)
+                .map_err(|e| SP1VerificationError::Groth16(e.into()))
+        }
+        proof => {
+            anyhow::bail!("{:?} proofs can only be verified with --elf", SP1ProofKind::from(proof))
+        }
+    })
+}
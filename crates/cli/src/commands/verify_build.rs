@@ -0,0 +1,158 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anstyle::*;
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use sp1_build::{execute_build_program, get_docker_image, BuildArgs};
+use sp1_sdk::{HashableKey, ProverClient, SP1_CIRCUIT_VERSION};
+
+use crate::util::write_status;
+
+#[derive(Parser)]
+#[command(
+    name = "verify-build",
+    about = "Rebuild a program in Docker and check that it matches an expected ELF or vkey hash."
+)]
+pub struct VerifyBuildCmd {
+    /// The directory of the program. Defaults to the current directory.
+    #[arg(long)]
+    path: Option<PathBuf>,
+
+    /// The expected SHA-256 hash of the ELF, as a hex string.
+    #[arg(long)]
+    elf_hash: Option<String>,
+
+    /// The expected vkey hash of the program, as printed by `cargo prove vkey`.
+    #[arg(long)]
+    vkey: Option<String>,
+
+    /// Write the attestation of the build to this file instead of stdout.
+    #[arg(long)]
+    attestation: Option<PathBuf>,
+
+    #[clap(flatten)]
+    build_args: BuildArgs,
+}
+
+impl VerifyBuildCmd {
+    pub fn run(&self) -> Result<()> {
+        let program_dir = match &self.path {
+            Some(path) => path.clone(),
+            None => std::env::current_dir()?,
+        };
+
+        // The build is only reproducible in the canonical Docker image.
+        let args = BuildArgs { docker: true, ..self.build_args.clone() };
+        let elf_paths = execute_build_program(&args, Some(program_dir.clone()))?;
+        let elf_path = if elf_paths.len() == 1 {
+            elf_paths[0].1.to_owned()
+        } else {
+            anyhow::bail!("the verify-build command does not work with multi-target builds");
+        };
+        let elf = fs::read(elf_path.as_std_path())?;
+        let elf_hash = hex::encode(Sha256::digest(&elf));
+
+        let client = ProverClient::new();
+        let (_, vk) = client.setup(&elf);
+        let vkey = vk.bytes32();
+
+        let image = get_docker_image(&args.tag);
+        let elf_matches =
+            self.elf_hash.as_ref().map(|expected| strip_hex_prefix(expected) == elf_hash);
+        let vkey_matches = self
+            .vkey
+            .as_ref()
+            .map(|expected| strip_hex_prefix(expected) == strip_hex_prefix(&vkey));
+
+        let attestation = json!({
+            "program": program_dir.canonicalize()?.display().to_string(),
+            "git_commit": git(&program_dir, &["rev-parse", "HEAD"]),
+            "git_dirty": git(&program_dir, &["status", "--porcelain"]).map(|s| !s.is_empty()),
+            "sp1_circuit_version": SP1_CIRCUIT_VERSION,
+            "docker_image": image,
+            "docker_image_digest": docker(&[
+                "image",
+                "inspect",
+                "--format",
+                "{{index .RepoDigests 0}}",
+                &image,
+            ]),
+            "toolchain": docker(&[
+                "run",
+                "--rm",
+                "--platform",
+                "linux/amd64",
+                "-e",
+                "RUSTUP_TOOLCHAIN=succinct",
+                "--entrypoint",
+                "",
+                &image,
+                "rustc",
+                "--version",
+            ]),
+            "build_args": {
+                "tag": args.tag,
+                "features": args.features,
+                "rustflags": args.rustflags,
+                "no_default_features": args.no_default_features,
+                "ignore_rust_version": args.ignore_rust_version,
+                "locked": args.locked,
+                "binary": args.binary,
+                "elf_name": args.elf_name,
+            },
+            "elf_sha256": format!("0x{elf_hash}"),
+            "vkey_hash": vkey,
+            "elf_matches": elf_matches,
+            "vkey_matches": vkey_matches,
+        });
+        let attestation = serde_json::to_string_pretty(&attestation)?;
+        match &self.attestation {
+            Some(path) => fs::write(path, attestation)
+                .with_context(|| format!("failed to write {}", path.display()))?,
+            None => println!("{attestation}"),
+        }
+
+        if elf_matches == Some(false) {
+            anyhow::bail!("the ELF hash 0x{elf_hash} does not match the expected hash");
+        }
+        if vkey_matches == Some(false) {
+            anyhow::bail!("the vkey hash {vkey} does not match the expected hash");
+        }
+
+        let green = AnsiColor::Green.on_default().effects(Effects::BOLD);
+        let checked = match (elf_matches, vkey_matches) {
+            (None, None) => "nothing to compare, attested the build",
+            _ => "the build matches the expected hashes",
+        };
+        write_status(&green, "Verified", checked);
+        Ok(())
+    }
+}
+
+fn strip_hex_prefix(s: &str) -> String {
+    s.trim_start_matches("0x").to_lowercase()
+}
+
+/// Run a git command in the program directory, returning its trimmed output.
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    output(Command::new("git").arg("-C").arg(dir).args(args))
+}
+
+/// Run a docker command, returning its trimmed output.
+fn docker(args: &[&str]) -> Option<String> {
+    output(Command::new("docker").args(args))
+}
+
+fn output(command: &mut Command) -> Option<String> {
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -2,6 +2,7 @@ use core::mem::take;
 use std::sync::Arc;
 
 use hashbrown::HashMap;
+use sp1_stark::{CancellationToken, ProgressSink, ProverMonitor};
 
 use crate::{
     hook::{hookify, BoxedHook, HookEnv, HookRegistry},
@@ -21,6 +22,9 @@ pub struct SP1Context<'a> {
 
     /// The maximum number of cpu cycles to use for execution.
     pub max_cycles: Option<u64>,
+
+    /// The progress sink and cancellation token used when proving.
+    pub monitor: ProverMonitor,
 }
 
 /// A builder for [`SP1Context`].
@@ -30,6 +34,7 @@ pub struct SP1ContextBuilder<'a> {
     hook_registry_entries: Vec<(u32, BoxedHook<'a>)>,
     subproof_verifier: Option<Arc<dyn SubproofVerifier + 'a>>,
     max_cycles: Option<u64>,
+    monitor: ProverMonitor,
 }
 
 impl<'a> SP1Context<'a> {
@@ -68,7 +73,8 @@ impl<'a> SP1ContextBuilder<'a> {
             });
         let subproof_verifier = take(&mut self.subproof_verifier);
         let cycle_limit = take(&mut self.max_cycles);
-        SP1Context { hook_registry, subproof_verifier, max_cycles: cycle_limit }
+        let monitor = take(&mut self.monitor);
+        SP1Context { hook_registry, subproof_verifier, max_cycles: cycle_limit, monitor }
     }
 
     /// Add a runtime [Hook](super::Hook) into the context.
@@ -110,21 +116,37 @@ impl<'a> SP1ContextBuilder<'a> {
         self.max_cycles = Some(max_cycles);
         self
     }
+
+    /// Report the progress of proving to the given sink.
+    pub fn progress(&mut self, sink: Arc<dyn ProgressSink>) -> &mut Self {
+        self.monitor.progress = Some(sink);
+        self
+    }
+
+    /// Abort proving when the given token is cancelled.
+    pub fn cancellation(&mut self, token: CancellationToken) -> &mut Self {
+        self.monitor.cancellation = Some(token);
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
+    use sp1_stark::CancellationToken;
+
     use crate::{subproof::DefaultSubproofVerifier, SP1Context};
 
     #[test]
     fn defaults() {
-        let SP1Context { hook_registry, subproof_verifier, max_cycles: cycle_limit } =
+        let SP1Context { hook_registry, subproof_verifier, max_cycles: cycle_limit, monitor } =
             SP1Context::builder().build();
         assert!(hook_registry.is_none());
         assert!(subproof_verifier.is_none());
         assert!(cycle_limit.is_none());
+        assert!(monitor.progress.is_none());
+        assert!(!monitor.is_cancelled());
     }
 
     #[test]
@@ -155,4 +177,13 @@ mod tests {
             .build();
         assert!(subproof_verifier.is_some());
     }
+
+    #[test]
+    fn cancellation() {
+        let token = CancellationToken::new();
+        let SP1Context { monitor, .. } = SP1Context::builder().cancellation(token.clone()).build();
+        assert!(!monitor.is_cancelled());
+        token.cancel();
+        assert!(monitor.is_cancelled());
+    }
 }
@@ -0,0 +1,148 @@
+//! Checkpoints for proving a long-running program in segments.
+//!
+//! A guest suspends itself with `syscall_suspend`, which commits its public values and halts with
+//! the address of the next instruction in `a1`. The final registers and memory of the segment,
+//! together with that address, form a [`SegmentCheckpoint`]. The next segment is the same program
+//! started at the resume address with the checkpoint as its memory image, so its verifying key
+//! commits to the state it starts from.
+//!
+//! The checkpoint keeps the final record of every address, which is what the memory finalize chip
+//! of the suspended segment receives, so that a verifier can check it against that segment's
+//! memory argument.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::{events::MemoryRecord, Executor, Opcode, Program, Register};
+
+/// The state a suspended segment hands over to the next one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentCheckpoint {
+    /// The address the next segment starts executing at.
+    pub pc_start: u32,
+    /// The final record of every touched register and memory word, including `x0`.
+    pub memory: BTreeMap<u32, MemoryRecord>,
+}
+
+/// An error that occurs when taking a checkpoint of a segment.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SegmentCheckpointError {
+    /// The segment has not halted yet.
+    #[error("the segment has not halted")]
+    NotHalted,
+
+    /// The segment halted without suspending.
+    #[error("the segment did not suspend, resume pc {0:#x} does not follow an ecall")]
+    NotSuspended(u32),
+}
+
+impl SegmentCheckpoint {
+    /// Take a checkpoint of an executor whose program suspended with `syscall_suspend`.
+    pub fn from_executor(executor: &Executor) -> Result<Self, SegmentCheckpointError> {
+        if executor.state.pc != 0 {
+            return Err(SegmentCheckpointError::NotHalted);
+        }
+
+        // `syscall_suspend` passes the resume address in `a1`, right after the halt ecall.
+        let pc_start = executor.state.memory.get(Register::X11 as u32).map_or(0, |r| r.value);
+        let program = &executor.program;
+        let is_after_ecall = pc_start % 4 == 0
+            && pc_start > program.pc_base
+            && ((pc_start - program.pc_base) / 4) as usize <= program.instructions.len()
+            && program.fetch(pc_start - 4).opcode == Opcode::ECALL;
+        if !is_after_ecall {
+            return Err(SegmentCheckpointError::NotSuspended(pc_start));
+        }
+
+        // The memory finalize chip always receives `x0`, even if it was never touched.
+        let mut memory: BTreeMap<u32, MemoryRecord> = executor
+            .state
+            .memory
+            .keys()
+            .map(|addr| (addr, *executor.state.memory.get(addr).unwrap()))
+            .collect();
+        memory.entry(0).or_insert(MemoryRecord { value: 0, shard: 0, timestamp: 1 });
+
+        Ok(Self { pc_start, memory })
+    }
+
+    /// The program of the segment resuming from this checkpoint.
+    ///
+    /// The instructions are the ones of `base`, the first segment's program. Address zero is `x0`,
+    /// which the memory argument constrains to be zero and uninitialized, so it is left out of the
+    /// memory image.
+    #[must_use]
+    pub fn program(&self, base: &Program) -> Program {
+        Program {
+            instructions: base.instructions.clone(),
+            pc_start: self.pc_start,
+            pc_base: base.pc_base,
+            memory_image: self
+                .memory
+                .iter()
+                .filter(|(&addr, _)| addr != 0)
+                .map(|(&addr, record)| (addr, record.value))
+                .collect(),
+            preprocessed_shape: None,
+        }
+    }
+
+    /// A keccak256 digest of the resume address and memory records, in address order.
+    #[must_use]
+    pub fn memory_root(&self) -> [u8; 32] {
+        let mut hasher = Keccak::v256();
+        hasher.update(&self.pc_start.to_le_bytes());
+        for (addr, record) in &self.memory {
+            hasher.update(&addr.to_le_bytes());
+            hasher.update(&record.shard.to_le_bytes());
+            hasher.update(&record.timestamp.to_le_bytes());
+            hasher.update(&record.value.to_le_bytes());
+        }
+        let mut root = [0u8; 32];
+        hasher.finalize(&mut root);
+        root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sp1_stark::SP1CoreOpts;
+
+    use super::*;
+    use crate::Instruction;
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        //     addi x29, x0, 5
+        //     addi x5, x0, 0
+        //     addi x10, x0, 0
+        //     addi x11, x0, 20
+        //     ecall
+        //     addi x31, x29, 37
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, 0, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 0, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 20, false, true),
+            Instruction::new(Opcode::ECALL, 5, 10, 11, false, false),
+            Instruction::new(Opcode::ADD, 31, 29, 37, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Executor::new(program.clone(), SP1CoreOpts::default());
+        runtime.run_fast().unwrap();
+        let checkpoint = SegmentCheckpoint::from_executor(&runtime).unwrap();
+        assert_eq!(checkpoint.pc_start, 20);
+        assert_eq!(checkpoint.memory[&29].value, 5);
+
+        let mut runtime = Executor::new(checkpoint.program(&program), SP1CoreOpts::default());
+        runtime.run_fast().unwrap();
+        assert_eq!(runtime.register(Register::X31), 42);
+        assert_eq!(
+            SegmentCheckpoint::from_executor(&runtime),
+            Err(SegmentCheckpointError::NotHalted)
+        );
+    }
+}
@@ -20,6 +20,7 @@
 #![warn(missing_docs)]
 
 mod context;
+mod continuation;
 mod dependencies;
 mod disassembler;
 pub mod events;
@@ -43,6 +44,7 @@ pub mod syscalls;
 mod utils;
 
 pub use context::*;
+pub use continuation::*;
 pub use executor::*;
 pub use hook::*;
 pub use instruction::*;
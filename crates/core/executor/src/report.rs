@@ -19,10 +19,26 @@ pub struct ExecutionReport {
     pub syscall_counts: Box<EnumMap<SyscallCode, u64>>,
     /// The cycle tracker counts.
     pub cycle_tracker: HashMap<String, u64>,
+    /// The spans of the cycle tracker aggregated by name, in the order they were first started.
+    pub cycle_tracker_spans: Vec<CycleTrackerSpan>,
     /// The unique memory address counts.
     pub touched_memory_addresses: u64,
 }
 
+/// The spans of cycles between a `cycle-tracker-start` and the matching `cycle-tracker-end` with
+/// the same name.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct CycleTrackerSpan {
+    /// The name of the spans.
+    pub name: String,
+    /// The number of spans enclosing the first span with this name.
+    pub depth: u32,
+    /// The number of ended spans.
+    pub count: u64,
+    /// The total number of cycles spent in the ended spans.
+    pub cycles: u64,
+}
+
 impl ExecutionReport {
     /// Compute the total number of instructions run during the execution.
     #[must_use]
@@ -35,6 +51,23 @@ impl ExecutionReport {
     pub fn total_syscall_count(&self) -> u64 {
         self.syscall_counts.values().sum()
     }
+
+    /// The aggregated cycle tracker spans with the given name, inserting them at `depth` if this is
+    /// the first span with that name.
+    pub fn cycle_tracker_span(&mut self, name: &str, depth: u32) -> &mut CycleTrackerSpan {
+        let index = match self.cycle_tracker_spans.iter().position(|span| span.name == name) {
+            Some(index) => index,
+            None => {
+                self.cycle_tracker_spans.push(CycleTrackerSpan {
+                    name: name.to_string(),
+                    depth,
+                    ..Default::default()
+                });
+                self.cycle_tracker_spans.len() - 1
+            }
+        };
+        &mut self.cycle_tracker_spans[index]
+    }
 }
 
 /// Combines two `HashMap`s together. If a key is in both maps, the values are added together.
@@ -52,6 +85,11 @@ impl AddAssign for ExecutionReport {
     fn add_assign(&mut self, rhs: Self) {
         counts_add_assign(&mut self.opcode_counts, *rhs.opcode_counts);
         counts_add_assign(&mut self.syscall_counts, *rhs.syscall_counts);
+        for span in rhs.cycle_tracker_spans {
+            let lhs = self.cycle_tracker_span(&span.name, span.depth);
+            lhs.count += span.count;
+            lhs.cycles += span.cycles;
+        }
         self.touched_memory_addresses += rhs.touched_memory_addresses;
     }
 }
@@ -80,3 +118,31 @@ impl Display for ExecutionReport {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_assign_merges_cycle_tracker_spans() {
+        let span = |name: &str, depth, count, cycles| CycleTrackerSpan {
+            name: name.to_string(),
+            depth,
+            count,
+            cycles,
+        };
+        let mut lhs = ExecutionReport {
+            cycle_tracker_spans: vec![span("main", 0, 1, 100), span("hash", 1, 3, 30)],
+            ..Default::default()
+        };
+        let rhs = ExecutionReport {
+            cycle_tracker_spans: vec![span("hash", 1, 2, 20), span("verify", 1, 1, 50)],
+            ..Default::default()
+        };
+        lhs += rhs;
+        assert_eq!(
+            lhs.cycle_tracker_spans,
+            vec![span("main", 0, 1, 100), span("hash", 1, 5, 50), span("verify", 1, 1, 50)]
+        );
+    }
+}
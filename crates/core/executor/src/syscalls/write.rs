@@ -127,6 +127,7 @@ fn handle_cycle_tracker_command(rt: &mut Executor, command: CycleTrackerCommand)
 fn start_cycle_tracker(rt: &mut Executor, name: &str) {
     let depth = rt.cycle_tracker.len() as u32;
     rt.cycle_tracker.insert(name.to_string(), (rt.state.global_clk, depth));
+    rt.report.cycle_tracker_span(name, depth);
     let padding = "│ ".repeat(depth as usize);
     log::info!("{}┌╴{}", padding, name);
 }
@@ -137,6 +138,9 @@ fn end_cycle_tracker(rt: &mut Executor, name: &str) -> Option<u64> {
     if let Some((start, depth)) = rt.cycle_tracker.remove(name) {
         let padding = "│ ".repeat(depth as usize);
         let total_cycles = rt.state.global_clk - start;
+        let span = rt.report.cycle_tracker_span(name, depth);
+        span.count += 1;
+        span.cycles += total_cycles;
         log::info!("{}└╴{} cycles", padding, num_to_comma_separated(total_cycles));
         return Some(total_cycles);
     }
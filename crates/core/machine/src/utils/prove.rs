@@ -39,7 +39,7 @@ use sp1_core_executor::{
 use sp1_stark::{
     air::{MachineAir, PublicValues},
     Com, CpuProver, DebugConstraintBuilder, InteractionBuilder, MachineProof, MachineProver,
-    MachineRecord, OpeningProof, PcsProverData, ProverConstraintFolder, SP1CoreOpts,
+    MachineRecord, OpeningProof, PcsProverData, ProverConstraintFolder, ProvingStage, SP1CoreOpts,
     StarkGenericConfig, StarkMachine, StarkProvingKey, StarkVerifyingKey, UniConfig, Val,
     VerifierConstraintFolder,
 };
@@ -52,6 +52,8 @@ pub enum SP1CoreProverError {
     IoError(io::Error),
     #[error("serialization error: {0}")]
     SerializationError(bincode::Error),
+    #[error("proving was cancelled")]
+    Cancelled,
 }
 
 pub fn prove_simple<SC: StarkGenericConfig, P: MachineProver<SC, RiscvAir<SC::Val>>>(
@@ -140,6 +142,40 @@ where
     Com<SC>: Send + Sync,
     PcsProverData<SC>: Send + Sync,
 {
+    prove_with_challenger::<SC, P>(prover, pk, program, stdin, opts, context, shape_config, |_| {
+        prover.config().challenger()
+    })
+}
+
+/// Like [prove_with_context], but starts the transcript from the challenger returned by
+/// `challenger` for the executor in its final state, so that the proof is bound to whatever it has
+/// observed about the execution.
+///
+/// `challenger` is called once the program has run, before anything is observed, and is not called
+/// if proving fails before that.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_with_challenger<SC: StarkGenericConfig, P: MachineProver<SC, RiscvAir<SC::Val>>>(
+    prover: &P,
+    pk: &P::DeviceProvingKey,
+    program: Program,
+    stdin: &SP1Stdin,
+    opts: SP1CoreOpts,
+    context: SP1Context,
+    shape_config: Option<&CoreShapeConfig<SC::Val>>,
+    challenger: impl FnOnce(&Executor) -> SC::Challenger,
+) -> Result<(MachineProof<SC>, Vec<u8>, u64), SP1CoreProverError>
+where
+    SC::Val: PrimeField32,
+    SC::Challenger: 'static + Clone + Send,
+    OpeningProof<SC>: Send,
+    Com<SC>: Send + Sync,
+    PcsProverData<SC>: Send + Sync,
+{
+    // Take the monitor before the context is moved into the runtime.
+    let monitor = context.monitor.clone();
+    let progress = monitor.stage(ProvingStage::Core, None);
+    let (monitor, progress) = (&monitor, &progress);
+
     // Setup the runtime.
     let mut runtime = Executor::with_context(program.clone(), opts, context);
     runtime.maximal_shapes = shape_config
@@ -173,6 +209,11 @@ where
                         let span = tracing::debug_span!("batch");
                         let _span = span.enter();
 
+                        // Stop executing once proving is cancelled.
+                        if monitor.is_cancelled() {
+                            break Err(SP1CoreProverError::Cancelled);
+                        }
+
                         // Execute the runtime until we reach a checkpoint.
                         let (checkpoint, done) = runtime
                             .execute_state(false)
@@ -190,7 +231,7 @@ where
 
                         // If we've reached the final checkpoint, break out of the loop.
                         if done {
-                            break Ok(runtime.state.public_values_stream);
+                            break Ok(runtime);
                         }
 
                         // Update the index.
@@ -350,14 +391,11 @@ where
         }
         drop(p1_records_and_traces_tx);
 
-        // Create the challenger and observe the verifying key.
-        let mut challenger = prover.config().challenger();
-        pk.observe_into(&mut challenger);
-
         // Spawn the phase 1 prover thread.
         let phase_1_prover_span = tracing::Span::current().clone();
         let phase_1_prover_handle = s.spawn(move || {
             let _span = phase_1_prover_span.enter();
+            let mut observations = Vec::new();
             tracing::debug_span!("phase 1 prover").in_scope(|| {
                 for (records, traces) in p1_records_and_traces_rx.iter() {
                     tracing::debug_span!("batch").in_scope(|| {
@@ -399,27 +437,37 @@ where
                             })
                             .collect::<Vec<_>>();
 
-                        //  the commitments.
-                        for (commit, public_values) in
-                            commitments.into_iter().zip(public_values.into_iter())
-                        {
-                            prover.observe(&mut challenger, commit.clone(), &public_values);
-                        }
+                        // Collect the commitments, observed once the execution is done.
+                        observations.extend(commitments.into_iter().zip(public_values));
                     });
                 }
             });
 
-            challenger
+            observations
         });
 
         // Wait until the checkpoint generator handle has fully finished.
-        let public_values_stream = checkpoint_generator_handle.join().unwrap().unwrap();
+        let runtime = checkpoint_generator_handle.join().unwrap()?;
+        let public_values_stream = runtime.state.public_values_stream.clone();
 
         // Wait until the records and traces have been fully generated.
         p1_record_and_trace_gen_handles.into_iter().for_each(|handle| handle.join().unwrap());
 
         // Wait until the phase 1 prover has completely finished.
-        let mut challenger = phase_1_prover_handle.join().unwrap();
+        let observations = phase_1_prover_handle.join().unwrap();
+        if monitor.is_cancelled() {
+            return Err(SP1CoreProverError::Cancelled);
+        }
+        let num_shards = observations.len();
+        progress.set_total(num_shards);
+
+        // Observe the verifying key and the commitments.
+        let mut challenger = challenger(&runtime);
+        drop(runtime);
+        pk.observe_into(&mut challenger);
+        for (commit, public_values) in observations {
+            prover.observe(&mut challenger, commit, &public_values);
+        }
 
         // Sample for the global permutation challenges.
         // Obtain the challenges used for the global permutation argument.
@@ -465,6 +513,11 @@ where
                 let _span = span.enter();
                 tracing::debug_span!("phase 2 trace generation").in_scope(|| {
                     loop {
+                        // Stop tracing new checkpoints once proving is cancelled.
+                        if monitor.is_cancelled() {
+                            break;
+                        }
+
                         // Receive the latest checkpoint.
                         let received = { checkpoints.lock().unwrap().pop_front() };
                         if let Some((index, mut checkpoint, done)) = received {
@@ -636,6 +689,7 @@ where
                                         )
                                         .unwrap();
                                     opening_span.exit();
+                                    progress.advance(1);
 
                                     #[cfg(debug_assertions)]
                                     {
@@ -661,6 +715,9 @@ where
 
         // Wait until the phase 2 prover has finished.
         let shard_proofs = p2_prover_handle.join().unwrap();
+        if monitor.is_cancelled() && shard_proofs.len() != num_shards {
+            return Err(SP1CoreProverError::Cancelled);
+        }
 
         // Log some of the `ExecutionReport` information.
         let report_aggregate = report_aggregate.lock().unwrap();
@@ -332,6 +332,102 @@ pub fn cycle_tracker(_attr: TokenStream, item: TokenStream) -> TokenStream {
     result.into()
 }
 
+/// Turns a function that configures a [`GuestTest`] into a test that runs it.
+///
+/// The function takes a `&mut sp1_sdk::testing::GuestTest` to declare the program's inputs and
+/// expected outputs. The attribute accepts:
+///
+/// - `elf = "PATH"`: the path of the ELF constant of the program, required.
+/// - `max_cycles = N`: the cycle budget of the program.
+/// - `mode = "execute" | "mock" | "prove"`: how the program is run, defaults to `execute`.
+///
+/// The cycle count is compared against the baseline in `sp1-test-baseline.json`, next to the
+/// crate's `Cargo.toml`.
+///
+/// [`GuestTest`]: https://docs.rs/sp1-sdk/latest/sp1_sdk/testing/struct.GuestTest.html
+#[proc_macro_attribute]
+pub fn sp1_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as syn::AttributeArgs);
+    let input = parse_macro_input!(item as ItemFn);
+
+    let mut elf = None;
+    let mut max_cycles = None;
+    let mut mode = None;
+    for arg in args {
+        let meta = match arg {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(meta)) => meta,
+            arg => {
+                return syn::Error::new_spanned(arg, "expected `name = value`")
+                    .to_compile_error()
+                    .into()
+            }
+        };
+        let value = match (meta.path.get_ident().map(|i| i.to_string()).as_deref(), &meta.lit) {
+            (Some("elf"), syn::Lit::Str(lit)) => lit.parse::<syn::Expr>().map(|e| elf = Some(e)),
+            (Some("max_cycles"), syn::Lit::Int(lit)) => lit
+                .base10_parse::<u64>()
+                .map(|n| max_cycles = Some(quote! { test.max_cycles(#n); })),
+            (Some("mode"), syn::Lit::Str(lit)) => {
+                let variant = match lit.value().as_str() {
+                    "execute" => quote!(Execute),
+                    "mock" => quote!(Mock),
+                    "prove" => quote!(Prove),
+                    _ => {
+                        return syn::Error::new_spanned(lit, "expected execute, mock or prove")
+                            .to_compile_error()
+                            .into()
+                    }
+                };
+                mode = Some(quote! { test.mode(::sp1_sdk::testing::TestMode::#variant); });
+                Ok(())
+            }
+            _ => Err(syn::Error::new_spanned(
+                &meta,
+                "expected `elf = \"...\"`, `max_cycles = N` or `mode = \"...\"`",
+            )),
+        };
+        if let Err(e) = value {
+            return e.to_compile_error().into();
+        }
+    }
+    let Some(elf) = elf else {
+        return syn::Error::new_spanned(&input.sig.ident, "missing `elf = \"...\"`")
+            .to_compile_error()
+            .into();
+    };
+
+    let attrs = &input.attrs;
+    let visibility = &input.vis;
+    let name = &input.sig.ident;
+    let inputs = &input.sig.inputs;
+    let block = &input.block;
+
+    let result = quote! {
+        #[test]
+        #(#attrs)*
+        #visibility fn #name() {
+            fn body(#inputs) #block
+
+            let mut test = ::sp1_sdk::testing::GuestTest::new(
+                concat!(module_path!(), "::", stringify!(#name)),
+                #elf,
+            );
+            test.baseline(
+                ::std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join(::sp1_sdk::testing::BASELINE_FILE),
+            );
+            #max_cycles
+            #mode
+            body(&mut test);
+            if let Err(e) = test.run() {
+                panic!("{}: {}", stringify!(#name), e);
+            }
+        }
+    };
+
+    result.into()
+}
+
 fn find_execution_record_path(attrs: &[syn::Attribute]) -> syn::Path {
     for attr in attrs {
         if attr.path.is_ident("execution_record_path") {
@@ -0,0 +1,128 @@
+//! A self-hosted server implementing the SP1 prover network protocol.
+//!
+//! The server answers the `ProverNetwork` and `ArtifactStore` RPCs used by `NetworkProverV2`, and
+//! stores the uploaded artifacts in a local directory served over HTTP on the same address. Proof
+//! requests are proven one at a time by a local `SP1Prover`, or left to external provers that
+//! poll the requests and submit proofs with the `fulfill_proof` RPC.
+//!
+//! Requests can only be updated by their requester or by an admin, and only admins can add credit.
+
+mod network;
+mod state;
+mod store;
+mod worker;
+
+use std::{path::PathBuf, sync::Arc};
+
+use alloy_primitives::Address;
+use sp1_sdk::{
+    network_v2::proto::{
+        artifact::artifact_store_server::ArtifactStoreServer,
+        network::prover_network_server::ProverNetworkServer,
+    },
+    CpuProver,
+};
+use tokio::{net::TcpListener, sync::mpsc};
+use tonic::service::Routes;
+
+pub use worker::RequestError;
+
+use network::{LocalArtifactService, LocalProverNetwork};
+use state::ServerState;
+use store::LocalArtifactStore;
+
+/// The configuration of a prover server.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// The directory the artifacts are stored in.
+    pub artifacts_dir: PathBuf,
+    /// The URL clients reach the server at, used in the artifact URIs.
+    pub public_url: String,
+    /// Whether to prove requests with a local prover, instead of waiting for them to be fulfilled.
+    pub local_prover: bool,
+    /// The accounts allowed to add credit, and to fulfill and execute any request.
+    pub admins: Vec<Address>,
+    /// The maximum size of a decoded RPC message, which bounds the size of fulfilled proofs, and of
+    /// an uploaded artifact.
+    pub max_message_size: usize,
+}
+
+/// The default maximum size of a decoded RPC message, large enough for core proofs.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 1 << 30;
+
+/// Serve the prover network RPCs and the artifact store on `listener` until an error occurs.
+pub async fn serve(listener: TcpListener, config: ServerConfig) -> anyhow::Result<()> {
+    let store = Arc::new(LocalArtifactStore::new(&config.artifacts_dir, &config.public_url)?);
+    tracing::info!("storing artifacts in {}", store.dir().display());
+
+    let prover = Arc::new(CpuProver::new());
+    let (queue, requests) = if config.local_prover {
+        let (queue, requests) = mpsc::unbounded_channel();
+        (Some(queue), Some(requests))
+    } else {
+        (None, None)
+    };
+    let state = Arc::new(ServerState::new(store.clone(), prover, queue, config.admins));
+    if let Some(requests) = requests {
+        let state = state.clone();
+        std::thread::spawn(move || worker::run(state, requests));
+    }
+
+    // Proofs are submitted inline with `fulfill_proof`, so raise the default message size limit.
+    let network = ProverNetworkServer::new(LocalProverNetwork::new(state.clone()))
+        .max_decoding_message_size(config.max_message_size);
+    let artifacts = ArtifactStoreServer::new(LocalArtifactService::new(state));
+    let router = Routes::new(network).add_service(artifacts).into_axum_router();
+
+    tracing::info!("listening on {}", listener.local_addr()?);
+    axum::serve(listener, router.merge(store::routes(store, config.max_message_size))).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sp1_sdk::{
+        provers::ProofOpts, utils, NetworkProverV2, Prover, SP1Context, SP1ProofKind, SP1Stdin,
+    };
+
+    use super::*;
+
+    /// The first development account of Anvil and Hardhat.
+    const TEST_PRIVATE_KEY: &str =
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    #[test]
+    fn test_e2e_network_core() {
+        utils::setup_logger();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let artifacts_dir = tempfile::tempdir().unwrap();
+        let config = ServerConfig {
+            artifacts_dir: artifacts_dir.path().to_path_buf(),
+            public_url: url.clone(),
+            local_prover: true,
+            admins: vec![],
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+        };
+        runtime.spawn(serve(listener, config));
+        std::env::set_var("PROVER_NETWORK_RPC", &url);
+
+        let prover = NetworkProverV2::new_from_key(TEST_PRIVATE_KEY);
+        let elf = include_bytes!("../../../tests/fibonacci/elf/riscv32im-succinct-zkvm-elf");
+        let (pk, vk) = prover.setup(elf);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&10usize);
+
+        let proof = Prover::prove(
+            &prover,
+            &pk,
+            stdin,
+            ProofOpts::default(),
+            SP1Context::default(),
+            SP1ProofKind::Core,
+        )
+        .unwrap();
+        prover.verify(&proof, &vk).unwrap();
+    }
+}
@@ -0,0 +1,56 @@
+use std::{net::SocketAddr, path::PathBuf};
+
+use alloy_primitives::Address;
+use clap::Parser;
+use sp1_prover_server::{serve, ServerConfig, DEFAULT_MAX_MESSAGE_SIZE};
+use sp1_sdk::utils::setup_logger;
+use tokio::net::TcpListener;
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Serve the SP1 prover network protocol from a local prover.")]
+struct ServerArgs {
+    /// The address to listen on.
+    #[arg(long, env = "SP1_PROVER_SERVER_ADDR", default_value = "127.0.0.1:50051")]
+    addr: SocketAddr,
+
+    /// The URL clients reach the server at. Defaults to `http://{addr}`.
+    #[arg(long, env = "SP1_PROVER_SERVER_URL")]
+    public_url: Option<String>,
+
+    /// The directory to store the artifacts in.
+    #[arg(long, env = "SP1_PROVER_SERVER_ARTIFACTS_DIR", default_value = "artifacts")]
+    artifacts_dir: PathBuf,
+
+    /// Leave the requests to external provers instead of proving them locally.
+    #[arg(long)]
+    no_local_prover: bool,
+
+    /// The accounts allowed to add credit, and to fulfill and execute any request.
+    #[arg(long = "admin", env = "SP1_PROVER_SERVER_ADMINS", value_delimiter = ',')]
+    admins: Vec<Address>,
+
+    /// The maximum size in bytes of an RPC message or uploaded artifact, which bounds the size of
+    /// fulfilled proofs.
+    #[arg(
+        long,
+        env = "SP1_PROVER_SERVER_MAX_MESSAGE_SIZE",
+        default_value_t = DEFAULT_MAX_MESSAGE_SIZE
+    )]
+    max_message_size: usize,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    setup_logger();
+    let args = ServerArgs::parse();
+
+    let config = ServerConfig {
+        artifacts_dir: args.artifacts_dir,
+        public_url: args.public_url.unwrap_or_else(|| format!("http://{}", args.addr)),
+        local_prover: !args.no_local_prover,
+        admins: args.admins,
+        max_message_size: args.max_message_size,
+    };
+    let listener = TcpListener::bind(args.addr).await?;
+    serve(listener, config).await
+}
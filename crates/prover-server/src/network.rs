@@ -0,0 +1,249 @@
+use std::{str::FromStr, sync::Arc};
+
+use alloy_primitives::{keccak256, Address, U256};
+use prost::Message;
+use sp1_sdk::{
+    network_v2::proto::{
+        artifact::{
+            artifact_store_server::ArtifactStore, CreateArtifactRequest, CreateArtifactResponse,
+        },
+        network::{
+            prover_network_server::ProverNetwork, AddCreditRequest, AddCreditResponse,
+            AddCreditResponseBody, ExecuteProofRequest, ExecuteProofResponse,
+            ExecuteProofResponseBody, ExecutionStatus, FulfillProofRequest, FulfillProofResponse,
+            FulfillProofResponseBody, GetBalanceRequest, GetBalanceResponse,
+            GetFilteredProofRequestsRequest, GetFilteredProofRequestsResponse,
+            GetLatestBridgeBlockRequest, GetLatestBridgeBlockResponse, GetNonceRequest,
+            GetNonceResponse, GetProofRequestStatusRequest, GetProofRequestStatusResponse,
+            ProofMode, ProofRequest, ProofStatus, RequestProofRequest, RequestProofResponse,
+            RequestProofResponseBody,
+        },
+    },
+    Prover, SP1ProofKind, SP1ProofWithPublicValues, SP1VerifyingKey, SP1_CIRCUIT_VERSION,
+};
+use tonic::{Request, Response, Status};
+
+use crate::state::{proof_kind, recover_signer, Entry, ServerState};
+
+/// The message signed by clients to create an artifact.
+const CREATE_ARTIFACT_MESSAGE: &[u8] = b"create_artifact";
+
+/// The default and maximum number of requests returned by a filter.
+const DEFAULT_FILTER_LIMIT: u32 = 10;
+const MAX_FILTER_LIMIT: u32 = 100;
+
+/// The prover network RPCs, served from the state of the local server.
+pub struct LocalProverNetwork {
+    state: Arc<ServerState>,
+}
+
+impl LocalProverNetwork {
+    pub(crate) fn new(state: Arc<ServerState>) -> Self {
+        Self { state }
+    }
+}
+
+#[tonic::async_trait]
+impl ProverNetwork for LocalProverNetwork {
+    async fn request_proof(
+        &self,
+        request: Request<RequestProofRequest>,
+    ) -> Result<Response<RequestProofResponse>, Status> {
+        let request = request.into_inner();
+        let body = request.body.as_ref().ok_or_else(|| Status::invalid_argument("missing body"))?;
+
+        let version = format!("sp1-{}", SP1_CIRCUIT_VERSION);
+        if body.version != version {
+            return Err(Status::failed_precondition(format!(
+                "unsupported version {}, the server runs {}",
+                body.version, version
+            )));
+        }
+        if proof_kind(body.mode()).is_none() {
+            return Err(Status::invalid_argument("missing proof mode"));
+        }
+        for uri in [&body.program_uri, &body.stdin_uri] {
+            if !self.state.store.contains(uri) {
+                return Err(Status::invalid_argument(format!("artifact {uri} was not uploaded")));
+            }
+        }
+        let requester =
+            self.state.authenticate(&request.signature, &body.encode_to_vec(), body.nonce)?;
+
+        let request_id = keccak256(&request.signature).to_vec();
+        let tx_hash = keccak256(request.encode_to_vec()).to_vec();
+        let proof_request = ProofRequest {
+            request_id: format!("0x{}", hex::encode(&request_id)),
+            version: body.version.clone(),
+            mode: body.mode,
+            strategy: body.strategy,
+            program_uri: body.program_uri.clone(),
+            stdin_uri: body.stdin_uri.clone(),
+            deadline: body.deadline,
+            cycle_limit: body.cycle_limit,
+            proof_status: ProofStatus::Requested.into(),
+            execution_status: ExecutionStatus::Unexecuted.into(),
+            requester: requester.to_vec(),
+        };
+        self.state.insert(
+            request_id.clone(),
+            Entry {
+                request: proof_request,
+                vkey: body.vkey.clone(),
+                request_tx_hash: tx_hash.clone(),
+                fulfill_tx_hash: None,
+                proof_uri: None,
+                public_values_hash: None,
+                cycles: None,
+                error: None,
+            },
+        )?;
+
+        Ok(Response::new(RequestProofResponse {
+            tx_hash,
+            body: Some(RequestProofResponseBody { request_id }),
+        }))
+    }
+
+    async fn fulfill_proof(
+        &self,
+        request: Request<FulfillProofRequest>,
+    ) -> Result<Response<FulfillProofResponse>, Status> {
+        let request = request.into_inner();
+        let body = request.body.ok_or_else(|| Status::invalid_argument("missing body"))?;
+        // The nonce is only consumed once the proof is accepted.
+        let signer =
+            self.state.verify_signer(&request.signature, &body.encode_to_vec(), body.nonce)?;
+        self.state.authorize(signer, &body.request_id)?;
+
+        // Check the proof against the request before accepting it.
+        let (mode, vkey) = self.state.pending(&body.request_id)?;
+        let proof: SP1ProofWithPublicValues = bincode::deserialize(&body.proof)
+            .map_err(|e| Status::invalid_argument(format!("invalid proof: {e}")))?;
+        if ProofMode::from(SP1ProofKind::from(&proof.proof)) != mode {
+            return Err(Status::invalid_argument("the proof does not match the requested mode"));
+        }
+        let vk: SP1VerifyingKey = bincode::deserialize(&vkey)
+            .map_err(|e| Status::internal(format!("invalid verifying key: {e}")))?;
+        let state = self.state.clone();
+        tokio::task::spawn_blocking(move || state.prover.verify(&proof, &vk))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map_err(|e| Status::invalid_argument(format!("invalid proof: {e}")))?;
+
+        let tx_hash =
+            self.state.fulfill(&body.request_id, &body.proof, Some((signer, body.nonce)))?;
+        Ok(Response::new(FulfillProofResponse { tx_hash, body: Some(FulfillProofResponseBody {}) }))
+    }
+
+    async fn execute_proof(
+        &self,
+        request: Request<ExecuteProofRequest>,
+    ) -> Result<Response<ExecuteProofResponse>, Status> {
+        let request = request.into_inner();
+        let body = request.body.ok_or_else(|| Status::invalid_argument("missing body"))?;
+        let signer =
+            self.state.authenticate(&request.signature, &body.encode_to_vec(), body.nonce)?;
+        self.state.authorize(signer, &body.request_id)?;
+        let tx_hash = keccak256(body.encode_to_vec()).to_vec();
+        let status = body.execution_status();
+        self.state.set_execution(&body.request_id, status, body.public_values_hash, body.cycles)?;
+        Ok(Response::new(ExecuteProofResponse { tx_hash, body: Some(ExecuteProofResponseBody {}) }))
+    }
+
+    async fn get_filtered_proof_requests(
+        &self,
+        request: Request<GetFilteredProofRequestsRequest>,
+    ) -> Result<Response<GetFilteredProofRequestsResponse>, Status> {
+        let request = request.into_inner();
+        let limit = request.limit.unwrap_or(DEFAULT_FILTER_LIMIT).min(MAX_FILTER_LIMIT);
+        let requests = self.state.filtered(
+            request.version.as_deref(),
+            request.proof_status,
+            request.execution_status,
+            limit as usize,
+        );
+        Ok(Response::new(GetFilteredProofRequestsResponse { requests }))
+    }
+
+    async fn get_proof_request_status(
+        &self,
+        request: Request<GetProofRequestStatusRequest>,
+    ) -> Result<Response<GetProofRequestStatusResponse>, Status> {
+        Ok(Response::new(self.state.status(&request.into_inner().request_id)?))
+    }
+
+    async fn get_latest_bridge_block(
+        &self,
+        _: Request<GetLatestBridgeBlockRequest>,
+    ) -> Result<Response<GetLatestBridgeBlockResponse>, Status> {
+        // There is no bridge in front of a local server.
+        Ok(Response::new(GetLatestBridgeBlockResponse { block_number: 0 }))
+    }
+
+    async fn get_balance(
+        &self,
+        request: Request<GetBalanceRequest>,
+    ) -> Result<Response<GetBalanceResponse>, Status> {
+        let address = parse_address(&request.into_inner().address)?;
+        let amount = self.state.balance(address).to_string();
+        Ok(Response::new(GetBalanceResponse { amount }))
+    }
+
+    async fn add_credit(
+        &self,
+        request: Request<AddCreditRequest>,
+    ) -> Result<Response<AddCreditResponse>, Status> {
+        let request = request.into_inner();
+        let body = request.body.ok_or_else(|| Status::invalid_argument("missing body"))?;
+        let address = parse_address(&body.address)?;
+        let amount = U256::from_str(&body.amount)
+            .map_err(|e| Status::invalid_argument(format!("invalid amount: {e}")))?;
+        let signer =
+            self.state.authenticate(&request.signature, &body.encode_to_vec(), body.nonce)?;
+        self.state.authorize_admin(signer)?;
+        self.state.add_credit(address, amount);
+        Ok(Response::new(AddCreditResponse {
+            tx_hash: keccak256(body.encode_to_vec()).to_vec(),
+            body: Some(AddCreditResponseBody {}),
+        }))
+    }
+
+    async fn get_nonce(
+        &self,
+        request: Request<GetNonceRequest>,
+    ) -> Result<Response<GetNonceResponse>, Status> {
+        let address = parse_address(&request.into_inner().address)?;
+        Ok(Response::new(GetNonceResponse { nonce: self.state.nonce(address) }))
+    }
+}
+
+/// The artifact store RPCs, handing out URLs of the local artifact store.
+pub struct LocalArtifactService {
+    state: Arc<ServerState>,
+}
+
+impl LocalArtifactService {
+    pub(crate) fn new(state: Arc<ServerState>) -> Self {
+        Self { state }
+    }
+}
+
+#[tonic::async_trait]
+impl ArtifactStore for LocalArtifactService {
+    async fn create_artifact(
+        &self,
+        request: Request<CreateArtifactRequest>,
+    ) -> Result<Response<CreateArtifactResponse>, Status> {
+        recover_signer(&request.into_inner().signature, CREATE_ARTIFACT_MESSAGE)?;
+        let uri = self.state.store.create();
+        Ok(Response::new(CreateArtifactResponse {
+            artifact_uri: uri.clone(),
+            artifact_presigned_url: uri,
+        }))
+    }
+}
+
+fn parse_address(address: &[u8]) -> Result<Address, Status> {
+    Address::try_from(address).map_err(|_| Status::invalid_argument("invalid address"))
+}
@@ -0,0 +1,319 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use alloy_primitives::{keccak256, Address, Signature, U256};
+use sp1_sdk::{
+    network_v2::proto::network::{
+        ExecutionStatus, GetProofRequestStatusResponse, ProofMode, ProofRequest, ProofStatus,
+    },
+    CpuProver, SP1ProofKind,
+};
+use tokio::sync::mpsc::UnboundedSender;
+use tonic::Status;
+
+use crate::{store::LocalArtifactStore, RequestError};
+
+/// The proof requests and accounts of the server, shared by the RPC services and the prover.
+pub(crate) struct ServerState {
+    pub store: Arc<LocalArtifactStore>,
+    pub prover: Arc<CpuProver>,
+    /// The queue of requests for the local prover, or `None` if proofs are fulfilled externally.
+    queue: Option<UnboundedSender<Vec<u8>>>,
+    /// The accounts allowed to add credit and to update any request.
+    admins: HashSet<Address>,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// The requests in the order they were made.
+    requests: Vec<Entry>,
+    /// The index of each request in `requests`, by request id.
+    index: HashMap<Vec<u8>, usize>,
+    nonces: HashMap<Address, u64>,
+    balances: HashMap<Address, U256>,
+}
+
+/// A proof request and everything known about its progress.
+pub(crate) struct Entry {
+    pub request: ProofRequest,
+    pub vkey: Vec<u8>,
+    pub request_tx_hash: Vec<u8>,
+    pub fulfill_tx_hash: Option<Vec<u8>>,
+    pub proof_uri: Option<String>,
+    pub public_values_hash: Option<Vec<u8>>,
+    pub cycles: Option<u64>,
+    pub error: Option<(u32, String)>,
+}
+
+impl ServerState {
+    pub fn new(
+        store: Arc<LocalArtifactStore>,
+        prover: Arc<CpuProver>,
+        queue: Option<UnboundedSender<Vec<u8>>>,
+        admins: impl IntoIterator<Item = Address>,
+    ) -> Self {
+        Self { store, prover, queue, admins: admins.into_iter().collect(), inner: Mutex::default() }
+    }
+
+    /// Recover the signer of `message` and consume its next nonce.
+    pub fn authenticate(
+        &self,
+        signature: &[u8],
+        message: &[u8],
+        nonce: u64,
+    ) -> Result<Address, Status> {
+        let signer = recover_signer(signature, message)?;
+        self.inner.lock().unwrap().consume_nonce(signer, nonce)?;
+        Ok(signer)
+    }
+
+    /// Recover the signer of `message` and check its next nonce, without consuming it.
+    ///
+    /// The nonce is consumed later, once the request succeeds, so that a rejected request can be
+    /// retried with the same nonce.
+    pub fn verify_signer(
+        &self,
+        signature: &[u8],
+        message: &[u8],
+        nonce: u64,
+    ) -> Result<Address, Status> {
+        let signer = recover_signer(signature, message)?;
+        self.inner.lock().unwrap().check_nonce(signer, nonce)?;
+        Ok(signer)
+    }
+
+    /// Check that `signer` is an admin.
+    pub fn authorize_admin(&self, signer: Address) -> Result<(), Status> {
+        if !self.admins.contains(&signer) {
+            return Err(Status::permission_denied(format!("{signer} is not an admin")));
+        }
+        Ok(())
+    }
+
+    /// Check that `signer` made the request or is an admin.
+    pub fn authorize(&self, signer: Address, request_id: &[u8]) -> Result<(), Status> {
+        let requester = self
+            .update(request_id, |entry| entry.request.requester.clone())
+            .ok_or_else(|| not_found(request_id))?;
+        if requester != signer.as_slice() && !self.admins.contains(&signer) {
+            return Err(Status::permission_denied(format!(
+                "{signer} is neither the requester of 0x{} nor an admin",
+                hex::encode(request_id)
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn nonce(&self, address: Address) -> u64 {
+        self.inner.lock().unwrap().nonces.get(&address).copied().unwrap_or_default()
+    }
+
+    pub fn balance(&self, address: Address) -> U256 {
+        self.inner.lock().unwrap().balances.get(&address).copied().unwrap_or_default()
+    }
+
+    pub fn add_credit(&self, address: Address, amount: U256) {
+        let mut inner = self.inner.lock().unwrap();
+        let balance = inner.balances.entry(address).or_default();
+        *balance = balance.saturating_add(amount);
+    }
+
+    /// Add a new request and queue it for the local prover.
+    pub fn insert(&self, request_id: Vec<u8>, entry: Entry) -> Result<(), Status> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.index.contains_key(&request_id) {
+                return Err(Status::already_exists(format!(
+                    "request {} already exists",
+                    entry.request.request_id
+                )));
+            }
+            let index = inner.requests.len();
+            inner.requests.push(entry);
+            inner.index.insert(request_id.clone(), index);
+        }
+        if let Some(queue) = &self.queue {
+            // The prover only stops when the server shuts down.
+            let _ = queue.send(request_id);
+        }
+        Ok(())
+    }
+
+    /// Assign a requested proof to the local prover, returning the request and its vkey.
+    pub fn assign(&self, request_id: &[u8]) -> Option<(ProofRequest, Vec<u8>)> {
+        self.update(request_id, |entry| {
+            if entry.request.proof_status() != ProofStatus::Requested {
+                return None;
+            }
+            entry.request.set_proof_status(ProofStatus::Assigned);
+            Some((entry.request.clone(), entry.vkey.clone()))
+        })
+        .flatten()
+    }
+
+    /// The mode and vkey of a request that has not been fulfilled yet.
+    pub fn pending(&self, request_id: &[u8]) -> Result<(ProofMode, Vec<u8>), Status> {
+        self.update(request_id, |entry| {
+            check_pending(entry)?;
+            Ok((entry.request.mode(), entry.vkey.clone()))
+        })
+        .ok_or_else(|| not_found(request_id))?
+    }
+
+    /// Record the result of executing a request.
+    pub fn set_execution(
+        &self,
+        request_id: &[u8],
+        status: ExecutionStatus,
+        public_values_hash: Option<Vec<u8>>,
+        cycles: Option<u64>,
+    ) -> Result<(), Status> {
+        self.update(request_id, |entry| {
+            entry.request.set_execution_status(status);
+            entry.public_values_hash = public_values_hash;
+            entry.cycles = cycles;
+        })
+        .ok_or_else(|| not_found(request_id))
+    }
+
+    /// Store the proof of a request, returning the transaction hash of the fulfillment.
+    ///
+    /// If `nonce` is given, the nonce of the signer is consumed together with the fulfillment.
+    pub fn fulfill(
+        &self,
+        request_id: &[u8],
+        proof: &[u8],
+        nonce: Option<(Address, u64)>,
+    ) -> Result<Vec<u8>, Status> {
+        let vkey = self
+            .update(request_id, |entry| entry.vkey.clone())
+            .ok_or_else(|| not_found(request_id))?;
+        let proof_uri = self.store.insert(proof).map_err(|e| Status::internal(e.to_string()))?;
+        let tx_hash = keccak256([request_id, &vkey, proof].concat()).to_vec();
+
+        let mut inner = self.inner.lock().unwrap();
+        if let Some((signer, nonce)) = nonce {
+            inner.check_nonce(signer, nonce)?;
+        }
+        let index = *inner.index.get(request_id).ok_or_else(|| not_found(request_id))?;
+        let entry = &mut inner.requests[index];
+        check_pending(entry)?;
+        entry.request.set_proof_status(ProofStatus::Fulfilled);
+        entry.proof_uri = Some(proof_uri);
+        entry.fulfill_tx_hash = Some(tx_hash.clone());
+        entry.error = None;
+        if let Some((signer, nonce)) = nonce {
+            inner.consume_nonce(signer, nonce)?;
+        }
+        Ok(tx_hash)
+    }
+
+    /// Record that the local prover failed to fulfill a request, which can then no longer be
+    /// fulfilled.
+    pub fn fail(&self, request_id: &[u8], error: &RequestError) {
+        self.update(request_id, |entry| {
+            if entry.request.proof_status() != ProofStatus::Fulfilled {
+                entry.request.set_proof_status(ProofStatus::Unfulfillable);
+                entry.error = Some((error.code(), error.to_string()));
+            }
+        });
+    }
+
+    pub fn status(&self, request_id: &[u8]) -> Result<GetProofRequestStatusResponse, Status> {
+        self.update(request_id, |entry| GetProofRequestStatusResponse {
+            proof_status: entry.request.proof_status,
+            execution_status: entry.request.execution_status,
+            request_tx_hash: entry.request_tx_hash.clone(),
+            fulfill_tx_hash: entry.fulfill_tx_hash.clone(),
+            proof_uri: entry.proof_uri.clone(),
+            error_code: entry.error.as_ref().map(|(code, _)| *code),
+            error_description: entry.error.as_ref().map(|(_, description)| description.clone()),
+        })
+        .ok_or_else(|| not_found(request_id))
+    }
+
+    /// The oldest requests matching the given filter.
+    pub fn filtered(
+        &self,
+        version: Option<&str>,
+        proof_status: Option<i32>,
+        execution_status: Option<i32>,
+        limit: usize,
+    ) -> Vec<ProofRequest> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .requests
+            .iter()
+            .map(|entry| &entry.request)
+            .filter(|request| version.map_or(true, |version| request.version == version))
+            .filter(|request| proof_status.map_or(true, |status| request.proof_status == status))
+            .filter(|request| {
+                execution_status.map_or(true, |status| request.execution_status == status)
+            })
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    fn update<T>(&self, request_id: &[u8], f: impl FnOnce(&mut Entry) -> T) -> Option<T> {
+        let mut inner = self.inner.lock().unwrap();
+        let index = *inner.index.get(request_id)?;
+        Some(f(&mut inner.requests[index]))
+    }
+}
+
+impl Inner {
+    fn check_nonce(&self, signer: Address, nonce: u64) -> Result<(), Status> {
+        let expected = self.nonces.get(&signer).copied().unwrap_or_default();
+        if expected != nonce {
+            return Err(Status::invalid_argument(format!(
+                "invalid nonce {nonce} for {signer}, expected {expected}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn consume_nonce(&mut self, signer: Address, nonce: u64) -> Result<(), Status> {
+        self.check_nonce(signer, nonce)?;
+        *self.nonces.entry(signer).or_default() += 1;
+        Ok(())
+    }
+}
+
+/// Recover the address that signed `message` with an EIP-191 personal signature.
+pub(crate) fn recover_signer(signature: &[u8], message: &[u8]) -> Result<Address, Status> {
+    Signature::try_from(signature)
+        .and_then(|signature| signature.recover_address_from_msg(message))
+        .map_err(|e| Status::unauthenticated(format!("invalid signature: {e}")))
+}
+
+/// The kind of proof to generate for a proof mode.
+pub(crate) fn proof_kind(mode: ProofMode) -> Option<SP1ProofKind> {
+    match mode {
+        ProofMode::UnspecifiedMode => None,
+        ProofMode::Core => Some(SP1ProofKind::Core),
+        ProofMode::Compressed => Some(SP1ProofKind::Compressed),
+        ProofMode::Plonk => Some(SP1ProofKind::Plonk),
+        ProofMode::Groth16 => Some(SP1ProofKind::Groth16),
+    }
+}
+
+/// Check that a request can still be fulfilled.
+fn check_pending(entry: &Entry) -> Result<(), Status> {
+    match entry.request.proof_status() {
+        ProofStatus::Fulfilled => {
+            Err(Status::failed_precondition("the request is already fulfilled"))
+        }
+        ProofStatus::Unfulfillable => {
+            Err(Status::failed_precondition("the request is unfulfillable"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn not_found(request_id: &[u8]) -> Status {
+    Status::not_found(format!("request 0x{} not found", hex::encode(request_id)))
+}
@@ -0,0 +1,124 @@
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path as UrlPath, State},
+    http::StatusCode,
+    routing::put,
+    Router,
+};
+use uuid::Uuid;
+
+/// An artifact store keeping every artifact as a file in a local directory.
+///
+/// Artifacts are uploaded and downloaded over HTTP at `{public_url}/artifacts/{id}`, which is both
+/// the URI and the presigned URL handed out to clients.
+pub struct LocalArtifactStore {
+    dir: PathBuf,
+    public_url: String,
+    /// The artifacts that were created but not uploaded yet.
+    pending: Mutex<HashSet<String>>,
+}
+
+impl LocalArtifactStore {
+    /// Create a store in `dir`, creating the directory if needed.
+    pub fn new(dir: impl Into<PathBuf>, public_url: &str) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            public_url: public_url.trim_end_matches('/').to_string(),
+            pending: Mutex::default(),
+        })
+    }
+
+    /// The directory the artifacts are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Reserve a new artifact, returning its URI. The artifact can be uploaded once.
+    pub fn create(&self) -> String {
+        let id = Uuid::new_v4().to_string();
+        self.pending.lock().unwrap().insert(id.clone());
+        self.uri(&id)
+    }
+
+    /// Store `bytes` as a new artifact, returning its URI.
+    pub fn insert(&self, bytes: &[u8]) -> io::Result<String> {
+        let id = Uuid::new_v4().to_string();
+        std::fs::write(self.path(&id), bytes)?;
+        Ok(self.uri(&id))
+    }
+
+    /// Read the artifact with the given URI.
+    pub fn read(&self, uri: &str) -> io::Result<Vec<u8>> {
+        let id = self
+            .id_from_uri(uri)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("unknown uri {uri}")))?;
+        std::fs::read(self.path(id))
+    }
+
+    /// Whether the artifact with the given URI has been uploaded.
+    pub fn contains(&self, uri: &str) -> bool {
+        self.id_from_uri(uri).is_some_and(|id| self.path(id).is_file())
+    }
+
+    fn uri(&self, id: &str) -> String {
+        format!("{}/artifacts/{}", self.public_url, id)
+    }
+
+    fn id_from_uri<'a>(&self, uri: &'a str) -> Option<&'a str> {
+        let id = uri.strip_prefix(&self.public_url)?.strip_prefix("/artifacts/")?;
+        is_valid_id(id).then_some(id)
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(id)
+    }
+}
+
+/// Whether `id` could have been generated by the store, so that it is safe to use as a file name.
+fn is_valid_id(id: &str) -> bool {
+    Uuid::parse_str(id).is_ok_and(|uuid| uuid.hyphenated().to_string() == id)
+}
+
+/// The HTTP routes uploading and downloading artifacts, of at most `max_size` bytes each.
+pub(crate) fn routes(store: Arc<LocalArtifactStore>, max_size: usize) -> Router {
+    Router::new()
+        .route("/artifacts/:id", put(upload).get(download))
+        .layer(DefaultBodyLimit::max(max_size))
+        .with_state(store)
+}
+
+async fn upload(
+    State(store): State<Arc<LocalArtifactStore>>,
+    UrlPath(id): UrlPath<String>,
+    body: Bytes,
+) -> StatusCode {
+    if !store.pending.lock().unwrap().remove(&id) {
+        return StatusCode::NOT_FOUND;
+    }
+    match tokio::fs::write(store.path(&id), body).await {
+        Ok(()) => StatusCode::OK,
+        Err(e) => {
+            tracing::error!("failed to write artifact {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+async fn download(
+    State(store): State<Arc<LocalArtifactStore>>,
+    UrlPath(id): UrlPath<String>,
+) -> Result<Vec<u8>, StatusCode> {
+    if !is_valid_id(&id) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    tokio::fs::read(store.path(&id)).await.map_err(|_| StatusCode::NOT_FOUND)
+}
@@ -0,0 +1,134 @@
+use std::{
+    io,
+    sync::{mpsc, Arc},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use sp1_sdk::{
+    network_v2::proto::network::{ExecutionStatus, ProofRequest},
+    provers::ProofOpts,
+    CancellationToken, Prover, SP1Context, SP1Stdin,
+};
+use thiserror::Error;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::state::{proof_kind, ServerState};
+
+/// An error that prevented the local prover from fulfilling a request.
+///
+/// The code and message are reported in the status of the request.
+#[derive(Error, Debug)]
+pub enum RequestError {
+    #[error("failed to read artifact: {0}")]
+    Artifact(#[from] io::Error),
+    #[error("failed to decode artifact: {0}")]
+    Decode(#[from] bincode::Error),
+    #[error("the verifying key does not match the program")]
+    VkeyMismatch,
+    #[error("execution failed: {0}")]
+    Execution(String),
+    #[error("the deadline passed before the proof was generated")]
+    DeadlineExceeded,
+    #[error("proving failed: {0}")]
+    Prover(anyhow::Error),
+}
+
+impl RequestError {
+    /// The error code reported in the status of the request.
+    pub fn code(&self) -> u32 {
+        match self {
+            Self::Artifact(_) => 1,
+            Self::Decode(_) => 2,
+            Self::VkeyMismatch => 3,
+            Self::Execution(_) => 4,
+            Self::DeadlineExceeded => 5,
+            Self::Prover(_) => 6,
+        }
+    }
+}
+
+/// Prove the queued requests one at a time until the server shuts down.
+pub(crate) fn run(state: Arc<ServerState>, mut queue: UnboundedReceiver<Vec<u8>>) {
+    while let Some(request_id) = queue.blocking_recv() {
+        // Requests fulfilled externally in the meantime are skipped.
+        let Some((request, vkey)) = state.assign(&request_id) else { continue };
+        tracing::info!("proving request {}", request.request_id);
+        match prove(&state, &request_id, &request, &vkey) {
+            Ok(()) => tracing::info!("fulfilled request {}", request.request_id),
+            Err(e) => {
+                tracing::error!("failed to prove request {}: {}", request.request_id, e);
+                state.fail(&request_id, &e);
+            }
+        }
+    }
+}
+
+fn prove(
+    state: &ServerState,
+    request_id: &[u8],
+    request: &ProofRequest,
+    vkey: &[u8],
+) -> Result<(), RequestError> {
+    let elf: Vec<u8> = bincode::deserialize(&state.store.read(&request.program_uri)?)?;
+    let stdin: SP1Stdin = bincode::deserialize(&state.store.read(&request.stdin_uri)?)?;
+    let kind = proof_kind(request.mode()).expect("requests without a mode are rejected");
+
+    let (pk, vk) = state.prover.setup(&elf);
+    if bincode::serialize(&vk)? != vkey {
+        return Err(RequestError::VkeyMismatch);
+    }
+
+    let mut context = SP1Context::builder();
+    if request.cycle_limit > 0 {
+        context.max_cycles(request.cycle_limit);
+    }
+    let (public_values, report) =
+        match state.prover.sp1_prover().execute(&elf, &stdin, context.build()) {
+            Ok(result) => result,
+            Err(e) => {
+                let _ = state.set_execution(request_id, ExecutionStatus::Failed, None, None);
+                return Err(RequestError::Execution(e.to_string()));
+            }
+        };
+    let _ = state.set_execution(
+        request_id,
+        ExecutionStatus::Executed,
+        Some(public_values.hash()),
+        Some(report.total_instruction_count()),
+    );
+
+    // Cancel the proof once the deadline passes. Dropping `done` stops the watchdog early.
+    let token = CancellationToken::new();
+    let (done, finished) = mpsc::channel::<()>();
+    let remaining = UNIX_EPOCH + Duration::from_secs(request.deadline);
+    let remaining = remaining.duration_since(SystemTime::now()).unwrap_or_default();
+    let watchdog = {
+        let token = token.clone();
+        std::thread::spawn(move || {
+            if finished.recv_timeout(remaining) == Err(mpsc::RecvTimeoutError::Timeout) {
+                token.cancel();
+            }
+        })
+    };
+
+    let mut context = SP1Context::builder();
+    context.cancellation(token.clone());
+    if request.cycle_limit > 0 {
+        context.max_cycles(request.cycle_limit);
+    }
+    let result = state.prover.prove(&pk, stdin, ProofOpts::default(), context.build(), kind);
+    drop(done);
+    watchdog.join().unwrap();
+    let proof = result.map_err(|e| {
+        if token.is_cancelled() {
+            RequestError::DeadlineExceeded
+        } else {
+            RequestError::Prover(e)
+        }
+    })?;
+
+    state
+        .fulfill(request_id, &bincode::serialize(&proof)?, None)
+        .map_err(|status| RequestError::Prover(anyhow::anyhow!(status.message().to_string())))?;
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use sp1_core_executor::SP1Context;
+use sp1_core_machine::{io::SP1Stdin, utils::setup_logger};
+use sp1_prover::{
+    components::DefaultProverComponents,
+    compress_constraints::{compress_constraints, pinned_constants},
+    HashableKey, SP1Prover,
+};
+use sp1_stark::SP1ProverOpts;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Where to write the constraints shipped with `sp1-verifier`.
+    #[clap(short, long, default_value = "../verifier/compressed/compress_constraints.bin")]
+    output: PathBuf,
+    /// Where to write the constants pinned by `sp1-verifier`. Run with `VERIFY_VK=true` so that
+    /// the pinned vk root is the one of the release vk map.
+    #[clap(short, long, default_value = "../verifier/src/compressed/pinned.rs")]
+    pinned: PathBuf,
+}
+
+fn main() {
+    setup_logger();
+    let args = Args::parse();
+
+    let constraints = bincode::serialize(&compress_constraints()).unwrap();
+    std::fs::write(&args.output, &constraints).unwrap();
+    tracing::info!("wrote the compress constraints to {}", args.output.display());
+
+    // The compress verifying key is read from the compressed proof of a small program.
+    let elf = include_bytes!("../../../tests/fibonacci/elf/riscv32im-succinct-zkvm-elf");
+    let prover = SP1Prover::<DefaultProverComponents>::new();
+    let opts = SP1ProverOpts::default();
+    let (pk, vk) = prover.setup(elf);
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&10usize);
+    let core_proof = prover.prove_core(&pk, &stdin, opts, SP1Context::default()).unwrap();
+    let compressed = prover.compress(&vk, core_proof, vec![], opts).unwrap();
+
+    let pinned = pinned_constants(&compressed.vk.hash_babybear(), &prover.vk_root, &constraints);
+    std::fs::write(&args.pinned, pinned).unwrap();
+    tracing::info!("wrote the pinned compress machine to {}", args.pinned.display());
+}
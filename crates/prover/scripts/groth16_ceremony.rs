@@ -0,0 +1,142 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand};
+use sp1_core_machine::utils::setup_logger;
+use sp1_prover::build::{build_constraints_and_witness, dummy_proof};
+use sp1_recursion_compiler::constraints::Constraint;
+use sp1_recursion_gnark_ffi::ark::ceremony::{finalize, Phase2Parameters};
+
+/// The constraints of the wrap circuit, in the ceremony directory.
+const CONSTRAINTS_FILE: &str = "constraints.json";
+/// The initial parameters of the ceremony, in the ceremony directory.
+const INITIAL_PARAMS_FILE: &str = "phase2_initial.bin";
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Derive the initial parameters of the wrap circuit from a phase 1 `.ptau` file.
+    Init {
+        #[clap(long)]
+        ptau: PathBuf,
+        #[clap(long)]
+        ceremony_dir: PathBuf,
+    },
+    /// Add a contribution to the parameters.
+    Contribute {
+        #[clap(long)]
+        input: PathBuf,
+        #[clap(long)]
+        output: PathBuf,
+        /// Extra entropy for the secret, mixed with the OS randomness.
+        #[clap(long, default_value = "")]
+        entropy: String,
+    },
+    /// Verify the contributions that the parameters add to earlier parameters of the ceremony.
+    Verify {
+        #[clap(long)]
+        base: PathBuf,
+        #[clap(long)]
+        params: PathBuf,
+    },
+    /// Verify the whole ceremony and write its keys to the build directory.
+    Finalize {
+        #[clap(long)]
+        ceremony_dir: PathBuf,
+        #[clap(long)]
+        params: PathBuf,
+        #[clap(long)]
+        build_dir: PathBuf,
+        /// Rebuild the initial parameters from this phase 1 `.ptau` file and check that they match
+        /// the ones of the ceremony directory, instead of only checking them against their hash.
+        #[clap(long)]
+        ptau: Option<PathBuf>,
+    },
+}
+
+fn read_params(path: &Path) -> Phase2Parameters {
+    let file = File::open(path).unwrap_or_else(|_| panic!("failed to open {}", path.display()));
+    Phase2Parameters::read(BufReader::new(file)).expect("failed to read the parameters")
+}
+
+fn write_params(path: &Path, params: &Phase2Parameters) {
+    let file = File::create(path).unwrap_or_else(|_| panic!("failed to create {}", path.display()));
+    params.write(BufWriter::new(file)).expect("failed to write the parameters");
+}
+
+fn verify(base: &Phase2Parameters, params: &Phase2Parameters) {
+    println!("initial parameters: {}", hex_string(&params.cs_hash));
+    let hashes = params.verify(base).expect("the ceremony is invalid");
+    for (i, hash) in hashes.iter().enumerate() {
+        println!("contribution {}: {}", base.contributions.len() + i, hex_string(hash));
+    }
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub fn main() {
+    setup_logger();
+    let args = Args::parse();
+    match args.command {
+        Command::Init { ptau, ceremony_dir } => {
+            std::fs::create_dir_all(&ceremony_dir).expect("failed to create ceremony directory");
+            let (wrap_vk, wrapped_proof) = dummy_proof();
+            let (constraints, witness) = build_constraints_and_witness(&wrap_vk, &wrapped_proof);
+            let constraints_file = File::create(ceremony_dir.join(CONSTRAINTS_FILE)).unwrap();
+            serde_json::to_writer(BufWriter::new(constraints_file), &constraints).unwrap();
+
+            let params = Phase2Parameters::new(&constraints, witness, &ptau)
+                .expect("failed to initialize the ceremony");
+            write_params(&ceremony_dir.join(INITIAL_PARAMS_FILE), &params);
+            println!("initial parameters: {}", hex_string(&params.cs_hash));
+        }
+        Command::Contribute { input, output, entropy } => {
+            let mut params = read_params(&input);
+            println!("initial parameters: {}", hex_string(&params.cs_hash));
+            let hash = params.contribute_with_entropy(entropy.as_bytes());
+            write_params(&output, &params);
+            println!("contribution {}: {}", params.contributions.len() - 1, hex_string(&hash));
+        }
+        Command::Verify { base, params } => {
+            verify(&read_params(&base), &read_params(&params));
+        }
+        Command::Finalize { ceremony_dir, params, build_dir, ptau } => {
+            let initial = read_params(&ceremony_dir.join(INITIAL_PARAMS_FILE));
+            let constraints_file = File::open(ceremony_dir.join(CONSTRAINTS_FILE)).unwrap();
+            let constraints: Vec<Constraint> =
+                serde_json::from_reader(BufReader::new(constraints_file)).unwrap();
+            if let Some(ptau) = ptau {
+                let (wrap_vk, wrapped_proof) = dummy_proof();
+                let (expected, witness) = build_constraints_and_witness(&wrap_vk, &wrapped_proof);
+                assert!(
+                    serde_json::to_string(&constraints).unwrap()
+                        == serde_json::to_string(&expected).unwrap(),
+                    "the constraints are not the ones of the circuit"
+                );
+                let rebuilt = Phase2Parameters::new(&expected, witness, &ptau)
+                    .expect("failed to rebuild the initial parameters");
+                assert!(
+                    rebuilt.cs_hash == initial.cs_hash,
+                    "the initial parameters are not the ones of the circuit and the ptau file"
+                );
+            }
+
+            let params = read_params(&params);
+            verify(&initial, &params);
+
+            std::fs::create_dir_all(&build_dir).expect("failed to create build directory");
+            finalize(&params, &constraints, &build_dir).expect("failed to write the keys");
+        }
+    }
+}
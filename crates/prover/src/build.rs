@@ -1,15 +1,20 @@
-use std::{borrow::Borrow, path::PathBuf};
+use std::{borrow::Borrow, fs::File, io::BufWriter, path::PathBuf};
 
+use p3_air::Air;
 use p3_baby_bear::BabyBear;
 use sp1_core_executor::SP1Context;
 use sp1_core_machine::io::SP1Stdin;
 use sp1_recursion_circuit::{
+    constraints::RecursiveVerifierConstraintFolder,
     hash::FieldHasherVariable,
     machine::{SP1CompressWitnessValues, SP1WrapVerifier},
 };
 use sp1_recursion_compiler::{
     config::OuterConfig,
-    constraints::{Constraint, ConstraintCompiler},
+    constraints::{
+        r1cs::{write_witness, R1csCompiler},
+        Constraint, ConstraintCompiler,
+    },
     ir::Builder,
 };
 
@@ -18,12 +23,12 @@ pub use sp1_recursion_core::stark::sp1_dev_mode;
 
 pub use sp1_recursion_circuit::witness::{OuterWitness, Witnessable};
 
-use sp1_recursion_gnark_ffi::{Groth16Bn254Prover, PlonkBn254Prover};
-use sp1_stark::{SP1ProverOpts, ShardProof, StarkVerifyingKey};
+use sp1_recursion_gnark_ffi::{Groth16Backend, Groth16Bn254Prover, PlonkBn254Prover};
+use sp1_stark::{air::MachineAir, SP1ProverOpts, ShardProof, StarkMachine, StarkVerifyingKey};
 
 use crate::{
     utils::{babybear_bytes_to_bn254, babybears_to_bn254, words_to_bytes},
-    OuterSC, SP1Prover, WrapAir,
+    OuterSC, SP1Prover, ShrinkAir, WrapAir,
 };
 
 /// Tries to build the PLONK artifacts inside the development directory.
@@ -73,6 +78,9 @@ pub fn build_plonk_bn254_artifacts(
 
 /// Build the groth16 bn254 artifacts to the given directory for the given verification key and
 /// template proof.
+///
+/// The backend is selected with the `SP1_GROTH16_BACKEND` environment variable, see
+/// [Groth16Backend::from_env].
 pub fn build_groth16_bn254_artifacts(
     template_vk: &StarkVerifyingKey<OuterSC>,
     template_proof: &ShardProof<OuterSC>,
@@ -81,7 +89,41 @@ pub fn build_groth16_bn254_artifacts(
     let build_dir = build_dir.into();
     std::fs::create_dir_all(&build_dir).expect("failed to create build directory");
     let (constraints, witness) = build_constraints_and_witness(template_vk, template_proof);
-    Groth16Bn254Prover::build(constraints, witness, build_dir);
+    Groth16Bn254Prover::build_with_backend(
+        Groth16Backend::from_env(),
+        constraints,
+        witness,
+        build_dir,
+    );
+}
+
+/// Build the wrap circuit as an R1CS to the given directory, in the iden3 `wrap.r1cs` format, along
+/// with the `wrap.wtns` witness of the template proof.
+pub fn build_r1cs_artifacts(
+    template_vk: &StarkVerifyingKey<OuterSC>,
+    template_proof: &ShardProof<OuterSC>,
+    build_dir: impl Into<PathBuf>,
+) {
+    let build_dir = build_dir.into();
+    std::fs::create_dir_all(&build_dir).expect("failed to create build directory");
+    let (constraints, witness) = build_constraints_and_witness(template_vk, template_proof);
+
+    tracing::info!("lowering {} constraints to r1cs", constraints.len());
+    let (r1cs, assignment) = R1csCompiler::compile(&constraints, &witness);
+    if let Err(index) = r1cs.check(&assignment) {
+        panic!("the template witness does not satisfy r1cs constraint {index}");
+    }
+    tracing::info!(
+        "built r1cs with {} constraints and {} wires",
+        r1cs.constraints.len(),
+        r1cs.num_wires
+    );
+
+    let r1cs_file = File::create(build_dir.join("wrap.r1cs")).expect("failed to create wrap.r1cs");
+    r1cs.write(BufWriter::new(r1cs_file)).expect("failed to write wrap.r1cs");
+    let witness_file =
+        File::create(build_dir.join("wrap.wtns")).expect("failed to create wrap.wtns");
+    write_witness(&assignment, BufWriter::new(witness_file)).expect("failed to write wrap.wtns");
 }
 
 /// Builds the plonk bn254 artifacts to the given directory.
@@ -118,18 +160,50 @@ pub fn build_groth16_bn254_artifacts_with_dummy(build_dir: impl Into<PathBuf>) {
     crate::build::build_groth16_bn254_artifacts(&wrap_vk, &wrapped_proof, build_dir.into());
 }
 
+/// Builds the R1CS artifacts to the given directory.
+///
+/// This may take a while as it needs to first generate a dummy proof.
+pub fn build_r1cs_artifacts_with_dummy(build_dir: impl Into<PathBuf>) {
+    let (wrap_vk, wrapped_proof) = dummy_proof();
+    build_r1cs_artifacts(&wrap_vk, &wrapped_proof, build_dir);
+}
+
 /// Build the verifier constraints and template witness for the circuit.
 pub fn build_constraints_and_witness(
     template_vk: &StarkVerifyingKey<OuterSC>,
     template_proof: &ShardProof<OuterSC>,
 ) -> (Vec<Constraint>, OuterWitness<OuterConfig>) {
+    let wrap_machine = WrapAir::wrap_machine(OuterSC::default());
+    build_machine_constraints_and_witness(&wrap_machine, template_vk, template_proof)
+}
+
+/// Build the verifier constraints and template witness for a circuit verifying shrink proofs with
+/// BN254 Poseidon2 commitments, as made by [SP1Prover::shrink_bn254], instead of wrap proofs.
+///
+/// The circuit has the same public inputs as the wrap circuit.
+pub fn build_shrink_bn254_constraints_and_witness(
+    template_vk: &StarkVerifyingKey<OuterSC>,
+    template_proof: &ShardProof<OuterSC>,
+) -> (Vec<Constraint>, OuterWitness<OuterConfig>) {
+    let shrink_machine = ShrinkAir::shrink_machine(OuterSC::compressed());
+    build_machine_constraints_and_witness(&shrink_machine, template_vk, template_proof)
+}
+
+fn build_machine_constraints_and_witness<A>(
+    machine: &StarkMachine<OuterSC, A>,
+    template_vk: &StarkVerifyingKey<OuterSC>,
+    template_proof: &ShardProof<OuterSC>,
+) -> (Vec<Constraint>, OuterWitness<OuterConfig>)
+where
+    A: MachineAir<BabyBear> + for<'a> Air<RecursiveVerifierConstraintFolder<'a, OuterConfig>>,
+{
     tracing::info!("building verifier constraints");
     let template_input = SP1CompressWitnessValues {
         vks_and_proofs: vec![(template_vk.clone(), template_proof.clone())],
         is_complete: true,
     };
-    let constraints =
-        tracing::info_span!("wrap circuit").in_scope(|| build_outer_circuit(&template_input));
+    let constraints = tracing::info_span!("wrap circuit")
+        .in_scope(|| build_outer_circuit(machine, &template_input));
 
     let pv: &RecursionPublicValues<BabyBear> = template_proof.public_values.as_slice().borrow();
     let vkey_hash = babybears_to_bn254(&pv.sp1_vk_digest);
@@ -176,9 +250,13 @@ pub fn dummy_proof() -> (StarkVerifyingKey<OuterSC>, ShardProof<OuterSC>) {
     (wrapped_proof.vk, wrapped_proof.proof)
 }
 
-fn build_outer_circuit(template_input: &SP1CompressWitnessValues<OuterSC>) -> Vec<Constraint> {
-    let wrap_machine = WrapAir::wrap_machine(OuterSC::default());
-
+fn build_outer_circuit<A>(
+    machine: &StarkMachine<OuterSC, A>,
+    template_input: &SP1CompressWitnessValues<OuterSC>,
+) -> Vec<Constraint>
+where
+    A: MachineAir<BabyBear> + for<'a> Air<RecursiveVerifierConstraintFolder<'a, OuterConfig>>,
+{
     let wrap_span = tracing::debug_span!("build wrap circuit").entered();
     let mut builder = Builder::<OuterConfig>::default();
 
@@ -200,7 +278,7 @@ fn build_outer_circuit(template_input: &SP1CompressWitnessValues<OuterSC>) -> Ve
     builder.assert_felt_eq(vk.pc_start, template_vk.pc_start);
 
     // Verify the proof.
-    SP1WrapVerifier::verify(&mut builder, &wrap_machine, input);
+    SP1WrapVerifier::verify(&mut builder, machine, input);
 
     let mut backend = ConstraintCompiler::<OuterConfig>::default();
     let operations = backend.emit(builder.into_operations());
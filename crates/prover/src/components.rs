@@ -19,6 +19,11 @@ pub trait SP1ProverComponents: Send + Sync {
         + Send
         + Sync;
 
+    /// The prover for shrinking compressed proofs with BN254 Poseidon2 commitments.
+    type ShrinkBn254Prover: MachineProver<OuterSC, ShrinkAir<<OuterSC as StarkGenericConfig>::Val>>
+        + Send
+        + Sync;
+
     /// The prover for wrapping compressed proofs into SNARK-friendly field elements.
     type WrapProver: MachineProver<OuterSC, WrapAir<<OuterSC as StarkGenericConfig>::Val>>
         + Send
@@ -31,5 +36,6 @@ impl SP1ProverComponents for DefaultProverComponents {
     type CoreProver = CpuProver<CoreSC, RiscvAir<<CoreSC as StarkGenericConfig>::Val>>;
     type CompressProver = CpuProver<InnerSC, CompressAir<<InnerSC as StarkGenericConfig>::Val>>;
     type ShrinkProver = CpuProver<InnerSC, ShrinkAir<<InnerSC as StarkGenericConfig>::Val>>;
+    type ShrinkBn254Prover = CpuProver<OuterSC, ShrinkAir<<OuterSC as StarkGenericConfig>::Val>>;
     type WrapProver = CpuProver<OuterSC, WrapAir<<OuterSC as StarkGenericConfig>::Val>>;
 }
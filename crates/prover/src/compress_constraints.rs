@@ -0,0 +1,335 @@
+//! Export of the compress machine's constraints for the `sp1-verifier` compressed proof verifier.
+
+use std::{collections::HashMap, fs::File, path::Path, rc::Rc};
+
+use p3_air::{BaseAir, VirtualPairCol};
+use p3_baby_bear::BabyBear;
+use p3_field::{AbstractField, PrimeField, PrimeField32};
+use p3_uni_stark::{get_symbolic_constraints, Entry, SymbolicExpression};
+use sha2::{Digest, Sha256};
+use sp1_stark::{
+    air::{InteractionScope, MachineAir},
+    lookup::Interaction,
+    DIGEST_SIZE,
+};
+use sp1_verifier::compressed::{
+    AirExpr, AirInteraction, ChipConstraints, Column, CompressConstraints, LinearColumn, Scope,
+};
+
+use crate::{utils::babybears_to_bn254, CompressAir, InnerSC};
+
+/// Describes the constraints and interactions of every chip of the compress machine.
+pub fn compress_constraints() -> CompressConstraints {
+    let machine = CompressAir::<BabyBear>::compress_machine(InnerSC::default());
+    let chips = machine
+        .chips()
+        .iter()
+        .map(|chip| {
+            let preprocessed_width = chip.preprocessed_width();
+            let width = chip.width();
+
+            let mut graph = ExprGraph::default();
+            let constraints =
+                get_symbolic_constraints(chip.air(), preprocessed_width, machine.num_pv_elts())
+                    .iter()
+                    .map(|constraint| graph.insert(constraint))
+                    .collect();
+
+            let interactions = |interactions: &[Interaction<BabyBear>]| {
+                interactions
+                    .iter()
+                    .map(|i| AirInteraction {
+                        values: i
+                            .values
+                            .iter()
+                            .map(|v| linear_column(v, preprocessed_width, width))
+                            .collect(),
+                        multiplicity: linear_column(&i.multiplicity, preprocessed_width, width),
+                        argument_index: i.argument_index(),
+                        scope: scope(i.scope),
+                    })
+                    .collect()
+            };
+
+            ChipConstraints {
+                name: chip.name(),
+                preprocessed_width,
+                width,
+                log_quotient_degree: chip.log_quotient_degree(),
+                commit_scope: scope(chip.commit_scope()),
+                local_only: chip.local_only(),
+                nodes: graph.nodes,
+                constraints,
+                sends: interactions(chip.sends()),
+                receives: interactions(chip.receives()),
+            }
+        })
+        .collect();
+
+    CompressConstraints {
+        chips,
+        num_public_values: machine.num_pv_elts(),
+        contains_global_bus: machine.contains_global_bus(),
+    }
+}
+
+/// Writes the bincode encoding of [`compress_constraints`] to `path`.
+pub fn build_compress_constraints_to_file(path: impl AsRef<Path>) -> anyhow::Result<()> {
+    let file = File::create(path)?;
+    bincode::serialize_into(file, &compress_constraints())?;
+    Ok(())
+}
+
+/// Renders `crates/verifier/src/compressed/pinned.rs`, which pins the compress machine accepted by
+/// `sp1-verifier` to this SP1 version.
+///
+/// `compress_vk_digest` is the digest of the verifying key carried by a compressed proof, as
+/// returned by `vk.hash_babybear()`, `vk_root` is `SP1Prover::vk_root`, and `constraints` is the
+/// bincode encoding of [`compress_constraints`].
+pub fn pinned_constants(
+    compress_vk_digest: &[BabyBear; DIGEST_SIZE],
+    vk_root: &[BabyBear; DIGEST_SIZE],
+    constraints: &[u8],
+) -> String {
+    let bytes32 = |digest: &[BabyBear; DIGEST_SIZE]| {
+        format!("0x{:0>64}", babybears_to_bn254(digest).as_canonical_biguint().to_str_radix(16))
+    };
+    let constant = |doc: &str, name: &str, value: &str| {
+        format!("\n/// {doc}\npub const {name}: &str =\n    \"{value}\";\n")
+    };
+    [
+        "//! The compress machine accepted by `CompressedVerifier`.\n\
+         //!\n\
+         //! This file is generated by `cargo run --release --bin build_compress_constraints` in\n\
+         //! `sp1-prover`. Regenerate it together with the constraints for every SP1 version.\n"
+            .to_string(),
+        constant(
+            "The digest of the verifying key of the compress program, as returned by `vk.bytes32()`.",
+            "COMPRESS_VK_DIGEST",
+            &bytes32(compress_vk_digest),
+        ),
+        constant(
+            "The root of the merkle tree of the recursion verifying keys, packed like `vk.bytes32()`.",
+            "RECURSION_VK_ROOT",
+            &bytes32(vk_root),
+        ),
+        constant(
+            "The SHA-256 digest of the serialized compress constraints.",
+            "COMPRESS_CONSTRAINTS_DIGEST",
+            &format!("0x{:x}", Sha256::digest(constraints)),
+        ),
+    ]
+    .concat()
+}
+
+/// The nodes of the constraints of a chip, with shared subexpressions stored once.
+#[derive(Default)]
+struct ExprGraph {
+    nodes: Vec<AirExpr>,
+    /// The index of every node, to deduplicate structurally equal subexpressions.
+    index: HashMap<AirExpr, usize>,
+    /// The index of the already visited subexpressions, which are shared through `Rc`s.
+    visited: HashMap<*const SymbolicExpression<BabyBear>, usize>,
+}
+
+impl ExprGraph {
+    fn insert(&mut self, expr: &SymbolicExpression<BabyBear>) -> usize {
+        let node = match expr {
+            SymbolicExpression::Variable(v) => match v.entry {
+                Entry::Preprocessed { offset } => {
+                    AirExpr::Preprocessed { next: next_row(offset), column: v.index }
+                }
+                Entry::Main { offset } => AirExpr::Main { next: next_row(offset), column: v.index },
+                Entry::Public => AirExpr::Public(v.index),
+                Entry::Permutation { .. } | Entry::Challenge => {
+                    panic!("the permutation constraints are not part of the chip's air")
+                }
+            },
+            SymbolicExpression::IsFirstRow => AirExpr::IsFirstRow,
+            SymbolicExpression::IsLastRow => AirExpr::IsLastRow,
+            SymbolicExpression::IsTransition => AirExpr::IsTransition,
+            SymbolicExpression::Constant(c) => AirExpr::Constant(c.as_canonical_u32()),
+            SymbolicExpression::Add { x, y, .. } => AirExpr::Add(self.child(x), self.child(y)),
+            SymbolicExpression::Sub { x, y, .. } => AirExpr::Sub(self.child(x), self.child(y)),
+            SymbolicExpression::Neg { x, .. } => AirExpr::Neg(self.child(x)),
+            SymbolicExpression::Mul { x, y, .. } => AirExpr::Mul(self.child(x), self.child(y)),
+        };
+        if let Some(&index) = self.index.get(&node) {
+            return index;
+        }
+        self.nodes.push(node);
+        self.index.insert(node, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    fn child(&mut self, expr: &Rc<SymbolicExpression<BabyBear>>) -> usize {
+        if let Some(&index) = self.visited.get(&Rc::as_ptr(expr)) {
+            return index;
+        }
+        let index = self.insert(expr);
+        self.visited.insert(Rc::as_ptr(expr), index);
+        index
+    }
+}
+
+fn next_row(offset: usize) -> bool {
+    match offset {
+        0 => false,
+        1 => true,
+        _ => panic!("uni-stark only supports a window size of 2"),
+    }
+}
+
+const fn scope(scope: InteractionScope) -> Scope {
+    match scope {
+        InteractionScope::Global => Scope::Global,
+        InteractionScope::Local => Scope::Local,
+    }
+}
+
+/// Recovers the weights of a virtual column by evaluating it on unit rows.
+fn linear_column(
+    column: &VirtualPairCol<BabyBear>,
+    preprocessed_width: usize,
+    width: usize,
+) -> LinearColumn {
+    let zeros = |n: usize| vec![BabyBear::zero(); n];
+    let apply = |preprocessed: &[BabyBear], main: &[BabyBear]| {
+        column.apply::<BabyBear, BabyBear>(preprocessed, main)
+    };
+    let constant = apply(&zeros(preprocessed_width), &zeros(width));
+
+    let mut terms = Vec::new();
+    for i in 0..preprocessed_width {
+        let mut row = zeros(preprocessed_width);
+        row[i] = BabyBear::one();
+        let weight = apply(&row, &zeros(width)) - constant;
+        if !weight.is_zero() {
+            terms.push((Column::Preprocessed(i), weight.as_canonical_u32()));
+        }
+    }
+    for i in 0..width {
+        let mut row = zeros(width);
+        row[i] = BabyBear::one();
+        let weight = apply(&zeros(preprocessed_width), &row) - constant;
+        if !weight.is_zero() {
+            terms.push((Column::Main(i), weight.as_canonical_u32()));
+        }
+    }
+    LinearColumn { terms, constant: constant.as_canonical_u32() }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use sp1_core_executor::SP1Context;
+    use sp1_core_machine::{io::SP1Stdin, utils::setup_logger};
+    use sp1_stark::SP1ProverOpts;
+    use sp1_verifier::{CompressedError, CompressedVerifier, COMPRESS_CONSTRAINTS_BYTES};
+
+    use super::*;
+    use crate::{components::DefaultProverComponents, HashableKey, SP1Prover};
+
+    #[test]
+    #[serial]
+    fn test_verify_compressed_no_std() {
+        setup_logger();
+        let elf = include_bytes!("../../../tests/fibonacci/elf/riscv32im-succinct-zkvm-elf");
+        let prover = SP1Prover::<DefaultProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let (pk, vk) = prover.setup(elf);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&10usize);
+        let core_proof = prover.prove_core(&pk, &stdin, opts, SP1Context::default()).unwrap();
+        let public_values = core_proof.public_values.to_vec();
+        let compressed = prover.compress(&vk, core_proof, vec![], opts).unwrap();
+
+        let proof = bincode::serialize(&compressed).unwrap();
+        let constraints = bincode::serialize(&compress_constraints()).unwrap();
+        assert_eq!(
+            pinned_constants(&compressed.vk.hash_babybear(), &prover.vk_root, &constraints),
+            include_str!("../../verifier/src/compressed/pinned.rs"),
+            "the pinned compress machine is stale, regenerate it with `build_compress_constraints`"
+        );
+        assert!(
+            constraints == *COMPRESS_CONSTRAINTS_BYTES,
+            "the shipped compress constraints are stale, regenerate them with \
+             `build_compress_constraints`"
+        );
+        CompressedVerifier::verify(
+            &proof,
+            &public_values,
+            &vk.bytes32(),
+            &COMPRESS_CONSTRAINTS_BYTES,
+        )
+        .unwrap();
+
+        let mut tampered = public_values.clone();
+        tampered[0] ^= 1;
+        assert!(matches!(
+            CompressedVerifier::verify(
+                &proof,
+                &tampered,
+                &vk.bytes32(),
+                &COMPRESS_CONSTRAINTS_BYTES
+            ),
+            Err(CompressedError::InvalidPublicValues(_))
+        ));
+    }
+
+    #[test]
+    fn test_recursion_public_values_layout() {
+        use sp1_recursion_core::air as recursion;
+        use sp1_verifier::compressed::{ChallengerPublicValues, RecursionPublicValues};
+        use std::mem::{offset_of, size_of};
+
+        macro_rules! assert_same_layout {
+            ($ty:ident, $($field:ident),* $(,)?) => {
+                assert_eq!(size_of::<$ty<u8>>(), size_of::<recursion::$ty<u8>>());
+                $(
+                    assert_eq!(
+                        offset_of!($ty<u8>, $field),
+                        offset_of!(recursion::$ty<u8>, $field),
+                        concat!(stringify!($ty), "::", stringify!($field)),
+                    );
+                )*
+            };
+        }
+
+        assert_same_layout!(
+            ChallengerPublicValues,
+            sponge_state,
+            num_inputs,
+            input_buffer,
+            num_outputs,
+            output_buffer,
+        );
+        assert_same_layout!(
+            RecursionPublicValues,
+            committed_value_digest,
+            deferred_proofs_digest,
+            start_pc,
+            next_pc,
+            start_shard,
+            next_shard,
+            start_execution_shard,
+            next_execution_shard,
+            previous_init_addr_bits,
+            last_init_addr_bits,
+            previous_finalize_addr_bits,
+            last_finalize_addr_bits,
+            start_reconstruct_challenger,
+            end_reconstruct_challenger,
+            start_reconstruct_deferred_digest,
+            end_reconstruct_deferred_digest,
+            sp1_vk_digest,
+            vk_root,
+            leaf_challenger,
+            cumulative_sum,
+            is_complete,
+            contains_execution_shard,
+            exit_code,
+            digest,
+        );
+    }
+}
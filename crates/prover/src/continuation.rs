@@ -0,0 +1,368 @@
+//! Proving a long-running program in segments.
+//!
+//! A program that calls `syscall_suspend` halts with its public values committed, and can be
+//! resumed from a [SegmentCheckpoint] of its final memory. Each segment is proven as a program of
+//! its own, whose memory image is the checkpoint of the previous segment and whose start pc is the
+//! resume address, so the verifying key of a segment commits to the state it starts from.
+//!
+//! A suspended segment is proven with the memory root of its checkpoint observed into the
+//! transcript before any challenge is drawn. The verifier then checks the checkpoint against the
+//! records received by the segment's memory finalize chip, using the global permutation
+//! challenges of the proof, so the next segment provably starts from the final memory of the
+//! previous one.
+//!
+//! Since their transcript starts from the checkpoint, segment proofs are only accepted by
+//! [SP1Prover::verify_segments]. They are not accepted by [SP1Prover::verify] or the recursive
+//! verifier, so they cannot be compressed, wrapped or verified in another program.
+
+use std::borrow::Borrow;
+
+use p3_baby_bear::BabyBear;
+use p3_challenger::CanObserve;
+use p3_field::{AbstractField, Field, PrimeField32};
+use serde::{Deserialize, Serialize};
+use sp1_core_executor::{Executor, Program, SP1Context, SegmentCheckpoint, SegmentCheckpointError};
+use sp1_core_machine::{io::SP1Stdin, utils::SP1CoreProverError};
+use sp1_primitives::io::SP1PublicValues;
+use sp1_stark::{
+    air::PublicValues, Challenge, InteractionKind, MachineProof, MachineProver,
+    MachineVerificationError, SP1ProverOpts, ShardProof, StarkGenericConfig, Word,
+};
+use thiserror::Error;
+use tracing::instrument;
+
+use crate::{
+    components::SP1ProverComponents, CoreSC, HashableKey, SP1CoreProof, SP1CoreProofData,
+    SP1Prover, SP1VerifyingKey,
+};
+
+/// A proof of one segment of a program.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SP1SegmentProof {
+    /// The core proof of the segment.
+    pub proof: SP1CoreProof,
+    /// The verifying key of the segment's program.
+    pub vk: SP1VerifyingKey,
+    /// The checkpoint the next segment resumes from, or `None` if the program halted.
+    pub checkpoint: Option<SegmentCheckpoint>,
+}
+
+#[derive(Error, Debug)]
+pub enum SP1ContinuationError {
+    #[error("invalid program: {0}")]
+    Program(eyre::Report),
+    #[error(transparent)]
+    Prover(#[from] SP1CoreProverError),
+    #[error(transparent)]
+    Checkpoint(#[from] SegmentCheckpointError),
+    #[error("segment {0} halted without a checkpoint to resume from")]
+    MissingCheckpoint(usize),
+    #[error("segment {0} was not proven from the checkpoint of the previous segment")]
+    VerifyingKeyMismatch(usize),
+    #[error("segment {0} is invalid: {1}")]
+    InvalidProof(usize, MachineVerificationError<CoreSC>),
+    #[error("segment {0} does not commit to its public values")]
+    InvalidPublicValues(usize),
+    #[error("segment {0} did not suspend before its checkpoint")]
+    NotSuspended(usize),
+    #[error("segment {0} does not end in the state of its checkpoint")]
+    CheckpointMismatch(usize),
+}
+
+/// The challenger a segment ending in `checkpoint` is proven and verified with.
+fn segment_challenger(
+    config: &CoreSC,
+    checkpoint: Option<&SegmentCheckpoint>,
+) -> <CoreSC as StarkGenericConfig>::Challenger {
+    let mut challenger = config.challenger();
+    if let Some(checkpoint) = checkpoint {
+        for byte in checkpoint.memory_root() {
+            challenger.observe(BabyBear::from_canonical_u8(byte));
+        }
+    }
+    challenger
+}
+
+/// The global cumulative sum of the memory finalize chip over the shards of a segment.
+fn finalized_memory_sum(shard_proofs: &[ShardProof<CoreSC>]) -> Challenge<CoreSC> {
+    shard_proofs
+        .iter()
+        .filter_map(|proof| {
+            let index = *proof.chip_ordering.get("MemoryGlobalFinalize")?;
+            Some(proof.opened_values.chips[index].global_cumulative_sum)
+        })
+        .sum()
+}
+
+/// The global cumulative sum of a memory finalize chip receiving exactly the records of
+/// `checkpoint`, with the permutation challenges `[alpha, beta]`.
+fn checkpoint_memory_sum(
+    checkpoint: &SegmentCheckpoint,
+    [alpha, beta]: [Challenge<CoreSC>; 2],
+) -> Challenge<CoreSC> {
+    checkpoint
+        .memory
+        .iter()
+        .map(|(&addr, record)| {
+            let values = [record.shard, record.timestamp, addr]
+                .into_iter()
+                .chain(record.value.to_le_bytes().map(u32::from));
+            let mut betas = beta.powers();
+            let mut denominator = alpha
+                + betas.next().unwrap()
+                    * BabyBear::from_canonical_usize(InteractionKind::Memory as usize);
+            for (value, beta) in values.zip(betas) {
+                denominator += beta * BabyBear::from_canonical_u32(value);
+            }
+            -denominator.inverse()
+        })
+        .sum()
+}
+
+impl<C: SP1ProverComponents> SP1Prover<C> {
+    /// Get the program of a segment, starting from `checkpoint` or from the entrypoint of the ELF.
+    pub fn segment_program(
+        &self,
+        elf: &[u8],
+        checkpoint: Option<&SegmentCheckpoint>,
+    ) -> eyre::Result<Program> {
+        self.resume_program(Program::from(elf)?, checkpoint)
+    }
+
+    /// Get the program of a segment, starting from `checkpoint` or from the entrypoint of `base`.
+    fn resume_program(
+        &self,
+        base: Program,
+        checkpoint: Option<&SegmentCheckpoint>,
+    ) -> eyre::Result<Program> {
+        let mut program = match checkpoint {
+            Some(checkpoint) => checkpoint.program(&base),
+            None => base,
+        };
+        if let Some(core_shape_config) = &self.core_shape_config {
+            core_shape_config.fix_preprocessed_shape(&mut program)?;
+        }
+        Ok(program)
+    }
+
+    /// Prove a segment of a program, starting from `checkpoint` or from the entrypoint of the ELF.
+    ///
+    /// The segment runs until the program suspends or halts. Each segment reads its own `stdin`.
+    #[instrument(name = "prove_segment", level = "info", skip_all)]
+    pub fn prove_segment<'a>(
+        &'a self,
+        elf: &[u8],
+        checkpoint: Option<&SegmentCheckpoint>,
+        stdin: &SP1Stdin,
+        opts: SP1ProverOpts,
+        context: SP1Context<'a>,
+    ) -> Result<SP1SegmentProof, SP1ContinuationError> {
+        let base = Program::from(elf).map_err(SP1ContinuationError::Program)?;
+        self.prove_program_segment(base, checkpoint, stdin, opts, context)
+    }
+
+    fn prove_program_segment<'a>(
+        &'a self,
+        base: Program,
+        checkpoint: Option<&SegmentCheckpoint>,
+        stdin: &SP1Stdin,
+        opts: SP1ProverOpts,
+        mut context: SP1Context<'a>,
+    ) -> Result<SP1SegmentProof, SP1ContinuationError> {
+        context.subproof_verifier.replace(std::sync::Arc::new(self));
+        let program =
+            self.resume_program(base, checkpoint).map_err(SP1ContinuationError::Program)?;
+        let (pk, vk) = self.core_prover.setup(&program);
+
+        // The checkpoint of the next segment is taken from the final state of the prover's
+        // execution, and observed before anything else.
+        let mut next = Ok(None);
+        let challenger = |runtime: &Executor| {
+            next = match SegmentCheckpoint::from_executor(runtime) {
+                Ok(checkpoint) => Ok(Some(checkpoint)),
+                Err(SegmentCheckpointError::NotSuspended(_)) => Ok(None),
+                Err(e) => Err(e),
+            };
+            let checkpoint = next.as_ref().ok().and_then(Option::as_ref);
+            segment_challenger(self.core_prover.config(), checkpoint)
+        };
+        let (proof, public_values_stream, cycles) =
+            sp1_core_machine::utils::prove_with_challenger::<_, C::CoreProver>(
+                &self.core_prover,
+                &pk,
+                program,
+                stdin,
+                opts.core_opts,
+                context,
+                self.core_shape_config.as_ref(),
+                challenger,
+            )?;
+        let next = next?;
+        let proof = SP1CoreProof {
+            proof: SP1CoreProofData(proof.shard_proofs),
+            stdin: stdin.clone(),
+            public_values: SP1PublicValues::from(&public_values_stream),
+            cycles,
+        };
+        Ok(SP1SegmentProof { proof, vk: SP1VerifyingKey { vk }, checkpoint: next })
+    }
+
+    /// Verify a chain of segments of a program, the first starting from the entrypoint of the ELF.
+    ///
+    /// Every segment is checked against the verifying key of the program resuming from the
+    /// checkpoint of the previous segment, and every checkpoint against the final memory of the
+    /// segment it was taken from.
+    pub fn verify_segments(
+        &self,
+        elf: &[u8],
+        segments: &[SP1SegmentProof],
+    ) -> Result<(), SP1ContinuationError> {
+        let base = Program::from(elf).map_err(SP1ContinuationError::Program)?;
+        self.verify_program_segments(&base, segments)
+    }
+
+    fn verify_program_segments(
+        &self,
+        base: &Program,
+        segments: &[SP1SegmentProof],
+    ) -> Result<(), SP1ContinuationError> {
+        let mut checkpoint = None;
+        for (i, segment) in segments.iter().enumerate() {
+            if i > 0 && checkpoint.is_none() {
+                return Err(SP1ContinuationError::MissingCheckpoint(i - 1));
+            }
+            let program = self
+                .resume_program(base.clone(), checkpoint)
+                .map_err(SP1ContinuationError::Program)?;
+            let (_, vk) = self.core_prover.setup(&program);
+            let vk = SP1VerifyingKey { vk };
+            if vk.hash_babybear() != segment.vk.hash_babybear() {
+                return Err(SP1ContinuationError::VerifyingKeyMismatch(i));
+            }
+            let next = segment.checkpoint.as_ref();
+            let challenger = segment_challenger(self.core_prover.config(), next);
+            self.verify_with_challenger(&segment.proof.proof, &vk, challenger.clone())
+                .map_err(|e| SP1ContinuationError::InvalidProof(i, e))?;
+
+            let shard_proofs = &segment.proof.proof.0;
+            let public_values: &PublicValues<Word<BabyBear>, BabyBear> =
+                shard_proofs.last().unwrap().public_values.as_slice().borrow();
+            let committed_value_digest = public_values
+                .committed_value_digest
+                .iter()
+                .flat_map(|word| word.0.iter().map(|byte| byte.as_canonical_u32() as u8));
+            if !committed_value_digest.eq(segment.proof.public_values.hash()) {
+                return Err(SP1ContinuationError::InvalidPublicValues(i));
+            }
+
+            if let Some(next) = next {
+                // `syscall_suspend` halts with exit code zero.
+                if public_values.next_pc != BabyBear::zero()
+                    || public_values.exit_code != BabyBear::zero()
+                {
+                    return Err(SP1ContinuationError::NotSuspended(i));
+                }
+                // The checkpoint was observed before the challenges were drawn, so matching the
+                // fingerprints of the finalized records binds it to the final memory.
+                let machine_proof = MachineProof { shard_proofs: shard_proofs.clone() };
+                let challenges = self.core_prover.machine().global_permutation_challenges(
+                    &vk.vk,
+                    &machine_proof,
+                    &mut challenger.clone(),
+                );
+                if finalized_memory_sum(shard_proofs) != checkpoint_memory_sum(next, challenges) {
+                    return Err(SP1ContinuationError::CheckpointMismatch(i));
+                }
+            }
+            checkpoint = next;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use sp1_core_executor::{syscalls::SyscallCode, Instruction, Opcode};
+    use sp1_core_machine::utils::setup_logger;
+
+    use super::*;
+    use crate::components::DefaultProverComponents;
+
+    const PC_BASE: u32 = 0x0020_0800;
+
+    /// A program that sets `x29`, suspends, and once resumed sets `x31` from `x29` and halts. Each
+    /// segment commits to empty public values.
+    fn suspending_program() -> Program {
+        let digest = SP1PublicValues::new().hash();
+        let addi = |rd, rs1, imm| Instruction::new(Opcode::ADD, rd, rs1, imm, false, true);
+        let ecall = |instructions: &mut Vec<Instruction>, code: SyscallCode, a0, a1| {
+            instructions.push(addi(5, 0, code.syscall_id()));
+            instructions.push(addi(10, 0, a0));
+            instructions.push(addi(11, 0, a1));
+            instructions.push(Instruction::new(Opcode::ECALL, 5, 10, 11, false, false));
+        };
+        let commit = |instructions: &mut Vec<Instruction>| {
+            for (i, word) in digest.chunks_exact(4).enumerate() {
+                let word = u32::from_le_bytes(word.try_into().unwrap());
+                ecall(instructions, SyscallCode::COMMIT, i as u32, word);
+            }
+        };
+
+        let mut instructions = vec![addi(29, 0, 5)];
+        commit(&mut instructions);
+        let resume = PC_BASE + 4 * (instructions.len() as u32 + 4);
+        ecall(&mut instructions, SyscallCode::HALT, 0, resume);
+        instructions.push(addi(31, 29, 37));
+        commit(&mut instructions);
+        ecall(&mut instructions, SyscallCode::HALT, 0, 0);
+        Program::new(instructions, PC_BASE, PC_BASE)
+    }
+
+    #[test]
+    #[serial]
+    fn test_prove_segments() {
+        setup_logger();
+        let prover = SP1Prover::<DefaultProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let base = suspending_program();
+        let stdin = SP1Stdin::new();
+
+        // Prove the segment before and after the suspension.
+        let first = prover
+            .prove_program_segment(base.clone(), None, &stdin, opts, SP1Context::default())
+            .unwrap();
+        let checkpoint = first.checkpoint.clone().expect("the program suspends");
+        assert_eq!(checkpoint.memory[&29].value, 5);
+        let second = prover
+            .prove_program_segment(
+                base.clone(),
+                Some(&checkpoint),
+                &stdin,
+                opts,
+                SP1Context::default(),
+            )
+            .unwrap();
+        assert!(second.checkpoint.is_none());
+        prover.verify_program_segments(&base, &[first.clone(), second.clone()]).unwrap();
+
+        // A tampered checkpoint is rejected.
+        let mut tampered = first.clone();
+        tampered.checkpoint.as_mut().unwrap().memory.get_mut(&29).unwrap().value = 6;
+        assert!(matches!(
+            prover.verify_program_segments(&base, &[tampered, second.clone()]),
+            Err(SP1ContinuationError::InvalidProof(0, _)
+                | SP1ContinuationError::CheckpointMismatch(0))
+        ));
+
+        // Segments must be in order, and start from the entrypoint.
+        assert!(matches!(
+            prover.verify_program_segments(&base, &[second.clone(), first]),
+            Err(SP1ContinuationError::VerifyingKeyMismatch(0))
+        ));
+        assert!(matches!(
+            prover.verify_program_segments(&base, &[second]),
+            Err(SP1ContinuationError::VerifyingKeyMismatch(0))
+        ));
+    }
+}
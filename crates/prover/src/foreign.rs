@@ -0,0 +1,289 @@
+//! Recursive verification of proofs of foreign machines.
+//!
+//! A foreign machine is any [MachineAir] over BabyBear proven with the inner Poseidon2 config. Its
+//! proofs are verified by a recursion program proven with the compress machine, which turns them
+//! into complete compressed proofs. These can be passed to SP1 programs as deferred proofs, and
+//! checked in the guest with `verify_sp1_proof` against [foreign_vk_digest] and
+//! [foreign_public_values_digest].
+//!
+//! The foreign program is not one of the recursion programs of the release vk map, so with vk
+//! verification enabled its verifying key must be allowed with
+//! [SP1Prover::allow_foreign_program_vks] before proving.
+//!
+//! Allowing keys recommits the vk root, so this path only goes as far as compressed proofs, which
+//! are verified against the vk root of the prover. The wrap program, and hence the Plonk and
+//! Groth16 circuit artifacts, are built for the vk root of the release vk map: proofs depending on
+//! a foreign proof can't be wrapped into Plonk or Groth16 proofs verified by the release
+//! artifacts.
+
+use std::{borrow::Borrow, collections::BTreeSet, sync::Arc};
+
+use p3_air::Air;
+use p3_baby_bear::BabyBear;
+use p3_field::AbstractField;
+use sp1_core_machine::reduce::SP1ReduceProof;
+use sp1_recursion_circuit::{
+    constraints::RecursiveVerifierConstraintFolder,
+    machine::{SP1ForeignVerifier, SP1ForeignWitnessValues},
+    merkle_tree::MerkleTree,
+    witness::Witnessable,
+};
+use sp1_recursion_compiler::{circuit::AsmCompiler, config::InnerConfig, ir::Builder};
+use sp1_recursion_core::{
+    air::RecursionPublicValues, RecursionProgram, Runtime as RecursionRuntime, DIGEST_SIZE,
+};
+use sp1_stark::{
+    air::MachineAir, Challenge, MachineProof, MachineProver, MachineVerificationError,
+    SP1ProverOpts, StarkGenericConfig, StarkMachine, StarkVerifyingKey, Val,
+};
+use tracing::instrument;
+
+pub use sp1_recursion_circuit::machine::{
+    foreign_public_values_digest, FOREIGN_VK_DOMAIN_SEPARATOR,
+};
+
+use crate::{
+    components::SP1ProverComponents, utils::assert_recursion_public_values_valid, CoreSC,
+    HashableKey, InnerSC, SP1Prover, SP1RecursionProverError,
+};
+
+/// The vk digest a compressed proof of the foreign machine with the given key commits to.
+pub fn foreign_vk_digest(vk: &StarkVerifyingKey<InnerSC>) -> [BabyBear; DIGEST_SIZE] {
+    sp1_recursion_circuit::machine::foreign_vk_digest(vk.hash_babybear())
+}
+
+impl<C: SP1ProverComponents> SP1Prover<C> {
+    /// The recursion program verifying a proof of the given foreign machine.
+    pub fn foreign_program<A>(
+        &self,
+        machine: &StarkMachine<InnerSC, A>,
+        input: &SP1ForeignWitnessValues<InnerSC>,
+    ) -> Arc<RecursionProgram<BabyBear>>
+    where
+        A: MachineAir<BabyBear> + for<'a> Air<RecursiveVerifierConstraintFolder<'a, InnerConfig>>,
+    {
+        // Get the operations.
+        let operations_span =
+            tracing::debug_span!("get operations for the foreign program").entered();
+        let mut builder = Builder::<InnerConfig>::default();
+        let input = input.read(&mut builder);
+        SP1ForeignVerifier::verify(&mut builder, machine, input);
+        let operations = builder.into_operations();
+        operations_span.exit();
+
+        // Compile the program.
+        let compiler_span = tracing::debug_span!("compile foreign program").entered();
+        let mut compiler = AsmCompiler::<InnerConfig>::default();
+        let mut program = compiler.compile(operations);
+        if let Some(recursion_shape_config) = &self.recursion_shape_config {
+            recursion_shape_config.fix_shape(&mut program);
+        }
+        let program = Arc::new(program);
+        compiler_span.exit();
+        program
+    }
+
+    /// The verifying key of the recursion program verifying proofs of the given foreign machine
+    /// with the shapes of the given proof.
+    pub fn foreign_program_vk<A>(
+        &self,
+        machine: &StarkMachine<InnerSC, A>,
+        vk: &StarkVerifyingKey<InnerSC>,
+        proof: &MachineProof<InnerSC>,
+    ) -> StarkVerifyingKey<InnerSC>
+    where
+        A: MachineAir<BabyBear> + for<'a> Air<RecursiveVerifierConstraintFolder<'a, InnerConfig>>,
+    {
+        let program = self.foreign_program(machine, &self.foreign_input(vk, proof));
+        let (_, foreign_vk) = self.compress_prover.setup(&program);
+        foreign_vk
+    }
+
+    /// Add the verifying keys of foreign programs to the allowed set, and recommit the vk root.
+    ///
+    /// Proofs made before the call commit to the previous vk root, so the keys must be allowed
+    /// before calling [SP1Prover::prove_foreign]. The vk root then differs from the one of the
+    /// release vk map, and the verifier of any proof depending on a foreign proof must allow the
+    /// same keys. Such proofs can't be wrapped for the release Plonk and Groth16 circuits.
+    pub fn allow_foreign_program_vks<'a>(
+        &mut self,
+        vks: impl IntoIterator<Item = &'a StarkVerifyingKey<InnerSC>>,
+    ) {
+        let mut vk_digests = self.allowed_vk_map.keys().copied().collect::<BTreeSet<_>>();
+        vk_digests.extend(vks.into_iter().map(|vk| vk.hash_babybear()));
+        self.allowed_vk_map =
+            vk_digests.iter().enumerate().map(|(i, vk_digest)| (*vk_digest, i)).collect();
+        let (root, merkle_tree) = MerkleTree::commit(vk_digests.into_iter().collect());
+        self.vk_root = root;
+        self.vk_merkle_tree = merkle_tree;
+    }
+
+    /// Verify a proof of a foreign machine in a recursion program, and prove it as a complete
+    /// compressed proof.
+    ///
+    /// The foreign program depends on the foreign machine and on the shapes of its shard proofs,
+    /// so with vk verification enabled, its verifying key, given by [SP1Prover::foreign_program_vk],
+    /// must be allowed with [SP1Prover::allow_foreign_program_vks] for the proof to be accepted by
+    /// [SP1Prover::verify_foreign] and as a deferred proof.
+    #[instrument(name = "prove_foreign", level = "info", skip_all)]
+    pub fn prove_foreign<A>(
+        &self,
+        machine: &StarkMachine<InnerSC, A>,
+        vk: &StarkVerifyingKey<InnerSC>,
+        proof: &MachineProof<InnerSC>,
+        opts: SP1ProverOpts,
+    ) -> Result<SP1ReduceProof<InnerSC>, SP1RecursionProverError>
+    where
+        A: MachineAir<BabyBear> + for<'a> Air<RecursiveVerifierConstraintFolder<'a, InnerConfig>>,
+    {
+        let input = self.foreign_input(vk, proof);
+        let program = self.foreign_program(machine, &input);
+
+        // Run the foreign program.
+        let mut runtime = RecursionRuntime::<Val<InnerSC>, Challenge<InnerSC>, _>::new(
+            program.clone(),
+            self.compress_prover.config().perm.clone(),
+        );
+        let mut witness_stream = Vec::new();
+        Witnessable::<InnerConfig>::write(&input, &mut witness_stream);
+        runtime.witness_stream = witness_stream.into();
+        runtime.run().map_err(|e| SP1RecursionProverError::RuntimeError(e.to_string()))?;
+        runtime.print_stats();
+
+        // Prove the foreign program.
+        let (foreign_pk, foreign_vk) =
+            tracing::debug_span!("setup foreign").in_scope(|| self.compress_prover.setup(&program));
+        let mut challenger = self.compress_prover.config().challenger();
+        let mut foreign_proof = self
+            .compress_prover
+            .prove(&foreign_pk, vec![runtime.record], &mut challenger, opts.recursion_opts)
+            .unwrap();
+
+        Ok(SP1ReduceProof { vk: foreign_vk, proof: foreign_proof.shard_proofs.pop().unwrap() })
+    }
+
+    fn foreign_input(
+        &self,
+        vk: &StarkVerifyingKey<InnerSC>,
+        proof: &MachineProof<InnerSC>,
+    ) -> SP1ForeignWitnessValues<InnerSC> {
+        SP1ForeignWitnessValues {
+            vk: vk.clone(),
+            shard_proofs: proof.shard_proofs.clone(),
+            vk_root: self.vk_root,
+        }
+    }
+
+    /// Verify a compressed proof made by [SP1Prover::prove_foreign] for the foreign machine with
+    /// the given verifying key.
+    pub fn verify_foreign(
+        &self,
+        proof: &SP1ReduceProof<InnerSC>,
+        vk: &StarkVerifyingKey<InnerSC>,
+    ) -> Result<(), MachineVerificationError<CoreSC>> {
+        let SP1ReduceProof { vk: foreign_vk, proof } = proof;
+        let mut challenger = self.compress_prover.config().challenger();
+        let machine_proof = MachineProof { shard_proofs: vec![proof.clone()] };
+        self.compress_prover.machine().verify(foreign_vk, &machine_proof, &mut challenger)?;
+
+        // Validate public values
+        let public_values: &RecursionPublicValues<_> = proof.public_values.as_slice().borrow();
+        assert_recursion_public_values_valid(
+            self.compress_prover.machine().config(),
+            public_values,
+        );
+
+        if self.vk_verification && !self.allowed_vk_map.contains_key(&foreign_vk.hash_babybear()) {
+            return Err(MachineVerificationError::InvalidVerificationKey);
+        }
+
+        if public_values.is_complete != BabyBear::one() {
+            return Err(MachineVerificationError::InvalidPublicValues("is_complete is not 1"));
+        }
+
+        // Verify that the proof is for the foreign vkey we are expecting.
+        if public_values.sp1_vk_digest != foreign_vk_digest(vk) {
+            return Err(MachineVerificationError::InvalidPublicValues("foreign vk hash mismatch"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::PrimeField32;
+    use serial_test::serial;
+    use sp1_core_machine::utils::{run_test_machine, setup_logger};
+    use sp1_recursion_core::machine::RecursionAir;
+
+    use super::*;
+
+    #[test]
+    #[serial]
+    fn test_prove_foreign() {
+        setup_logger();
+
+        // Use a small recursion machine as the foreign machine.
+        let program: RecursionProgram<BabyBear> = "
+            .total_memory 4
+            mem.write @0:1 = (3, 0, 0, 0)
+            mem.write @1:1 = (4, 0, 0, 0)
+            addf @2:1 = @0, @1
+            mem.read @2:1 = (7, 0, 0, 0)
+        "
+        .parse()
+        .unwrap();
+        let program = Arc::new(program);
+        let machine = RecursionAir::<BabyBear, 3>::machine_wide_with_all_chips(InnerSC::default());
+        let mut runtime = RecursionRuntime::<Val<InnerSC>, Challenge<InnerSC>, _>::new(
+            program.clone(),
+            machine.config().perm.clone(),
+        );
+        runtime.run().unwrap();
+        let (pk, vk) = machine.setup(&program);
+        let proof = run_test_machine(
+            vec![runtime.record],
+            RecursionAir::<BabyBear, 3>::machine_wide_with_all_chips(InnerSC::default()),
+            pk,
+            vk.clone(),
+        )
+        .expect("foreign proof should verify");
+
+        let mut prover = SP1Prover::<crate::components::DefaultProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+
+        // The foreign program is only accepted once its vk is allowed.
+        let foreign_program_vk = prover.foreign_program_vk(&machine, &vk, &proof);
+        prover.vk_verification = true;
+        let foreign_proof = prover.prove_foreign(&machine, &vk, &proof, opts).unwrap();
+        assert_eq!(foreign_proof.vk.hash_babybear(), foreign_program_vk.hash_babybear());
+        assert!(matches!(
+            prover.verify_foreign(&foreign_proof, &vk),
+            Err(MachineVerificationError::InvalidVerificationKey)
+        ));
+
+        let vk_root = prover.vk_root;
+        prover.allow_foreign_program_vks([&foreign_program_vk]);
+        assert_ne!(prover.vk_root, vk_root);
+        let foreign_proof = prover.prove_foreign(&machine, &vk, &proof, opts).unwrap();
+        prover.verify_foreign(&foreign_proof, &vk).unwrap();
+
+        let public_values: &RecursionPublicValues<_> =
+            foreign_proof.proof.public_values.as_slice().borrow();
+        let committed_value_digest = public_values
+            .committed_value_digest
+            .iter()
+            .flat_map(|word| word.0.map(|byte| byte.as_canonical_u32() as u8))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            committed_value_digest,
+            foreign_public_values_digest(machine.num_pv_elts(), &proof.shard_proofs)
+        );
+
+        // The proof is not accepted for another foreign vk.
+        let mut other_vk = vk.clone();
+        other_vk.pc_start = BabyBear::one();
+        assert!(prover.verify_foreign(&foreign_proof, &other_vk).is_err());
+    }
+}
@@ -13,7 +13,11 @@
 
 pub mod build;
 pub mod components;
+pub mod compress_constraints;
+pub mod continuation;
+pub mod foreign;
 pub mod shapes;
+pub mod shrink;
 pub mod types;
 pub mod utils;
 pub mod verify;
@@ -72,9 +76,10 @@ pub use sp1_recursion_gnark_ffi::proof::{Groth16Bn254Proof, PlonkBn254Proof};
 use sp1_recursion_gnark_ffi::{groth16_bn254::Groth16Bn254Prover, plonk_bn254::PlonkBn254Prover};
 use sp1_stark::{air::InteractionScope, MachineProvingKey, ProofShape};
 use sp1_stark::{
-    air::PublicValues, baby_bear_poseidon2::BabyBearPoseidon2, Challenge, Challenger,
-    MachineProver, SP1CoreOpts, SP1ProverOpts, ShardProof, StarkGenericConfig, StarkVerifyingKey,
-    Val, Word, DIGEST_SIZE,
+    air::PublicValues,
+    baby_bear_poseidon2::{compressed_fri_config, BabyBearPoseidon2},
+    Challenge, Challenger, MachineProver, ProverMonitor, ProvingStage, SP1CoreOpts, SP1ProverOpts,
+    ShardProof, StarkGenericConfig, StarkVerifyingKey, Val, Word, DIGEST_SIZE,
 };
 use tracing::instrument;
 
@@ -125,6 +130,9 @@ pub struct SP1Prover<C: SP1ProverComponents = DefaultProverComponents> {
     /// The machine used for proving the shrink step.
     pub shrink_prover: C::ShrinkProver,
 
+    /// The machine used for shrinking compressed proofs with BN254 Poseidon2 commitments.
+    pub shrink_bn254_prover: C::ShrinkBn254Prover,
+
     /// The machine used for proving the wrapping step.
     pub wrap_prover: C::WrapProver,
 
@@ -151,6 +159,10 @@ pub struct SP1Prover<C: SP1ProverComponents = DefaultProverComponents> {
 
     pub wrap_vk: OnceLock<StarkVerifyingKey<OuterSC>>,
 
+    /// The serialized verifying keys of the shrink bn254 programs, one for each shape of
+    /// compressed proof, computed on first use.
+    pub shrink_bn254_vks: OnceLock<Vec<Vec<u8>>>,
+
     pub vk_verification: bool,
 }
 
@@ -174,6 +186,9 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         let shrink_machine = ShrinkAir::shrink_machine(InnerSC::compressed());
         let shrink_prover = C::ShrinkProver::new(shrink_machine);
 
+        let shrink_bn254_machine = ShrinkAir::shrink_machine(OuterSC::compressed());
+        let shrink_bn254_prover = C::ShrinkBn254Prover::new(shrink_bn254_machine);
+
         let wrap_machine = WrapAir::wrap_machine(OuterSC::default());
         let wrap_prover = C::WrapProver::new(wrap_machine);
 
@@ -221,6 +236,7 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             core_prover,
             compress_prover,
             shrink_prover,
+            shrink_bn254_prover,
             wrap_prover,
             recursion_programs: Mutex::new(LruCache::new(core_cache_size)),
             recursion_cache_misses: AtomicUsize::new(0),
@@ -234,6 +250,7 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             vk_verification,
             wrap_program: OnceLock::new(),
             wrap_vk: OnceLock::new(),
+            shrink_bn254_vks: OnceLock::new(),
         }
     }
 
@@ -390,9 +407,13 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             .clone()
     }
 
+    /// The shrink program, with the public values digest of the given kind:
+    /// [PublicValuesOutputDigest::Reduce] for the proofs passed to the wrap program, and
+    /// [PublicValuesOutputDigest::Root] for the proofs of [SP1Prover::shrink_bn254].
     pub fn shrink_program(
         &self,
         input: &SP1CompressWithVKeyWitnessValues<InnerSC>,
+        kind: PublicValuesOutputDigest,
     ) -> Arc<RecursionProgram<BabyBear>> {
         // Get the operations.
         let builder_span = tracing::debug_span!("build shrink program").entered();
@@ -404,7 +425,7 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             self.compress_prover.machine(),
             input,
             self.vk_verification,
-            PublicValuesOutputDigest::Reduce,
+            kind,
         );
         let operations = builder.into_operations();
         builder_span.exit();
@@ -619,7 +640,6 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
     }
 
     /// Reduce shards proofs to a single shard proof using the recursion prover.
-    #[instrument(name = "compress", level = "info", skip_all)]
     pub fn compress(
         &self,
         vk: &SP1VerifyingKey,
@@ -627,6 +647,26 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         deferred_proofs: Vec<SP1ReduceProof<InnerSC>>,
         opts: SP1ProverOpts,
     ) -> Result<SP1ReduceProof<InnerSC>, SP1RecursionProverError> {
+        self.compress_with_monitor(vk, proof, deferred_proofs, opts, &ProverMonitor::default())
+    }
+
+    /// Reduce shards proofs to a single shard proof using the recursion prover.
+    ///
+    /// The progress of the proofs in the tree is reported to `monitor`. Once it is cancelled, the
+    /// proofs already started are finished and no new ones are started.
+    #[instrument(name = "compress", level = "info", skip_all)]
+    pub fn compress_with_monitor(
+        &self,
+        vk: &SP1VerifyingKey,
+        proof: SP1CoreProof,
+        deferred_proofs: Vec<SP1ReduceProof<InnerSC>>,
+        opts: SP1ProverOpts,
+        monitor: &ProverMonitor,
+    ) -> Result<SP1ReduceProof<InnerSC>, SP1RecursionProverError> {
+        if monitor.is_cancelled() {
+            return Err(SP1RecursionProverError::Cancelled);
+        }
+
         // The batch size for reducing two layers of recursion.
         let batch_size = REDUCE_BATCH_SIZE;
         // The batch size for reducing the first layer of recursion.
@@ -660,9 +700,18 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             expected_height += 1;
         }
 
+        // Count the proofs in the tree, each layer halving the number of proofs.
+        let mut num_proofs = num_first_layer_inputs;
+        let mut num_layer_proofs = num_first_layer_inputs;
+        while num_layer_proofs > 1 {
+            num_layer_proofs = num_layer_proofs.div_ceil(batch_size);
+            num_proofs += num_layer_proofs;
+        }
+        let progress = &monitor.stage(ProvingStage::Compress, Some(num_proofs));
+
         // Generate the proofs.
         let span = tracing::Span::current().clone();
-        let (vk, proof) = thread::scope(|s| {
+        let vk_and_proof = thread::scope(|s| {
             let _span = span.enter();
 
             // Spawn a worker that sends the first layer inputs to a bounded channel.
@@ -689,9 +738,11 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                 sync_channel::<(
                     usize,
                     usize,
-                    Arc<RecursionProgram<BabyBear>>,
-                    ExecutionRecord<BabyBear>,
-                    Vec<(String, RowMajorMatrix<BabyBear>)>,
+                    Option<(
+                        Arc<RecursionProgram<BabyBear>>,
+                        ExecutionRecord<BabyBear>,
+                        Vec<(String, RowMajorMatrix<BabyBear>)>,
+                    )>,
                 )>(opts.recursion_opts.records_and_traces_channel_capacity);
             let record_and_trace_tx = Arc::new(Mutex::new(record_and_trace_tx));
             let record_and_trace_rx = Arc::new(Mutex::new(record_and_trace_rx));
@@ -706,6 +757,19 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                     loop {
                         let received = { input_rx.lock().unwrap().recv() };
                         if let Ok((index, height, input)) = received {
+                            // Once proving is cancelled, pass an empty item down the pipeline to
+                            // keep the turns in order.
+                            if monitor.is_cancelled() {
+                                record_and_trace_sync.wait_for_turn(index);
+                                record_and_trace_tx
+                                    .lock()
+                                    .unwrap()
+                                    .send((index, height, None))
+                                    .unwrap();
+                                record_and_trace_sync.advance_turn();
+                                continue;
+                            }
+
                             // Get the program and witness stream.
                             let (program, witness_stream) = tracing::debug_span!(
                                 "get program and witness stream"
@@ -776,7 +840,7 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                             record_and_trace_tx
                                 .lock()
                                 .unwrap()
-                                .send((index, height, program, record, traces))
+                                .send((index, height, Some((program, record, traces))))
                                 .unwrap();
 
                             // Advance the turn.
@@ -790,10 +854,11 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
 
             // Spawn workers who generate the compress proofs.
             let proofs_sync = Arc::new(TurnBasedSync::new());
-            let (proofs_tx, proofs_rx) =
-                sync_channel::<(usize, usize, StarkVerifyingKey<InnerSC>, ShardProof<InnerSC>)>(
-                    num_first_layer_inputs * 2,
-                );
+            let (proofs_tx, proofs_rx) = sync_channel::<(
+                usize,
+                usize,
+                Option<(StarkVerifyingKey<InnerSC>, ShardProof<InnerSC>)>,
+            )>(num_first_layer_inputs * 2);
             let proofs_tx = Arc::new(Mutex::new(proofs_tx));
             let proofs_rx = Arc::new(Mutex::new(proofs_rx));
             let mut prover_handles = Vec::new();
@@ -806,7 +871,15 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                     let _span = span.enter();
                     loop {
                         let received = { record_and_trace_rx.lock().unwrap().recv() };
-                        if let Ok((index, height, program, record, traces)) = received {
+                        if let Ok((index, height, item)) = received {
+                            let Some((program, record, traces)) =
+                                item.filter(|_| !monitor.is_cancelled())
+                            else {
+                                prover_sync.wait_for_turn(index);
+                                proofs_tx.lock().unwrap().send((index, height, None)).unwrap();
+                                prover_sync.advance_turn();
+                                continue;
+                            };
                             tracing::debug_span!("batch").in_scope(|| {
                                 // Get the keys.
                                 let (pk, vk) = tracing::debug_span!("Setup compress program")
@@ -851,6 +924,7 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                                         )
                                         .unwrap()
                                 });
+                                progress.advance(1);
 
                                 // Verify the proof.
                                 #[cfg(feature = "debug")]
@@ -869,7 +943,11 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                                 prover_sync.wait_for_turn(index);
 
                                 // Send the proof.
-                                proofs_tx.lock().unwrap().send((index, height, vk, proof)).unwrap();
+                                proofs_tx
+                                    .lock()
+                                    .unwrap()
+                                    .send((index, height, Some((vk, proof))))
+                                    .unwrap();
 
                                 // Advance the turn.
                                 prover_sync.advance_turn();
@@ -898,7 +976,11 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                     )> = Vec::new();
                     loop {
                         let received = { proofs_rx.lock().unwrap().recv() };
-                        if let Ok((index, height, vk, proof)) = received {
+                        if let Ok((index, height, vk_and_proof)) = received {
+                            // Stop generating inputs once proving is cancelled.
+                            let Some((vk, proof)) = vk_and_proof else {
+                                break false;
+                            };
                             batch.push((index, height, vk, proof));
 
                             // If we haven't reached the batch size, continue.
@@ -941,7 +1023,7 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
 
                             // If we're at the root of the tree, stop generating inputs.
                             if is_complete {
-                                break;
+                                break true;
                             }
 
                             // If we were at the last input of a layer, we keep everything but the
@@ -952,7 +1034,7 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
                                 batch = Vec::new();
                             }
                         } else {
-                            break;
+                            break false;
                         }
                     }
                 })
@@ -965,22 +1047,39 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             for handle in prover_handles {
                 handle.join().unwrap();
             }
-            handle.join().unwrap();
+            let is_complete = handle.join().unwrap();
 
-            let (_, _, vk, proof) = proofs_rx.lock().unwrap().recv().unwrap();
-            (vk, proof)
+            // The root proof is only proven if the inputs were generated up to the root.
+            let received = proofs_rx.lock().unwrap().recv();
+            received.ok().and_then(|(_, _, vk_and_proof)| vk_and_proof).filter(|_| is_complete)
         });
 
+        let (vk, proof) = vk_and_proof.ok_or(SP1RecursionProverError::Cancelled)?;
         Ok(SP1ReduceProof { vk, proof })
     }
 
     /// Wrap a reduce proof into a STARK proven over a SNARK-friendly field.
-    #[instrument(name = "shrink", level = "info", skip_all)]
     pub fn shrink(
         &self,
         reduced_proof: SP1ReduceProof<InnerSC>,
         opts: SP1ProverOpts,
     ) -> Result<SP1ReduceProof<InnerSC>, SP1RecursionProverError> {
+        self.shrink_with_monitor(reduced_proof, opts, &ProverMonitor::default())
+    }
+
+    /// Like [Self::shrink], reporting progress to `monitor` and stopping once it is cancelled.
+    #[instrument(name = "shrink", level = "info", skip_all)]
+    pub fn shrink_with_monitor(
+        &self,
+        reduced_proof: SP1ReduceProof<InnerSC>,
+        opts: SP1ProverOpts,
+        monitor: &ProverMonitor,
+    ) -> Result<SP1ReduceProof<InnerSC>, SP1RecursionProverError> {
+        if monitor.is_cancelled() {
+            return Err(SP1RecursionProverError::Cancelled);
+        }
+        let progress = monitor.stage(ProvingStage::Shrink, Some(1));
+
         // Make the compress proof.
         let SP1ReduceProof { vk: compressed_vk, proof: compressed_proof } = reduced_proof;
         let input = SP1CompressWitnessValues {
@@ -990,7 +1089,7 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
 
         let input_with_merkle = self.make_merkle_proofs(input);
 
-        let program = self.shrink_program(&input_with_merkle);
+        let program = self.shrink_program(&input_with_merkle, PublicValuesOutputDigest::Reduce);
 
         // Run the compress program.
         let mut runtime = RecursionRuntime::<Val<InnerSC>, Challenge<InnerSC>, _>::new(
@@ -1008,6 +1107,9 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         runtime.print_stats();
         tracing::debug!("Shrink program executed successfully");
 
+        if monitor.is_cancelled() {
+            return Err(SP1RecursionProverError::Cancelled);
+        }
         let (shrink_pk, shrink_vk) =
             tracing::debug_span!("setup shrink").in_scope(|| self.shrink_prover.setup(&program));
 
@@ -1017,17 +1119,40 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             .shrink_prover
             .prove(&shrink_pk, vec![runtime.record], &mut compress_challenger, opts.recursion_opts)
             .unwrap();
+        progress.advance(1);
 
         Ok(SP1ReduceProof { vk: shrink_vk, proof: compress_proof.shard_proofs.pop().unwrap() })
     }
 
     /// Wrap a reduce proof into a STARK proven over a SNARK-friendly field.
-    #[instrument(name = "wrap_bn254", level = "info", skip_all)]
     pub fn wrap_bn254(
         &self,
         compressed_proof: SP1ReduceProof<InnerSC>,
         opts: SP1ProverOpts,
     ) -> Result<SP1ReduceProof<OuterSC>, SP1RecursionProverError> {
+        self.wrap_bn254_with_monitor(compressed_proof, opts, &ProverMonitor::default())
+    }
+
+    /// Like [Self::wrap_bn254], reporting progress to `monitor` and stopping once it is cancelled.
+    #[instrument(name = "wrap_bn254", level = "info", skip_all)]
+    pub fn wrap_bn254_with_monitor(
+        &self,
+        compressed_proof: SP1ReduceProof<InnerSC>,
+        opts: SP1ProverOpts,
+        monitor: &ProverMonitor,
+    ) -> Result<SP1ReduceProof<OuterSC>, SP1RecursionProverError> {
+        if monitor.is_cancelled() {
+            return Err(SP1RecursionProverError::Cancelled);
+        }
+        // The wrap program only verifies proofs of the default shrink config, so budgeted shrink
+        // proofs, which have another number of queries, can't be wrapped.
+        let num_queries = compressed_proof.proof.opening_proof.fri_proof.query_proofs.len();
+        let expected = compressed_fri_config().num_queries;
+        if num_queries != expected {
+            return Err(SP1RecursionProverError::UnsupportedShrinkConfig { num_queries, expected });
+        }
+        let progress = monitor.stage(ProvingStage::Wrap, Some(1));
+
         let SP1ReduceProof { vk: compressed_vk, proof: compressed_proof } = compressed_proof;
         let input = SP1CompressWitnessValues {
             vks_and_proofs: vec![(compressed_vk, compressed_proof)],
@@ -1054,6 +1179,9 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         tracing::debug!("wrap program executed successfully");
 
         // Setup the wrap program.
+        if monitor.is_cancelled() {
+            return Err(SP1RecursionProverError::Cancelled);
+        }
         let (wrap_pk, wrap_vk) =
             tracing::debug_span!("setup wrap").in_scope(|| self.wrap_prover.setup(&program));
 
@@ -1069,6 +1197,7 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             .prove(&wrap_pk, vec![runtime.record], &mut wrap_challenger, opts.recursion_opts)
             .unwrap();
         let elapsed = time.elapsed();
+        progress.advance(1);
         tracing::debug!("wrap proving time: {:?}", elapsed);
         let mut wrap_challenger = self.wrap_prover.config().challenger();
         self.wrap_prover.machine().verify(&wrap_vk, &wrap_proof, &mut wrap_challenger).unwrap();
@@ -1078,12 +1207,32 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
     }
 
     /// Wrap the STARK proven over a SNARK-friendly field into a PLONK proof.
-    #[instrument(name = "wrap_plonk_bn254", level = "info", skip_all)]
     pub fn wrap_plonk_bn254(
         &self,
         proof: SP1ReduceProof<OuterSC>,
         build_dir: &Path,
     ) -> PlonkBn254Proof {
+        self.wrap_plonk_bn254_with_monitor(proof, build_dir, &ProverMonitor::default())
+            .expect("the default monitor is never cancelled")
+    }
+
+    /// Like [Self::wrap_plonk_bn254], reporting progress to `monitor` and stopping if it is
+    /// cancelled.
+    ///
+    /// Cancellation is only checked before the stage starts: gnark proves the circuit in one call,
+    /// so a cancellation while it runs is ignored and the proof is still returned.
+    #[instrument(name = "wrap_plonk_bn254", level = "info", skip_all)]
+    pub fn wrap_plonk_bn254_with_monitor(
+        &self,
+        proof: SP1ReduceProof<OuterSC>,
+        build_dir: &Path,
+        monitor: &ProverMonitor,
+    ) -> Result<PlonkBn254Proof, SP1RecursionProverError> {
+        if monitor.is_cancelled() {
+            return Err(SP1RecursionProverError::Cancelled);
+        }
+        let progress = monitor.stage(ProvingStage::Plonk, Some(1));
+
         let input = SP1CompressWitnessValues {
             vks_and_proofs: vec![(proof.vk.clone(), proof.proof.clone())],
             is_complete: true,
@@ -1106,17 +1255,38 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             &committed_values_digest.as_canonical_biguint(),
             build_dir,
         );
+        progress.advance(1);
 
-        proof
+        Ok(proof)
     }
 
     /// Wrap the STARK proven over a SNARK-friendly field into a Groth16 proof.
-    #[instrument(name = "wrap_groth16_bn254", level = "info", skip_all)]
     pub fn wrap_groth16_bn254(
         &self,
         proof: SP1ReduceProof<OuterSC>,
         build_dir: &Path,
     ) -> Groth16Bn254Proof {
+        self.wrap_groth16_bn254_with_monitor(proof, build_dir, &ProverMonitor::default())
+            .expect("the default monitor is never cancelled")
+    }
+
+    /// Like [Self::wrap_groth16_bn254], reporting progress to `monitor` and stopping if it is
+    /// cancelled.
+    ///
+    /// Cancellation is only checked before the stage starts: gnark proves the circuit in one call,
+    /// so a cancellation while it runs is ignored and the proof is still returned.
+    #[instrument(name = "wrap_groth16_bn254", level = "info", skip_all)]
+    pub fn wrap_groth16_bn254_with_monitor(
+        &self,
+        proof: SP1ReduceProof<OuterSC>,
+        build_dir: &Path,
+        monitor: &ProverMonitor,
+    ) -> Result<Groth16Bn254Proof, SP1RecursionProverError> {
+        if monitor.is_cancelled() {
+            return Err(SP1RecursionProverError::Cancelled);
+        }
+        let progress = monitor.stage(ProvingStage::Groth16, Some(1));
+
         let input = SP1CompressWitnessValues {
             vks_and_proofs: vec![(proof.vk.clone(), proof.proof.clone())],
             is_complete: true,
@@ -1139,8 +1309,9 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             &committed_values_digest.as_canonical_biguint(),
             build_dir,
         );
+        progress.advance(1);
 
-        proof
+        Ok(proof)
     }
 
     /// Accumulate deferred proofs into a single digest.
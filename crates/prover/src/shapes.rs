@@ -15,8 +15,8 @@ use p3_field::AbstractField;
 use serde::{Deserialize, Serialize};
 use sp1_core_machine::riscv::CoreShapeConfig;
 use sp1_recursion_circuit::machine::{
-    SP1CompressWithVKeyWitnessValues, SP1CompressWithVkeyShape, SP1DeferredShape,
-    SP1DeferredWitnessValues, SP1RecursionShape, SP1RecursionWitnessValues,
+    PublicValuesOutputDigest, SP1CompressWithVKeyWitnessValues, SP1CompressWithVkeyShape,
+    SP1DeferredShape, SP1DeferredWitnessValues, SP1RecursionShape, SP1RecursionWitnessValues,
 };
 use sp1_recursion_core::{shape::RecursionShapeConfig, RecursionProgram};
 use sp1_stark::{MachineProver, ProofShape, DIGEST_SIZE};
@@ -303,7 +303,7 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
             SP1CompressProgramShape::Shrink(shape) => {
                 let input =
                     SP1CompressWithVKeyWitnessValues::dummy(self.compress_prover.machine(), &shape);
-                self.shrink_program(&input)
+                self.shrink_program(&input, PublicValuesOutputDigest::Reduce)
             }
         }
     }
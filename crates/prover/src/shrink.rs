@@ -0,0 +1,550 @@
+//! Shrinking compressed proofs under a proof size budget or with BN254 Poseidon2 commitments.
+//!
+//! [SP1Prover::shrink] always proves with the fixed `compressed` FRI config. The functions in this
+//! module instead search over FRI blowups and query counts of equal conjectured security, trading
+//! prover time for smaller proofs. A budgeted shrink proof, [SP1BudgetedShrinkProof], carries the
+//! FRI parameters it was made with. It is meant to be posted and verified as it is: the wrap
+//! program only accepts proofs made with the default shrink config, and
+//! [SP1Prover::wrap_bn254] rejects budgeted proofs.
+//!
+//! [SP1Prover::shrink_bn254] instead commits to the shrink proof with BN254 Poseidon2, the hash of
+//! the wrap stage, so that the shrink proof can be verified by a BN254 circuit without wrapping.
+
+use std::{
+    borrow::Borrow,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use p3_baby_bear::BabyBear;
+use serde::{Deserialize, Serialize};
+use sp1_core_machine::reduce::SP1ReduceProof;
+use sp1_recursion_circuit::{
+    machine::{
+        PublicValuesOutputDigest, RootPublicValues, SP1CompressWithVKeyWitnessValues,
+        SP1CompressWithVkeyShape, SP1CompressWitnessValues,
+    },
+    witness::Witnessable,
+};
+use sp1_recursion_compiler::config::InnerConfig;
+use sp1_recursion_core::{runtime::ExecutionRecord, RecursionProgram, Runtime as RecursionRuntime};
+use sp1_stark::{
+    Challenge, CommitmentHash, MachineProof, MachineProver, MachineVerificationError,
+    ProverMonitor, ProvingStage, SP1ProverOpts, ShardProof, StarkGenericConfig, StarkVerifyingKey,
+    Val,
+};
+use tracing::instrument;
+
+use crate::{
+    components::SP1ProverComponents, utils::assert_root_public_values_valid, CoreSC, HashableKey,
+    InnerSC, OuterSC, SP1Prover, SP1RecursionProverError, SP1VerifyingKey, ShrinkAir,
+};
+
+/// The FRI parameters used to prove a shrink proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShrinkFriParams {
+    /// The log of the blowup factor of the low degree extension.
+    pub log_blowup: usize,
+    /// The number of FRI queries.
+    pub num_queries: usize,
+    /// The number of proof of work bits required before sampling the queries.
+    pub proof_of_work_bits: usize,
+}
+
+impl ShrinkFriParams {
+    /// The parameters of the default shrink config, [`InnerSC::compressed`].
+    pub const COMPRESSED: Self = Self { log_blowup: 2, num_queries: 50, proof_of_work_bits: 16 };
+
+    /// The smallest number of queries that reaches `security_bits` at the given blowup.
+    pub fn for_security(
+        log_blowup: usize,
+        security_bits: usize,
+        proof_of_work_bits: usize,
+    ) -> Self {
+        assert!(log_blowup > 0, "log_blowup must be at least 1");
+        let num_queries = security_bits.saturating_sub(proof_of_work_bits).div_ceil(log_blowup);
+        Self { log_blowup, num_queries, proof_of_work_bits }
+    }
+
+    /// The conjectured security of these parameters in bits.
+    pub fn conjectured_security_bits(&self) -> usize {
+        self.log_blowup * self.num_queries + self.proof_of_work_bits
+    }
+
+    /// The stark config proving with these parameters.
+    pub fn config(&self) -> InnerSC {
+        InnerSC::with_fri_params(self.log_blowup, self.num_queries, self.proof_of_work_bits)
+    }
+}
+
+/// A proof size budget for [SP1Prover::shrink_with_budget].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShrinkBudget {
+    /// The maximum size of the serialized shrink proof in bytes.
+    pub max_proof_size: usize,
+    /// The conjectured security every candidate config must reach.
+    pub security_bits: usize,
+    /// The proof of work bits used by every candidate config.
+    pub proof_of_work_bits: usize,
+    /// The largest log blowup to try. Larger blowups give smaller proofs but slower proving.
+    pub max_log_blowup: usize,
+}
+
+impl ShrinkBudget {
+    /// A budget with the same security level as the default shrink config.
+    pub fn new(max_proof_size: usize) -> Self {
+        Self {
+            max_proof_size,
+            security_bits: ShrinkFriParams::COMPRESSED.conjectured_security_bits(),
+            proof_of_work_bits: ShrinkFriParams::COMPRESSED.proof_of_work_bits,
+            max_log_blowup: 4,
+        }
+    }
+
+    /// The candidate FRI parameters, ordered from the fastest to prove to the smallest proof.
+    pub fn candidates(&self) -> Vec<ShrinkFriParams> {
+        (1..=self.max_log_blowup)
+            .map(|log_blowup| {
+                ShrinkFriParams::for_security(
+                    log_blowup,
+                    self.security_bits,
+                    self.proof_of_work_bits,
+                )
+            })
+            .collect()
+    }
+}
+
+/// A shrink proof made by [SP1Prover::shrink_with_budget], with the FRI parameters it was made
+/// with.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SP1BudgetedShrinkProof {
+    pub proof: SP1ReduceProof<InnerSC>,
+    pub params: ShrinkFriParams,
+}
+
+/// A summary of a budgeted shrink proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShrinkReport {
+    /// The FRI parameters the proof was made with.
+    pub params: ShrinkFriParams,
+    /// The size of the bincode serialized shard proof in bytes.
+    pub proof_size: usize,
+    /// The number of Merkle path hashes the verifier recomputes, the dominant verification cost.
+    pub verifier_hashes: usize,
+    /// The time it took to verify the proof natively.
+    pub verification_time: Duration,
+    /// The time it took to set up and prove the shrink program.
+    pub proving_time: Duration,
+}
+
+impl<C: SP1ProverComponents> SP1Prover<C> {
+    /// Shrink a reduce proof with the fastest FRI config whose proof fits in the budget.
+    ///
+    /// The shrink program is executed once and then proven with each candidate of
+    /// [ShrinkBudget::candidates] that is predicted to fit, until a proof fits in the budget.
+    #[instrument(name = "shrink_with_budget", level = "info", skip_all)]
+    pub fn shrink_with_budget(
+        &self,
+        reduced_proof: SP1ReduceProof<InnerSC>,
+        budget: ShrinkBudget,
+        opts: SP1ProverOpts,
+    ) -> Result<(SP1BudgetedShrinkProof, ShrinkReport), SP1RecursionProverError> {
+        let SP1ReduceProof { vk: compressed_vk, proof: compressed_proof } = reduced_proof;
+        let input = SP1CompressWitnessValues {
+            vks_and_proofs: vec![(compressed_vk, compressed_proof)],
+            is_complete: true,
+        };
+        let input_with_merkle = self.make_merkle_proofs(input);
+        let program = self.shrink_program(&input_with_merkle, PublicValuesOutputDigest::Reduce);
+
+        // Run the shrink program once, the record does not depend on the FRI config.
+        let mut runtime = RecursionRuntime::<Val<InnerSC>, Challenge<InnerSC>, _>::new(
+            program.clone(),
+            self.shrink_prover.config().perm.clone(),
+        );
+        let mut witness_stream = Vec::new();
+        Witnessable::<InnerConfig>::write(&input_with_merkle, &mut witness_stream);
+        runtime.witness_stream = witness_stream.into();
+        runtime.run().map_err(|e| SP1RecursionProverError::RuntimeError(e.to_string()))?;
+        let record = runtime.record;
+
+        let mut size_model: Option<ProofSizeModel> = None;
+        let mut smallest = usize::MAX;
+        for params in budget.candidates() {
+            if let Some(model) = &size_model {
+                if model.predict(params) > budget.max_proof_size {
+                    tracing::debug!("skipping {:?}, predicted to exceed the budget", params);
+                    continue;
+                }
+            }
+
+            let (proof, report) =
+                self.prove_shrink_with_params(&program, record.clone(), params, opts)?;
+            tracing::info!(
+                "shrink proof with {:?}: {} bytes, proved in {:?}",
+                params,
+                report.proof_size,
+                report.proving_time
+            );
+            if report.proof_size <= budget.max_proof_size {
+                return Ok((SP1BudgetedShrinkProof { proof, params }, report));
+            }
+            smallest = smallest.min(report.proof_size);
+            size_model = Some(ProofSizeModel::new(&proof.proof, params));
+        }
+
+        Err(SP1RecursionProverError::ProofSizeBudgetExceeded {
+            budget: budget.max_proof_size,
+            smallest,
+        })
+    }
+
+    /// Verify a shrink proof made by [SP1Prover::shrink_with_budget], with the parameters it was
+    /// made with.
+    pub fn verify_budgeted_shrink(
+        &self,
+        proof: &SP1BudgetedShrinkProof,
+        vk: &SP1VerifyingKey,
+    ) -> Result<(), MachineVerificationError<CoreSC>> {
+        let machine = ShrinkAir::shrink_machine(proof.params.config());
+        self.verify_shrink_with_machine(&machine, &proof.proof, vk)
+    }
+
+    fn prove_shrink_with_params(
+        &self,
+        program: &Arc<RecursionProgram<BabyBear>>,
+        record: ExecutionRecord<BabyBear>,
+        params: ShrinkFriParams,
+        opts: SP1ProverOpts,
+    ) -> Result<(SP1ReduceProof<InnerSC>, ShrinkReport), SP1RecursionProverError> {
+        let prover = C::ShrinkProver::new(ShrinkAir::shrink_machine(params.config()));
+
+        let time = Instant::now();
+        let (pk, vk) = tracing::debug_span!("setup shrink").in_scope(|| prover.setup(program));
+        let mut challenger = prover.config().challenger();
+        let mut proof = prover
+            .prove(&pk, vec![record], &mut challenger, opts.recursion_opts)
+            .map_err(|e| SP1RecursionProverError::ProvingError(e.to_string()))?;
+        let proving_time = time.elapsed();
+
+        let time = Instant::now();
+        let mut challenger = prover.config().challenger();
+        prover
+            .machine()
+            .verify(&vk, &proof, &mut challenger)
+            .map_err(|e| SP1RecursionProverError::VerificationError(e.to_string()))?;
+        let verification_time = time.elapsed();
+
+        let proof = proof.shard_proofs.pop().unwrap();
+        let report = ShrinkReport {
+            params,
+            proof_size: bincode::serialized_size(&proof).unwrap() as usize,
+            verifier_hashes: verifier_hashes(&proof),
+            verification_time,
+            proving_time,
+        };
+        Ok((SP1ReduceProof { vk, proof }, report))
+    }
+}
+
+/// A shrink proof, committed to with either of the supported hashes.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum SP1ShrinkProof {
+    /// A shrink proof with BabyBear Poseidon2 commitments, as made by [SP1Prover::shrink].
+    BabyBear(SP1ReduceProof<InnerSC>),
+    /// A shrink proof with BN254 Poseidon2 commitments, as made by [SP1Prover::shrink_bn254].
+    Bn254(SP1ReduceProof<OuterSC>),
+}
+
+impl SP1ShrinkProof {
+    /// The hash the proof is committed to with.
+    pub fn commitment_hash(&self) -> CommitmentHash {
+        match self {
+            Self::BabyBear(_) => InnerSC::COMMITMENT_HASH,
+            Self::Bn254(_) => OuterSC::COMMITMENT_HASH,
+        }
+    }
+}
+
+impl<C: SP1ProverComponents> SP1Prover<C> {
+    /// Shrink a reduce proof, committing to the shrink proof with the given hash.
+    pub fn shrink_with_hash(
+        &self,
+        reduced_proof: SP1ReduceProof<InnerSC>,
+        hash: CommitmentHash,
+        opts: SP1ProverOpts,
+        monitor: &ProverMonitor,
+    ) -> Result<SP1ShrinkProof, SP1RecursionProverError> {
+        match hash {
+            CommitmentHash::BabyBearPoseidon2 => {
+                self.shrink_with_monitor(reduced_proof, opts, monitor).map(SP1ShrinkProof::BabyBear)
+            }
+            CommitmentHash::Bn254Poseidon2 => {
+                self.shrink_bn254(reduced_proof, opts, monitor).map(SP1ShrinkProof::Bn254)
+            }
+        }
+    }
+
+    /// Shrink a reduce proof into a proof with BN254 Poseidon2 commitments.
+    ///
+    /// The shrink program is the same as in [SP1Prover::shrink], except that it commits to the
+    /// root public values digest, like the wrap program. The proof can therefore be verified by
+    /// the same BN254 circuit as a wrap proof, built for the shrink machine with
+    /// [crate::build::build_shrink_bn254_constraints_and_witness].
+    #[instrument(name = "shrink_bn254", level = "info", skip_all)]
+    pub fn shrink_bn254(
+        &self,
+        reduced_proof: SP1ReduceProof<InnerSC>,
+        opts: SP1ProverOpts,
+        monitor: &ProverMonitor,
+    ) -> Result<SP1ReduceProof<OuterSC>, SP1RecursionProverError> {
+        if monitor.is_cancelled() {
+            return Err(SP1RecursionProverError::Cancelled);
+        }
+        let progress = monitor.stage(ProvingStage::Shrink, Some(1));
+
+        let SP1ReduceProof { vk: compressed_vk, proof: compressed_proof } = reduced_proof;
+        let input = SP1CompressWitnessValues {
+            vks_and_proofs: vec![(compressed_vk, compressed_proof)],
+            is_complete: true,
+        };
+        let input_with_merkle = self.make_merkle_proofs(input);
+        let program = self.shrink_program(&input_with_merkle, PublicValuesOutputDigest::Root);
+
+        // Run the shrink program.
+        let mut runtime = RecursionRuntime::<Val<InnerSC>, Challenge<InnerSC>, _>::new(
+            program.clone(),
+            self.shrink_prover.config().perm.clone(),
+        );
+        let mut witness_stream = Vec::new();
+        Witnessable::<InnerConfig>::write(&input_with_merkle, &mut witness_stream);
+        runtime.witness_stream = witness_stream.into();
+        runtime.run().map_err(|e| SP1RecursionProverError::RuntimeError(e.to_string()))?;
+        runtime.print_stats();
+        tracing::debug!("Shrink bn254 program executed successfully");
+
+        if monitor.is_cancelled() {
+            return Err(SP1RecursionProverError::Cancelled);
+        }
+        let (shrink_pk, shrink_vk) = tracing::debug_span!("setup shrink bn254")
+            .in_scope(|| self.shrink_bn254_prover.setup(&program));
+
+        // Prove the shrink program.
+        let mut challenger = self.shrink_bn254_prover.config().challenger();
+        let mut shrink_proof = self
+            .shrink_bn254_prover
+            .prove(&shrink_pk, vec![runtime.record], &mut challenger, opts.recursion_opts)
+            .map_err(|e| SP1RecursionProverError::ProvingError(e.to_string()))?;
+        progress.advance(1);
+
+        Ok(SP1ReduceProof { vk: shrink_vk, proof: shrink_proof.shard_proofs.pop().unwrap() })
+    }
+
+    /// Verify a shrink proof made by [SP1Prover::shrink_bn254].
+    ///
+    /// With vk verification, the proof must be of one of the shrink bn254 programs, as for the
+    /// other recursion proofs.
+    pub fn verify_shrink_bn254(
+        &self,
+        proof: &SP1ReduceProof<OuterSC>,
+        vk: &SP1VerifyingKey,
+    ) -> Result<(), MachineVerificationError<OuterSC>> {
+        let mut challenger = self.shrink_bn254_prover.config().challenger();
+        let machine_proof = MachineProof { shard_proofs: vec![proof.proof.clone()] };
+        self.shrink_bn254_prover.machine().verify(&proof.vk, &machine_proof, &mut challenger)?;
+
+        // Validate public values
+        let public_values: &RootPublicValues<_> = proof.proof.public_values.as_slice().borrow();
+        assert_root_public_values_valid(self.shrink_prover.machine().config(), public_values);
+
+        if self.vk_verification && !self.is_shrink_bn254_vk_allowed(&proof.vk) {
+            return Err(MachineVerificationError::InvalidVerificationKey);
+        }
+
+        // Verify that the proof is for the sp1 vkey we are expecting.
+        let vkey_hash = vk.hash_babybear();
+        if *public_values.sp1_vk_digest() != vkey_hash {
+            return Err(MachineVerificationError::InvalidPublicValues("sp1 vk hash mismatch"));
+        }
+
+        Ok(())
+    }
+
+    /// Whether `vk` is the verifying key of the shrink bn254 program of an allowed shape of
+    /// compressed proof.
+    fn is_shrink_bn254_vk_allowed(&self, vk: &StarkVerifyingKey<OuterSC>) -> bool {
+        let allowed = self.shrink_bn254_vks.get_or_init(|| {
+            let Some(recursion_shape_config) = &self.recursion_shape_config else {
+                return vec![];
+            };
+            recursion_shape_config
+                .get_all_shape_combinations(1)
+                .map(|compress_shape| {
+                    let shape = SP1CompressWithVkeyShape {
+                        compress_shape: compress_shape.into(),
+                        merkle_tree_height: self.vk_merkle_tree.height,
+                    };
+                    let input = SP1CompressWithVKeyWitnessValues::dummy(
+                        self.compress_prover.machine(),
+                        &shape,
+                    );
+                    let program = self.shrink_program(&input, PublicValuesOutputDigest::Root);
+                    let (_, vk) = self.shrink_bn254_prover.setup(&program);
+                    bincode::serialize(&vk).unwrap()
+                })
+                .collect()
+        });
+        allowed.contains(&bincode::serialize(vk).unwrap())
+    }
+}
+
+/// Counts the Merkle path hashes a verifier recomputes to check the FRI queries of a proof.
+pub fn verifier_hashes(proof: &ShardProof<InnerSC>) -> usize {
+    let pcs_proof = &proof.opening_proof;
+    let commit_phase = pcs_proof
+        .fri_proof
+        .query_proofs
+        .iter()
+        .flat_map(|query| query.commit_phase_openings.iter())
+        .map(|step| step.opening_proof.len())
+        .sum::<usize>();
+    let input_batches = pcs_proof
+        .query_openings
+        .iter()
+        .flatten()
+        .map(|batch| batch.opening_proof.len())
+        .sum::<usize>();
+    commit_phase + input_batches
+}
+
+/// A linear model of the proof size in the number of FRI queries.
+struct ProofSizeModel {
+    fixed: usize,
+    per_query: usize,
+}
+
+impl ProofSizeModel {
+    fn new(proof: &ShardProof<InnerSC>, params: ShrinkFriParams) -> Self {
+        let total = bincode::serialized_size(proof).unwrap() as usize;
+        let queries = bincode::serialized_size(&proof.opening_proof.fri_proof.query_proofs).unwrap()
+            as usize
+            + bincode::serialized_size(&proof.opening_proof.query_openings).unwrap() as usize;
+        Self { fixed: total - queries, per_query: queries / params.num_queries.max(1) }
+    }
+
+    /// The predicted proof size. Merkle paths grow with the blowup, so this is a lower bound when
+    /// predicting for a larger blowup than the model was built from.
+    fn predict(&self, params: ShrinkFriParams) -> usize {
+        self.fixed + self.per_query * params.num_queries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+    use serial_test::serial;
+    use sp1_core_executor::SP1Context;
+    use sp1_core_machine::{io::SP1Stdin, utils::setup_logger};
+    use sp1_recursion_gnark_ffi::PlonkBn254Prover;
+
+    use super::*;
+    use crate::{
+        build::{build_constraints_and_witness, build_shrink_bn254_constraints_and_witness},
+        components::DefaultProverComponents,
+    };
+
+    #[test]
+    fn test_shrink_budget_candidates() {
+        let budget = ShrinkBudget::new(0);
+        let candidates = budget.candidates();
+        assert_eq!(candidates.len(), 4);
+        for params in candidates.iter() {
+            assert!(params.conjectured_security_bits() >= budget.security_bits);
+        }
+        assert!(candidates.windows(2).all(|w| w[0].num_queries > w[1].num_queries));
+        assert_eq!(candidates[1], ShrinkFriParams::COMPRESSED);
+    }
+
+    #[test]
+    #[serial]
+    fn test_shrink_with_budget() {
+        setup_logger();
+        let elf = include_bytes!("../../../tests/fibonacci/elf/riscv32im-succinct-zkvm-elf");
+        let prover = SP1Prover::<DefaultProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+
+        let (pk, vk) = prover.setup(elf);
+        let core_proof =
+            prover.prove_core(&pk, &SP1Stdin::new(), opts, SP1Context::default()).unwrap();
+        let compressed_proof = prover.compress(&vk, core_proof, vec![], opts).unwrap();
+
+        // Any proof fits, so the fastest candidate is used.
+        let budget = ShrinkBudget::new(usize::MAX);
+        let (shrink_proof, report) =
+            prover.shrink_with_budget(compressed_proof, budget, opts).unwrap();
+        assert_eq!(shrink_proof.params, budget.candidates()[0]);
+        assert_eq!(report.params, shrink_proof.params);
+        prover.verify_budgeted_shrink(&shrink_proof, &vk).unwrap();
+
+        let mut other_params = shrink_proof.clone();
+        other_params.params = ShrinkFriParams::COMPRESSED;
+        assert!(prover.verify_budgeted_shrink(&other_params, &vk).is_err());
+
+        assert!(matches!(
+            prover.wrap_bn254(shrink_proof.proof, opts),
+            Err(SP1RecursionProverError::UnsupportedShrinkConfig { .. })
+        ));
+    }
+
+    /// Tests that shrink proofs committed to with either hash are accepted by a BN254 circuit:
+    /// BabyBear Poseidon2 shrink proofs through the wrap program, and BN254 Poseidon2 shrink proofs
+    /// directly.
+    #[test]
+    #[serial]
+    fn test_shrink_verifier_circuit() {
+        setup_logger();
+        let elf = include_bytes!("../../../tests/fibonacci/elf/riscv32im-succinct-zkvm-elf");
+        let prover = SP1Prover::<DefaultProverComponents>::new();
+        let opts = SP1ProverOpts::default();
+        let monitor = ProverMonitor::default();
+
+        let (pk, vk) = prover.setup(elf);
+        let core_proof =
+            prover.prove_core(&pk, &SP1Stdin::new(), opts, SP1Context::default()).unwrap();
+        let compressed_proof = prover.compress(&vk, core_proof, vec![], opts).unwrap();
+
+        // BabyBear Poseidon2.
+        let shrink_proof = prover
+            .shrink_with_hash(
+                compressed_proof.clone(),
+                CommitmentHash::BabyBearPoseidon2,
+                opts,
+                &monitor,
+            )
+            .unwrap();
+        assert_eq!(shrink_proof.commitment_hash(), CommitmentHash::BabyBearPoseidon2);
+        let SP1ShrinkProof::BabyBear(shrink_proof) = shrink_proof else { unreachable!() };
+        prover.verify_shrink(&shrink_proof, &vk).unwrap();
+        let wrapped_proof = prover.wrap_bn254(shrink_proof, opts).unwrap();
+        let (constraints, witness) =
+            build_constraints_and_witness(&wrapped_proof.vk, &wrapped_proof.proof);
+        PlonkBn254Prover::test(constraints, witness);
+
+        // BN254 Poseidon2.
+        let shrink_proof = prover
+            .shrink_with_hash(compressed_proof, CommitmentHash::Bn254Poseidon2, opts, &monitor)
+            .unwrap();
+        assert_eq!(shrink_proof.commitment_hash(), CommitmentHash::Bn254Poseidon2);
+        let SP1ShrinkProof::Bn254(shrink_proof) = shrink_proof else { unreachable!() };
+        prover.verify_shrink_bn254(&shrink_proof, &vk).unwrap();
+        let (constraints, witness) =
+            build_shrink_bn254_constraints_and_witness(&shrink_proof.vk, &shrink_proof.proof);
+        PlonkBn254Prover::test(constraints, witness);
+
+        // The proof is not accepted for another program.
+        let mut other_vk = vk.clone();
+        other_vk.vk.pc_start = BabyBear::one();
+        assert!(prover.verify_shrink_bn254(&shrink_proof, &other_vk).is_err());
+    }
+}
@@ -209,6 +209,16 @@ pub enum SP1ReduceProofWrapper {
 pub enum SP1RecursionProverError {
     #[error("Runtime error: {0}")]
     RuntimeError(String),
+    #[error("Proving error: {0}")]
+    ProvingError(String),
+    #[error("Verification error: {0}")]
+    VerificationError(String),
+    #[error("No shrink config fits the budget of {budget} bytes, the smallest proof was {smallest} bytes")]
+    ProofSizeBudgetExceeded { budget: usize, smallest: usize },
+    #[error("Budgeted shrink proofs can't be wrapped: the proof has {num_queries} FRI queries, but the wrap program only accepts the {expected} of the default shrink config")]
+    UnsupportedShrinkConfig { num_queries: usize, expected: usize },
+    #[error("Proving was cancelled")]
+    Cancelled,
 }
 
 #[allow(clippy::large_enum_variant)]
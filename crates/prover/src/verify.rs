@@ -16,14 +16,14 @@ use sp1_recursion_gnark_ffi::{
 use sp1_stark::{
     air::{PublicValues, POSEIDON_NUM_WORDS, PV_DIGEST_NUM_WORDS},
     baby_bear_poseidon2::BabyBearPoseidon2,
-    MachineProof, MachineProver, MachineVerificationError, StarkGenericConfig, Word,
+    MachineProof, MachineProver, MachineVerificationError, StarkGenericConfig, StarkMachine, Word,
 };
 use thiserror::Error;
 
 use crate::{
     components::SP1ProverComponents,
     utils::{assert_recursion_public_values_valid, assert_root_public_values_valid},
-    CoreSC, HashableKey, OuterSC, SP1CoreProofData, SP1Prover, SP1VerifyingKey,
+    CoreSC, HashableKey, InnerSC, OuterSC, SP1CoreProofData, SP1Prover, SP1VerifyingKey, ShrinkAir,
 };
 
 #[derive(Error, Debug)]
@@ -57,6 +57,16 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         &self,
         proof: &SP1CoreProofData,
         vk: &SP1VerifyingKey,
+    ) -> Result<(), MachineVerificationError<CoreSC>> {
+        self.verify_with_challenger(proof, vk, self.core_prover.config().challenger())
+    }
+
+    /// Verify a core proof whose transcript starts from `challenger`, see [Self::verify].
+    pub fn verify_with_challenger(
+        &self,
+        proof: &SP1CoreProofData,
+        vk: &SP1VerifyingKey,
+        mut challenger: <CoreSC as StarkGenericConfig>::Challenger,
     ) -> Result<(), MachineVerificationError<CoreSC>> {
         // First shard has a "CPU" constraint.
         //
@@ -281,7 +291,6 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         }
 
         // Verify the shard proof.
-        let mut challenger = self.core_prover.config().challenger();
         let machine_proof = MachineProof { shard_proofs: proof.0.to_vec() };
         self.core_prover.machine().verify(&vk.vk, &machine_proof, &mut challenger)?;
 
@@ -331,9 +340,19 @@ impl<C: SP1ProverComponents> SP1Prover<C> {
         proof: &SP1ReduceProof<BabyBearPoseidon2>,
         vk: &SP1VerifyingKey,
     ) -> Result<(), MachineVerificationError<CoreSC>> {
-        let mut challenger = self.shrink_prover.config().challenger();
+        self.verify_shrink_with_machine(self.shrink_prover.machine(), proof, vk)
+    }
+
+    /// Verify a shrink proof against the given shrink machine.
+    pub(crate) fn verify_shrink_with_machine(
+        &self,
+        machine: &StarkMachine<InnerSC, ShrinkAir<BabyBear>>,
+        proof: &SP1ReduceProof<BabyBearPoseidon2>,
+        vk: &SP1VerifyingKey,
+    ) -> Result<(), MachineVerificationError<CoreSC>> {
+        let mut challenger = machine.config().challenger();
         let machine_proof = MachineProof { shard_proofs: vec![proof.proof.clone()] };
-        self.shrink_prover.machine().verify(&proof.vk, &machine_proof, &mut challenger)?;
+        machine.verify(&proof.vk, &machine_proof, &mut challenger)?;
 
         // Validate public values
         let public_values: &RecursionPublicValues<_> =
@@ -0,0 +1,193 @@
+use std::{array, borrow::BorrowMut, marker::PhantomData};
+
+use p3_air::Air;
+use p3_baby_bear::BabyBear;
+use p3_commit::Mmcs;
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::dense::RowMajorMatrix;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use sp1_primitives::consts::WORD_SIZE;
+use sp1_recursion_compiler::ir::{Builder, Ext, ExtConst, Felt};
+use sp1_recursion_core::{
+    air::{RecursionPublicValues, PV_DIGEST_NUM_WORDS, RECURSIVE_PROOF_NUM_PV_ELTS},
+    DIGEST_SIZE,
+};
+use sp1_stark::{
+    air::MachineAir, Dom, ShardProof, StarkGenericConfig, StarkMachine, StarkVerifyingKey, Word,
+};
+
+use crate::{
+    challenger::{CanObserveVariable, DuplexChallengerVariable, FieldChallengerVariable},
+    constraints::RecursiveVerifierConstraintFolder,
+    hash::FieldHasherVariable,
+    machine::recursion_public_values_digest,
+    stark::{ShardProofVariable, StarkVerifier},
+    BabyBearFriConfigVariable, CircuitConfig, VerifyingKeyVariable,
+};
+
+/// The element hashed after the verifying key digest of a foreign machine, so that the digests
+/// of foreign machines can never be mistaken for the digests of SP1 programs.
+pub const FOREIGN_VK_DOMAIN_SEPARATOR: u32 = u32::from_be_bytes(*b"FRGN");
+
+/// A program to verify a proof of an arbitrary machine over BabyBear and Poseidon2.
+#[derive(Debug, Clone, Copy)]
+pub struct SP1ForeignVerifier<C, SC, A> {
+    _phantom: PhantomData<(C, SC, A)>,
+}
+
+/// Witness layout for the foreign proof verifier.
+pub struct SP1ForeignWitnessVariable<
+    C: CircuitConfig<F = BabyBear>,
+    SC: BabyBearFriConfigVariable<C>,
+> {
+    /// The verifying key of the foreign machine.
+    pub vk: VerifyingKeyVariable<C, SC>,
+    /// The shard proofs of the foreign machine.
+    pub shard_proofs: Vec<ShardProofVariable<C, SC>>,
+    pub vk_root: [Felt<C::F>; DIGEST_SIZE],
+}
+
+/// An input layout for the foreign proof verifier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "ShardProof<SC>: Serialize, Dom<SC>: Serialize"))]
+#[serde(bound(deserialize = "ShardProof<SC>: Deserialize<'de>, Dom<SC>: DeserializeOwned"))]
+pub struct SP1ForeignWitnessValues<SC: StarkGenericConfig> {
+    pub vk: StarkVerifyingKey<SC>,
+    pub shard_proofs: Vec<ShardProof<SC>>,
+    pub vk_root: [SC::Val; DIGEST_SIZE],
+}
+
+impl<C, SC, A> SP1ForeignVerifier<C, SC, A>
+where
+    SC: BabyBearFriConfigVariable<
+        C,
+        FriChallengerVariable = DuplexChallengerVariable<C>,
+        DigestVariable = [Felt<BabyBear>; DIGEST_SIZE],
+    >,
+    C: CircuitConfig<F = SC::Val, EF = SC::Challenge, Bit = Felt<BabyBear>>,
+    <SC::ValMmcs as Mmcs<BabyBear>>::ProverData<RowMajorMatrix<BabyBear>>: Clone,
+    A: MachineAir<SC::Val> + for<'a> Air<RecursiveVerifierConstraintFolder<'a, C>>,
+{
+    /// Verify a complete proof of a foreign machine.
+    ///
+    /// The verifier follows [StarkMachine::verify]: it observes the verifying key and the public
+    /// values of all shards, verifies each shard, and asserts that the global cumulative sum is
+    /// zero. The proof is then committed as a complete compressed proof, so that it can be
+    /// passed to SP1 programs as a deferred proof:
+    /// - `sp1_vk_digest` is the Poseidon2 hash of the verifying key digest followed by
+    ///   [FOREIGN_VK_DOMAIN_SEPARATOR].
+    /// - `committed_value_digest` is the Poseidon2 hash of the public values of all shards, in
+    ///   order, with each element written as four little-endian bytes.
+    ///
+    /// The other execution values are zero, as a foreign machine has no SP1 execution state.
+    pub fn verify(
+        builder: &mut Builder<C>,
+        machine: &StarkMachine<SC, A>,
+        input: SP1ForeignWitnessVariable<C, SC>,
+    ) {
+        let SP1ForeignWitnessVariable { vk, shard_proofs, vk_root } = input;
+
+        // Make sure there is at least one proof.
+        assert!(!shard_proofs.is_empty());
+
+        // Observe the vk and the public values of all shards.
+        let mut challenger = machine.config().challenger_variable(builder);
+        vk.observe_into(builder, &mut challenger);
+        for shard_proof in shard_proofs.iter() {
+            if machine.contains_global_bus() {
+                challenger.observe(builder, shard_proof.commitment.global_main_commit);
+            }
+            challenger.observe_slice(
+                builder,
+                shard_proof.public_values[0..machine.num_pv_elts()].iter().copied(),
+            );
+        }
+
+        // Sample the challenges of the global permutation argument.
+        let zero_ext: Ext<C::F, C::EF> = builder.eval(C::F::zero());
+        let global_permutation_challenges = if machine.contains_global_bus() {
+            [challenger.sample_ext(builder), challenger.sample_ext(builder)]
+        } else {
+            [zero_ext, zero_ext]
+        };
+
+        // Verify the shard proofs and collect their public values.
+        let mut global_cumulative_sum: Ext<_, _> = builder.eval(C::EF::zero().cons());
+        let mut public_values = Vec::new();
+        for shard_proof in shard_proofs.iter() {
+            let mut challenger = challenger.copy(builder);
+            StarkVerifier::verify_shard(
+                builder,
+                &vk,
+                machine,
+                &mut challenger,
+                shard_proof,
+                &global_permutation_challenges,
+            );
+
+            for values in shard_proof.opened_values.chips.iter() {
+                global_cumulative_sum =
+                    builder.eval(global_cumulative_sum + values.global_cumulative_sum);
+            }
+            public_values.extend_from_slice(&shard_proof.public_values[0..machine.num_pv_elts()]);
+        }
+
+        // Assert that all the global interactions are balanced.
+        builder.assert_ext_eq(global_cumulative_sum, C::EF::zero().cons());
+
+        // Compute the digest of the foreign vk.
+        let vk_digest = vk.hash(builder);
+        let domain_separator: Felt<_> =
+            builder.eval(C::F::from_canonical_u32(FOREIGN_VK_DOMAIN_SEPARATOR));
+        let foreign_vk_digest =
+            SC::hash(builder, &[vk_digest.as_slice(), &[domain_separator]].concat());
+
+        // Compute the digest of the public values, decomposed into bytes.
+        let public_values_digest = SC::hash(builder, &public_values);
+        let committed_value_digest: [Word<Felt<_>>; PV_DIGEST_NUM_WORDS] = array::from_fn(|i| {
+            let bits = C::num2bits(builder, public_values_digest[i], 31);
+            Word(array::from_fn(|j| C::bits2num(builder, bits.iter().skip(8 * j).take(8).copied())))
+        });
+
+        // Write all values to the public values struct and commit to them.
+        let zero: Felt<_> = builder.eval(C::F::zero());
+        let mut foreign_public_values_stream = [zero; RECURSIVE_PROOF_NUM_PV_ELTS];
+        let foreign_public_values: &mut RecursionPublicValues<_> =
+            foreign_public_values_stream.as_mut_slice().borrow_mut();
+        foreign_public_values.sp1_vk_digest = foreign_vk_digest;
+        foreign_public_values.committed_value_digest = committed_value_digest;
+        foreign_public_values.is_complete = builder.eval(C::F::one());
+        foreign_public_values.vk_root = vk_root;
+        foreign_public_values.digest =
+            recursion_public_values_digest::<C, SC>(builder, foreign_public_values);
+
+        SC::commit_recursion_public_values(builder, *foreign_public_values);
+    }
+}
+
+/// The bytes of the digest of the public values of a foreign proof, as committed by
+/// [SP1ForeignVerifier::verify].
+pub fn foreign_public_values_digest<SC: StarkGenericConfig<Val = BabyBear>>(
+    num_pv_elts: usize,
+    shard_proofs: &[ShardProof<SC>],
+) -> [u8; DIGEST_SIZE * WORD_SIZE] {
+    let public_values = shard_proofs
+        .iter()
+        .flat_map(|proof| proof.public_values[0..num_pv_elts].iter().copied())
+        .collect();
+    let digest = sp1_primitives::poseidon2_hash(public_values);
+    let mut bytes = [0; DIGEST_SIZE * WORD_SIZE];
+    for (chunk, element) in bytes.chunks_exact_mut(WORD_SIZE).zip(digest) {
+        chunk.copy_from_slice(&element.as_canonical_u32().to_le_bytes());
+    }
+    bytes
+}
+
+/// The vk digest of a foreign machine, as committed by [SP1ForeignVerifier::verify], given the
+/// digest of its verifying key.
+pub fn foreign_vk_digest(vk_digest: [BabyBear; DIGEST_SIZE]) -> [BabyBear; DIGEST_SIZE] {
+    let mut inputs = vk_digest.to_vec();
+    inputs.push(BabyBear::from_canonical_u32(FOREIGN_VK_DOMAIN_SEPARATOR));
+    sp1_primitives::poseidon2_hash(inputs)
+}
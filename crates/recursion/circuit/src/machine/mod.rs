@@ -2,6 +2,7 @@ mod complete;
 mod compress;
 mod core;
 mod deferred;
+mod foreign;
 mod public_values;
 mod root;
 mod vkey_proof;
@@ -12,6 +13,7 @@ pub(crate) use complete::*;
 pub use compress::*;
 pub use core::*;
 pub use deferred::*;
+pub use foreign::*;
 pub use public_values::*;
 pub use root::*;
 pub use vkey_proof::*;
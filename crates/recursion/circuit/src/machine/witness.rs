@@ -24,8 +24,9 @@ use crate::{
 
 use super::{
     SP1CompressWitnessValues, SP1CompressWitnessVariable, SP1DeferredWitnessValues,
-    SP1DeferredWitnessVariable, SP1MerkleProofWitnessValues, SP1MerkleProofWitnessVariable,
-    SP1RecursionWitnessValues, SP1RecursionWitnessVariable,
+    SP1DeferredWitnessVariable, SP1ForeignWitnessValues, SP1ForeignWitnessVariable,
+    SP1MerkleProofWitnessValues, SP1MerkleProofWitnessVariable, SP1RecursionWitnessValues,
+    SP1RecursionWitnessVariable,
 };
 
 impl<C: CircuitConfig, T: Witnessable<C>> Witnessable<C> for Word<T> {
@@ -213,6 +214,26 @@ where
     }
 }
 
+impl<C> Witnessable<C> for SP1ForeignWitnessValues<BabyBearPoseidon2>
+where
+    C: CircuitConfig<F = InnerVal, EF = InnerChallenge, Bit = Felt<InnerVal>>,
+{
+    type WitnessVariable = SP1ForeignWitnessVariable<C, BabyBearPoseidon2>;
+
+    fn read(&self, builder: &mut Builder<C>) -> Self::WitnessVariable {
+        let vk = self.vk.read(builder);
+        let shard_proofs = self.shard_proofs.read(builder);
+        let vk_root = self.vk_root.read(builder);
+        SP1ForeignWitnessVariable { vk, shard_proofs, vk_root }
+    }
+
+    fn write(&self, witness: &mut impl WitnessWriter<C>) {
+        self.vk.write(witness);
+        self.shard_proofs.write(witness);
+        self.vk_root.write(witness);
+    }
+}
+
 impl<C: CircuitConfig, HV: FieldHasherVariable<C>> Witnessable<C> for MerkleProof<C::F, HV>
 where
     HV::Digest: Witnessable<C, WitnessVariable = HV::DigestVariable>,
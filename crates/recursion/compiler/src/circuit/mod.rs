@@ -1,10 +1,14 @@
 mod builder;
 mod compiler;
 mod config;
+mod optimize;
+mod profile;
 
 pub use builder::*;
 pub use compiler::*;
 pub use config::*;
+pub use optimize::*;
+pub use profile::*;
 
 #[cfg(test)]
 mod tests {
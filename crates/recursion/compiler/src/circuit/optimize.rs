@@ -0,0 +1,430 @@
+//! Optimization passes over the operations of a recursion program, run before compilation.
+//!
+//! The passes rely on every variable being written exactly once, which [AsmCompiler] enforces. They
+//! only handle the operations [AsmCompiler] compiles; a program with any other operation is
+//! returned as is.
+
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+    mem::Discriminant,
+};
+
+use core::fmt::Debug;
+
+use p3_field::{Field, PrimeField, TwoAdicField};
+use sp1_recursion_core::air::RecursionPublicValues;
+
+use crate::prelude::*;
+
+use super::{chip_rows, Imm, ProgramProfile};
+
+/// The number of operations removed by each pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OptimizationStats {
+    /// Operations that recomputed the result of an earlier one.
+    pub common_subexpressions: usize,
+    /// Operations whose results were never used.
+    pub dead_operations: usize,
+}
+
+/// The effect of the passes on the rows of each chip.
+#[derive(Debug, Clone)]
+pub struct OptimizationReport {
+    pub stats: OptimizationStats,
+    pub before: ProgramProfile,
+    pub after: ProgramProfile,
+}
+
+impl std::fmt::Display for OptimizationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "removed {} common subexpressions and {} dead operations",
+            self.stats.common_subexpressions, self.stats.dead_operations
+        )?;
+        writeln!(f, "{:<20}{:>12}{:>12}", "chip", "before", "after")?;
+        for ((chip, before), (_, after)) in
+            chip_rows(&self.before.total).into_iter().zip(chip_rows(&self.after.total))
+        {
+            writeln!(f, "{chip:<20}{before:>12}{after:>12}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a variable is read or written by an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+}
+
+/// Runs common-subexpression elimination, then dead-code elimination.
+pub fn optimize<F: Field, C: Config<N = F, F = F>>(
+    operations: TracedVec<DslIr<C>>,
+) -> (TracedVec<DslIr<C>>, OptimizationStats) {
+    let (operations, common_subexpressions) = eliminate_common_subexpressions(operations);
+    let (operations, dead_operations) = eliminate_dead_code(operations);
+    (operations, OptimizationStats { common_subexpressions, dead_operations })
+}
+
+/// Runs [optimize], profiling the program before and after the passes.
+pub fn optimize_with_report<F, C>(
+    operations: TracedVec<DslIr<C>>,
+) -> (TracedVec<DslIr<C>>, OptimizationReport)
+where
+    F: PrimeField + TwoAdicField,
+    C: Config<N = F, F = F> + Debug,
+{
+    let before = ProgramProfile::new(operations.clone());
+    let (operations, stats) = optimize(operations);
+    let after = ProgramProfile::new(operations.clone());
+    (operations, OptimizationReport { stats, before, after })
+}
+
+/// Removes the operations that compute the same result as an earlier one, reading the earlier
+/// result in their place. Returns the number of operations removed.
+pub fn eliminate_common_subexpressions<F: Field, C: Config<N = F, F = F>>(
+    mut operations: TracedVec<DslIr<C>>,
+) -> (TracedVec<DslIr<C>>, usize) {
+    if !is_supported(&mut operations) {
+        return (operations, 0);
+    }
+
+    let mut renamed = HashMap::<u32, u32>::new();
+    let mut computed =
+        HashMap::<(Discriminant<DslIr<C>>, Vec<u32>, Vec<Imm<F, C::EF>>), Vec<u32>>::new();
+    let mut optimized = TracedVec::new();
+    let mut removed = 0;
+    for (mut op, trace) in operations {
+        let (mut reads, mut writes) = (Vec::new(), Vec::new());
+        visit(&mut op, &mut |access, idx| match access {
+            Access::Read => {
+                if let Some(&new_idx) = renamed.get(idx) {
+                    *idx = new_idx;
+                }
+                reads.push(*idx);
+            }
+            Access::Write => writes.push(*idx),
+        });
+
+        if let Some(imms) = immediates(&op) {
+            if is_commutative(&op) {
+                reads.sort_unstable();
+            }
+            match computed.get(&(std::mem::discriminant(&op), reads.clone(), imms.clone())) {
+                Some(earlier) => {
+                    renamed.extend(writes.into_iter().zip(earlier.iter().copied()));
+                    removed += 1;
+                    continue;
+                }
+                None => {
+                    computed.insert((std::mem::discriminant(&op), reads, imms), writes);
+                }
+            }
+        }
+        optimized.vec.push(op);
+        optimized.traces.push(trace);
+    }
+    (optimized, removed)
+}
+
+/// Removes the operations without side effects whose results are never read. Returns the number
+/// of operations removed.
+pub fn eliminate_dead_code<F: Field, C: Config<N = F, F = F>>(
+    mut operations: TracedVec<DslIr<C>>,
+) -> (TracedVec<DslIr<C>>, usize) {
+    if !is_supported(&mut operations) {
+        return (operations, 0);
+    }
+
+    let mut live = HashSet::<u32>::new();
+    let mut keep = vec![true; operations.vec.len()];
+    for (op, keep) in operations.vec.iter_mut().zip(keep.iter_mut()).rev() {
+        if is_removable(op) {
+            let mut is_live = false;
+            visit(op, &mut |access, idx| {
+                is_live |= access == Access::Write && live.contains(&*idx)
+            });
+            if !is_live {
+                *keep = false;
+                continue;
+            }
+        }
+        visit(op, &mut |access, idx| {
+            if access == Access::Read {
+                live.insert(*idx);
+            }
+        });
+    }
+
+    let removed = keep.iter().filter(|keep| !**keep).count();
+    let mut optimized = TracedVec::new();
+    optimized.extend(operations.into_iter().zip(keep).filter(|(_, keep)| *keep).map(|(op, _)| op));
+    (optimized, removed)
+}
+
+fn is_supported<C: Config>(operations: &mut TracedVec<DslIr<C>>) -> bool {
+    operations.vec.iter_mut().all(|op| visit(op, &mut |_, _| {}))
+}
+
+/// Calls `f` on the index of each variable the operation accesses. Returns `false` if the operation
+/// is not one the passes handle.
+fn visit<C: Config>(op: &mut DslIr<C>, f: &mut impl FnMut(Access, &mut u32)) -> bool {
+    use Access::*;
+
+    match op {
+        DslIr::ImmV(dst, _) => f(Write, &mut dst.idx),
+        DslIr::ImmF(dst, _) => f(Write, &mut dst.idx),
+        DslIr::ImmE(dst, _) => f(Write, &mut dst.idx),
+
+        DslIr::AddV(dst, lhs, rhs) | DslIr::SubV(dst, lhs, rhs) | DslIr::MulV(dst, lhs, rhs) => {
+            f(Read, &mut lhs.idx);
+            f(Read, &mut rhs.idx);
+            f(Write, &mut dst.idx);
+        }
+        DslIr::AddF(dst, lhs, rhs)
+        | DslIr::SubF(dst, lhs, rhs)
+        | DslIr::MulF(dst, lhs, rhs)
+        | DslIr::DivF(dst, lhs, rhs) => {
+            f(Read, &mut lhs.idx);
+            f(Read, &mut rhs.idx);
+            f(Write, &mut dst.idx);
+        }
+        DslIr::AddE(dst, lhs, rhs)
+        | DslIr::SubE(dst, lhs, rhs)
+        | DslIr::MulE(dst, lhs, rhs)
+        | DslIr::DivE(dst, lhs, rhs) => {
+            f(Read, &mut lhs.idx);
+            f(Read, &mut rhs.idx);
+            f(Write, &mut dst.idx);
+        }
+        DslIr::AddEF(dst, lhs, rhs)
+        | DslIr::SubEF(dst, lhs, rhs)
+        | DslIr::MulEF(dst, lhs, rhs)
+        | DslIr::DivEF(dst, lhs, rhs) => {
+            f(Read, &mut lhs.idx);
+            f(Read, &mut rhs.idx);
+            f(Write, &mut dst.idx);
+        }
+
+        DslIr::AddVI(dst, src, _)
+        | DslIr::SubVI(dst, src, _)
+        | DslIr::SubVIN(dst, _, src)
+        | DslIr::MulVI(dst, src, _)
+        | DslIr::NegV(dst, src)
+        | DslIr::InvV(dst, src) => {
+            f(Read, &mut src.idx);
+            f(Write, &mut dst.idx);
+        }
+        DslIr::AddFI(dst, src, _)
+        | DslIr::SubFI(dst, src, _)
+        | DslIr::SubFIN(dst, _, src)
+        | DslIr::MulFI(dst, src, _)
+        | DslIr::DivFI(dst, src, _)
+        | DslIr::DivFIN(dst, _, src)
+        | DslIr::NegF(dst, src)
+        | DslIr::InvF(dst, src) => {
+            f(Read, &mut src.idx);
+            f(Write, &mut dst.idx);
+        }
+        DslIr::AddEI(dst, src, _)
+        | DslIr::AddEFI(dst, src, _)
+        | DslIr::SubEI(dst, src, _)
+        | DslIr::SubEIN(dst, _, src)
+        | DslIr::SubEFI(dst, src, _)
+        | DslIr::MulEI(dst, src, _)
+        | DslIr::MulEFI(dst, src, _)
+        | DslIr::DivEI(dst, src, _)
+        | DslIr::DivEIN(dst, _, src)
+        | DslIr::DivEFI(dst, src, _)
+        | DslIr::DivEFIN(dst, _, src)
+        | DslIr::NegE(dst, src)
+        | DslIr::InvE(dst, src) => {
+            f(Read, &mut src.idx);
+            f(Write, &mut dst.idx);
+        }
+        DslIr::AddEFFI(dst, src, _) => {
+            f(Read, &mut src.idx);
+            f(Write, &mut dst.idx);
+        }
+
+        DslIr::Select(bit, dst1, dst2, lhs, rhs) => {
+            f(Read, &mut bit.idx);
+            f(Read, &mut lhs.idx);
+            f(Read, &mut rhs.idx);
+            f(Write, &mut dst1.idx);
+            f(Write, &mut dst2.idx);
+        }
+
+        DslIr::AssertEqV(lhs, rhs) | DslIr::AssertNeV(lhs, rhs) => {
+            f(Read, &mut lhs.idx);
+            f(Read, &mut rhs.idx);
+        }
+        DslIr::AssertEqF(lhs, rhs) | DslIr::AssertNeF(lhs, rhs) => {
+            f(Read, &mut lhs.idx);
+            f(Read, &mut rhs.idx);
+        }
+        DslIr::AssertEqE(lhs, rhs) | DslIr::AssertNeE(lhs, rhs) => {
+            f(Read, &mut lhs.idx);
+            f(Read, &mut rhs.idx);
+        }
+        DslIr::AssertEqVI(lhs, _) | DslIr::AssertNeVI(lhs, _) => f(Read, &mut lhs.idx),
+        DslIr::AssertEqFI(lhs, _) | DslIr::AssertNeFI(lhs, _) => f(Read, &mut lhs.idx),
+        DslIr::AssertEqEI(lhs, _) | DslIr::AssertNeEI(lhs, _) => f(Read, &mut lhs.idx),
+
+        DslIr::CircuitV2Poseidon2PermuteBabyBear(data) => {
+            let (output, input) = data.as_mut();
+            input.iter_mut().for_each(|x| f(Read, &mut x.idx));
+            output.iter_mut().for_each(|x| f(Write, &mut x.idx));
+        }
+        DslIr::CircuitV2ExpReverseBits(dst, base, exp) => {
+            f(Read, &mut base.idx);
+            exp.iter_mut().for_each(|x| f(Read, &mut x.idx));
+            f(Write, &mut dst.idx);
+        }
+        DslIr::CircuitV2HintBitsF(output, value) => {
+            f(Read, &mut value.idx);
+            output.iter_mut().for_each(|x| f(Write, &mut x.idx));
+        }
+        DslIr::CircuitV2FriFold(data) => {
+            let (output, input) = data.as_mut();
+            f(Read, &mut input.z.idx);
+            f(Read, &mut input.alpha.idx);
+            f(Read, &mut input.x.idx);
+            input
+                .mat_opening
+                .iter_mut()
+                .chain(input.ps_at_z.iter_mut())
+                .chain(input.alpha_pow_input.iter_mut())
+                .chain(input.ro_input.iter_mut())
+                .for_each(|x| f(Read, &mut x.idx));
+            output
+                .alpha_pow_output
+                .iter_mut()
+                .chain(output.ro_output.iter_mut())
+                .for_each(|x| f(Write, &mut x.idx));
+        }
+        DslIr::CircuitV2BatchFRI(data) => {
+            let (acc, alpha_pows, p_at_zs, p_at_xs) = data.as_mut();
+            alpha_pows.iter_mut().chain(p_at_zs.iter_mut()).for_each(|x| f(Read, &mut x.idx));
+            p_at_xs.iter_mut().for_each(|x| f(Read, &mut x.idx));
+            f(Write, &mut acc.idx);
+        }
+        DslIr::CircuitV2CommitPublicValues(public_values) => {
+            let mut array = public_values.as_array();
+            array.iter_mut().for_each(|x| f(Read, &mut x.idx));
+            let renamed: &RecursionPublicValues<_> = array.as_slice().borrow();
+            **public_values = *renamed;
+        }
+
+        DslIr::PrintV(dst) => f(Read, &mut dst.idx),
+        DslIr::PrintF(dst) => f(Read, &mut dst.idx),
+        DslIr::PrintE(dst) => f(Read, &mut dst.idx),
+        DslIr::CircuitV2HintFelts(output) => output.iter_mut().for_each(|x| f(Write, &mut x.idx)),
+        DslIr::CircuitV2HintExts(output) => output.iter_mut().for_each(|x| f(Write, &mut x.idx)),
+        DslIr::CircuitExt2Felt(felts, ext) => {
+            f(Read, &mut ext.idx);
+            felts.iter_mut().for_each(|x| f(Write, &mut x.idx));
+        }
+        DslIr::ReduceE(ext) => f(Read, &mut ext.idx),
+        DslIr::CycleTrackerV2Enter(_) | DslIr::CycleTrackerV2Exit => {}
+        _ => return false,
+    }
+    true
+}
+
+/// The immediates of an operation whose results only depend on its inputs, or `None` if the
+/// operation has other effects.
+fn immediates<F: Field, C: Config<N = F, F = F>>(op: &DslIr<C>) -> Option<Vec<Imm<F, C::EF>>> {
+    let imm = match op {
+        DslIr::ImmV(_, x)
+        | DslIr::ImmF(_, x)
+        | DslIr::AddVI(_, _, x)
+        | DslIr::SubVI(_, _, x)
+        | DslIr::SubVIN(_, x, _)
+        | DslIr::MulVI(_, _, x)
+        | DslIr::AddFI(_, _, x)
+        | DslIr::SubFI(_, _, x)
+        | DslIr::SubFIN(_, x, _)
+        | DslIr::MulFI(_, _, x)
+        | DslIr::DivFI(_, _, x)
+        | DslIr::DivFIN(_, x, _)
+        | DslIr::AddEFI(_, _, x)
+        | DslIr::SubEFI(_, _, x)
+        | DslIr::MulEFI(_, _, x)
+        | DslIr::DivEFI(_, _, x)
+        | DslIr::DivEFIN(_, x, _) => Imm::F(*x),
+        DslIr::ImmE(_, x)
+        | DslIr::AddEI(_, _, x)
+        | DslIr::AddEFFI(_, _, x)
+        | DslIr::SubEI(_, _, x)
+        | DslIr::SubEIN(_, x, _)
+        | DslIr::MulEI(_, _, x)
+        | DslIr::DivEI(_, _, x)
+        | DslIr::DivEIN(_, x, _) => Imm::EF(*x),
+        DslIr::AddV(..)
+        | DslIr::SubV(..)
+        | DslIr::MulV(..)
+        | DslIr::AddF(..)
+        | DslIr::SubF(..)
+        | DslIr::MulF(..)
+        | DslIr::DivF(..)
+        | DslIr::AddE(..)
+        | DslIr::SubE(..)
+        | DslIr::MulE(..)
+        | DslIr::DivE(..)
+        | DslIr::AddEF(..)
+        | DslIr::SubEF(..)
+        | DslIr::MulEF(..)
+        | DslIr::DivEF(..)
+        | DslIr::NegV(..)
+        | DslIr::NegF(..)
+        | DslIr::NegE(..)
+        | DslIr::InvV(..)
+        | DslIr::InvF(..)
+        | DslIr::InvE(..)
+        | DslIr::Select(..)
+        | DslIr::CircuitV2Poseidon2PermuteBabyBear(_)
+        | DslIr::CircuitV2ExpReverseBits(..)
+        | DslIr::CircuitV2FriFold(_)
+        | DslIr::CircuitV2BatchFRI(_)
+        | DslIr::CircuitExt2Felt(..) => return Some(vec![]),
+        _ => return None,
+    };
+    Some(vec![imm])
+}
+
+fn is_commutative<C: Config>(op: &DslIr<C>) -> bool {
+    matches!(
+        op,
+        DslIr::AddV(..)
+            | DslIr::MulV(..)
+            | DslIr::AddF(..)
+            | DslIr::MulF(..)
+            | DslIr::AddE(..)
+            | DslIr::MulE(..)
+    )
+}
+
+/// Whether the operation can be removed when its results are unused. Divisions and inversions are
+/// kept, since they fail on a zero divisor.
+fn is_removable<F: Field, C: Config<N = F, F = F>>(op: &DslIr<C>) -> bool {
+    let is_division = matches!(
+        op,
+        DslIr::DivF(..)
+            | DslIr::DivFI(..)
+            | DslIr::DivFIN(..)
+            | DslIr::DivE(..)
+            | DslIr::DivEI(..)
+            | DslIr::DivEIN(..)
+            | DslIr::DivEF(..)
+            | DslIr::DivEFI(..)
+            | DslIr::DivEFIN(..)
+            | DslIr::InvV(..)
+            | DslIr::InvF(..)
+            | DslIr::InvE(..)
+    );
+    !is_division && immediates(op).is_some()
+}
@@ -0,0 +1,214 @@
+//! Attributes the rows of a recursion program to the code that emitted them.
+//!
+//! Each instruction is attributed to the cycle tracker span it is emitted in, and to the first
+//! frame of its backtrace outside of the builder. Backtraces are only recorded when `SP1_DEBUG` is
+//! set, so call sites are otherwise reported as [UNKNOWN_CALL_SITE].
+
+use std::{collections::BTreeMap, fmt::Debug};
+
+use p3_field::{PrimeField, TwoAdicField};
+use sp1_recursion_core::{
+    chips::{
+        alu_base::NUM_BASE_ALU_ENTRIES_PER_ROW,
+        alu_ext::NUM_EXT_ALU_ENTRIES_PER_ROW,
+        mem::{constant::NUM_CONST_MEM_ENTRIES_PER_ROW, variable::NUM_VAR_MEM_ENTRIES_PER_ROW},
+    },
+    machine::RecursionAirEventCount,
+    runtime::debugger::call_site,
+};
+
+use crate::prelude::*;
+
+use super::{AsmCompiler, CompileOneErr};
+
+/// The span and call site of the instructions outside of any cycle tracker span, or without a
+/// backtrace.
+pub const UNKNOWN_CALL_SITE: &str = "<unknown>";
+
+/// The span and call site the constants are attributed to, as they are shared by the whole
+/// program.
+pub const CONSTANTS_CALL_SITE: &str = "<constants>";
+
+/// The events of a recursion program, in total and grouped by span and by call site. The rows they
+/// take in each chip are given by [chip_rows].
+#[derive(Debug, Clone, Default)]
+pub struct ProgramProfile {
+    /// The events of the whole program.
+    pub total: RecursionAirEventCount,
+    /// The events of each cycle tracker span, with the names of the enclosing spans joined by `/`.
+    pub spans: BTreeMap<String, RecursionAirEventCount>,
+    /// The events of each call site, as `file:line`.
+    pub call_sites: BTreeMap<String, RecursionAirEventCount>,
+}
+
+impl ProgramProfile {
+    /// Compiles the operations and attributes the instructions to their span and call site.
+    pub fn new<F, C>(operations: TracedVec<DslIr<C>>) -> Self
+    where
+        F: PrimeField + TwoAdicField,
+        C: Config<N = F, F = F> + Debug,
+    {
+        let mut compiler = AsmCompiler::<C>::default();
+        let mut profile = Self::default();
+        let mut span_stack = Vec::<String>::new();
+        for (op, trace) in operations {
+            let call_site = trace.and_then(call_site);
+            let call_site = call_site.as_deref().unwrap_or(UNKNOWN_CALL_SITE);
+            compiler.compile_one(op, |item| match item {
+                Ok(instr) => {
+                    let span = span_stack.last().map(String::as_str).unwrap_or(UNKNOWN_CALL_SITE);
+                    profile.record(span, call_site, |count| *count += &instr);
+                }
+                Err(CompileOneErr::CycleTrackerEnter(name)) => {
+                    let span = match span_stack.last() {
+                        Some(parent) => format!("{parent}/{name}"),
+                        None => name,
+                    };
+                    span_stack.push(span);
+                }
+                Err(CompileOneErr::CycleTrackerExit) => {
+                    span_stack.pop();
+                }
+                Err(CompileOneErr::Unsupported(instr)) => {
+                    panic!("unsupported instruction: {instr:?}\ncall site: {call_site}")
+                }
+            });
+        }
+        let consts = compiler.consts.len();
+        profile.record(CONSTANTS_CALL_SITE, CONSTANTS_CALL_SITE, |count| {
+            count.mem_const_events += consts
+        });
+        profile
+    }
+
+    fn record(&mut self, span: &str, call_site: &str, f: impl Fn(&mut RecursionAirEventCount)) {
+        f(&mut self.total);
+        f(self.spans.entry(span.to_string()).or_default());
+        f(self.call_sites.entry(call_site.to_string()).or_default());
+    }
+
+    /// The number of rows of each chip in the program.
+    pub fn rows(&self) -> Vec<(&'static str, usize)> {
+        chip_rows(&self.total)
+    }
+}
+
+/// The rows of each chip for the given events. The chips that pack several events in a row are
+/// rounded up, so the rows of the groups of a profile can add up to more than the total.
+pub fn chip_rows(count: &RecursionAirEventCount) -> Vec<(&'static str, usize)> {
+    vec![
+        ("MemoryConst", count.mem_const_events.div_ceil(NUM_CONST_MEM_ENTRIES_PER_ROW)),
+        ("MemoryVar", count.mem_var_events.div_ceil(NUM_VAR_MEM_ENTRIES_PER_ROW)),
+        ("BaseAlu", count.base_alu_events.div_ceil(NUM_BASE_ALU_ENTRIES_PER_ROW)),
+        ("ExtAlu", count.ext_alu_events.div_ceil(NUM_EXT_ALU_ENTRIES_PER_ROW)),
+        ("Poseidon2", count.poseidon2_wide_events),
+        ("FriFold", count.fri_fold_events),
+        ("BatchFRI", count.batch_fri_events),
+        ("Select", count.select_events),
+        ("ExpReverseBitsLen", count.exp_reverse_bits_len_events),
+    ]
+}
+
+impl std::fmt::Display for ProgramProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let header = chip_rows(&self.total).into_iter().map(|(chip, _)| chip).collect::<Vec<_>>();
+        let mut table = |title: &str, groups: Vec<(&str, &RecursionAirEventCount)>| {
+            writeln!(
+                f,
+                "{title:<60}{}",
+                header.iter().map(|c| format!("{c:>18}")).collect::<String>()
+            )?;
+            for (name, count) in groups {
+                let row = chip_rows(count).into_iter().map(|(_, n)| format!("{n:>18}"));
+                writeln!(f, "{name:<60}{}", row.collect::<String>())?;
+            }
+            writeln!(f)
+        };
+
+        let by_rows = |groups: &BTreeMap<String, RecursionAirEventCount>| {
+            let mut groups =
+                groups.iter().map(|(name, count)| (name.as_str(), count)).collect::<Vec<_>>();
+            groups.sort_by_key(|(_, count)| {
+                std::cmp::Reverse(chip_rows(count).iter().map(|(_, n)| n).sum::<usize>())
+            });
+            groups
+        };
+        table("total", vec![("", &self.total)])?;
+        table("span", by_rows(&self.spans))?;
+        table("call site", by_rows(&self.call_sites))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use p3_baby_bear::DiffusionMatrixBabyBear;
+    use p3_field::AbstractField;
+    use sp1_recursion_core::Runtime;
+    use sp1_stark::{BabyBearPoseidon2Inner, StarkGenericConfig};
+
+    use crate::circuit::{optimize_with_report, AsmBuilder, CircuitV2Builder, OptimizationReport};
+
+    use super::*;
+
+    type SC = BabyBearPoseidon2Inner;
+    type F = <SC as StarkGenericConfig>::Val;
+    type EF = <SC as StarkGenericConfig>::Challenge;
+
+    #[test]
+    fn test_profile_and_optimize() {
+        let mut builder = AsmBuilder::<F, EF>::default();
+        let inputs = builder.hint_felts_v2(16);
+        let input: [Felt<F>; 16] = inputs.try_into().unwrap();
+
+        builder.cycle_tracker_v2_enter("hash".to_string());
+        let first = builder.poseidon2_permute_v2(input);
+        let second = builder.poseidon2_permute_v2(input);
+        builder.cycle_tracker_v2_exit();
+
+        builder.cycle_tracker_v2_enter("arithmetic".to_string());
+        let sum: Felt<_> = builder.eval(first[0] + second[1]);
+        let same_sum: Felt<_> = builder.eval(second[1] + first[0]);
+        builder.assert_felt_eq(sum, same_sum);
+        let _unused: Felt<_> = builder.eval(first[2] * first[3]);
+        builder.cycle_tracker_v2_exit();
+
+        let (optimized, report) = optimize_with_report(builder.into_operations());
+        let OptimizationReport { stats, before, after } = &report;
+        assert_eq!(before.total.poseidon2_wide_events, 2);
+        assert_eq!(before.spans["hash"].poseidon2_wide_events, 2);
+        assert_eq!(before.spans["arithmetic"].base_alu_events, 5);
+        assert_eq!(before.spans[UNKNOWN_CALL_SITE].mem_var_events, 16);
+
+        assert_eq!(stats.common_subexpressions, 2);
+        assert_eq!(stats.dead_operations, 1);
+        assert_eq!(after.total.poseidon2_wide_events, 1);
+        assert_eq!(after.spans["arithmetic"].base_alu_events, 3);
+
+        let columns = |line: &str| line.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+        let report = report.to_string();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "removed 2 common subexpressions and 1 dead operations");
+        assert_eq!(columns(lines[1]), ["chip", "before", "after"]);
+        assert!(lines.iter().any(|line| columns(line) == ["Poseidon2", "2", "1"]), "{report}");
+
+        let profile = before.to_string();
+        let lines = profile.lines().collect::<Vec<_>>();
+        let header = columns(lines[0]);
+        assert_eq!(header[0], "total");
+        let poseidon2 = header.iter().position(|chip| chip == "Poseidon2").unwrap();
+        let hash = lines
+            .iter()
+            .map(|line| columns(line))
+            .find(|row| row.first().is_some_and(|name| name == "hash"))
+            .unwrap();
+        assert_eq!(hash[poseidon2], "2", "{profile}");
+
+        // The optimized program still runs.
+        let program = Arc::new(AsmCompiler::default().compile(optimized));
+        let mut runtime = Runtime::<F, EF, DiffusionMatrixBabyBear>::new(program, SC::new().perm);
+        runtime.witness_stream = (0..16).map(|i| F::from_canonical_u32(i).into()).collect();
+        runtime.run().unwrap();
+    }
+}
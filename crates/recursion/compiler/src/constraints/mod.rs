@@ -1,4 +1,5 @@
 pub mod opcodes;
+pub mod r1cs;
 
 use core::fmt::Debug;
 use p3_field::{AbstractExtensionField, PrimeField};
@@ -0,0 +1,1223 @@
+//! Export of the outer circuit as a rank-1 constraint system.
+//!
+//! [R1csCompiler] lowers the [Constraint]s that gnark consumes into an R1CS over BN254. It follows
+//! the circuit in `sp1-recursion-gnark-ffi` opcode for opcode, including the lazy reduction of
+//! BabyBear elements and the bit decomposition range checks of its Groth16 build, and solves the
+//! witness along the way. Both can then be written in the iden3 `.r1cs` and `.wtns` formats and used
+//! with other SNARK backends and audit tools.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+};
+
+use num_bigint::BigUint;
+use p3_baby_bear::BabyBear;
+use p3_bn254_fr::Bn254Fr;
+use p3_field::{
+    extension::BinomialExtensionField, AbstractExtensionField, AbstractField, Field, PrimeField,
+    PrimeField32,
+};
+use sp1_primitives::RC_16_30_U32;
+use sp1_recursion_core::stark::bn254_poseidon2_rc3;
+
+use super::{opcodes::ConstraintOpcode, Constraint};
+use crate::{config::OuterConfig, ir::Witness};
+
+/// The number of terms above which a linear combination is moved into a new wire.
+const MAX_TERMS: usize = 64;
+
+const BABYBEAR_MODULUS: u64 = 2013265921;
+
+/// A linear combination of wires, as a list of `(wire, coefficient)` pairs sorted by wire. Wire 0
+/// is the constant one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinearCombination(pub Vec<(usize, Bn254Fr)>);
+
+impl LinearCombination {
+    fn constant(value: Bn254Fr) -> Self {
+        if value.is_zero() {
+            Self::default()
+        } else {
+            Self(vec![(0, value)])
+        }
+    }
+
+    fn wire(wire: usize) -> Self {
+        Self(vec![(wire, Bn254Fr::one())])
+    }
+
+    fn is_constant(&self) -> bool {
+        self.0.iter().all(|(wire, _)| *wire == 0)
+    }
+
+    fn scale(&self, scalar: Bn254Fr) -> Self {
+        if scalar.is_zero() {
+            return Self::default();
+        }
+        Self(self.0.iter().map(|(wire, coeff)| (*wire, *coeff * scalar)).collect())
+    }
+
+    /// Returns `self + scalar * other`.
+    fn add_scaled(&self, other: &Self, scalar: Bn254Fr) -> Self {
+        let (lhs, rhs) = (&self.0, &other.0);
+        let mut terms = Vec::with_capacity(lhs.len() + rhs.len());
+        let (mut i, mut j) = (0, 0);
+        while i < lhs.len() || j < rhs.len() {
+            let term = match (lhs.get(i), rhs.get(j)) {
+                (Some(&(a, x)), Some(&(b, y))) if a == b => {
+                    i += 1;
+                    j += 1;
+                    (a, x + y * scalar)
+                }
+                (Some(&(a, x)), Some(&(b, _))) if a < b => {
+                    i += 1;
+                    (a, x)
+                }
+                (Some(&(a, x)), None) => {
+                    i += 1;
+                    (a, x)
+                }
+                (_, Some(&(b, y))) => {
+                    j += 1;
+                    (b, y * scalar)
+                }
+                (None, None) => unreachable!(),
+            };
+            if !term.1.is_zero() {
+                terms.push(term);
+            }
+        }
+        Self(terms)
+    }
+
+    /// Evaluates the linear combination on a full assignment of the wires.
+    pub fn evaluate(&self, witness: &[Bn254Fr]) -> Bn254Fr {
+        self.0.iter().map(|(wire, coeff)| witness[*wire] * *coeff).sum()
+    }
+}
+
+/// A constraint `a * b = c`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct R1csConstraint {
+    pub a: LinearCombination,
+    pub b: LinearCombination,
+    pub c: LinearCombination,
+}
+
+/// A rank-1 constraint system over BN254.
+///
+/// The wires are laid out as iden3 tools expect: the constant one, the public inputs (the vkey
+/// hash and the committed values digest), the private inputs of the [Witness] (its vars, felts and
+/// the four coefficients of each ext), and then the internal wires.
+#[derive(Debug, Clone)]
+pub struct R1cs {
+    pub num_wires: usize,
+    pub num_public_inputs: usize,
+    pub num_private_inputs: usize,
+    pub constraints: Vec<R1csConstraint>,
+}
+
+impl R1cs {
+    /// Checks that the witness satisfies every constraint, returning the index of the first one
+    /// that it doesn't.
+    pub fn check(&self, witness: &[Bn254Fr]) -> Result<(), usize> {
+        assert_eq!(witness.len(), self.num_wires, "the witness has the wrong number of wires");
+        match self.constraints.iter().position(|constraint| {
+            constraint.a.evaluate(witness) * constraint.b.evaluate(witness)
+                != constraint.c.evaluate(witness)
+        }) {
+            Some(index) => Err(index),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the constraint system in the iden3 `.r1cs` binary format.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(b"r1cs")?;
+        writer.write_all(&1u32.to_le_bytes())?;
+        writer.write_all(&3u32.to_le_bytes())?;
+
+        // The header section.
+        write_section_header(&mut writer, 1, 4 + 32 + 4 * 4 + 8 + 4)?;
+        writer.write_all(&32u32.to_le_bytes())?;
+        write_biguint(&mut writer, &Bn254Fr::order())?;
+        writer.write_all(&(self.num_wires as u32).to_le_bytes())?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(&(self.num_public_inputs as u32).to_le_bytes())?;
+        writer.write_all(&(self.num_private_inputs as u32).to_le_bytes())?;
+        writer.write_all(&(self.num_wires as u64).to_le_bytes())?;
+        writer.write_all(&(self.constraints.len() as u32).to_le_bytes())?;
+
+        // The constraints section.
+        let size = self
+            .constraints
+            .iter()
+            .flat_map(|constraint| [&constraint.a, &constraint.b, &constraint.c])
+            .map(|lc| 4 + 36 * lc.0.len() as u64)
+            .sum();
+        write_section_header(&mut writer, 2, size)?;
+        for constraint in self.constraints.iter() {
+            for lc in [&constraint.a, &constraint.b, &constraint.c] {
+                writer.write_all(&(lc.0.len() as u32).to_le_bytes())?;
+                for (wire, coeff) in lc.0.iter() {
+                    writer.write_all(&(*wire as u32).to_le_bytes())?;
+                    write_biguint(&mut writer, &coeff.as_canonical_biguint())?;
+                }
+            }
+        }
+
+        // The wire to label section, which maps every wire to itself.
+        write_section_header(&mut writer, 3, 8 * self.num_wires as u64)?;
+        for wire in 0..self.num_wires as u64 {
+            writer.write_all(&wire.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a full assignment of the wires in the iden3 `.wtns` binary format.
+pub fn write_witness<W: Write>(witness: &[Bn254Fr], mut writer: W) -> io::Result<()> {
+    writer.write_all(b"wtns")?;
+    writer.write_all(&2u32.to_le_bytes())?;
+    writer.write_all(&2u32.to_le_bytes())?;
+
+    write_section_header(&mut writer, 1, 4 + 32 + 4)?;
+    writer.write_all(&32u32.to_le_bytes())?;
+    write_biguint(&mut writer, &Bn254Fr::order())?;
+    writer.write_all(&(witness.len() as u32).to_le_bytes())?;
+
+    write_section_header(&mut writer, 2, 32 * witness.len() as u64)?;
+    for value in witness.iter() {
+        write_biguint(&mut writer, &value.as_canonical_biguint())?;
+    }
+    Ok(())
+}
+
+fn write_section_header<W: Write>(writer: &mut W, section: u32, size: u64) -> io::Result<()> {
+    writer.write_all(&section.to_le_bytes())?;
+    writer.write_all(&size.to_le_bytes())
+}
+
+/// Writes a field element as 32 little-endian bytes.
+fn write_biguint<W: Write>(writer: &mut W, value: &BigUint) -> io::Result<()> {
+    let mut bytes = value.to_bytes_le();
+    bytes.resize(32, 0);
+    writer.write_all(&bytes)
+}
+
+fn bn254_from_biguint(value: &BigUint) -> Bn254Fr {
+    value.to_u32_digits().iter().rev().fold(Bn254Fr::zero(), |acc, digit| {
+        acc * Bn254Fr::from_canonical_u64(1 << 32) + Bn254Fr::from_canonical_u32(*digit)
+    })
+}
+
+fn bn254_from_str(value: &str) -> Bn254Fr {
+    bn254_from_biguint(&value.parse().expect("invalid field element"))
+}
+
+/// The value of a babybear element, which the circuit only reduces lazily.
+fn babybear_value(value: Bn254Fr) -> BabyBear {
+    let reduced = value.as_canonical_biguint() % BABYBEAR_MODULUS;
+    BabyBear::from_canonical_u64(reduced.to_u64_digits().first().copied().unwrap_or(0))
+}
+
+/// A linear combination of wires, along with its value.
+#[derive(Debug, Clone)]
+struct Signal {
+    lc: LinearCombination,
+    value: Bn254Fr,
+}
+
+/// A babybear element, held as a BN254 value that is reduced only once its upper bound gets large.
+#[derive(Debug, Clone)]
+struct Felt {
+    value: Signal,
+    upper_bound: BigUint,
+}
+
+type Ext = [Felt; 4];
+
+/// Lowers the constraints of the outer circuit into an [R1cs] and solves its witness.
+#[derive(Debug, Default)]
+pub struct R1csCompiler {
+    constraints: Vec<R1csConstraint>,
+    witness: Vec<Bn254Fr>,
+    booleans: HashSet<usize>,
+}
+
+impl R1csCompiler {
+    /// Compiles the constraints into an R1CS, returning it together with the full assignment of its
+    /// wires for the given witness.
+    pub fn compile(
+        constraints: &[Constraint],
+        witness: &Witness<OuterConfig>,
+    ) -> (R1cs, Vec<Bn254Fr>) {
+        let mut compiler = Self { witness: vec![Bn254Fr::one()], ..Default::default() };
+
+        // The public inputs.
+        let vkey_hash = compiler.new_wire(witness.vkey_hash);
+        let committed_values_digest = compiler.new_wire(witness.committed_values_digest);
+        let num_public_inputs = compiler.witness.len() - 1;
+
+        // The private inputs.
+        let input_vars: Vec<Signal> = witness.vars.iter().map(|v| compiler.new_wire(*v)).collect();
+        let input_felts: Vec<Felt> =
+            witness.felts.iter().map(|f| compiler.new_felt_wire(*f)).collect();
+        let input_exts: Vec<Ext> = witness
+            .exts
+            .iter()
+            .map(|e| {
+                let base = e.as_base_slice();
+                core::array::from_fn(|i| compiler.new_felt_wire(base[i]))
+            })
+            .collect();
+        let num_private_inputs = compiler.witness.len() - 1 - num_public_inputs;
+
+        // Range check the felts of the witness.
+        for felt in input_felts.iter().chain(input_exts.iter().flatten()) {
+            compiler.to_binary(&felt.value, 31);
+        }
+
+        let mut vars: HashMap<String, Signal> = HashMap::new();
+        let mut felts: HashMap<String, Felt> = HashMap::new();
+        let mut exts: HashMap<String, Ext> = HashMap::new();
+        let var = |vars: &HashMap<String, Signal>, id: &str| {
+            vars.get(id).unwrap_or_else(|| panic!("unknown var {id}")).clone()
+        };
+        let felt = |felts: &HashMap<String, Felt>, id: &str| {
+            felts.get(id).unwrap_or_else(|| panic!("unknown felt {id}")).clone()
+        };
+        let ext = |exts: &HashMap<String, Ext>, id: &str| {
+            exts.get(id).unwrap_or_else(|| panic!("unknown ext {id}")).clone()
+        };
+        let index = |arg: &str| arg.parse::<usize>().expect("invalid witness index");
+
+        for cs in constraints.iter() {
+            let args = &cs.args;
+            let a = || args[0][0].clone();
+            match cs.opcode {
+                ConstraintOpcode::ImmV => {
+                    vars.insert(a(), constant(bn254_from_str(&args[1][0])));
+                }
+                ConstraintOpcode::ImmF => {
+                    felts.insert(a(), new_f(bn254_from_str(&args[1][0])));
+                }
+                ConstraintOpcode::ImmE => {
+                    exts.insert(a(), core::array::from_fn(|i| new_f(bn254_from_str(&args[1][i]))));
+                }
+                ConstraintOpcode::AddV => {
+                    let sum = compiler.add(&var(&vars, &args[1][0]), &var(&vars, &args[2][0]));
+                    vars.insert(a(), sum);
+                }
+                ConstraintOpcode::AddF => {
+                    let sum =
+                        compiler.add_f(&felt(&felts, &args[1][0]), &felt(&felts, &args[2][0]));
+                    felts.insert(a(), sum);
+                }
+                ConstraintOpcode::AddE => {
+                    let sum = compiler.add_e(&ext(&exts, &args[1][0]), &ext(&exts, &args[2][0]));
+                    exts.insert(a(), sum);
+                }
+                ConstraintOpcode::AddEF => {
+                    let mut sum = ext(&exts, &args[1][0]);
+                    sum[0] = compiler.add_f(&sum[0], &felt(&felts, &args[2][0]));
+                    exts.insert(a(), sum);
+                }
+                ConstraintOpcode::SubV => {
+                    let diff = compiler.sub(&var(&vars, &args[1][0]), &var(&vars, &args[2][0]));
+                    vars.insert(a(), diff);
+                }
+                ConstraintOpcode::SubF => {
+                    let diff =
+                        compiler.sub_f(&felt(&felts, &args[1][0]), &felt(&felts, &args[2][0]));
+                    felts.insert(a(), diff);
+                }
+                ConstraintOpcode::SubE => {
+                    let lhs = ext(&exts, &args[1][0]);
+                    let rhs = ext(&exts, &args[2][0]);
+                    let diff = core::array::from_fn(|i| compiler.sub_f(&lhs[i], &rhs[i]));
+                    exts.insert(a(), diff);
+                }
+                ConstraintOpcode::SubEF => {
+                    let mut diff = ext(&exts, &args[1][0]);
+                    diff[0] = compiler.sub_f(&diff[0], &felt(&felts, &args[2][0]));
+                    exts.insert(a(), diff);
+                }
+                ConstraintOpcode::MulV => {
+                    let product = compiler.mul(&var(&vars, &args[1][0]), &var(&vars, &args[2][0]));
+                    vars.insert(a(), product);
+                }
+                ConstraintOpcode::MulF => {
+                    let product = compiler.mul_f(
+                        &felt(&felts, &args[1][0]),
+                        &felt(&felts, &args[2][0]),
+                        true,
+                    );
+                    felts.insert(a(), product);
+                }
+                ConstraintOpcode::MulE => {
+                    let product =
+                        compiler.mul_e(&ext(&exts, &args[1][0]), &ext(&exts, &args[2][0]));
+                    exts.insert(a(), product);
+                }
+                ConstraintOpcode::MulEF => {
+                    let product =
+                        compiler.mul_ef(&ext(&exts, &args[1][0]), &felt(&felts, &args[2][0]));
+                    exts.insert(a(), product);
+                }
+                ConstraintOpcode::DivF => {
+                    let inverse = compiler.inv_f(&felt(&felts, &args[2][0]));
+                    let quotient = compiler.mul_f(&felt(&felts, &args[1][0]), &inverse, true);
+                    felts.insert(a(), quotient);
+                }
+                ConstraintOpcode::DivE => {
+                    let inverse = compiler.inv_e(&ext(&exts, &args[2][0]));
+                    let quotient = compiler.mul_e(&ext(&exts, &args[1][0]), &inverse);
+                    exts.insert(a(), quotient);
+                }
+                ConstraintOpcode::DivEF => {
+                    let inverse = compiler.inv_f(&felt(&felts, &args[2][0]));
+                    let quotient = compiler.mul_ef(&ext(&exts, &args[1][0]), &inverse);
+                    exts.insert(a(), quotient);
+                }
+                ConstraintOpcode::NegE => {
+                    let value = ext(&exts, &args[1][0]);
+                    let negated = core::array::from_fn(|i| compiler.neg_f(&value[i]));
+                    exts.insert(a(), negated);
+                }
+                ConstraintOpcode::InvE => {
+                    let inverse = compiler.inv_e(&ext(&exts, &args[1][0]));
+                    exts.insert(a(), inverse);
+                }
+                ConstraintOpcode::Num2BitsV => {
+                    let num_bits = args[2][0].parse().expect("invalid number of bits");
+                    let bits = compiler.to_binary(&var(&vars, &args[1][0]), num_bits);
+                    for (id, bit) in args[0].iter().zip(bits) {
+                        vars.insert(id.clone(), bit);
+                    }
+                }
+                ConstraintOpcode::Num2BitsF => {
+                    let value = compiler.reduce_slow(&felt(&felts, &args[1][0]));
+                    let bits = compiler.to_binary(&value.value, 31);
+                    for (id, bit) in args[0].iter().zip(bits) {
+                        vars.insert(id.clone(), bit);
+                    }
+                }
+                ConstraintOpcode::Permute => {
+                    let mut state: [Signal; 3] = core::array::from_fn(|i| var(&vars, &args[i][0]));
+                    compiler.poseidon2_permute(&mut state);
+                    for (arg, value) in args.iter().zip(state) {
+                        vars.insert(arg[0].clone(), value);
+                    }
+                }
+                ConstraintOpcode::PermuteBabyBear => {
+                    let mut state: [Felt; 16] = core::array::from_fn(|i| felt(&felts, &args[i][0]));
+                    compiler.poseidon2_babybear_permute(&mut state);
+                    for (arg, value) in args.iter().zip(state) {
+                        felts.insert(arg[0].clone(), value);
+                    }
+                }
+                ConstraintOpcode::SelectV => {
+                    let selected = compiler.select(
+                        &var(&vars, &args[1][0]),
+                        &var(&vars, &args[2][0]),
+                        &var(&vars, &args[3][0]),
+                    );
+                    vars.insert(a(), selected);
+                }
+                ConstraintOpcode::SelectF => {
+                    let selected = compiler.select_f(
+                        &var(&vars, &args[1][0]),
+                        &felt(&felts, &args[2][0]),
+                        &felt(&felts, &args[3][0]),
+                    );
+                    felts.insert(a(), selected);
+                }
+                ConstraintOpcode::SelectE => {
+                    let cond = var(&vars, &args[1][0]);
+                    let lhs = ext(&exts, &args[2][0]);
+                    let rhs = ext(&exts, &args[3][0]);
+                    let selected =
+                        core::array::from_fn(|i| compiler.select_f(&cond, &lhs[i], &rhs[i]));
+                    exts.insert(a(), selected);
+                }
+                ConstraintOpcode::Ext2Felt => {
+                    let value = ext(&exts, &args[4][0]);
+                    for (arg, felt) in args.iter().zip(value) {
+                        felts.insert(arg[0].clone(), felt);
+                    }
+                }
+                ConstraintOpcode::AssertEqV => {
+                    compiler.assert_eq(&var(&vars, &args[0][0]), &var(&vars, &args[1][0]));
+                }
+                ConstraintOpcode::AssertEqF => {
+                    compiler.assert_eq_f(&felt(&felts, &args[0][0]), &felt(&felts, &args[1][0]));
+                }
+                ConstraintOpcode::AssertNeF => {
+                    let lhs = compiler.reduce_slow(&felt(&felts, &args[0][0]));
+                    let rhs = compiler.reduce_slow(&felt(&felts, &args[1][0]));
+                    compiler.assert_different(&lhs.value, &rhs.value);
+                }
+                ConstraintOpcode::AssertEqE => {
+                    let lhs = ext(&exts, &args[0][0]);
+                    let rhs = ext(&exts, &args[1][0]);
+                    compiler.assert_eq_e(&lhs, &rhs);
+                }
+                // Printing adds nothing to the constraint system.
+                ConstraintOpcode::PrintV | ConstraintOpcode::PrintF | ConstraintOpcode::PrintE => {}
+                ConstraintOpcode::WitnessV => {
+                    vars.insert(a(), input_vars[index(&args[1][0])].clone());
+                }
+                ConstraintOpcode::WitnessF => {
+                    felts.insert(a(), input_felts[index(&args[1][0])].clone());
+                }
+                ConstraintOpcode::WitnessE => {
+                    exts.insert(a(), input_exts[index(&args[1][0])].clone());
+                }
+                ConstraintOpcode::CommitVkeyHash => {
+                    compiler.assert_eq(&vkey_hash, &var(&vars, &args[0][0]));
+                }
+                ConstraintOpcode::CommitCommitedValuesDigest => {
+                    compiler.assert_eq(&committed_values_digest, &var(&vars, &args[0][0]));
+                }
+                ConstraintOpcode::CircuitFelts2Ext => {
+                    let value = core::array::from_fn(|i| felt(&felts, &args[i + 1][0]));
+                    exts.insert(a(), value);
+                }
+                ConstraintOpcode::CircuitFelt2Var => {
+                    let value = compiler.reduce_slow(&felt(&felts, &args[1][0]));
+                    vars.insert(a(), value.value);
+                }
+                ConstraintOpcode::ReduceE => {
+                    let value = ext(&exts, &args[0][0]);
+                    let reduced = core::array::from_fn(|i| compiler.reduce_slow(&value[i]));
+                    exts.insert(a(), reduced);
+                }
+                ConstraintOpcode::NegV
+                | ConstraintOpcode::NegF
+                | ConstraintOpcode::InvV
+                | ConstraintOpcode::InvF => panic!("unhandled opcode: {:?}", cs.opcode),
+            }
+        }
+
+        let r1cs = R1cs {
+            num_wires: compiler.witness.len(),
+            num_public_inputs,
+            num_private_inputs,
+            constraints: compiler.constraints,
+        };
+        (r1cs, compiler.witness)
+    }
+
+    fn new_wire(&mut self, value: Bn254Fr) -> Signal {
+        self.witness.push(value);
+        Signal { lc: LinearCombination::wire(self.witness.len() - 1), value }
+    }
+
+    fn new_felt_wire(&mut self, value: BabyBear) -> Felt {
+        let value = self.new_wire(Bn254Fr::from_canonical_u32(value.as_canonical_u32()));
+        Felt { value, upper_bound: BigUint::from(1u64 << 32) }
+    }
+
+    fn enforce(&mut self, a: LinearCombination, b: LinearCombination, c: LinearCombination) {
+        self.constraints.push(R1csConstraint { a, b, c });
+    }
+
+    /// Moves a linear combination that has grown too large into a new wire.
+    fn compress(&mut self, signal: Signal) -> Signal {
+        if signal.lc.0.len() <= MAX_TERMS {
+            return signal;
+        }
+        let wire = self.new_wire(signal.value);
+        self.enforce(signal.lc, LinearCombination::constant(Bn254Fr::one()), wire.lc.clone());
+        wire
+    }
+
+    fn add(&mut self, a: &Signal, b: &Signal) -> Signal {
+        let sum = Signal { lc: a.lc.add_scaled(&b.lc, Bn254Fr::one()), value: a.value + b.value };
+        self.compress(sum)
+    }
+
+    fn sub(&mut self, a: &Signal, b: &Signal) -> Signal {
+        let diff = Signal { lc: a.lc.add_scaled(&b.lc, -Bn254Fr::one()), value: a.value - b.value };
+        self.compress(diff)
+    }
+
+    fn mul(&mut self, a: &Signal, b: &Signal) -> Signal {
+        if a.lc.is_constant() {
+            return scale(b, a.value);
+        }
+        if b.lc.is_constant() {
+            return scale(a, b.value);
+        }
+        let product = self.new_wire(a.value * b.value);
+        self.enforce(a.lc.clone(), b.lc.clone(), product.lc.clone());
+        product
+    }
+
+    fn assert_eq(&mut self, a: &Signal, b: &Signal) {
+        let diff = a.lc.add_scaled(&b.lc, -Bn254Fr::one());
+        if diff.is_constant() {
+            assert!(diff.0.is_empty(), "constant assertion failed: {} != {}", a.value, b.value);
+            return;
+        }
+        self.enforce(
+            diff,
+            LinearCombination::constant(Bn254Fr::one()),
+            LinearCombination::default(),
+        );
+    }
+
+    fn assert_boolean(&mut self, a: &Signal) {
+        if a.lc.is_constant() {
+            assert!(a.value.is_zero() || a.value.is_one(), "{} is not a boolean", a.value);
+            return;
+        }
+        if let [(wire, coeff)] = a.lc.0.as_slice() {
+            if coeff.is_one() && self.booleans.contains(wire) {
+                return;
+            }
+        }
+        let one_minus_a =
+            LinearCombination::constant(Bn254Fr::one()).add_scaled(&a.lc, -Bn254Fr::one());
+        self.enforce(a.lc.clone(), one_minus_a, LinearCombination::default());
+        if let [(wire, coeff)] = a.lc.0.as_slice() {
+            if coeff.is_one() {
+                self.booleans.insert(*wire);
+            }
+        }
+    }
+
+    /// Decomposes a value into its `num_bits` least significant bits, constraining it to fit.
+    ///
+    /// As in gnark's `ToBinary`, a decomposition with as many bits as the modulus is also
+    /// constrained to be at most `p - 1`, so that it is the canonical one.
+    fn to_binary(&mut self, a: &Signal, num_bits: usize) -> Vec<Signal> {
+        let value = a.value.as_canonical_biguint();
+        if a.lc.is_constant() {
+            return (0..num_bits as u64)
+                .map(|i| constant(Bn254Fr::from_bool(value.bit(i))))
+                .collect();
+        }
+
+        let modulus = Bn254Fr::order();
+        let modulus_bits = modulus.bits() as usize;
+        let mut bits = Vec::with_capacity(num_bits);
+        let mut sum = LinearCombination::default();
+        let mut power = Bn254Fr::one();
+        for i in 0..num_bits.min(modulus_bits) as u64 {
+            let bit = self.new_wire(Bn254Fr::from_bool(value.bit(i)));
+            self.assert_boolean(&bit);
+            sum = sum.add_scaled(&bit.lc, power);
+            power = power.double();
+            bits.push(bit);
+        }
+        self.assert_eq(&Signal { lc: sum, value: a.value }, a);
+        if num_bits >= modulus_bits {
+            self.assert_less_or_equal(&bits, &(modulus - 1u32));
+            // The bits of a canonical value past those of the modulus are zero.
+            bits.resize(num_bits, constant(Bn254Fr::zero()));
+        }
+        bits
+    }
+
+    /// Constrains little-endian boolean bits to encode a value of at most `bound`.
+    fn assert_less_or_equal(&mut self, bits: &[Signal], bound: &BigUint) {
+        // One while the bits read so far, from the most significant one, equal those of the bound.
+        let mut prefix = constant(Bn254Fr::one());
+        for (i, bit) in bits.iter().enumerate().rev() {
+            if bound.bit(i as u64) {
+                prefix = self.mul(&prefix, bit);
+            } else if prefix.lc.is_constant() {
+                self.assert_eq(&scale(bit, prefix.value), &constant(Bn254Fr::zero()));
+            } else {
+                self.enforce(prefix.lc.clone(), bit.lc.clone(), LinearCombination::default());
+            }
+        }
+    }
+
+    fn is_zero(&mut self, a: &Signal) -> Signal {
+        if a.lc.is_constant() {
+            return constant(Bn254Fr::from_bool(a.value.is_zero()));
+        }
+        let inverse = self.new_wire(a.value.try_inverse().unwrap_or(Bn254Fr::zero()));
+        let product = self.mul(a, &inverse);
+        let result = self.sub(&constant(Bn254Fr::one()), &product);
+        self.enforce(a.lc.clone(), result.lc.clone(), LinearCombination::default());
+        result
+    }
+
+    fn assert_different(&mut self, a: &Signal, b: &Signal) {
+        let diff = self.sub(a, b);
+        if diff.lc.is_constant() {
+            assert!(!diff.value.is_zero(), "constant assertion failed: {} == {}", a.value, b.value);
+            return;
+        }
+        let inverse = self.new_wire(diff.value.try_inverse().unwrap_or(Bn254Fr::zero()));
+        self.enforce(diff.lc, inverse.lc, LinearCombination::constant(Bn254Fr::one()));
+    }
+
+    fn select(&mut self, cond: &Signal, a: &Signal, b: &Signal) -> Signal {
+        self.assert_boolean(cond);
+        let diff = self.sub(a, b);
+        let selected = self.mul(cond, &diff);
+        self.add(b, &selected)
+    }
+
+    fn poseidon2_permute(&mut self, state: &mut [Signal; 3]) {
+        const ROUNDS_F: usize = 8;
+        const ROUNDS_P: usize = 56;
+        let round_constants = bn254_poseidon2_rc3();
+
+        let external_round = |compiler: &mut Self, state: &mut [Signal; 3], round: usize| {
+            for (value, rc) in state.iter_mut().zip(round_constants[round]) {
+                let with_rc = compiler.add(value, &constant(rc));
+                *value = compiler.sbox(&with_rc);
+            }
+            compiler.poseidon2_external_linear_layer(state);
+        };
+
+        self.poseidon2_external_linear_layer(state);
+        for round in 0..ROUNDS_F / 2 {
+            external_round(self, state, round);
+        }
+        for round in ROUNDS_F / 2..ROUNDS_F / 2 + ROUNDS_P {
+            let with_rc = self.add(&state[0], &constant(round_constants[round][0]));
+            state[0] = self.sbox(&with_rc);
+            let sum = self.add(&state[0], &state[1]);
+            let sum = self.add(&sum, &state[2]);
+            for (value, diag) in state.iter_mut().zip([1u32, 1, 2]) {
+                let scaled = scale(value, Bn254Fr::from_canonical_u32(diag));
+                *value = self.add(&scaled, &sum);
+            }
+        }
+        for round in ROUNDS_F / 2 + ROUNDS_P..ROUNDS_F + ROUNDS_P {
+            external_round(self, state, round);
+        }
+    }
+
+    fn poseidon2_external_linear_layer(&mut self, state: &mut [Signal; 3]) {
+        let sum = self.add(&state[0], &state[1]);
+        let sum = self.add(&sum, &state[2]);
+        for value in state.iter_mut() {
+            *value = self.add(value, &sum);
+        }
+    }
+
+    fn sbox(&mut self, a: &Signal) -> Signal {
+        let squared = self.mul(a, a);
+        let fourth = self.mul(&squared, &squared);
+        self.mul(&fourth, a)
+    }
+
+    fn add_f(&mut self, a: &Felt, b: &Felt) -> Felt {
+        let sum = self.add_f_unreduced(a, b);
+        self.reduce_fast(&sum)
+    }
+
+    fn add_f_unreduced(&mut self, a: &Felt, b: &Felt) -> Felt {
+        Felt { value: self.add(&a.value, &b.value), upper_bound: &a.upper_bound + &b.upper_bound }
+    }
+
+    fn sub_f(&mut self, a: &Felt, b: &Felt) -> Felt {
+        let negated = self.neg_f(b);
+        self.add_f(a, &negated)
+    }
+
+    fn neg_f(&mut self, a: &Felt) -> Felt {
+        let modulus = BigUint::from(BABYBEAR_MODULUS);
+        let lifted_modulus = (&a.upper_bound / &modulus + 1u32) * &modulus;
+        let value = self.sub(&constant(bn254_from_biguint(&lifted_modulus)), &a.value);
+        self.reduce_fast(&Felt { value, upper_bound: lifted_modulus })
+    }
+
+    fn mul_f(&mut self, a: &Felt, b: &Felt, reduce: bool) -> Felt {
+        let product = Felt {
+            value: self.mul(&a.value, &b.value),
+            upper_bound: &a.upper_bound * &b.upper_bound,
+        };
+        if reduce {
+            self.reduce_fast(&product)
+        } else {
+            product
+        }
+    }
+
+    fn mul_f_const(&mut self, a: &Felt, b: u32, reduce: bool) -> Felt {
+        let product = Felt {
+            value: scale(&a.value, Bn254Fr::from_canonical_u32(b)),
+            upper_bound: &a.upper_bound * b,
+        };
+        if reduce {
+            self.reduce_fast(&product)
+        } else {
+            product
+        }
+    }
+
+    fn inv_f(&mut self, a: &Felt) -> Felt {
+        let inverse = babybear_value(a.value.value).try_inverse().unwrap_or(BabyBear::zero());
+        let inverse = self.new_wire(Bn254Fr::from_canonical_u32(inverse.as_canonical_u32()));
+        self.to_binary(&inverse, 31);
+        let inverse = Felt { value: inverse, upper_bound: BigUint::from(1u64 << 31) };
+        let product = self.mul_f(a, &inverse, true);
+        self.assert_eq_f(&product, &felt_const(1));
+        inverse
+    }
+
+    fn assert_eq_f(&mut self, a: &Felt, b: &Felt) {
+        let a = self.reduce_slow(a);
+        let b = self.reduce_slow(b);
+        self.assert_eq(&a.value, &b.value);
+    }
+
+    fn select_f(&mut self, cond: &Signal, a: &Felt, b: &Felt) -> Felt {
+        Felt {
+            value: self.select(cond, &a.value, &b.value),
+            upper_bound: a.upper_bound.clone().max(b.upper_bound.clone()),
+        }
+    }
+
+    fn add_e(&mut self, a: &Ext, b: &Ext) -> Ext {
+        core::array::from_fn(|i| self.add_f(&a[i], &b[i]))
+    }
+
+    fn mul_e(&mut self, a: &Ext, b: &Ext) -> Ext {
+        let mut product: Ext = core::array::from_fn(|_| felt_const(0));
+        for i in 0..4 {
+            for j in 0..4 {
+                let term = self.mul_f(&a[i], &b[j], false);
+                if i + j >= 4 {
+                    // The extension is defined by x^4 = 11.
+                    let term = self.mul_f_const(&term, 11, false);
+                    product[i + j - 4] = self.add_f_unreduced(&product[i + j - 4], &term);
+                } else {
+                    product[i + j] = self.add_f_unreduced(&product[i + j], &term);
+                }
+            }
+        }
+        core::array::from_fn(|i| self.reduce_fast(&product[i]))
+    }
+
+    fn mul_ef(&mut self, a: &Ext, b: &Felt) -> Ext {
+        core::array::from_fn(|i| self.mul_f(&a[i], b, true))
+    }
+
+    fn inv_e(&mut self, a: &Ext) -> Ext {
+        let value = BinomialExtensionField::<BabyBear, 4>::from_base_fn(|i| {
+            babybear_value(a[i].value.value)
+        });
+        let inverse = value.try_inverse().unwrap_or(BinomialExtensionField::zero());
+        let inverse: Ext = core::array::from_fn(|i| {
+            let coeff = inverse.as_base_slice()[i].as_canonical_u32();
+            let wire = self.new_wire(Bn254Fr::from_canonical_u32(coeff));
+            Felt { value: wire, upper_bound: BigUint::from(1u64 << 31) }
+        });
+        for coeff in inverse.iter() {
+            self.to_binary(&coeff.value, 31);
+        }
+        let product = self.mul_e(a, &inverse);
+        self.assert_eq_e(&product, &[felt_const(1), felt_const(0), felt_const(0), felt_const(0)]);
+        inverse
+    }
+
+    fn assert_eq_e(&mut self, a: &Ext, b: &Ext) {
+        for (a, b) in a.iter().zip(b.iter()) {
+            self.assert_eq_f(a, b);
+        }
+    }
+
+    fn reduce_fast(&mut self, a: &Felt) -> Felt {
+        if a.upper_bound.bits() >= 120 {
+            let value = self.reduce_with_max_bits(&a.value, a.upper_bound.bits());
+            return Felt { value, upper_bound: BigUint::from(BABYBEAR_MODULUS - 1) };
+        }
+        a.clone()
+    }
+
+    fn reduce_slow(&mut self, a: &Felt) -> Felt {
+        if a.upper_bound < BigUint::from(BABYBEAR_MODULUS) {
+            return a.clone();
+        }
+        let value = self.reduce_with_max_bits(&a.value, a.upper_bound.bits());
+        Felt { value, upper_bound: BigUint::from(BABYBEAR_MODULUS - 1) }
+    }
+
+    /// Reduces a value of at most `max_bits` bits modulo the babybear prime.
+    fn reduce_with_max_bits(&mut self, a: &Signal, max_bits: u64) -> Signal {
+        if max_bits <= 30 {
+            return a.clone();
+        }
+        let value = a.value.as_canonical_biguint();
+        let quotient = self.new_wire(bn254_from_biguint(&(&value / BABYBEAR_MODULUS)));
+        let remainder_value =
+            (&value % BABYBEAR_MODULUS).to_u64_digits().first().copied().unwrap_or(0);
+        let remainder = self.new_wire(Bn254Fr::from_canonical_u64(remainder_value));
+        self.to_binary(&quotient, (max_bits - 30) as usize);
+
+        // Check that the remainder is less than the modulus, by splitting it into a 27 bit limb and
+        // a 4 bit limb. The low limb must be zero if the high limb is all ones.
+        let low = self.new_wire(Bn254Fr::from_canonical_u64(remainder_value & ((1 << 27) - 1)));
+        let high = self.new_wire(Bn254Fr::from_canonical_u64(remainder_value >> 27));
+        let recomposed = self.add(&scale(&high, Bn254Fr::from_canonical_u32(1 << 27)), &low);
+        self.assert_eq(&recomposed, &remainder);
+        self.to_binary(&high, 4);
+        self.to_binary(&low, 27);
+        let high_minus_max = self.sub(&high, &constant(Bn254Fr::from_canonical_u32(15)));
+        let should_check = self.is_zero(&high_minus_max);
+        let checked = self.mul(&should_check, &low);
+        self.assert_eq(&checked, &constant(Bn254Fr::zero()));
+
+        let multiple = scale(&quotient, Bn254Fr::from_canonical_u64(BABYBEAR_MODULUS));
+        let recomposed = self.add(&multiple, &remainder);
+        self.assert_eq(a, &recomposed);
+        remainder
+    }
+
+    fn poseidon2_babybear_permute(&mut self, state: &mut [Felt; 16]) {
+        const ROUNDS_F: usize = 8;
+        const ROUNDS_P: usize = 13;
+
+        let external_round = |compiler: &mut Self, state: &mut [Felt; 16], round: usize| {
+            for (value, rc) in state.iter_mut().zip(RC_16_30_U32[round]) {
+                let with_rc = compiler.add_f(value, &felt_const(rc));
+                *value = compiler.babybear_sbox(&with_rc);
+            }
+            compiler.babybear_external_linear_layer(state);
+        };
+
+        self.babybear_external_linear_layer(state);
+        for round in 0..ROUNDS_F / 2 {
+            external_round(self, state, round);
+        }
+        for round in ROUNDS_F / 2..ROUNDS_F / 2 + ROUNDS_P {
+            let with_rc = self.add_f(&state[0], &felt_const(RC_16_30_U32[round][0]));
+            state[0] = self.babybear_sbox(&with_rc);
+            self.babybear_internal_linear_layer(state);
+        }
+        for round in ROUNDS_F / 2 + ROUNDS_P..ROUNDS_F + ROUNDS_P {
+            external_round(self, state, round);
+        }
+    }
+
+    fn babybear_sbox(&mut self, a: &Felt) -> Felt {
+        let a = self.add_f(a, &felt_const(0));
+        let a = self.reduce_slow(&a).value;
+        let a2 = self.mul(&a, &a);
+        let a4 = self.mul(&a2, &a2);
+        let a6 = self.mul(&a4, &a2);
+        let a7 = self.mul(&a6, &a);
+        self.reduce_slow(&Felt { value: a7, upper_bound: BigUint::from(BABYBEAR_MODULUS).pow(7) })
+    }
+
+    fn babybear_mds_light_4x4(&mut self, state: &mut [Felt]) {
+        let t01 = self.add_f(&state[0], &state[1]);
+        let t23 = self.add_f(&state[2], &state[3]);
+        let t0123 = self.add_f(&t01, &t23);
+        let t01123 = self.add_f(&t0123, &state[1]);
+        let t01233 = self.add_f(&t0123, &state[3]);
+        let double_0 = self.mul_f_const(&state[0], 2, true);
+        let double_2 = self.mul_f_const(&state[2], 2, true);
+        state[3] = self.add_f(&t01233, &double_0);
+        state[1] = self.add_f(&t01123, &double_2);
+        state[0] = self.add_f(&t01123, &t01);
+        state[2] = self.add_f(&t01233, &t23);
+    }
+
+    fn babybear_external_linear_layer(&mut self, state: &mut [Felt; 16]) {
+        for chunk in state.chunks_mut(4) {
+            self.babybear_mds_light_4x4(chunk);
+        }
+        let mut sums: [Felt; 4] = core::array::from_fn(|i| state[i].clone());
+        for i in (4..16).step_by(4) {
+            for j in 0..4 {
+                sums[j] = self.add_f(&sums[j], &state[i + j]);
+            }
+        }
+        for i in 0..16 {
+            state[i] = self.add_f(&state[i], &sums[i % 4]);
+        }
+    }
+
+    fn babybear_internal_linear_layer(&mut self, state: &mut [Felt; 16]) {
+        const MAT_INTERNAL_DIAG_M1: [u32; 16] =
+            [2013265919, 1, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096, 8192, 32768];
+        const MONTY_INVERSE: u32 = 943718400;
+
+        let mut sum = felt_const(0);
+        for value in state.iter() {
+            sum = self.add_f(&sum, value);
+        }
+        for (value, diag) in state.iter_mut().zip(MAT_INTERNAL_DIAG_M1) {
+            let scaled = self.mul_f(value, &felt_const(diag), true);
+            *value = self.add_f(&scaled, &sum);
+        }
+        for value in state.iter_mut() {
+            *value = self.mul_f(value, &felt_const(MONTY_INVERSE), true);
+        }
+    }
+}
+
+fn constant(value: Bn254Fr) -> Signal {
+    Signal { lc: LinearCombination::constant(value), value }
+}
+
+fn scale(a: &Signal, scalar: Bn254Fr) -> Signal {
+    Signal { lc: a.lc.scale(scalar), value: a.value * scalar }
+}
+
+/// A babybear constant, with its value as the upper bound.
+fn felt_const(value: u32) -> Felt {
+    Felt { value: constant(Bn254Fr::from_canonical_u32(value)), upper_bound: BigUint::from(value) }
+}
+
+/// A babybear element read from an immediate, which may not be reduced.
+fn new_f(value: Bn254Fr) -> Felt {
+    Felt { value: constant(value), upper_bound: BigUint::from(1u64 << 32) }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_symmetric::Permutation;
+    use sp1_recursion_core::stark::outer_perm;
+    use sp1_stark::inner_perm;
+
+    use super::*;
+    use crate::{constraints::ConstraintCompiler, prelude::*};
+
+    type F = BabyBear;
+    type EF = BinomialExtensionField<BabyBear, 4>;
+
+    #[test]
+    fn test_r1cs_export() {
+        let mut builder = Builder::<OuterConfig>::default();
+        let mut witness = Witness::<OuterConfig>::default();
+
+        let a_value = F::from_canonical_u32(123456789);
+        let b_value = F::from_canonical_u32(987654321);
+        witness.felts = vec![a_value, b_value];
+        let a = builder.witness_felt();
+        let b = builder.witness_felt();
+        let c: Felt<_> = builder.eval(a * b - a / b + b);
+        builder.assert_felt_eq(c, a_value * b_value - a_value / b_value + b_value);
+        let bits = builder.num2bits_f_circuit(a);
+        for (i, bit) in bits.iter().enumerate() {
+            let expected = (a_value.as_canonical_u32() >> i) & 1 == 1;
+            builder.assert_var_eq(*bit, Bn254Fr::from_bool(expected));
+        }
+
+        let x_value = EF::from_base_fn(|i| F::from_canonical_usize(1000 * i + 7));
+        let y_value = EF::from_base_fn(|i| F::from_canonical_usize(31 * i + 5));
+        witness.exts = vec![x_value, y_value];
+        let x = builder.witness_ext();
+        let y = builder.witness_ext();
+        let z: Ext<_, _> = builder.eval(x * y / (x + y) - x);
+        builder.assert_ext_eq(z, (x_value * y_value / (x_value + y_value) - x_value).cons());
+
+        let state_value = [1, 2, 3].map(Bn254Fr::from_canonical_u32);
+        witness.vars = state_value.to_vec();
+        let state: [Var<_>; 3] = core::array::from_fn(|_| builder.witness_var());
+        builder.push_op(DslIr::CircuitPoseidon2Permute(state));
+        for (value, expected) in state.iter().zip(outer_perm().permute(state_value)) {
+            builder.assert_var_eq(*value, expected);
+        }
+
+        let state_value: [F; 16] = core::array::from_fn(F::from_canonical_usize);
+        let state: [Felt<_>; 16] = state_value.map(|value| builder.eval(value));
+        builder.push_op(DslIr::CircuitPoseidon2PermuteBabyBear(Box::new(state)));
+        for (value, expected) in state.iter().zip(inner_perm().permute(state_value)) {
+            builder.assert_felt_eq(*value, expected);
+        }
+
+        let mut backend = ConstraintCompiler::<OuterConfig>::default();
+        let constraints = backend.emit(builder.into_operations());
+        let (r1cs, assignment) = R1csCompiler::compile(&constraints, &witness);
+        assert_eq!(r1cs.num_public_inputs, 2);
+        assert_eq!(r1cs.num_private_inputs, 3 + 2 + 2 * 4);
+        r1cs.check(&assignment).unwrap();
+
+        // Changing an input breaks the constraints that depend on it.
+        let mut tampered = assignment.clone();
+        tampered[1 + r1cs.num_public_inputs] += Bn254Fr::one();
+        assert!(r1cs.check(&tampered).is_err());
+
+        let mut bytes = Vec::new();
+        write_witness(&assignment, &mut bytes).unwrap();
+        assert_eq!(&bytes[..4], b"wtns");
+        assert_eq!(bytes.len(), 12 + 12 + 40 + 12 + 32 * assignment.len());
+    }
+
+    #[test]
+    fn test_r1cs_num2bits_v() {
+        let values = [Bn254Fr::zero(), Bn254Fr::from_canonical_u32(0xdeadbeef), -Bn254Fr::one()];
+        for num_bits in [32, 254, 256] {
+            let mut builder = Builder::<OuterConfig>::default();
+            let mut witness = Witness::<OuterConfig>::default();
+            witness.vars = values.to_vec();
+            for value in values {
+                let var = builder.witness_var();
+                let bits = builder.num2bits_v_circuit(var, num_bits);
+                let value = value.as_canonical_biguint();
+                for (i, bit) in bits.iter().enumerate() {
+                    builder.assert_var_eq(*bit, Bn254Fr::from_bool(value.bit(i as u64)));
+                }
+            }
+
+            let mut backend = ConstraintCompiler::<OuterConfig>::default();
+            let constraints = backend.emit(builder.into_operations());
+            let (r1cs, assignment) = R1csCompiler::compile(&constraints, &witness);
+            // The decomposition of p - 1 does not fit in 32 bits.
+            assert_eq!(r1cs.check(&assignment).is_ok(), num_bits != 32);
+        }
+    }
+
+    #[test]
+    fn test_r1cs_canonical_bits() {
+        // The bits of x and of x + p both sum to x in the field, but only the first are canonical.
+        let modulus = Bn254Fr::order();
+        for (value, canonical) in [
+            (BigUint::from(5u32), true),
+            (&modulus - 1u32, true),
+            (modulus.clone(), false),
+            (&modulus + 5u32, false),
+        ] {
+            let mut compiler = R1csCompiler { witness: vec![Bn254Fr::one()], ..Default::default() };
+            let bits: Vec<Signal> = (0..modulus.bits())
+                .map(|i| compiler.new_wire(Bn254Fr::from_bool(value.bit(i))))
+                .collect();
+            compiler.assert_less_or_equal(&bits, &(&modulus - 1u32));
+            let r1cs = R1cs {
+                num_wires: compiler.witness.len(),
+                num_public_inputs: 0,
+                num_private_inputs: 0,
+                constraints: compiler.constraints,
+            };
+            assert_eq!(r1cs.check(&compiler.witness).is_ok(), canonical, "{value}");
+        }
+    }
+
+    #[test]
+    fn test_r1cs_select() {
+        let mut builder = Builder::<OuterConfig>::default();
+        let mut witness = Witness::<OuterConfig>::default();
+        witness.vars = vec![Bn254Fr::one(), Bn254Fr::zero()];
+        let a_value = F::from_canonical_u32(17);
+        let b_value = F::from_canonical_u32(23);
+        let x_value = EF::from_base_fn(|i| F::from_canonical_usize(i + 1));
+        let y_value = EF::from_base_fn(|i| F::from_canonical_usize(10 * i + 3));
+
+        for cond_value in [true, false] {
+            let cond = builder.witness_var();
+            let three: Var<_> = builder.eval(Bn254Fr::from_canonical_u32(3));
+            let four: Var<_> = builder.eval(Bn254Fr::from_canonical_u32(4));
+            let v = builder.select_v(cond, three, four);
+            let expected = if cond_value { 3 } else { 4 };
+            builder.assert_var_eq(v, Bn254Fr::from_canonical_u32(expected));
+
+            let a: Felt<_> = builder.eval(a_value);
+            let b: Felt<_> = builder.eval(b_value);
+            let f = builder.select_f(cond, a, b);
+            builder.assert_felt_eq(f, if cond_value { a_value } else { b_value });
+
+            let x: Ext<_, _> = builder.eval(x_value.cons());
+            let y: Ext<_, _> = builder.eval(y_value.cons());
+            let e = builder.select_ef(cond, x, y);
+            builder.assert_ext_eq(e, (if cond_value { x_value } else { y_value }).cons());
+        }
+
+        let mut backend = ConstraintCompiler::<OuterConfig>::default();
+        let constraints = backend.emit(builder.into_operations());
+        let (r1cs, assignment) = R1csCompiler::compile(&constraints, &witness);
+        r1cs.check(&assignment).unwrap();
+
+        // The condition must be a boolean.
+        let mut tampered = assignment.clone();
+        tampered[1 + r1cs.num_public_inputs] = Bn254Fr::two();
+        assert!(r1cs.check(&tampered).is_err());
+    }
+
+    /// Reads the `.r1cs` file of a constraint system back following the iden3 format, and checks
+    /// that it holds the same header, constraints and wire labels.
+    #[test]
+    fn test_r1cs_file_layout() {
+        let mut builder = Builder::<OuterConfig>::default();
+        let mut witness = Witness::<OuterConfig>::default();
+        witness.vars = vec![Bn254Fr::from_canonical_u32(6)];
+        witness.felts = vec![F::from_canonical_u32(7)];
+        let v = builder.witness_var();
+        let f = builder.witness_felt();
+        let square: Var<_> = builder.eval(v * v);
+        builder.assert_var_eq(square, Bn254Fr::from_canonical_u32(36));
+        let product: Felt<_> = builder.eval(f * f);
+        builder.assert_felt_eq(product, F::from_canonical_u32(49));
+
+        let mut backend = ConstraintCompiler::<OuterConfig>::default();
+        let constraints = backend.emit(builder.into_operations());
+        let (r1cs, _) = R1csCompiler::compile(&constraints, &witness);
+        let mut bytes = Vec::new();
+        r1cs.write(&mut bytes).unwrap();
+
+        let mut reader = bytes.as_slice();
+        let mut take = |n: usize| {
+            let (head, tail) = reader.split_at(n);
+            reader = tail;
+            head.to_vec()
+        };
+        let u32_at = |bytes: Vec<u8>| u32::from_le_bytes(bytes.try_into().unwrap());
+        let u64_at = |bytes: Vec<u8>| u64::from_le_bytes(bytes.try_into().unwrap());
+
+        // The magic, the version and the number of sections.
+        assert_eq!(take(4), b"r1cs");
+        assert_eq!(u32_at(take(4)), 1);
+        assert_eq!(u32_at(take(4)), 3);
+
+        // The header section.
+        assert_eq!(u32_at(take(4)), 1);
+        assert_eq!(u64_at(take(8)), 64);
+        assert_eq!(u32_at(take(4)), 32);
+        assert_eq!(BigUint::from_bytes_le(&take(32)), Bn254Fr::order());
+        assert_eq!(u32_at(take(4)) as usize, r1cs.num_wires);
+        assert_eq!(u32_at(take(4)), 0, "the number of public outputs");
+        assert_eq!(u32_at(take(4)) as usize, r1cs.num_public_inputs);
+        assert_eq!(u32_at(take(4)) as usize, r1cs.num_private_inputs);
+        assert_eq!(u64_at(take(8)) as usize, r1cs.num_wires, "the number of labels");
+        assert_eq!(u32_at(take(4)) as usize, r1cs.constraints.len());
+
+        // The constraints section.
+        assert_eq!(u32_at(take(4)), 2);
+        let size = u64_at(take(8)) as usize;
+        let mut section = take(size);
+        let mut read = Vec::new();
+        let read_lc = |section: &mut Vec<u8>| {
+            let rest = section.split_off(4);
+            let len = u32_at(std::mem::replace(section, rest)) as usize;
+            let terms = (0..len)
+                .map(|_| {
+                    let rest = section.split_off(36);
+                    let term = std::mem::replace(section, rest);
+                    let wire = u32_at(term[..4].to_vec()) as usize;
+                    let coeff = BigUint::from_bytes_le(&term[4..]);
+                    assert!(coeff < Bn254Fr::order(), "coefficients are reduced");
+                    (wire, bn254_from_biguint(&coeff))
+                })
+                .collect();
+            LinearCombination(terms)
+        };
+        while !section.is_empty() {
+            let a = read_lc(&mut section);
+            let b = read_lc(&mut section);
+            let c = read_lc(&mut section);
+            read.push(R1csConstraint { a, b, c });
+        }
+        assert_eq!(read, r1cs.constraints);
+
+        // The wire to label section.
+        assert_eq!(u32_at(take(4)), 3);
+        assert_eq!(u64_at(take(8)) as usize, 8 * r1cs.num_wires);
+        for wire in 0..r1cs.num_wires as u64 {
+            assert_eq!(u64_at(take(8)), wire);
+        }
+        assert!(reader.is_empty());
+    }
+}
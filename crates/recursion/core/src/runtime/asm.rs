@@ -0,0 +1,627 @@
+//! A textual assembly format for recursion programs.
+//!
+//! Each line holds one instruction, written as its mnemonic, the operands it writes, `=` and the
+//! operands it reads. Written addresses carry the number of times they are read, as `@addr:mult`,
+//! and read addresses are written as `@addr`. Vectors of operands are enclosed in brackets, and
+//! memory values in parentheses. Comments start with `;`.
+//!
+//! ```text
+//! .total_memory 3
+//! mem.write @0:1 = (1, 0, 0, 0)
+//! mem.write @1:1 = (2, 0, 0, 0)
+//! addf @2:1 = @0, @1
+//! mem.read @2:1 = (3, 0, 0, 0)
+//! ```
+//!
+//! The shape and the backtraces of a program are not part of the format.
+
+use std::{
+    borrow::Borrow,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use p3_field::PrimeField32;
+use thiserror::Error;
+
+use super::instruction::{FieldEltType, HintBitsInstr, HintExt2FeltsInstr, HintInstr, PrintInstr};
+use crate::{
+    air::{Block, RecursionPublicValues, RECURSIVE_PROOF_NUM_PV_ELTS},
+    *,
+};
+
+/// An error in the assembly of a recursion program.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("line {line}: {message}")]
+pub struct AsmError {
+    /// The line of the error, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl<F: PrimeField32> Display for RecursionProgram<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, ".total_memory {}", self.total_memory)?;
+        for instruction in self.instructions.iter() {
+            writeln!(f, "{instruction}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: PrimeField32> FromStr for RecursionProgram<F> {
+    type Err = AsmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut program = RecursionProgram::default();
+        for (i, line) in s.lines().enumerate() {
+            let error = |message: String| AsmError { line: i + 1, message };
+            let line = line.split(';').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.strip_prefix(".total_memory") {
+                Some(total_memory) => {
+                    program.total_memory = total_memory
+                        .trim()
+                        .parse()
+                        .map_err(|e| error(format!("invalid total memory: {e}")))?;
+                }
+                None => program.instructions.push(parse_instruction(line).map_err(error)?),
+            }
+        }
+        Ok(program)
+    }
+}
+
+impl<F: PrimeField32> Display for Instruction<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::BaseAlu(BaseAluInstr { opcode, mult, addrs }) => {
+                let mnemonic = match opcode {
+                    BaseAluOpcode::AddF => "addf",
+                    BaseAluOpcode::SubF => "subf",
+                    BaseAluOpcode::MulF => "mulf",
+                    BaseAluOpcode::DivF => "divf",
+                };
+                let BaseAluIo { out, in1, in2 } = addrs;
+                write!(f, "{mnemonic} {} = {}, {}", Out(out, mult), In(in1), In(in2))
+            }
+            Instruction::ExtAlu(ExtAluInstr { opcode, mult, addrs }) => {
+                let mnemonic = match opcode {
+                    ExtAluOpcode::AddE => "adde",
+                    ExtAluOpcode::SubE => "sube",
+                    ExtAluOpcode::MulE => "mule",
+                    ExtAluOpcode::DivE => "dive",
+                };
+                let ExtAluIo { out, in1, in2 } = addrs;
+                write!(f, "{mnemonic} {} = {}, {}", Out(out, mult), In(in1), In(in2))
+            }
+            Instruction::Mem(MemInstr { addrs, vals, mult, kind }) => {
+                let mnemonic = match kind {
+                    MemAccessKind::Read => "mem.read",
+                    MemAccessKind::Write => "mem.write",
+                };
+                let values = vals.inner.0.iter().map(|x| x.as_canonical_u32().to_string());
+                write!(
+                    f,
+                    "{mnemonic} {} = ({})",
+                    Out(&addrs.inner, mult),
+                    values.collect::<Vec<_>>().join(", ")
+                )
+            }
+            Instruction::Poseidon2(instr) => {
+                let Poseidon2SkinnyInstr { addrs: Poseidon2Io { input, output }, mults } =
+                    instr.as_ref();
+                write!(f, "poseidon2 {} = {}", Outs(output.iter().zip(mults)), Ins(input))
+            }
+            Instruction::Select(SelectInstr { addrs, mult1, mult2 }) => {
+                let SelectIo { bit, out1, out2, in1, in2 } = addrs;
+                write!(
+                    f,
+                    "select {}, {} = {}, {}, {}",
+                    Out(out1, mult1),
+                    Out(out2, mult2),
+                    In(bit),
+                    In(in1),
+                    In(in2)
+                )
+            }
+            Instruction::ExpReverseBitsLen(ExpReverseBitsInstr { addrs, mult }) => {
+                let ExpReverseBitsIo { base, exp, result } = addrs;
+                write!(f, "exp_reverse_bits {} = {}, {}", Out(result, mult), In(base), Ins(exp))
+            }
+            Instruction::HintBits(HintBitsInstr { output_addrs_mults, input_addr }) => {
+                let outputs = output_addrs_mults.iter().map(|(addr, mult)| (addr, mult));
+                write!(f, "hint_bits {} = {}", Outs(outputs), In(input_addr))
+            }
+            Instruction::FriFold(instr) => {
+                let FriFoldInstr {
+                    base_single_addrs: FriFoldBaseIo { x },
+                    ext_single_addrs: FriFoldExtSingleIo { z, alpha },
+                    ext_vec_addrs:
+                        FriFoldExtVecIo {
+                            mat_opening,
+                            ps_at_z,
+                            alpha_pow_input,
+                            ro_input,
+                            alpha_pow_output,
+                            ro_output,
+                        },
+                    alpha_pow_mults,
+                    ro_mults,
+                } = instr.as_ref();
+                write!(
+                    f,
+                    "fri_fold {}, {} = {}, {}, {}, {}, {}, {}, {}",
+                    Outs(alpha_pow_output.iter().zip(alpha_pow_mults)),
+                    Outs(ro_output.iter().zip(ro_mults)),
+                    In(z),
+                    In(alpha),
+                    In(x),
+                    Ins(mat_opening),
+                    Ins(ps_at_z),
+                    Ins(alpha_pow_input),
+                    Ins(ro_input)
+                )
+            }
+            Instruction::BatchFRI(instr) => {
+                let BatchFRIInstr {
+                    base_vec_addrs: BatchFRIBaseVecIo { p_at_x },
+                    ext_single_addrs: BatchFRIExtSingleIo { acc },
+                    ext_vec_addrs: BatchFRIExtVecIo { p_at_z, alpha_pow },
+                    acc_mult,
+                } = instr.as_ref();
+                write!(
+                    f,
+                    "batch_fri {} = {}, {}, {}",
+                    Out(acc, acc_mult),
+                    Ins(alpha_pow),
+                    Ins(p_at_z),
+                    Ins(p_at_x)
+                )
+            }
+            Instruction::Print(PrintInstr { field_elt_type, addr }) => match field_elt_type {
+                FieldEltType::Base => write!(f, "print.f = {}", In(addr)),
+                FieldEltType::Extension => write!(f, "print.e = {}", In(addr)),
+            },
+            Instruction::HintExt2Felts(HintExt2FeltsInstr { output_addrs_mults, input_addr }) => {
+                let outputs = output_addrs_mults.iter().map(|(addr, mult)| (addr, mult));
+                write!(f, "hint_ext2felts {} = {}", Outs(outputs), In(input_addr))
+            }
+            Instruction::CommitPublicValues(instr) => {
+                write!(f, "commit_public_values = {}", Ins(&instr.pv_addrs.as_array()))
+            }
+            Instruction::Hint(HintInstr { output_addrs_mults }) => {
+                let outputs = output_addrs_mults.iter().map(|(addr, mult)| (addr, mult));
+                write!(f, "hint {}", Outs(outputs))
+            }
+        }
+    }
+}
+
+/// An address that is read.
+struct In<'a, F>(&'a Address<F>);
+
+/// An address that is written, with its number of reads.
+struct Out<'a, F>(&'a Address<F>, &'a F);
+
+struct Ins<'a, F>(&'a [Address<F>]);
+
+struct Outs<I>(I);
+
+impl<F: PrimeField32> Display for In<'_, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "@{}", self.0 .0.as_canonical_u32())
+    }
+}
+
+impl<F: PrimeField32> Display for Out<'_, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "@{}:{}", self.0 .0.as_canonical_u32(), self.1.as_canonical_u32())
+    }
+}
+
+impl<F: PrimeField32> Display for Ins<'_, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let addrs = self.0.iter().map(|addr| In(addr).to_string()).collect::<Vec<_>>();
+        write!(f, "[{}]", addrs.join(", "))
+    }
+}
+
+impl<'a, F: PrimeField32 + 'a, I: Iterator<Item = (&'a Address<F>, &'a F)> + Clone> Display
+    for Outs<I>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let addrs = self.0.clone().map(|(addr, mult)| Out(addr, mult).to_string());
+        write!(f, "[{}]", addrs.collect::<Vec<_>>().join(", "))
+    }
+}
+
+/// A parsed operand, before it is checked against the instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Operand {
+    In(u32),
+    Out(u32, u32),
+    Vec(Vec<Operand>),
+    Block(Vec<u32>),
+}
+
+/// Parses an instruction, without its comment.
+pub fn parse_instruction<F: PrimeField32>(line: &str) -> Result<Instruction<F>, String> {
+    let (head, inputs) = line.split_once('=').unwrap_or((line, ""));
+    let head = head.trim();
+    let (mnemonic, outputs) = head.split_once(char::is_whitespace).unwrap_or((head, ""));
+    let mut outputs = Operands::<F>::parse(outputs)?;
+    let mut inputs = Operands::<F>::parse(inputs)?;
+
+    let instruction = match mnemonic {
+        "addf" | "subf" | "mulf" | "divf" => {
+            let opcode = match mnemonic {
+                "addf" => BaseAluOpcode::AddF,
+                "subf" => BaseAluOpcode::SubF,
+                "mulf" => BaseAluOpcode::MulF,
+                _ => BaseAluOpcode::DivF,
+            };
+            let (out, mult) = outputs.out()?;
+            let addrs = BaseAluIo { out, in1: inputs.addr()?, in2: inputs.addr()? };
+            Instruction::BaseAlu(BaseAluInstr { opcode, mult, addrs })
+        }
+        "adde" | "sube" | "mule" | "dive" => {
+            let opcode = match mnemonic {
+                "adde" => ExtAluOpcode::AddE,
+                "sube" => ExtAluOpcode::SubE,
+                "mule" => ExtAluOpcode::MulE,
+                _ => ExtAluOpcode::DivE,
+            };
+            let (out, mult) = outputs.out()?;
+            let addrs = ExtAluIo { out, in1: inputs.addr()?, in2: inputs.addr()? };
+            Instruction::ExtAlu(ExtAluInstr { opcode, mult, addrs })
+        }
+        "mem.read" | "mem.write" => {
+            let kind =
+                if mnemonic == "mem.read" { MemAccessKind::Read } else { MemAccessKind::Write };
+            let (addr, mult) = outputs.out()?;
+            let vals = MemIo { inner: inputs.block()? };
+            Instruction::Mem(MemInstr { addrs: MemIo { inner: addr }, vals, mult, kind })
+        }
+        "poseidon2" => {
+            let (output, mults) = outputs.outs()?.into_iter().unzip::<_, _, Vec<_>, Vec<_>>();
+            let input = inputs.addrs()?;
+            Instruction::Poseidon2(Box::new(Poseidon2SkinnyInstr {
+                addrs: Poseidon2Io { input: array(input)?, output: array(output)? },
+                mults: array(mults)?,
+            }))
+        }
+        "select" => {
+            let ((out1, mult1), (out2, mult2)) = (outputs.out()?, outputs.out()?);
+            let (bit, in1, in2) = (inputs.addr()?, inputs.addr()?, inputs.addr()?);
+            Instruction::Select(SelectInstr {
+                addrs: SelectIo { bit, out1, out2, in1, in2 },
+                mult1,
+                mult2,
+            })
+        }
+        "exp_reverse_bits" => {
+            let (result, mult) = outputs.out()?;
+            let addrs = ExpReverseBitsIo { base: inputs.addr()?, exp: inputs.addrs()?, result };
+            Instruction::ExpReverseBitsLen(ExpReverseBitsInstr { addrs, mult })
+        }
+        "hint_bits" => Instruction::HintBits(HintBitsInstr {
+            output_addrs_mults: outputs.outs()?,
+            input_addr: inputs.addr()?,
+        }),
+        "fri_fold" => {
+            let (alpha_pow_output, alpha_pow_mults) = outputs.outs()?.into_iter().unzip();
+            let (ro_output, ro_mults) = outputs.outs()?.into_iter().unzip();
+            let (z, alpha, x) = (inputs.addr()?, inputs.addr()?, inputs.addr()?);
+            Instruction::FriFold(Box::new(FriFoldInstr {
+                base_single_addrs: FriFoldBaseIo { x },
+                ext_single_addrs: FriFoldExtSingleIo { z, alpha },
+                ext_vec_addrs: FriFoldExtVecIo {
+                    mat_opening: inputs.addrs()?,
+                    ps_at_z: inputs.addrs()?,
+                    alpha_pow_input: inputs.addrs()?,
+                    ro_input: inputs.addrs()?,
+                    alpha_pow_output,
+                    ro_output,
+                },
+                alpha_pow_mults,
+                ro_mults,
+            }))
+        }
+        "batch_fri" => {
+            let (acc, acc_mult) = outputs.out()?;
+            let (alpha_pow, p_at_z) = (inputs.addrs()?, inputs.addrs()?);
+            Instruction::BatchFRI(Box::new(BatchFRIInstr {
+                base_vec_addrs: BatchFRIBaseVecIo { p_at_x: inputs.addrs()? },
+                ext_single_addrs: BatchFRIExtSingleIo { acc },
+                ext_vec_addrs: BatchFRIExtVecIo { p_at_z, alpha_pow },
+                acc_mult,
+            }))
+        }
+        "print.f" | "print.e" => {
+            let field_elt_type =
+                if mnemonic == "print.f" { FieldEltType::Base } else { FieldEltType::Extension };
+            Instruction::Print(PrintInstr { field_elt_type, addr: inputs.addr()? })
+        }
+        "hint_ext2felts" => Instruction::HintExt2Felts(HintExt2FeltsInstr {
+            output_addrs_mults: array(outputs.outs()?)?,
+            input_addr: inputs.addr()?,
+        }),
+        "commit_public_values" => {
+            let pv_addrs: [Address<F>; RECURSIVE_PROOF_NUM_PV_ELTS] = array(inputs.addrs()?)?;
+            let pv_addrs: &RecursionPublicValues<Address<F>> = pv_addrs.as_slice().borrow();
+            Instruction::CommitPublicValues(Box::new(CommitPublicValuesInstr {
+                pv_addrs: *pv_addrs,
+            }))
+        }
+        "hint" => Instruction::Hint(HintInstr { output_addrs_mults: outputs.outs()? }),
+        _ => return Err(format!("unknown instruction `{mnemonic}`")),
+    };
+    outputs.finish()?;
+    inputs.finish()?;
+    Ok(instruction)
+}
+
+fn array<T, const N: usize>(vec: Vec<T>) -> Result<[T; N], String> {
+    let len = vec.len();
+    vec.try_into().map_err(|_| format!("expected {N} operands, found {len}"))
+}
+
+/// The operands on one side of the `=`, consumed in order.
+struct Operands<F> {
+    operands: std::vec::IntoIter<Operand>,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: PrimeField32> Operands<F> {
+    fn parse(s: &str) -> Result<Self, String> {
+        let mut chars = s.trim().chars().peekable();
+        let operands = if chars.peek().is_some() { parse_list(&mut chars, None)? } else { vec![] };
+        Ok(Self { operands: operands.into_iter(), _marker: std::marker::PhantomData })
+    }
+
+    fn next(&mut self) -> Result<Operand, String> {
+        self.operands.next().ok_or_else(|| "missing operand".to_string())
+    }
+
+    fn finish(mut self) -> Result<(), String> {
+        match self.operands.next() {
+            Some(operand) => Err(format!("unexpected operand {operand:?}")),
+            None => Ok(()),
+        }
+    }
+
+    fn addr(&mut self) -> Result<Address<F>, String> {
+        match self.next()? {
+            Operand::In(addr) => Ok(Address(field(addr)?)),
+            operand => Err(format!("expected a read address, found {operand:?}")),
+        }
+    }
+
+    fn out(&mut self) -> Result<(Address<F>, F), String> {
+        match self.next()? {
+            Operand::Out(addr, mult) => Ok((Address(field(addr)?), field(mult)?)),
+            operand => Err(format!("expected a written address, found {operand:?}")),
+        }
+    }
+
+    fn addrs(&mut self) -> Result<Vec<Address<F>>, String> {
+        match self.next()? {
+            Operand::Vec(operands) => {
+                let mut operands = Self { operands: operands.into_iter(), _marker: self._marker };
+                (0..operands.operands.len()).map(|_| operands.addr()).collect()
+            }
+            operand => Err(format!("expected a vector of read addresses, found {operand:?}")),
+        }
+    }
+
+    fn outs(&mut self) -> Result<Vec<(Address<F>, F)>, String> {
+        match self.next()? {
+            Operand::Vec(operands) => {
+                let mut operands = Self { operands: operands.into_iter(), _marker: self._marker };
+                (0..operands.operands.len()).map(|_| operands.out()).collect()
+            }
+            operand => Err(format!("expected a vector of written addresses, found {operand:?}")),
+        }
+    }
+
+    fn block(&mut self) -> Result<Block<F>, String> {
+        match self.next()? {
+            Operand::Block(values) => {
+                let values = values.into_iter().map(field).collect::<Result<Vec<_>, _>>()?;
+                Ok(Block(array(values)?))
+            }
+            operand => Err(format!("expected a memory value, found {operand:?}")),
+        }
+    }
+}
+
+fn field<F: PrimeField32>(value: u32) -> Result<F, String> {
+    if value >= F::ORDER_U32 {
+        return Err(format!("{value} is not a canonical field element"));
+    }
+    Ok(F::from_canonical_u32(value))
+}
+
+/// Parses comma-separated operands, up to the closing delimiter if there is one.
+fn parse_list(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    close: Option<char>,
+) -> Result<Vec<Operand>, String> {
+    let mut operands = vec![];
+    loop {
+        skip_whitespace(chars);
+        if close.is_some() && chars.peek() == close.as_ref() && operands.is_empty() {
+            chars.next();
+            return Ok(operands);
+        }
+        operands.push(parse_operand(chars)?);
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => continue,
+            c if c == close => return Ok(operands),
+            Some(c) => return Err(format!("unexpected `{c}`")),
+            None => return Err(format!("expected `{}`", close.unwrap())),
+        }
+    }
+}
+
+fn parse_operand(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Result<Operand, String> {
+    match chars.next() {
+        Some('@') => {
+            let addr = parse_number(chars)?;
+            if chars.peek() == Some(&':') {
+                chars.next();
+                Ok(Operand::Out(addr, parse_number(chars)?))
+            } else {
+                Ok(Operand::In(addr))
+            }
+        }
+        Some('[') => Ok(Operand::Vec(parse_list(chars, Some(']'))?)),
+        Some('(') => {
+            let values = parse_list(chars, Some(')'))?;
+            let values = values.into_iter().map(|operand| match operand {
+                Operand::In(value) => Ok(value),
+                operand => Err(format!("expected a field element, found {operand:?}")),
+            });
+            Ok(Operand::Block(values.collect::<Result<_, _>>()?))
+        }
+        Some(c) if c.is_ascii_digit() => {
+            // Field elements are only allowed in memory values, where they are read back as `In`.
+            let mut digits = c.to_string();
+            while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                digits.push(c);
+            }
+            digits.parse().map(Operand::In).map_err(|e| format!("invalid number `{digits}`: {e}"))
+        }
+        Some(c) => Err(format!("unexpected `{c}`")),
+        None => Err("missing operand".to_string()),
+    }
+}
+
+fn parse_number(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Result<u32, String> {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits.parse().map_err(|e| format!("invalid number `{digits}`: {e}"))
+}
+
+fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+
+    use super::*;
+    use crate::machine::tests::run_recursion_test_machines;
+
+    type F = BabyBear;
+
+    #[test]
+    fn test_hand_written_program() {
+        let program: RecursionProgram<F> = "
+            .total_memory 12
+            ; Computes 2 * (3 + 4), then selects and exponentiates it.
+            mem.write @0:1 = (3, 0, 0, 0)
+            mem.write @1:1 = (4, 0, 0, 0)
+            mem.write @2:1 = (2, 0, 0, 0)
+            addf @3:1 = @0, @1
+            mulf @4:3 = @2, @3
+            mem.read @4:1 = (14, 0, 0, 0)
+            mem.write @5:1 = (1, 0, 0, 0)
+            mem.write @6:1 = (0, 0, 0, 0)
+            mem.write @7:1 = (5, 0, 0, 0)
+            select @8:1, @9:1 = @5, @4, @7
+            mem.read @8:1 = (5, 0, 0, 0)
+            mem.read @9:1 = (14, 0, 0, 0)
+            exp_reverse_bits @10:1 = @4, [@6]
+            mem.read @10:1 = (1, 0, 0, 0)
+        "
+        .parse()
+        .unwrap();
+        assert_eq!(program.instructions.len(), 14);
+        run_recursion_test_machines(program);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let pv_addrs: [u32; RECURSIVE_PROOF_NUM_PV_ELTS] = std::array::from_fn(|i| i as u32);
+        let program = RecursionProgram::<F> {
+            instructions: vec![
+                instruction::base_alu(BaseAluOpcode::DivF, 1, 3, 1, 2),
+                instruction::ext_alu(ExtAluOpcode::SubE, 0, 4, 3, 1),
+                instruction::mem_block(
+                    MemAccessKind::Read,
+                    2,
+                    7,
+                    Block([1, 2, 3, 4].map(F::from_canonical_u32)),
+                ),
+                instruction::poseidon2(
+                    [1; 16],
+                    std::array::from_fn(|i| 16 + i as u32),
+                    std::array::from_fn(|i| i as u32),
+                ),
+                instruction::select(1, 0, 5, 6, 7, 8, 9),
+                instruction::exp_reverse_bits_len(
+                    3,
+                    F::from_canonical_u32(4),
+                    vec![F::from_canonical_u32(5)],
+                    F::from_canonical_u32(6),
+                ),
+                instruction::fri_fold(
+                    1,
+                    2,
+                    3,
+                    vec![4, 5],
+                    vec![6, 7],
+                    vec![8, 9],
+                    vec![10, 11],
+                    vec![12, 13],
+                    vec![14, 15],
+                    vec![1, 0],
+                    vec![0, 1],
+                ),
+                instruction::batch_fri(1, vec![2, 3], vec![4, 5], vec![6, 7], 1),
+                instruction::commit_public_values(pv_addrs.as_slice().borrow()),
+                Instruction::HintBits(HintBitsInstr {
+                    output_addrs_mults: vec![(Address(F::from_canonical_u32(9)), F::one())],
+                    input_addr: Address(F::two()),
+                }),
+                Instruction::Hint(HintInstr { output_addrs_mults: vec![] }),
+                Instruction::HintExt2Felts(HintExt2FeltsInstr {
+                    output_addrs_mults: std::array::from_fn(|i| {
+                        (Address(F::from_canonical_usize(i)), F::zero())
+                    }),
+                    input_addr: Address(F::one()),
+                }),
+                Instruction::Print(PrintInstr {
+                    field_elt_type: FieldEltType::Extension,
+                    addr: Address(F::one()),
+                }),
+            ],
+            total_memory: 42,
+            ..Default::default()
+        };
+
+        let text = program.to_string();
+        let parsed: RecursionProgram<F> = text.parse().unwrap();
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(format!("{:?}", parsed.instructions), format!("{:?}", program.instructions));
+        assert_eq!(parsed.total_memory, 42);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |text: &str| text.parse::<RecursionProgram<F>>().unwrap_err();
+        assert_eq!(error("addf @1:1 = @2").message, "missing operand");
+        assert_eq!(error("\naddf @1 = @2, @3").line, 2);
+        assert_eq!(error("jump @1").message, "unknown instruction `jump`");
+        assert!(error("mem.write @0:1 = (2013265921, 0, 0, 0)").message.contains("canonical"));
+        assert!(error("addf @1:1 = @2, @3, @4").message.starts_with("unexpected operand"));
+    }
+}
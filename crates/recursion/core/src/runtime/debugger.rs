@@ -0,0 +1,466 @@
+//! A step debugger for the recursion runtime.
+//!
+//! The [Debugger] drives a [Runtime] one instruction at a time, and stops at breakpoints on
+//! instruction indices and on memory addresses. Instructions are mapped back to the DSL code that
+//! emitted them through the backtraces of the program, which are only recorded when `SP1_DEBUG` is
+//! set. Failed assertions are reported with the values that were compared.
+
+use std::{collections::BTreeSet, fmt::Write};
+
+use backtrace::Backtrace;
+use p3_field::{AbstractField, ExtensionField, PrimeField32};
+use p3_poseidon2::{Poseidon2, Poseidon2ExternalMatrixGeneral};
+use p3_symmetric::CryptographicPermutation;
+
+use super::{Instruction, Runtime, RuntimeError, PERMUTATION_WIDTH, POSEIDON2_SBOX_DEGREE};
+use crate::{air::Block, Address, BaseAluOpcode, ExtAluOpcode, MemAccessKind};
+
+/// The prefixes of the functions skipped when looking for the source location in a backtrace.
+pub const SKIPPED_FRAMES: &[&str] = &[
+    "backtrace::",
+    "std::",
+    "core::",
+    "alloc::",
+    "sp1_recursion_compiler::ir::",
+    "sp1_recursion_compiler::circuit::builder",
+];
+
+/// The first frame of the backtrace outside of the DSL and the builder, as `file:line`.
+pub fn call_site(mut trace: Backtrace) -> Option<String> {
+    trace.resolve();
+    trace.frames().iter().flat_map(|frame| frame.symbols()).find_map(|symbol| {
+        let name = symbol.name()?.to_string();
+        let name = name.trim_start_matches('<');
+        if SKIPPED_FRAMES.iter().any(|prefix| name.starts_with(prefix)) {
+            return None;
+        }
+        Some(format!("{}:{}", symbol.filename()?.display(), symbol.lineno()?))
+    })
+}
+
+/// The assertion a failed division was compiled from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Assertion<F> {
+    /// `lhs - rhs` divided by the constant zero.
+    Eq(Address<F>, Address<F>),
+    /// The constant one divided by `lhs - rhs`.
+    Ne(Address<F>, Address<F>),
+}
+
+/// The reason the debugger gave control back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop<F> {
+    /// A single instruction was executed.
+    Step,
+    /// The instruction at the given pc has a breakpoint and is about to be executed.
+    Breakpoint(usize),
+    /// The instruction at the given pc wrote or read a watched address.
+    Watchpoint { pc: usize, addr: Address<F> },
+    /// The program ran to completion.
+    Halted,
+}
+
+/// Executes a program with breakpoints on instruction indices and on memory addresses.
+pub struct Debugger<'r, 'a, F: PrimeField32, EF: ExtensionField<F>, Diffusion> {
+    runtime: &'r mut Runtime<'a, F, EF, Diffusion>,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: Vec<Address<F>>,
+}
+
+impl<'r, 'a, F: PrimeField32, EF: ExtensionField<F>, Diffusion> Debugger<'r, 'a, F, EF, Diffusion>
+where
+    Poseidon2<
+        F,
+        Poseidon2ExternalMatrixGeneral,
+        Diffusion,
+        PERMUTATION_WIDTH,
+        POSEIDON2_SBOX_DEGREE,
+    >: CryptographicPermutation<[F; PERMUTATION_WIDTH]>,
+{
+    /// Attaches a debugger to a runtime that has not started running.
+    pub fn new(runtime: &'r mut Runtime<'a, F, EF, Diffusion>) -> Self {
+        runtime.preallocate_record();
+        Self { runtime, breakpoints: BTreeSet::new(), watchpoints: Vec::new() }
+    }
+
+    /// Stops before executing the instruction at the given pc.
+    pub fn break_at(&mut self, pc: usize) -> &mut Self {
+        self.breakpoints.insert(pc);
+        self
+    }
+
+    /// Stops after executing any instruction that writes the address, or reads it with a nonzero
+    /// multiplicity.
+    pub fn watch(&mut self, addr: Address<F>) -> &mut Self {
+        if !self.watchpoints.contains(&addr) {
+            self.watchpoints.push(addr);
+        }
+        self
+    }
+
+    /// Removes all breakpoints and watchpoints.
+    pub fn clear(&mut self) {
+        self.breakpoints.clear();
+        self.watchpoints.clear();
+    }
+
+    /// The runtime being debugged.
+    pub fn runtime(&mut self) -> &mut Runtime<'a, F, EF, Diffusion> {
+        self.runtime
+    }
+
+    /// The pc of the next instruction to execute.
+    pub fn pc(&self) -> usize {
+        self.runtime.pc.as_canonical_u32() as usize
+    }
+
+    /// Whether the program ran to completion.
+    pub fn is_halted(&self) -> bool {
+        self.pc() >= self.runtime.program.instructions.len()
+    }
+
+    /// The next instruction to execute.
+    pub fn instruction(&self) -> Option<&Instruction<F>> {
+        self.runtime.program.instructions.get(self.pc())
+    }
+
+    /// The value at a memory address, if it has been written.
+    pub fn read(&self, addr: Address<F>) -> Option<Block<F>> {
+        self.runtime.memory.0.get(addr.as_usize()).map(|entry| entry.val)
+    }
+
+    /// Executes the next instruction.
+    ///
+    /// On error, the pc is left at the failing instruction, which can be passed to
+    /// [Debugger::report].
+    pub fn step(&mut self) -> Result<Stop<F>, RuntimeError<F, EF>> {
+        if self.is_halted() {
+            return Ok(Stop::Halted);
+        }
+        let pc = self.pc();
+        let before = self.watched();
+        self.runtime.step()?;
+        let after = self.watched();
+        let changed =
+            self.watchpoints.iter().zip(before.iter().zip(&after)).find(|(_, (b, a))| b != a);
+        Ok(match changed {
+            Some((&addr, _)) => Stop::Watchpoint { pc, addr },
+            None => Stop::Step,
+        })
+    }
+
+    /// Executes instructions until a breakpoint or a watchpoint is hit, or the program halts.
+    ///
+    /// A breakpoint on the next instruction is not hit again, so that resuming from a breakpoint
+    /// makes progress.
+    pub fn resume(&mut self) -> Result<Stop<F>, RuntimeError<F, EF>> {
+        loop {
+            match self.step()? {
+                Stop::Step => {}
+                stop => return Ok(stop),
+            }
+            if self.is_halted() {
+                return Ok(Stop::Halted);
+            }
+            if self.breakpoints.contains(&self.pc()) {
+                return Ok(Stop::Breakpoint(self.pc()));
+            }
+        }
+    }
+
+    /// The DSL source location of the instruction at the given pc, as `file:line`, together with
+    /// the pc it was taken from. Instructions without a backtrace are attributed to the nearest
+    /// preceding instruction that has one.
+    pub fn source_location(&self, pc: usize) -> Option<(usize, String)> {
+        let traces = &self.runtime.program.traces;
+        (0..=pc.min(traces.len().checked_sub(1)?))
+            .rev()
+            .find_map(|pc| Some((pc, call_site(traces[pc].clone()?)?)))
+    }
+
+    /// Describes a runtime error with the failing instruction, the values involved and its source
+    /// location.
+    ///
+    /// `assert_eq` is compiled to the division of `lhs - rhs` by the constant zero, and `assert_ne`
+    /// to the division of the constant one by `lhs - rhs`. A failed division with one of these
+    /// shapes is reported as a failed assertion on the operands of the subtraction, and any other
+    /// failed division as a division by zero.
+    pub fn report(&self, err: &RuntimeError<F, EF>) -> String {
+        let mut report = String::new();
+        let pc = match err {
+            RuntimeError::DivFOutOfDomain { in1, in2, instr, pc, .. } => {
+                let instruction = Instruction::BaseAlu(instr.clone());
+                match self.assertion(*pc, instr.addrs.in1, instr.addrs.in2) {
+                    Some(assertion) => {
+                        let (lhs, rhs) = write_assertion(&mut report, *pc, &instruction, assertion);
+                        self.write_felt(&mut report, "lhs", lhs);
+                        self.write_felt(&mut report, "rhs", rhs);
+                    }
+                    None => {
+                        writeln!(report, "division by zero at pc {pc}: {instruction}").unwrap();
+                        writeln!(
+                            report,
+                            "  {} / {}",
+                            in1.as_canonical_u32(),
+                            in2.as_canonical_u32()
+                        )
+                        .unwrap();
+                    }
+                }
+                *pc
+            }
+            RuntimeError::DivEOutOfDomain { in1, in2, instr, pc, .. } => {
+                let instruction = Instruction::ExtAlu(instr.clone());
+                match self.assertion(*pc, instr.addrs.in1, instr.addrs.in2) {
+                    Some(assertion) => {
+                        let (lhs, rhs) = write_assertion(&mut report, *pc, &instruction, assertion);
+                        self.write_ext(&mut report, "lhs", lhs);
+                        self.write_ext(&mut report, "rhs", rhs);
+                    }
+                    None => {
+                        writeln!(report, "division by zero at pc {pc}: {instruction}").unwrap();
+                        writeln!(
+                            report,
+                            "  {} / {}",
+                            block_string(in1.as_base_slice()),
+                            block_string(in2.as_base_slice())
+                        )
+                        .unwrap();
+                    }
+                }
+                *pc
+            }
+            err => {
+                let pc = self.pc();
+                match self.instruction() {
+                    Some(instruction) => writeln!(report, "{err} at pc {pc}: {instruction}"),
+                    None => writeln!(report, "{err}"),
+                }
+                .unwrap();
+                pc
+            }
+        };
+        match self.source_location(pc) {
+            Some((trace_pc, location)) if trace_pc == pc => {
+                writeln!(report, "  at {location}").unwrap()
+            }
+            Some((trace_pc, location)) => {
+                writeln!(report, "  near {location} (pc {trace_pc})").unwrap()
+            }
+            None => writeln!(report, "  no backtrace, set SP1_DEBUG=1 when building the program")
+                .unwrap(),
+        }
+        report
+    }
+
+    /// The assertion a division at the given pc was compiled from, if its operands have the shape
+    /// of one.
+    fn assertion(&self, pc: usize, in1: Address<F>, in2: Address<F>) -> Option<Assertion<F>> {
+        let zero = Block::from(F::zero());
+        let one = Block::from(F::one());
+        match (self.writer(pc, in1)?, self.writer(pc, in2)?) {
+            (Writer::Sub(lhs, rhs), Writer::Const(divisor)) if divisor == zero => {
+                Some(Assertion::Eq(lhs, rhs))
+            }
+            (Writer::Const(dividend), Writer::Sub(lhs, rhs)) if dividend == one => {
+                Some(Assertion::Ne(lhs, rhs))
+            }
+            _ => None,
+        }
+    }
+
+    /// The last instruction before the given pc that wrote the given address.
+    fn writer(&self, pc: usize, addr: Address<F>) -> Option<Writer<F>> {
+        self.runtime.program.instructions[..pc].iter().rev().find_map(|instruction| {
+            match instruction {
+                Instruction::BaseAlu(instr) if instr.addrs.out == addr => {
+                    Some(match instr.opcode {
+                        BaseAluOpcode::SubF => Writer::Sub(instr.addrs.in1, instr.addrs.in2),
+                        _ => Writer::Other,
+                    })
+                }
+                Instruction::ExtAlu(instr) if instr.addrs.out == addr => Some(match instr.opcode {
+                    ExtAluOpcode::SubE => Writer::Sub(instr.addrs.in1, instr.addrs.in2),
+                    _ => Writer::Other,
+                }),
+                Instruction::Mem(instr)
+                    if instr.kind == MemAccessKind::Write && instr.addrs.inner == addr =>
+                {
+                    Some(Writer::Const(instr.vals.inner))
+                }
+                _ => None,
+            }
+        })
+    }
+
+    fn write_felt(&self, report: &mut String, name: &str, addr: Address<F>) {
+        let value = self
+            .read(addr)
+            .map_or("<unassigned>".to_string(), |block| block.0[0].as_canonical_u32().to_string());
+        writeln!(report, "  {name} @{} = {value}", addr.as_usize()).unwrap();
+    }
+
+    fn write_ext(&self, report: &mut String, name: &str, addr: Address<F>) {
+        let value =
+            self.read(addr).map_or("<unassigned>".to_string(), |block| block_string(&block.0));
+        writeln!(report, "  {name} @{} = {value}", addr.as_usize()).unwrap();
+    }
+
+    /// The value and multiplicity of each watched address.
+    fn watched(&self) -> Vec<Option<(Block<F>, F)>> {
+        self.watchpoints
+            .iter()
+            .map(|addr| {
+                self.runtime.memory.0.get(addr.as_usize()).map(|entry| (entry.val, entry.mult))
+            })
+            .collect()
+    }
+}
+
+/// The instruction that wrote an operand of a failed division.
+enum Writer<F> {
+    /// A subtraction of the given addresses.
+    Sub(Address<F>, Address<F>),
+    /// A memory write of a constant.
+    Const(Block<F>),
+    /// Any other instruction.
+    Other,
+}
+
+/// Writes the header of a failed assertion and returns the addresses of its operands.
+fn write_assertion<F: PrimeField32>(
+    report: &mut String,
+    pc: usize,
+    instruction: &Instruction<F>,
+    assertion: Assertion<F>,
+) -> (Address<F>, Address<F>) {
+    let (expected, lhs, rhs) = match assertion {
+        Assertion::Eq(lhs, rhs) => ("==", lhs, rhs),
+        Assertion::Ne(lhs, rhs) => ("!=", lhs, rhs),
+    };
+    writeln!(report, "assertion `lhs {expected} rhs` failed at pc {pc}: {instruction}").unwrap();
+    (lhs, rhs)
+}
+
+fn block_string<F: PrimeField32>(values: &[F]) -> String {
+    format!(
+        "({})",
+        values.iter().map(|x| x.as_canonical_u32().to_string()).collect::<Vec<_>>().join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use p3_baby_bear::{BabyBear, DiffusionMatrixBabyBear};
+    use p3_field::extension::BinomialExtensionField;
+    use sp1_stark::baby_bear_poseidon2::BabyBearPoseidon2;
+
+    use super::*;
+    use crate::RecursionProgram;
+
+    type F = BabyBear;
+    type EF = BinomialExtensionField<BabyBear, 4>;
+
+    fn runtime(program: RecursionProgram<F>) -> Runtime<'static, F, EF, DiffusionMatrixBabyBear> {
+        Runtime::new(Arc::new(program), BabyBearPoseidon2::new().perm)
+    }
+
+    /// Runs the program to its failure and returns the lines of the report.
+    fn report(program: &str) -> Vec<String> {
+        let mut runtime = runtime(program.parse().unwrap());
+        let mut debugger = Debugger::new(&mut runtime);
+        let err = debugger.resume().unwrap_err();
+        debugger.report(&err).lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_debugger() {
+        let mut program: RecursionProgram<F> = "
+            .total_memory 6
+            mem.write @0:1 = (3, 0, 0, 0)
+            mem.write @1:1 = (4, 0, 0, 0)
+            mem.write @2:1 = (0, 0, 0, 0)
+            subf @3:1 = @0, @1
+            mulf @4:0 = @0, @0
+            divf @5:0 = @3, @2
+        "
+        .parse()
+        .unwrap();
+        program.traces = vec![None; program.instructions.len()];
+        program.traces[3] = Some(Backtrace::new_unresolved());
+
+        let mut runtime = runtime(program);
+        let mut debugger = Debugger::new(&mut runtime);
+        debugger.break_at(5).watch(Address(F::from_canonical_u32(3)));
+
+        assert_eq!(debugger.step().unwrap(), Stop::Step);
+        assert_eq!(
+            debugger.resume().unwrap(),
+            Stop::Watchpoint { pc: 3, addr: Address(F::from_canonical_u32(3)) }
+        );
+        assert_eq!(
+            debugger.read(Address(F::from_canonical_u32(3))),
+            Some(-F::one()).map(Block::from)
+        );
+        assert_eq!(debugger.resume().unwrap(), Stop::Breakpoint(5));
+        let err = debugger.resume().unwrap_err();
+        assert_eq!(debugger.pc(), 5);
+
+        let report = debugger.report(&err);
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4, "{report}");
+        assert_eq!(lines[0], "assertion `lhs == rhs` failed at pc 5: divf @5:0 = @3, @2");
+        assert_eq!(lines[1], "  lhs @0 = 3");
+        assert_eq!(lines[2], "  rhs @1 = 4");
+        assert!(lines[3].starts_with("  near ") && lines[3].ends_with(" (pc 3)"), "{report}");
+        assert!(lines[3].contains("debugger.rs:"), "{report}");
+    }
+
+    #[test]
+    fn test_report_assert_ne() {
+        let report = report(
+            "
+            .total_memory 5
+            mem.write @0:1 = (3, 0, 0, 0)
+            mem.write @1:1 = (3, 0, 0, 0)
+            mem.write @2:1 = (1, 0, 0, 0)
+            subf @3:1 = @0, @1
+            divf @4:0 = @2, @3
+            ",
+        );
+        assert_eq!(
+            report,
+            [
+                "assertion `lhs != rhs` failed at pc 4: divf @4:0 = @2, @3",
+                "  lhs @0 = 3",
+                "  rhs @1 = 3",
+                "  no backtrace, set SP1_DEBUG=1 when building the program",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_division_by_zero() {
+        // The numerator is a difference, but the divisor is not the constant zero of an assertion.
+        let report = report(
+            "
+            .total_memory 6
+            mem.write @0:1 = (3, 0, 0, 0)
+            mem.write @1:1 = (4, 0, 0, 0)
+            subf @2:1 = @0, @1
+            subf @3:1 = @0, @0
+            divf @4:0 = @2, @3
+            ",
+        );
+        assert_eq!(
+            report,
+            [
+                "division by zero at pc 4: divf @4:0 = @2, @3",
+                "  2013265920 / 0",
+                "  no backtrace, set SP1_DEBUG=1 when building the program",
+            ]
+        );
+    }
+}
@@ -1,3 +1,5 @@
+pub mod asm;
+pub mod debugger;
 pub mod instruction;
 mod memory;
 mod opcode;
@@ -257,339 +259,338 @@ where
             .map_or(usize::MAX, |ts: String| ts.parse().unwrap());
         self.preallocate_record();
         while self.pc < F::from_canonical_u32(self.program.instructions.len() as u32) {
-            let idx = self.pc.as_canonical_u32() as usize;
-            let instruction = self.program.instructions[idx].clone();
-
-            let next_clk = self.clk + F::from_canonical_u32(4);
-            let next_pc = self.pc + F::one();
-            match instruction {
-                Instruction::BaseAlu(instr @ BaseAluInstr { opcode, mult, addrs }) => {
-                    self.nb_base_ops += 1;
-                    let in1 = self.memory.mr(addrs.in1).val[0];
-                    let in2 = self.memory.mr(addrs.in2).val[0];
-                    // Do the computation.
-                    let out = match opcode {
-                        BaseAluOpcode::AddF => in1 + in2,
-                        BaseAluOpcode::SubF => in1 - in2,
-                        BaseAluOpcode::MulF => in1 * in2,
-                        BaseAluOpcode::DivF => match in1.try_div(in2) {
-                            Some(x) => x,
-                            None => {
-                                // Check for division exceptions and error. Note that 0/0 is defined
-                                // to be 1.
-                                if in1.is_zero() {
-                                    AbstractField::one()
-                                } else {
-                                    return Err(RuntimeError::DivFOutOfDomain {
-                                        in1,
-                                        in2,
-                                        instr,
-                                        pc: self.pc.as_canonical_u32() as usize,
-                                        trace: self.nearest_pc_backtrace(),
-                                    });
-                                }
+            self.step()?;
+
+            if self.timestamp >= early_exit_ts {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes the instruction at the current pc and advances the pc and the clock.
+    fn step(&mut self) -> Result<(), RuntimeError<F, EF>> {
+        let idx = self.pc.as_canonical_u32() as usize;
+        let instruction = self.program.instructions[idx].clone();
+
+        let next_clk = self.clk + F::from_canonical_u32(4);
+        let next_pc = self.pc + F::one();
+        match instruction {
+            Instruction::BaseAlu(instr @ BaseAluInstr { opcode, mult, addrs }) => {
+                self.nb_base_ops += 1;
+                let in1 = self.memory.mr(addrs.in1).val[0];
+                let in2 = self.memory.mr(addrs.in2).val[0];
+                // Do the computation.
+                let out = match opcode {
+                    BaseAluOpcode::AddF => in1 + in2,
+                    BaseAluOpcode::SubF => in1 - in2,
+                    BaseAluOpcode::MulF => in1 * in2,
+                    BaseAluOpcode::DivF => match in1.try_div(in2) {
+                        Some(x) => x,
+                        None => {
+                            // Check for division exceptions and error. Note that 0/0 is defined
+                            // to be 1.
+                            if in1.is_zero() {
+                                AbstractField::one()
+                            } else {
+                                return Err(RuntimeError::DivFOutOfDomain {
+                                    in1,
+                                    in2,
+                                    instr,
+                                    pc: self.pc.as_canonical_u32() as usize,
+                                    trace: self.nearest_pc_backtrace(),
+                                });
                             }
-                        },
-                    };
-                    self.memory.mw(addrs.out, Block::from(out), mult);
-                    self.record.base_alu_events.push(BaseAluEvent { out, in1, in2 });
-                }
-                Instruction::ExtAlu(instr @ ExtAluInstr { opcode, mult, addrs }) => {
-                    self.nb_ext_ops += 1;
-                    let in1 = self.memory.mr(addrs.in1).val;
-                    let in2 = self.memory.mr(addrs.in2).val;
-                    // Do the computation.
-                    let in1_ef = EF::from_base_slice(&in1.0);
-                    let in2_ef = EF::from_base_slice(&in2.0);
-                    let out_ef = match opcode {
-                        ExtAluOpcode::AddE => in1_ef + in2_ef,
-                        ExtAluOpcode::SubE => in1_ef - in2_ef,
-                        ExtAluOpcode::MulE => in1_ef * in2_ef,
-                        ExtAluOpcode::DivE => match in1_ef.try_div(in2_ef) {
-                            Some(x) => x,
-                            None => {
-                                // Check for division exceptions and error. Note that 0/0 is defined
-                                // to be 1.
-                                if in1_ef.is_zero() {
-                                    AbstractField::one()
-                                } else {
-                                    return Err(RuntimeError::DivEOutOfDomain {
-                                        in1: in1_ef,
-                                        in2: in2_ef,
-                                        instr,
-                                        pc: self.pc.as_canonical_u32() as usize,
-                                        trace: self.nearest_pc_backtrace(),
-                                    });
-                                }
+                        }
+                    },
+                };
+                self.memory.mw(addrs.out, Block::from(out), mult);
+                self.record.base_alu_events.push(BaseAluEvent { out, in1, in2 });
+            }
+            Instruction::ExtAlu(instr @ ExtAluInstr { opcode, mult, addrs }) => {
+                self.nb_ext_ops += 1;
+                let in1 = self.memory.mr(addrs.in1).val;
+                let in2 = self.memory.mr(addrs.in2).val;
+                // Do the computation.
+                let in1_ef = EF::from_base_slice(&in1.0);
+                let in2_ef = EF::from_base_slice(&in2.0);
+                let out_ef = match opcode {
+                    ExtAluOpcode::AddE => in1_ef + in2_ef,
+                    ExtAluOpcode::SubE => in1_ef - in2_ef,
+                    ExtAluOpcode::MulE => in1_ef * in2_ef,
+                    ExtAluOpcode::DivE => match in1_ef.try_div(in2_ef) {
+                        Some(x) => x,
+                        None => {
+                            // Check for division exceptions and error. Note that 0/0 is defined
+                            // to be 1.
+                            if in1_ef.is_zero() {
+                                AbstractField::one()
+                            } else {
+                                return Err(RuntimeError::DivEOutOfDomain {
+                                    in1: in1_ef,
+                                    in2: in2_ef,
+                                    instr,
+                                    pc: self.pc.as_canonical_u32() as usize,
+                                    trace: self.nearest_pc_backtrace(),
+                                });
                             }
-                        },
-                    };
-                    let out = Block::from(out_ef.as_base_slice());
-                    self.memory.mw(addrs.out, out, mult);
-                    self.record.ext_alu_events.push(ExtAluEvent { out, in1, in2 });
-                }
-                Instruction::Mem(MemInstr {
-                    addrs: MemIo { inner: addr },
-                    vals: MemIo { inner: val },
-                    mult,
-                    kind,
-                }) => {
-                    self.nb_memory_ops += 1;
-                    match kind {
-                        MemAccessKind::Read => {
-                            let mem_entry = self.memory.mr_mult(addr, mult);
-                            assert_eq!(
-                                mem_entry.val, val,
-                                "stored memory value should be the specified value"
-                            );
                         }
-                        MemAccessKind::Write => drop(self.memory.mw(addr, val, mult)),
+                    },
+                };
+                let out = Block::from(out_ef.as_base_slice());
+                self.memory.mw(addrs.out, out, mult);
+                self.record.ext_alu_events.push(ExtAluEvent { out, in1, in2 });
+            }
+            Instruction::Mem(MemInstr {
+                addrs: MemIo { inner: addr },
+                vals: MemIo { inner: val },
+                mult,
+                kind,
+            }) => {
+                self.nb_memory_ops += 1;
+                match kind {
+                    MemAccessKind::Read => {
+                        let mem_entry = self.memory.mr_mult(addr, mult);
+                        assert_eq!(
+                            mem_entry.val, val,
+                            "stored memory value should be the specified value"
+                        );
                     }
-                    self.record.mem_const_count += 1;
-                }
-                Instruction::Poseidon2(instr) => {
-                    let Poseidon2Instr { addrs: Poseidon2Io { input, output }, mults } = *instr;
-                    self.nb_poseidons += 1;
-                    let in_vals = std::array::from_fn(|i| self.memory.mr(input[i]).val[0]);
-                    let perm_output = self.perm.as_ref().unwrap().permute(in_vals);
-
-                    perm_output.iter().zip(output).zip(mults).for_each(|((&val, addr), mult)| {
-                        self.memory.mw(addr, Block::from(val), mult);
-                    });
-                    self.record
-                        .poseidon2_events
-                        .push(Poseidon2Event { input: in_vals, output: perm_output });
-                }
-                Instruction::Select(SelectInstr {
-                    addrs: SelectIo { bit, out1, out2, in1, in2 },
-                    mult1,
-                    mult2,
-                }) => {
-                    self.nb_select += 1;
-                    let bit = self.memory.mr(bit).val[0];
-                    let in1 = self.memory.mr(in1).val[0];
-                    let in2 = self.memory.mr(in2).val[0];
-                    let out1_val = bit * in2 + (F::one() - bit) * in1;
-                    let out2_val = bit * in1 + (F::one() - bit) * in2;
-                    self.memory.mw(out1, Block::from(out1_val), mult1);
-                    self.memory.mw(out2, Block::from(out2_val), mult2);
-                    self.record.select_events.push(SelectEvent {
-                        bit,
-                        out1: out1_val,
-                        out2: out2_val,
-                        in1,
-                        in2,
-                    })
+                    MemAccessKind::Write => drop(self.memory.mw(addr, val, mult)),
                 }
-                Instruction::ExpReverseBitsLen(ExpReverseBitsInstr {
-                    addrs: ExpReverseBitsIo { base, exp, result },
-                    mult,
-                }) => {
-                    self.nb_exp_reverse_bits += 1;
-                    let base_val = self.memory.mr(base).val[0];
-                    let exp_bits: Vec<_> =
-                        exp.iter().map(|bit| self.memory.mr(*bit).val[0]).collect();
-                    let exp_val = exp_bits
-                        .iter()
-                        .enumerate()
-                        .fold(0, |acc, (i, &val)| acc + val.as_canonical_u32() * (1 << i));
-                    let out =
-                        base_val.exp_u64(reverse_bits_len(exp_val as usize, exp_bits.len()) as u64);
-                    self.memory.mw(result, Block::from(out), mult);
-                    self.record.exp_reverse_bits_len_events.push(ExpReverseBitsEvent {
-                        result: out,
-                        base: base_val,
-                        exp: exp_bits,
-                    });
-                }
-                Instruction::HintBits(HintBitsInstr { output_addrs_mults, input_addr }) => {
-                    self.nb_bit_decompositions += 1;
-                    let num = self.memory.mr_mult(input_addr, F::zero()).val[0].as_canonical_u32();
-                    // Decompose the num into LE bits.
-                    let bits = (0..output_addrs_mults.len())
-                        .map(|i| Block::from(F::from_canonical_u32((num >> i) & 1)))
-                        .collect::<Vec<_>>();
-                    // Write the bits to the array at dst.
-                    for (bit, (addr, mult)) in bits.into_iter().zip(output_addrs_mults) {
-                        self.memory.mw(addr, bit, mult);
-                        self.record.mem_var_events.push(MemEvent { inner: bit });
-                    }
+                self.record.mem_const_count += 1;
+            }
+            Instruction::Poseidon2(instr) => {
+                let Poseidon2Instr { addrs: Poseidon2Io { input, output }, mults } = *instr;
+                self.nb_poseidons += 1;
+                let in_vals = std::array::from_fn(|i| self.memory.mr(input[i]).val[0]);
+                let perm_output = self.perm.as_ref().unwrap().permute(in_vals);
+
+                perm_output.iter().zip(output).zip(mults).for_each(|((&val, addr), mult)| {
+                    self.memory.mw(addr, Block::from(val), mult);
+                });
+                self.record
+                    .poseidon2_events
+                    .push(Poseidon2Event { input: in_vals, output: perm_output });
+            }
+            Instruction::Select(SelectInstr {
+                addrs: SelectIo { bit, out1, out2, in1, in2 },
+                mult1,
+                mult2,
+            }) => {
+                self.nb_select += 1;
+                let bit = self.memory.mr(bit).val[0];
+                let in1 = self.memory.mr(in1).val[0];
+                let in2 = self.memory.mr(in2).val[0];
+                let out1_val = bit * in2 + (F::one() - bit) * in1;
+                let out2_val = bit * in1 + (F::one() - bit) * in2;
+                self.memory.mw(out1, Block::from(out1_val), mult1);
+                self.memory.mw(out2, Block::from(out2_val), mult2);
+                self.record.select_events.push(SelectEvent {
+                    bit,
+                    out1: out1_val,
+                    out2: out2_val,
+                    in1,
+                    in2,
+                })
+            }
+            Instruction::ExpReverseBitsLen(ExpReverseBitsInstr {
+                addrs: ExpReverseBitsIo { base, exp, result },
+                mult,
+            }) => {
+                self.nb_exp_reverse_bits += 1;
+                let base_val = self.memory.mr(base).val[0];
+                let exp_bits: Vec<_> = exp.iter().map(|bit| self.memory.mr(*bit).val[0]).collect();
+                let exp_val = exp_bits
+                    .iter()
+                    .enumerate()
+                    .fold(0, |acc, (i, &val)| acc + val.as_canonical_u32() * (1 << i));
+                let out =
+                    base_val.exp_u64(reverse_bits_len(exp_val as usize, exp_bits.len()) as u64);
+                self.memory.mw(result, Block::from(out), mult);
+                self.record.exp_reverse_bits_len_events.push(ExpReverseBitsEvent {
+                    result: out,
+                    base: base_val,
+                    exp: exp_bits,
+                });
+            }
+            Instruction::HintBits(HintBitsInstr { output_addrs_mults, input_addr }) => {
+                self.nb_bit_decompositions += 1;
+                let num = self.memory.mr_mult(input_addr, F::zero()).val[0].as_canonical_u32();
+                // Decompose the num into LE bits.
+                let bits = (0..output_addrs_mults.len())
+                    .map(|i| Block::from(F::from_canonical_u32((num >> i) & 1)))
+                    .collect::<Vec<_>>();
+                // Write the bits to the array at dst.
+                for (bit, (addr, mult)) in bits.into_iter().zip(output_addrs_mults) {
+                    self.memory.mw(addr, bit, mult);
+                    self.record.mem_var_events.push(MemEvent { inner: bit });
                 }
+            }
 
-                Instruction::FriFold(instr) => {
-                    let FriFoldInstr {
-                        base_single_addrs,
-                        ext_single_addrs,
-                        ext_vec_addrs,
-                        alpha_pow_mults,
-                        ro_mults,
-                    } = *instr;
-                    self.nb_fri_fold += 1;
-                    let x = self.memory.mr(base_single_addrs.x).val[0];
-                    let z = self.memory.mr(ext_single_addrs.z).val;
-                    let z: EF = z.ext();
-                    let alpha = self.memory.mr(ext_single_addrs.alpha).val;
-                    let alpha: EF = alpha.ext();
-                    let mat_opening = ext_vec_addrs
-                        .mat_opening
-                        .iter()
-                        .map(|addr| self.memory.mr(*addr).val)
-                        .collect_vec();
-                    let ps_at_z = ext_vec_addrs
-                        .ps_at_z
-                        .iter()
-                        .map(|addr| self.memory.mr(*addr).val)
-                        .collect_vec();
-
-                    for m in 0..ps_at_z.len() {
-                        // let m = F::from_canonical_u32(m);
-                        // Get the opening values.
-                        let p_at_x = mat_opening[m];
-                        let p_at_x: EF = p_at_x.ext();
-                        let p_at_z = ps_at_z[m];
-                        let p_at_z: EF = p_at_z.ext();
-
-                        // Calculate the quotient and update the values
-                        let quotient = (-p_at_z + p_at_x) / (-z + x);
-
-                        // First we peek to get the current value.
-                        let alpha_pow: EF =
-                            self.memory.mr(ext_vec_addrs.alpha_pow_input[m]).val.ext();
-
-                        let ro: EF = self.memory.mr(ext_vec_addrs.ro_input[m]).val.ext();
-
-                        let new_ro = ro + alpha_pow * quotient;
-                        let new_alpha_pow = alpha_pow * alpha;
-
-                        let _ = self.memory.mw(
-                            ext_vec_addrs.ro_output[m],
-                            Block::from(new_ro.as_base_slice()),
-                            ro_mults[m],
-                        );
+            Instruction::FriFold(instr) => {
+                let FriFoldInstr {
+                    base_single_addrs,
+                    ext_single_addrs,
+                    ext_vec_addrs,
+                    alpha_pow_mults,
+                    ro_mults,
+                } = *instr;
+                self.nb_fri_fold += 1;
+                let x = self.memory.mr(base_single_addrs.x).val[0];
+                let z = self.memory.mr(ext_single_addrs.z).val;
+                let z: EF = z.ext();
+                let alpha = self.memory.mr(ext_single_addrs.alpha).val;
+                let alpha: EF = alpha.ext();
+                let mat_opening = ext_vec_addrs
+                    .mat_opening
+                    .iter()
+                    .map(|addr| self.memory.mr(*addr).val)
+                    .collect_vec();
+                let ps_at_z = ext_vec_addrs
+                    .ps_at_z
+                    .iter()
+                    .map(|addr| self.memory.mr(*addr).val)
+                    .collect_vec();
+
+                for m in 0..ps_at_z.len() {
+                    // let m = F::from_canonical_u32(m);
+                    // Get the opening values.
+                    let p_at_x = mat_opening[m];
+                    let p_at_x: EF = p_at_x.ext();
+                    let p_at_z = ps_at_z[m];
+                    let p_at_z: EF = p_at_z.ext();
+
+                    // Calculate the quotient and update the values
+                    let quotient = (-p_at_z + p_at_x) / (-z + x);
+
+                    // First we peek to get the current value.
+                    let alpha_pow: EF = self.memory.mr(ext_vec_addrs.alpha_pow_input[m]).val.ext();
+
+                    let ro: EF = self.memory.mr(ext_vec_addrs.ro_input[m]).val.ext();
+
+                    let new_ro = ro + alpha_pow * quotient;
+                    let new_alpha_pow = alpha_pow * alpha;
 
-                        let _ = self.memory.mw(
-                            ext_vec_addrs.alpha_pow_output[m],
-                            Block::from(new_alpha_pow.as_base_slice()),
-                            alpha_pow_mults[m],
-                        );
-
-                        self.record.fri_fold_events.push(FriFoldEvent {
-                            base_single: FriFoldBaseIo { x },
-                            ext_single: FriFoldExtSingleIo {
-                                z: Block::from(z.as_base_slice()),
-                                alpha: Block::from(alpha.as_base_slice()),
-                            },
-                            ext_vec: FriFoldExtVecIo {
-                                mat_opening: Block::from(p_at_x.as_base_slice()),
-                                ps_at_z: Block::from(p_at_z.as_base_slice()),
-                                alpha_pow_input: Block::from(alpha_pow.as_base_slice()),
-                                ro_input: Block::from(ro.as_base_slice()),
-                                alpha_pow_output: Block::from(new_alpha_pow.as_base_slice()),
-                                ro_output: Block::from(new_ro.as_base_slice()),
-                            },
-                        });
-                    }
-                }
-                Instruction::BatchFRI(instr) => {
-                    let BatchFRIInstr { base_vec_addrs, ext_single_addrs, ext_vec_addrs, acc_mult } =
-                        *instr;
-
-                    let mut acc = EF::zero();
-                    let p_at_xs = base_vec_addrs
-                        .p_at_x
-                        .iter()
-                        .map(|addr| self.memory.mr(*addr).val[0])
-                        .collect_vec();
-                    let p_at_zs = ext_vec_addrs
-                        .p_at_z
-                        .iter()
-                        .map(|addr| self.memory.mr(*addr).val.ext::<EF>())
-                        .collect_vec();
-                    let alpha_pows: Vec<_> = ext_vec_addrs
-                        .alpha_pow
-                        .iter()
-                        .map(|addr| self.memory.mr(*addr).val.ext::<EF>())
-                        .collect_vec();
-
-                    self.nb_batch_fri += p_at_zs.len();
-                    for m in 0..p_at_zs.len() {
-                        acc += alpha_pows[m] * (p_at_zs[m] - EF::from_base(p_at_xs[m]));
-                        self.record.batch_fri_events.push(BatchFRIEvent {
-                            base_vec: BatchFRIBaseVecIo { p_at_x: p_at_xs[m] },
-                            ext_single: BatchFRIExtSingleIo {
-                                acc: Block::from(acc.as_base_slice()),
-                            },
-                            ext_vec: BatchFRIExtVecIo {
-                                p_at_z: Block::from(p_at_zs[m].as_base_slice()),
-                                alpha_pow: Block::from(alpha_pows[m].as_base_slice()),
-                            },
-                        });
-                    }
+                    let _ = self.memory.mw(
+                        ext_vec_addrs.ro_output[m],
+                        Block::from(new_ro.as_base_slice()),
+                        ro_mults[m],
+                    );
 
                     let _ = self.memory.mw(
-                        ext_single_addrs.acc,
-                        Block::from(acc.as_base_slice()),
-                        acc_mult,
+                        ext_vec_addrs.alpha_pow_output[m],
+                        Block::from(new_alpha_pow.as_base_slice()),
+                        alpha_pow_mults[m],
                     );
+
+                    self.record.fri_fold_events.push(FriFoldEvent {
+                        base_single: FriFoldBaseIo { x },
+                        ext_single: FriFoldExtSingleIo {
+                            z: Block::from(z.as_base_slice()),
+                            alpha: Block::from(alpha.as_base_slice()),
+                        },
+                        ext_vec: FriFoldExtVecIo {
+                            mat_opening: Block::from(p_at_x.as_base_slice()),
+                            ps_at_z: Block::from(p_at_z.as_base_slice()),
+                            alpha_pow_input: Block::from(alpha_pow.as_base_slice()),
+                            ro_input: Block::from(ro.as_base_slice()),
+                            alpha_pow_output: Block::from(new_alpha_pow.as_base_slice()),
+                            ro_output: Block::from(new_ro.as_base_slice()),
+                        },
+                    });
                 }
-                Instruction::CommitPublicValues(instr) => {
-                    let pv_addrs = instr.pv_addrs.as_array();
-                    let pv_values: [F; RECURSIVE_PROOF_NUM_PV_ELTS] =
-                        array::from_fn(|i| self.memory.mr(pv_addrs[i]).val[0]);
-                    self.record.public_values = *pv_values.as_slice().borrow();
-                    self.record
-                        .commit_pv_hash_events
-                        .push(CommitPublicValuesEvent { public_values: self.record.public_values });
+            }
+            Instruction::BatchFRI(instr) => {
+                let BatchFRIInstr { base_vec_addrs, ext_single_addrs, ext_vec_addrs, acc_mult } =
+                    *instr;
+
+                let mut acc = EF::zero();
+                let p_at_xs = base_vec_addrs
+                    .p_at_x
+                    .iter()
+                    .map(|addr| self.memory.mr(*addr).val[0])
+                    .collect_vec();
+                let p_at_zs = ext_vec_addrs
+                    .p_at_z
+                    .iter()
+                    .map(|addr| self.memory.mr(*addr).val.ext::<EF>())
+                    .collect_vec();
+                let alpha_pows: Vec<_> = ext_vec_addrs
+                    .alpha_pow
+                    .iter()
+                    .map(|addr| self.memory.mr(*addr).val.ext::<EF>())
+                    .collect_vec();
+
+                self.nb_batch_fri += p_at_zs.len();
+                for m in 0..p_at_zs.len() {
+                    acc += alpha_pows[m] * (p_at_zs[m] - EF::from_base(p_at_xs[m]));
+                    self.record.batch_fri_events.push(BatchFRIEvent {
+                        base_vec: BatchFRIBaseVecIo { p_at_x: p_at_xs[m] },
+                        ext_single: BatchFRIExtSingleIo { acc: Block::from(acc.as_base_slice()) },
+                        ext_vec: BatchFRIExtVecIo {
+                            p_at_z: Block::from(p_at_zs[m].as_base_slice()),
+                            alpha_pow: Block::from(alpha_pows[m].as_base_slice()),
+                        },
+                    });
                 }
 
-                Instruction::Print(PrintInstr { field_elt_type, addr }) => match field_elt_type {
-                    FieldEltType::Base => {
-                        self.nb_print_f += 1;
-                        let f = self.memory.mr_mult(addr, F::zero()).val[0];
-                        writeln!(self.debug_stdout, "PRINTF={f}")
-                    }
-                    FieldEltType::Extension => {
-                        self.nb_print_e += 1;
-                        let ef = self.memory.mr_mult(addr, F::zero()).val;
-                        writeln!(self.debug_stdout, "PRINTEF={ef:?}")
-                    }
+                let _ = self.memory.mw(
+                    ext_single_addrs.acc,
+                    Block::from(acc.as_base_slice()),
+                    acc_mult,
+                );
+            }
+            Instruction::CommitPublicValues(instr) => {
+                let pv_addrs = instr.pv_addrs.as_array();
+                let pv_values: [F; RECURSIVE_PROOF_NUM_PV_ELTS] =
+                    array::from_fn(|i| self.memory.mr(pv_addrs[i]).val[0]);
+                self.record.public_values = *pv_values.as_slice().borrow();
+                self.record
+                    .commit_pv_hash_events
+                    .push(CommitPublicValuesEvent { public_values: self.record.public_values });
+            }
+
+            Instruction::Print(PrintInstr { field_elt_type, addr }) => match field_elt_type {
+                FieldEltType::Base => {
+                    self.nb_print_f += 1;
+                    let f = self.memory.mr_mult(addr, F::zero()).val[0];
+                    writeln!(self.debug_stdout, "PRINTF={f}")
                 }
-                .map_err(RuntimeError::DebugPrint)?,
-                Instruction::HintExt2Felts(HintExt2FeltsInstr {
-                    output_addrs_mults,
-                    input_addr,
-                }) => {
-                    self.nb_bit_decompositions += 1;
-                    let fs = self.memory.mr_mult(input_addr, F::zero()).val;
-                    // Write the bits to the array at dst.
-                    for (f, (addr, mult)) in fs.into_iter().zip(output_addrs_mults) {
-                        let felt = Block::from(f);
-                        self.memory.mw(addr, felt, mult);
-                        self.record.mem_var_events.push(MemEvent { inner: felt });
-                    }
+                FieldEltType::Extension => {
+                    self.nb_print_e += 1;
+                    let ef = self.memory.mr_mult(addr, F::zero()).val;
+                    writeln!(self.debug_stdout, "PRINTEF={ef:?}")
                 }
-                Instruction::Hint(HintInstr { output_addrs_mults }) => {
-                    // Check that enough Blocks can be read, so `drain` does not panic.
-                    if self.witness_stream.len() < output_addrs_mults.len() {
-                        return Err(RuntimeError::EmptyWitnessStream);
-                    }
-                    let witness = self.witness_stream.drain(0..output_addrs_mults.len());
-                    for ((addr, mult), val) in zip(output_addrs_mults, witness) {
-                        // Inline [`Self::mw`] to mutably borrow multiple fields of `self`.
-                        self.memory.mw(addr, val, mult);
-                        self.record.mem_var_events.push(MemEvent { inner: val });
-                    }
+            }
+            .map_err(RuntimeError::DebugPrint)?,
+            Instruction::HintExt2Felts(HintExt2FeltsInstr { output_addrs_mults, input_addr }) => {
+                self.nb_bit_decompositions += 1;
+                let fs = self.memory.mr_mult(input_addr, F::zero()).val;
+                // Write the bits to the array at dst.
+                for (f, (addr, mult)) in fs.into_iter().zip(output_addrs_mults) {
+                    let felt = Block::from(f);
+                    self.memory.mw(addr, felt, mult);
+                    self.record.mem_var_events.push(MemEvent { inner: felt });
                 }
             }
-
-            self.pc = next_pc;
-            self.clk = next_clk;
-            self.timestamp += 1;
-
-            if self.timestamp >= early_exit_ts {
-                break;
+            Instruction::Hint(HintInstr { output_addrs_mults }) => {
+                // Check that enough Blocks can be read, so `drain` does not panic.
+                if self.witness_stream.len() < output_addrs_mults.len() {
+                    return Err(RuntimeError::EmptyWitnessStream);
+                }
+                let witness = self.witness_stream.drain(0..output_addrs_mults.len());
+                for ((addr, mult), val) in zip(output_addrs_mults, witness) {
+                    // Inline [`Self::mw`] to mutably borrow multiple fields of `self`.
+                    self.memory.mw(addr, val, mult);
+                    self.record.mem_var_events.push(MemEvent { inner: val });
+                }
             }
         }
+
+        self.pc = next_pc;
+        self.clk = next_clk;
+        self.timestamp += 1;
         Ok(())
     }
 
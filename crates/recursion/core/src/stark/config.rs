@@ -12,7 +12,7 @@ use p3_merkle_tree::FieldMerkleTreeMmcs;
 use p3_poseidon2::{Poseidon2, Poseidon2ExternalMatrixGeneral};
 use p3_symmetric::{Hash, MultiField32PaddingFreeSponge, TruncatedPermutation};
 use serde::{Deserialize, Serialize};
-use sp1_stark::{Com, StarkGenericConfig, ZeroCommitment};
+use sp1_stark::{Com, CommitmentHash, StarkGenericConfig, ZeroCommitment};
 
 use super::{poseidon2::bn254_poseidon2_rc3, sp1_dev_mode};
 
@@ -153,6 +153,12 @@ impl BabyBearPoseidon2Outer {
         let pcs = OuterPcs::new(27, dft, val_mmcs, fri_config);
         Self { pcs, perm }
     }
+
+    /// A config with the FRI parameters of the compressed inner config, for shrinking compressed
+    /// proofs with BN254 Poseidon2 commitments.
+    pub fn compressed() -> Self {
+        Self::new_with_log_blowup(2)
+    }
 }
 
 impl Default for BabyBearPoseidon2Outer {
@@ -168,6 +174,8 @@ impl StarkGenericConfig for BabyBearPoseidon2Outer {
     type Challenge = OuterChallenge;
     type Challenger = OuterChallenger;
 
+    const COMMITMENT_HASH: CommitmentHash = CommitmentHash::Bn254Poseidon2;
+
     fn pcs(&self) -> &Self::Pcs {
         &self.pcs
     }
@@ -0,0 +1,415 @@
+//! A pure Rust Groth16 backend for the BN254 wrap circuit, built on arkworks.
+//!
+//! The constraints are lowered to an R1CS with [R1csCompiler], which follows the gnark circuit, and
+//! proven with `ark-groth16`. The verifying key and proofs are written in gnark's encoding, so that
+//! `sp1-verifier` and the rest of the pipeline accept them as they do gnark's.
+//!
+//! It is selected at runtime with [Groth16Backend::Ark], and a build directory holding an arkworks
+//! proving key is proven with it. The release artifacts are built with gnark and have no arkworks
+//! keys, so they are always proven with gnark. Keys built with arkworks are not accepted by the
+//! verifying key of `sp1-verifier` or by the deployed Solidity verifiers.
+//!
+//! [build] samples its toxic waste locally, so keys built this way are only as trustworthy as the
+//! machine that built them. For production keys, run a multi-party [ceremony] instead.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use anyhow::{anyhow, Context, Result};
+use ark_bn254::{Bn254, Fq, Fr, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_ff::{BigInteger, PrimeField as ArkPrimeField, Zero};
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+use p3_baby_bear::BabyBear;
+use p3_bn254_fr::Bn254Fr;
+use p3_field::{AbstractExtensionField, AbstractField, PrimeField};
+use sha2::{Digest, Sha256};
+use sp1_recursion_compiler::{
+    config::OuterConfig,
+    constraints::{
+        r1cs::{R1cs, R1csCompiler},
+        Constraint,
+    },
+    ir::{Config, Witness},
+};
+
+use crate::{Groth16Backend, Groth16Bn254Proof};
+
+pub mod ceremony;
+
+/// The arkworks proving key, next to gnark's artifacts in the build directory.
+pub const ARK_PK_FILE: &str = "groth16_ark_pk.bin";
+/// The arkworks verifying key.
+pub const ARK_VK_FILE: &str = "groth16_ark_vk.bin";
+/// The verifying key in gnark's encoding, as read by `sp1-verifier`.
+pub const GNARK_VK_FILE: &str = "groth16_vk.bin";
+/// The constraints of the circuit, needed to solve the witness of each proof.
+pub const CONSTRAINTS_FILE: &str = "constraints.json";
+
+/// Flags of gnark's point encoding, in the two most significant bits.
+const COMPRESSED_SMALLEST: u8 = 0b10 << 6;
+const COMPRESSED_LARGEST: u8 = 0b11 << 6;
+const INFINITY: u8 = 0b01 << 6;
+
+/// Runs the Groth16 setup for the constraints, writing the keys to the build directory.
+pub fn build<C: Config>(
+    constraints: &[Constraint],
+    witness: Witness<C>,
+    build_dir: &Path,
+) -> Result<()> {
+    let (r1cs, assignment) = compile(constraints, witness)?;
+    let circuit = R1csCircuit { r1cs, assignment };
+    let (pk, _) = Groth16::<Bn254>::circuit_specific_setup(circuit, &mut rand::thread_rng())
+        .map_err(|e| anyhow!("groth16 setup failed: {e}"))?;
+    write_keys(&pk, constraints, build_dir)
+}
+
+/// Writes the constraints and the keys of the circuit to the build directory.
+fn write_keys(pk: &ProvingKey<Bn254>, constraints: &[Constraint], build_dir: &Path) -> Result<()> {
+    let constraints_file = File::create(build_dir.join(CONSTRAINTS_FILE))?;
+    serde_json::to_writer(BufWriter::new(constraints_file), constraints)?;
+    let pk_file = File::create(build_dir.join(ARK_PK_FILE))?;
+    pk.serialize_uncompressed(BufWriter::new(pk_file))?;
+    let vk_file = File::create(build_dir.join(ARK_VK_FILE))?;
+    pk.vk.serialize_uncompressed(BufWriter::new(vk_file))?;
+    std::fs::write(build_dir.join(GNARK_VK_FILE), gnark_vk_bytes(pk))?;
+    Ok(())
+}
+
+/// Proves the circuit in the build directory for the given witness.
+pub fn prove<C: Config>(witness: Witness<C>, build_dir: &Path) -> Result<Groth16Bn254Proof> {
+    let constraints_file = File::open(build_dir.join(CONSTRAINTS_FILE))
+        .with_context(|| format!("missing {CONSTRAINTS_FILE}, was the circuit built with ark?"))?;
+    let constraints: Vec<Constraint> = serde_json::from_reader(BufReader::new(constraints_file))?;
+    let pk_file = File::open(build_dir.join(ARK_PK_FILE))
+        .with_context(|| format!("missing {ARK_PK_FILE}, was the circuit built with ark?"))?;
+    let pk = ProvingKey::<Bn254>::deserialize_uncompressed_unchecked(BufReader::new(pk_file))?;
+
+    let (r1cs, assignment) = compile(&constraints, witness)?;
+    let public_inputs =
+        assignment[1..=r1cs.num_public_inputs].iter().map(|x| x.as_canonical_biguint().to_string());
+    let public_inputs: Vec<String> = public_inputs.collect();
+    let proof =
+        Groth16::<Bn254>::prove(&pk, R1csCircuit { r1cs, assignment }, &mut rand::thread_rng())
+            .map_err(|e| anyhow!("groth16 proving failed: {e}"))?;
+
+    let encoded_proof = encode_proof(&proof);
+    let mut raw_proof = encoded_proof.clone();
+    // gnark appends the commitments of the proof, which the wrap circuit doesn't use.
+    raw_proof.extend_from_slice(&0u32.to_be_bytes());
+    raw_proof.push(INFINITY);
+    raw_proof.extend_from_slice(&[0; 63]);
+
+    Ok(Groth16Bn254Proof {
+        public_inputs: public_inputs.try_into().unwrap(),
+        encoded_proof: hex::encode(encoded_proof),
+        raw_proof: hex::encode(raw_proof),
+        groth16_vkey_hash: Sha256::digest(std::fs::read(build_dir.join(GNARK_VK_FILE))?).into(),
+    })
+}
+
+/// Verifies a proof against the verifying key in the build directory.
+pub fn verify(raw_proof: &str, public_inputs: &[&str; 2], build_dir: &Path) -> Result<()> {
+    let vk_file = File::open(build_dir.join(ARK_VK_FILE))
+        .with_context(|| format!("missing {ARK_VK_FILE}, was the circuit built with ark?"))?;
+    let vk = VerifyingKey::<Bn254>::deserialize_uncompressed(BufReader::new(vk_file))?;
+
+    let proof = decode_proof(&hex::decode(raw_proof)?)?;
+    let public_inputs = public_inputs
+        .iter()
+        .map(|x| {
+            let x: num_bigint::BigUint = x.parse().context("invalid public input")?;
+            Ok(Fr::from_le_bytes_mod_order(&x.to_bytes_le()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let valid = Groth16::<Bn254>::verify(&vk, &public_inputs, &proof)
+        .map_err(|e| anyhow!("groth16 verification failed: {e}"))?;
+    anyhow::ensure!(valid, "the groth16 proof is invalid");
+    Ok(())
+}
+
+/// Checks that the witness satisfies the constraints.
+pub fn test<C: Config>(constraints: &[Constraint], witness: Witness<C>) -> Result<()> {
+    compile(constraints, witness).map(|_| ())
+}
+
+/// Lowers the constraints to an R1CS, and solves its wires for the witness.
+fn compile<C: Config>(
+    constraints: &[Constraint],
+    witness: Witness<C>,
+) -> Result<(R1cs, Vec<Bn254Fr>)> {
+    let (r1cs, assignment) = R1csCompiler::compile(constraints, &outer_witness(witness));
+    r1cs.check(&assignment)
+        .map_err(|index| anyhow!("the witness does not satisfy r1cs constraint {index}"))?;
+    Ok((r1cs, assignment))
+}
+
+/// Converts a witness to the outer config, which the R1CS compiler works with.
+fn outer_witness<C: Config>(witness: Witness<C>) -> Witness<OuterConfig> {
+    fn bn254<F: PrimeField>(x: &F) -> Bn254Fr {
+        x.as_canonical_biguint().to_u32_digits().iter().rev().fold(Bn254Fr::zero(), |acc, d| {
+            acc * Bn254Fr::from_canonical_u64(1 << 32) + Bn254Fr::from_canonical_u32(*d)
+        })
+    }
+    fn babybear<F: PrimeField>(x: &F) -> BabyBear {
+        BabyBear::from_canonical_u32(
+            x.as_canonical_biguint().to_u32_digits().first().copied().unwrap_or(0),
+        )
+    }
+
+    Witness {
+        vars: witness.vars.iter().map(bn254).collect(),
+        felts: witness.felts.iter().map(babybear).collect(),
+        exts: witness
+            .exts
+            .iter()
+            .map(|e| AbstractExtensionField::from_base_fn(|i| babybear(&e.as_base_slice()[i])))
+            .collect(),
+        vkey_hash: bn254(&witness.vkey_hash),
+        committed_values_digest: bn254(&witness.committed_values_digest),
+    }
+}
+
+fn ark_fr(x: &Bn254Fr) -> Fr {
+    Fr::from_le_bytes_mod_order(&x.as_canonical_biguint().to_bytes_le())
+}
+
+/// An R1CS and the assignment of its wires, as an arkworks circuit.
+struct R1csCircuit {
+    r1cs: R1cs,
+    assignment: Vec<Bn254Fr>,
+}
+
+impl ConstraintSynthesizer<Fr> for R1csCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let R1csCircuit { r1cs, assignment } = self;
+        let mut variables = Vec::with_capacity(r1cs.num_wires);
+        variables.push(Variable::One);
+        for wire in 1..r1cs.num_wires {
+            let value = || Ok(ark_fr(&assignment[wire]));
+            let variable = if wire <= r1cs.num_public_inputs {
+                cs.new_input_variable(value)?
+            } else {
+                cs.new_witness_variable(value)?
+            };
+            variables.push(variable);
+        }
+
+        for constraint in r1cs.constraints.iter() {
+            let lc = |lc: &sp1_recursion_compiler::constraints::r1cs::LinearCombination| {
+                LinearCombination(
+                    lc.0.iter().map(|(wire, coeff)| (ark_fr(coeff), variables[*wire])).collect(),
+                )
+            };
+            cs.enforce_constraint(lc(&constraint.a), lc(&constraint.b), lc(&constraint.c))?;
+        }
+        Ok(())
+    }
+}
+
+fn fq_bytes(x: &Fq) -> Vec<u8> {
+    x.into_bigint().to_bytes_be()
+}
+
+fn is_lexicographically_largest(x: &Fq) -> bool {
+    x.into_bigint() > Fq::MODULUS_MINUS_ONE_DIV_TWO
+}
+
+/// Encodes a G1 point as its x coordinate, with the sign of y in the flags.
+fn compressed_g1(point: &G1Affine) -> Vec<u8> {
+    let Some((x, y)) = point.xy() else {
+        let mut bytes = vec![0; 32];
+        bytes[0] = INFINITY;
+        return bytes;
+    };
+    let mut bytes = fq_bytes(x);
+    bytes[0] |=
+        if is_lexicographically_largest(y) { COMPRESSED_LARGEST } else { COMPRESSED_SMALLEST };
+    bytes
+}
+
+/// Encodes a G2 point as its x coordinate, with the sign of y in the flags.
+fn compressed_g2(point: &G2Affine) -> Vec<u8> {
+    let Some((x, y)) = point.xy() else {
+        let mut bytes = vec![0; 64];
+        bytes[0] = INFINITY;
+        return bytes;
+    };
+    let largest = if y.c1.is_zero() {
+        is_lexicographically_largest(&y.c0)
+    } else {
+        is_lexicographically_largest(&y.c1)
+    };
+    let mut bytes = fq_bytes(&x.c1);
+    bytes.extend(fq_bytes(&x.c0));
+    bytes[0] |= if largest { COMPRESSED_LARGEST } else { COMPRESSED_SMALLEST };
+    bytes
+}
+
+/// Encodes the verifying key as gnark's `groth16_vk.bin`, without commitment keys.
+fn gnark_vk_bytes(pk: &ProvingKey<Bn254>) -> Vec<u8> {
+    let vk = &pk.vk;
+    let mut bytes = Vec::new();
+    bytes.extend(compressed_g1(&vk.alpha_g1));
+    bytes.extend(compressed_g1(&pk.beta_g1));
+    bytes.extend(compressed_g2(&vk.beta_g2));
+    bytes.extend(compressed_g2(&vk.gamma_g2));
+    bytes.extend(compressed_g1(&pk.delta_g1));
+    bytes.extend(compressed_g2(&vk.delta_g2));
+    bytes.extend((vk.gamma_abc_g1.len() as u32).to_be_bytes());
+    for point in vk.gamma_abc_g1.iter() {
+        bytes.extend(compressed_g1(point));
+    }
+    // The public and commitment committed wires, and the commitment keys.
+    bytes.extend(0u32.to_be_bytes());
+    bytes.extend(0u32.to_be_bytes());
+    bytes
+}
+
+/// Encodes a proof as gnark's uncompressed `Ar`, `Bs` and `Krs` points, which is also the layout
+/// of the Solidity verifier's calldata.
+fn encode_proof(proof: &Proof<Bn254>) -> Vec<u8> {
+    let g1 = |point: &G1Affine| {
+        let (x, y) = point.xy().expect("proof points are never at infinity");
+        [fq_bytes(x), fq_bytes(y)].concat()
+    };
+    let (bx, by) = proof.b.xy().expect("proof points are never at infinity");
+    [
+        g1(&proof.a),
+        fq_bytes(&bx.c1),
+        fq_bytes(&bx.c0),
+        fq_bytes(&by.c1),
+        fq_bytes(&by.c0),
+        g1(&proof.c),
+    ]
+    .concat()
+}
+
+fn decode_proof(bytes: &[u8]) -> Result<Proof<Bn254>> {
+    anyhow::ensure!(bytes.len() >= 256, "the groth16 proof is too short");
+    let fq = |offset: usize| Fq::from_be_bytes_mod_order(&bytes[offset..offset + 32]);
+    let a = G1Affine::new_unchecked(fq(0), fq(32));
+    let b = G2Affine::new_unchecked(
+        ark_bn254::Fq2::new(fq(96), fq(64)),
+        ark_bn254::Fq2::new(fq(160), fq(128)),
+    );
+    let c = G1Affine::new_unchecked(fq(192), fq(224));
+    anyhow::ensure!(
+        a.is_on_curve() && a.is_in_correct_subgroup_assuming_on_curve(),
+        "invalid point A"
+    );
+    anyhow::ensure!(
+        b.is_on_curve() && b.is_in_correct_subgroup_assuming_on_curve(),
+        "invalid point B"
+    );
+    anyhow::ensure!(
+        c.is_on_curve() && c.is_in_correct_subgroup_assuming_on_curve(),
+        "invalid point C"
+    );
+    Ok(Proof { a, b, c })
+}
+
+#[cfg(test)]
+mod tests {
+    use sp1_recursion_compiler::{
+        constraints::ConstraintCompiler,
+        ir::{Builder, Var},
+    };
+    use sp1_verifier::{hash_public_inputs, Groth16Verifier};
+
+    use super::*;
+
+    const PUBLIC_VALUES: &[u8] = b"public values";
+
+    fn vkey_hash() -> Vec<u8> {
+        [[0u8], [7u8; 31]].concat()
+    }
+
+    /// A small circuit with the public inputs of the wrap circuit, and its witness.
+    pub(super) fn test_circuit() -> (Vec<Constraint>, Witness<OuterConfig>) {
+        let committed_values_digest = hash_public_inputs(PUBLIC_VALUES);
+
+        let bn254 = |bytes: &[u8]| {
+            bytes.iter().fold(Bn254Fr::zero(), |acc, byte| {
+                acc * Bn254Fr::from_canonical_u32(256) + Bn254Fr::from_canonical_u8(*byte)
+            })
+        };
+        let mut witness = Witness::<OuterConfig>::default();
+        witness.write_vkey_hash(bn254(&vkey_hash()));
+        witness.write_committed_values_digest(bn254(&committed_values_digest));
+
+        let mut builder = Builder::<OuterConfig>::default();
+        let vkey_hash_var = builder.witness_var();
+        let committed_values_digest_var = builder.witness_var();
+        let product: Var<_> = builder.eval(vkey_hash_var * committed_values_digest_var);
+        builder.assert_var_eq(product, witness.vkey_hash * witness.committed_values_digest);
+        builder.commit_vkey_hash_circuit(vkey_hash_var);
+        builder.commit_committed_values_digest_circuit(committed_values_digest_var);
+        let constraints =
+            ConstraintCompiler::<OuterConfig>::default().emit(builder.into_operations());
+        (constraints, witness)
+    }
+
+    /// Proves the test circuit with the keys in the build directory, and checks that
+    /// `sp1-verifier` accepts the proof and verifying key.
+    pub(super) fn prove_and_verify(witness: Witness<OuterConfig>, build_dir: &Path) {
+        let proof = prove(witness, build_dir).unwrap();
+        let [vkey_hash_input, committed_values_digest_input] = &proof.public_inputs;
+        verify(&proof.raw_proof, &[vkey_hash_input, committed_values_digest_input], build_dir)
+            .unwrap();
+
+        let groth16_vk = std::fs::read(build_dir.join(GNARK_VK_FILE)).unwrap();
+        let proof_bytes =
+            [&proof.groth16_vkey_hash[..4], &hex::decode(&proof.raw_proof).unwrap()].concat();
+        let sp1_vkey_hash = format!("0x{}", hex::encode(vkey_hash()));
+        Groth16Verifier::verify(&proof_bytes, PUBLIC_VALUES, &sp1_vkey_hash, &groth16_vk).unwrap();
+        assert!(Groth16Verifier::verify(
+            &proof_bytes,
+            b"other values",
+            &sp1_vkey_hash,
+            &groth16_vk
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_ark_groth16() {
+        let (constraints, witness) = test_circuit();
+        let build_dir = tempfile::tempdir().unwrap();
+        build(&constraints, witness.clone(), build_dir.path()).unwrap();
+        prove_and_verify(witness, build_dir.path());
+    }
+
+    #[test]
+    fn test_groth16_backend() {
+        let (constraints, witness) = test_circuit();
+        let build_dir = tempfile::tempdir().unwrap();
+        assert_eq!(Groth16Backend::of_build_dir(build_dir.path()), Groth16Backend::Gnark);
+
+        // A directory built with arkworks is proven and verified with arkworks.
+        crate::Groth16Bn254Prover::build_with_backend(
+            Groth16Backend::Ark,
+            constraints,
+            witness.clone(),
+            build_dir.path().to_path_buf(),
+        );
+        assert_eq!(Groth16Backend::of_build_dir(build_dir.path()), Groth16Backend::Ark);
+        let prover = crate::Groth16Bn254Prover::new();
+        let proof = prover.prove(witness.clone(), build_dir.path().to_path_buf());
+        prover.verify(
+            &proof,
+            &witness.vkey_hash.as_canonical_biguint(),
+            &witness.committed_values_digest.as_canonical_biguint(),
+            build_dir.path(),
+        );
+    }
+}
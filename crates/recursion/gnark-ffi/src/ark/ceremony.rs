@@ -0,0 +1,686 @@
+//! A multi-party trusted setup ceremony for the Groth16 wrap circuit, run on local files.
+//!
+//! This is phase 2 of the [BGM17] setup, as described in `crates/prover/TRUSTED_SETUP.md`, without
+//! external tools. [Phase2Parameters::new] derives the initial keys of the circuit from a phase 1
+//! `.ptau` file in the snarkjs format, such as the final files of the Perpetual Powers of Tau. Each
+//! participant then [contributes](Phase2Parameters::contribute) by multiplying `delta` with a
+//! secret they destroy afterwards. The keys are sound as long as one participant did.
+//!
+//! Every contribution carries a proof of knowledge of its secret, chained to the contributions
+//! before it, so anyone can [verify](Phase2Parameters::verify) the ceremony from the initial
+//! parameters, which they can derive themselves from the circuit and the `.ptau` file. [finalize]
+//! then writes the keys to a build directory, for the arkworks backend to prove with.
+//!
+//! [BGM17]: https://eprint.iacr.org/2017/1050
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use anyhow::{ensure, Context, Result};
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{BigInt, BigInteger, Field, PrimeField, UniformRand, Zero};
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use p3_bn254_fr::Bn254Fr;
+use p3_field::AbstractField;
+use rand::{CryptoRng, Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+use sp1_recursion_compiler::{
+    constraints::Constraint,
+    ir::{Config, Witness},
+};
+
+use super::{ark_fr, compile, write_keys};
+
+/// The magic bytes at the start of encoded [Phase2Parameters].
+const MAGIC: &[u8; 8] = b"sp1phase";
+
+/// The sections of a `.ptau` file that the ceremony reads.
+const PTAU_HEADER: u32 = 1;
+const PTAU_TAU_G1: u32 = 2;
+const PTAU_ALPHA_TAU_G1: u32 = 4;
+const PTAU_BETA_TAU_G1: u32 = 5;
+const PTAU_BETA_G2: u32 = 6;
+const PTAU_LAGRANGE_TAU_G1: u32 = 12;
+const PTAU_LAGRANGE_TAU_G2: u32 = 13;
+const PTAU_LAGRANGE_ALPHA_TAU_G1: u32 = 14;
+const PTAU_LAGRANGE_BETA_TAU_G1: u32 = 15;
+
+/// A contribution to the ceremony, with the proof of knowledge of its secret `d`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contribution {
+    /// `delta` in G1 after the contribution.
+    pub delta_after: G1Affine,
+    /// A random point `s`.
+    pub s: G1Affine,
+    /// `s * d`.
+    pub s_d: G1Affine,
+    /// `r * d`, where `r` is hashed from the transcript.
+    pub r_d: G2Affine,
+    /// The hash of the transcript, from the initial parameters to `s` and `s_d`.
+    pub transcript: [u8; 32],
+}
+
+impl Contribution {
+    fn write<W: Write>(&self, mut writer: W) -> Result<()> {
+        self.delta_after.serialize_uncompressed(&mut writer)?;
+        self.s.serialize_uncompressed(&mut writer)?;
+        self.s_d.serialize_uncompressed(&mut writer)?;
+        self.r_d.serialize_uncompressed(&mut writer)?;
+        writer.write_all(&self.transcript)?;
+        Ok(())
+    }
+
+    fn read<R: Read>(mut reader: R) -> Result<Self> {
+        let delta_after = G1Affine::deserialize_uncompressed(&mut reader)?;
+        let s = G1Affine::deserialize_uncompressed(&mut reader)?;
+        let s_d = G1Affine::deserialize_uncompressed(&mut reader)?;
+        let r_d = G2Affine::deserialize_uncompressed(&mut reader)?;
+        let mut transcript = [0; 32];
+        reader.read_exact(&mut transcript)?;
+        Ok(Self { delta_after, s, s_d, r_d, transcript })
+    }
+
+    /// The hash of the contribution, which its participant publishes to attest to it.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        self.write(&mut hasher).expect("failed to hash the contribution");
+        hasher.finalize().into()
+    }
+}
+
+/// The keys of the circuit at some point of the ceremony, with the contributions so far.
+#[derive(Debug, Clone)]
+pub struct Phase2Parameters {
+    /// The hash of the initial parameters, which commits to the circuit and to phase 1.
+    pub cs_hash: [u8; 32],
+    pub contributions: Vec<Contribution>,
+    pub pk: ProvingKey<Bn254>,
+}
+
+impl Phase2Parameters {
+    /// Derives the initial parameters of the circuit from a phase 1 `.ptau` file.
+    ///
+    /// The file must have the Lagrange sections of a prepared phase 1, as the final files of the
+    /// Perpetual Powers of Tau do, for a domain that fits the constraints and the public inputs.
+    pub fn new<C: Config>(
+        constraints: &[Constraint],
+        witness: Witness<C>,
+        ptau: &Path,
+    ) -> Result<Self> {
+        let (r1cs, _) = compile(constraints, witness)?;
+        let mut ptau = Ptau::open(ptau)?;
+
+        // The QAP of `ark-groth16`, which constrains each public input, and the constant one, in a
+        // row of its own after the constraints.
+        let num_inputs = r1cs.num_public_inputs + 1;
+        let num_constraints = r1cs.constraints.len();
+        let domain_size = (num_constraints + num_inputs).next_power_of_two();
+        ensure!(
+            domain_size <= 1 << ptau.power,
+            "the circuit needs 2^{} powers of tau, but the ptau file only has 2^{}",
+            domain_size.trailing_zeros(),
+            ptau.power
+        );
+
+        // The Lagrange bases of the domains are stored by increasing size.
+        let offset = domain_size - 1;
+        let tau_g1 = ptau.g1_points(PTAU_LAGRANGE_TAU_G1, offset, domain_size)?;
+        let tau_g2 = ptau.g2_points(PTAU_LAGRANGE_TAU_G2, offset, domain_size)?;
+        let alpha_tau_g1 = ptau.g1_points(PTAU_LAGRANGE_ALPHA_TAU_G1, offset, domain_size)?;
+        let beta_tau_g1 = ptau.g1_points(PTAU_LAGRANGE_BETA_TAU_G1, offset, domain_size)?;
+
+        // The polynomials of each wire at tau, and `beta * A + alpha * B + C` for the public inputs
+        // and the witness.
+        let mut a = vec![G1Projective::zero(); r1cs.num_wires];
+        let mut b_g1 = vec![G1Projective::zero(); r1cs.num_wires];
+        let mut b_g2 = vec![G2Projective::zero(); r1cs.num_wires];
+        let mut abc = vec![G1Projective::zero(); r1cs.num_wires];
+        for (row, constraint) in r1cs.constraints.iter().enumerate() {
+            for (wire, coeff) in constraint.a.0.iter() {
+                a[*wire] += scale(tau_g1[row], coeff);
+                abc[*wire] += scale(beta_tau_g1[row], coeff);
+            }
+            for (wire, coeff) in constraint.b.0.iter() {
+                b_g1[*wire] += scale(tau_g1[row], coeff);
+                b_g2[*wire] += scale(tau_g2[row], coeff);
+                abc[*wire] += scale(alpha_tau_g1[row], coeff);
+            }
+            for (wire, coeff) in constraint.c.0.iter() {
+                abc[*wire] += scale(tau_g1[row], coeff);
+            }
+        }
+        let input_rows = num_constraints..num_constraints + num_inputs;
+        for (value, point) in a.iter_mut().zip(&tau_g1[input_rows.clone()]) {
+            *value += point.into_group();
+        }
+        for (value, point) in abc.iter_mut().zip(&beta_tau_g1[input_rows]) {
+            *value += point.into_group();
+        }
+        let witness_abc = abc.split_off(num_inputs);
+
+        // `tau^i * t(tau)` for the vanishing polynomial `t(X) = X^n - 1` of the domain.
+        let tau_powers = ptau.g1_points(PTAU_TAU_G1, 0, 2 * domain_size - 1)?;
+        let h = (0..domain_size - 1)
+            .map(|i| tau_powers[i + domain_size].into_group() - tau_powers[i].into_group())
+            .collect::<Vec<_>>();
+
+        let pk = ProvingKey {
+            vk: VerifyingKey {
+                alpha_g1: ptau.g1_points(PTAU_ALPHA_TAU_G1, 0, 1)?[0],
+                beta_g2: ptau.g2_points(PTAU_BETA_G2, 0, 1)?[0],
+                // gamma only separates the public inputs from the witness, so it can be public.
+                gamma_g2: G2Affine::generator(),
+                delta_g2: G2Affine::generator(),
+                gamma_abc_g1: G1Projective::normalize_batch(&abc),
+            },
+            beta_g1: ptau.g1_points(PTAU_BETA_TAU_G1, 0, 1)?[0],
+            delta_g1: G1Affine::generator(),
+            a_query: G1Projective::normalize_batch(&a),
+            b_g1_query: G1Projective::normalize_batch(&b_g1),
+            b_g2_query: G2Projective::normalize_batch(&b_g2),
+            h_query: G1Projective::normalize_batch(&h),
+            l_query: G1Projective::normalize_batch(&witness_abc),
+        };
+
+        Ok(Self { cs_hash: pk_hash(&pk)?, contributions: Vec::new(), pk })
+    }
+
+    /// Contributes a secret sampled from `rng` to the parameters, and returns the hash of the
+    /// contribution.
+    ///
+    /// The secret is dropped on return, but anything that can reproduce `rng` can reproduce it.
+    pub fn contribute<R: Rng + CryptoRng>(&mut self, rng: &mut R) -> [u8; 32] {
+        let d = loop {
+            let d = Fr::rand(rng);
+            if !d.is_zero() {
+                break d;
+            }
+        };
+        let s = G1Projective::rand(rng).into_affine();
+        let s_d = (s * d).into_affine();
+        let transcript = transcript(&self.cs_hash, &self.contributions, &s, &s_d);
+        let r_d = (hash_to_g2(&transcript) * d).into_affine();
+
+        let d_inverse = d.inverse().unwrap();
+        let pk = &mut self.pk;
+        pk.delta_g1 = (pk.delta_g1 * d).into_affine();
+        pk.vk.delta_g2 = (pk.vk.delta_g2 * d).into_affine();
+        pk.h_query = scale_all(&pk.h_query, d_inverse);
+        pk.l_query = scale_all(&pk.l_query, d_inverse);
+
+        let contribution = Contribution { delta_after: pk.delta_g1, s, s_d, r_d, transcript };
+        let hash = contribution.hash();
+        self.contributions.push(contribution);
+        hash
+    }
+
+    /// Contributes a secret seeded from both the OS randomness and the participant's `entropy`, so
+    /// that neither alone determines it.
+    pub fn contribute_with_entropy(&mut self, entropy: &[u8]) -> [u8; 32] {
+        let mut seed = Sha256::new();
+        seed.update(rand::thread_rng().gen::<[u8; 32]>());
+        seed.update(entropy);
+        self.contribute(&mut ChaCha20Rng::from_seed(seed.finalize().into()))
+    }
+
+    /// Verifies the contributions these parameters add to `base`, an earlier state of the same
+    /// ceremony, and returns their hashes.
+    ///
+    /// With the initial parameters as `base`, this verifies the whole ceremony, which must have at
+    /// least one contribution. The initial parameters are only checked against their `cs_hash`,
+    /// which should be compared with the one of parameters derived independently.
+    pub fn verify(&self, base: &Self) -> Result<Vec<[u8; 32]>> {
+        ensure!(
+            self.cs_hash == base.cs_hash,
+            "the parameters are for another circuit or phase 1 than the base parameters"
+        );
+        if base.contributions.is_empty() {
+            ensure!(
+                pk_hash(&base.pk)? == base.cs_hash,
+                "the base parameters are not the initial parameters of their cs_hash"
+            );
+            ensure!(!self.contributions.is_empty(), "the parameters have no contributions");
+        }
+        let num_base_contributions = base.contributions.len();
+        ensure!(
+            self.contributions.len() >= num_base_contributions
+                && self.contributions[..num_base_contributions] == base.contributions[..],
+            "the contributions do not extend the contributions of the base parameters"
+        );
+        ensure_same_circuit(&base.pk, &self.pk)?;
+
+        let mut delta = base.pk.delta_g1;
+        let mut hashes = Vec::new();
+        for (i, contribution) in self.contributions.iter().enumerate() {
+            if i < num_base_contributions {
+                continue;
+            }
+            let Contribution { delta_after, s, s_d, r_d, transcript: hash } = contribution;
+            ensure!(!s.is_zero() && !s_d.is_zero(), "contribution {i} has a trivial secret");
+            ensure!(
+                *hash == transcript(&self.cs_hash, &self.contributions[..i], s, s_d),
+                "contribution {i} has an invalid transcript"
+            );
+            let r = hash_to_g2(hash);
+            ensure!(
+                same_ratio((*s, *s_d), (r, *r_d)),
+                "contribution {i} has an invalid proof of knowledge"
+            );
+            ensure!(
+                same_ratio((delta, *delta_after), (r, *r_d)),
+                "contribution {i} does not multiply delta with its secret"
+            );
+            delta = *delta_after;
+            hashes.push(contribution.hash());
+        }
+
+        let pk = &self.pk;
+        ensure!(pk.delta_g1 == delta, "delta is not the one of the last contribution");
+        ensure!(
+            same_ratio(
+                (G1Affine::generator(), pk.delta_g1),
+                (G2Affine::generator(), pk.vk.delta_g2)
+            ),
+            "delta is not the same in G1 and G2"
+        );
+        let (base_h, h) = merge_pairs(&base.pk.h_query, &pk.h_query);
+        ensure!(
+            same_ratio((base_h, h), (pk.vk.delta_g2, base.pk.vk.delta_g2)),
+            "the h query is not divided by the contributions"
+        );
+        let (base_l, l) = merge_pairs(&base.pk.l_query, &pk.l_query);
+        ensure!(
+            same_ratio((base_l, l), (pk.vk.delta_g2, base.pk.vk.delta_g2)),
+            "the l query is not divided by the contributions"
+        );
+        Ok(hashes)
+    }
+
+    /// Writes the parameters, with the proving key uncompressed.
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&self.cs_hash)?;
+        writer.write_all(&(self.contributions.len() as u32).to_le_bytes())?;
+        for contribution in self.contributions.iter() {
+            contribution.write(&mut writer)?;
+        }
+        self.pk.serialize_uncompressed(&mut writer)?;
+        Ok(())
+    }
+
+    /// Reads parameters, checking that all their points are in the right subgroups.
+    pub fn read<R: Read>(mut reader: R) -> Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        ensure!(&magic == MAGIC, "not a ceremony parameters file");
+        let mut cs_hash = [0; 32];
+        reader.read_exact(&mut cs_hash)?;
+        let mut num_contributions = [0; 4];
+        reader.read_exact(&mut num_contributions)?;
+        let contributions = (0..u32::from_le_bytes(num_contributions))
+            .map(|_| Contribution::read(&mut reader))
+            .collect::<Result<Vec<_>>>()?;
+        let pk = ProvingKey::<Bn254>::deserialize_uncompressed(&mut reader)?;
+        Ok(Self { cs_hash, contributions, pk })
+    }
+}
+
+/// Writes the keys of the ceremony to the build directory, for [super::prove] and [super::verify].
+///
+/// The parameters should be [verified](Phase2Parameters::verify) against the initial parameters
+/// of the constraints first. Parameters without contributions are rejected, as their toxic waste
+/// is public.
+pub fn finalize(
+    params: &Phase2Parameters,
+    constraints: &[Constraint],
+    build_dir: &Path,
+) -> Result<()> {
+    ensure!(!params.contributions.is_empty(), "the parameters have no contributions");
+    write_keys(&params.pk, constraints, build_dir)
+}
+
+/// The SHA-256 hash of the uncompressed encoding of a proving key.
+fn pk_hash(pk: &ProvingKey<Bn254>) -> Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    pk.serialize_uncompressed(&mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+fn scale<G: AffineRepr<ScalarField = Fr>>(point: G, coeff: &Bn254Fr) -> G::Group {
+    if *coeff == Bn254Fr::one() {
+        point.into_group()
+    } else {
+        point * ark_fr(coeff)
+    }
+}
+
+fn scale_all(points: &[G1Affine], scalar: Fr) -> Vec<G1Affine> {
+    let points = points.iter().map(|point| *point * scalar).collect::<Vec<_>>();
+    G1Projective::normalize_batch(&points)
+}
+
+/// Hashes the initial parameters and the contributions before the new one, along with its `s` and
+/// `s_d`.
+fn transcript(
+    cs_hash: &[u8; 32],
+    previous: &[Contribution],
+    s: &G1Affine,
+    s_d: &G1Affine,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(cs_hash);
+    for contribution in previous {
+        contribution.write(&mut hasher).expect("failed to hash the transcript");
+    }
+    s.serialize_uncompressed(&mut hasher).expect("failed to hash the transcript");
+    s_d.serialize_uncompressed(&mut hasher).expect("failed to hash the transcript");
+    hasher.finalize().into()
+}
+
+/// Maps a transcript hash to a point of G2 with no known discrete logarithm.
+fn hash_to_g2(transcript: &[u8; 32]) -> G2Affine {
+    G2Projective::rand(&mut ChaCha20Rng::from_seed(*transcript)).into_affine()
+}
+
+/// Checks that `g1.1 / g1.0 == g2.1 / g2.0`.
+fn same_ratio(g1: (G1Affine, G1Affine), g2: (G2Affine, G2Affine)) -> bool {
+    Bn254::pairing(g1.0, g2.1) == Bn254::pairing(g1.1, g2.0)
+}
+
+/// Combines two lists of points with the same random coefficients, so that a single
+/// [same_ratio] checks that all pairs of points have the same ratio.
+fn merge_pairs(v1: &[G1Affine], v2: &[G1Affine]) -> (G1Affine, G1Affine) {
+    let mut rng = rand::thread_rng();
+    let coeffs = (0..v1.len()).map(|_| Fr::rand(&mut rng)).collect::<Vec<_>>();
+    (
+        G1Projective::msm_unchecked(v1, &coeffs).into_affine(),
+        G1Projective::msm_unchecked(v2, &coeffs).into_affine(),
+    )
+}
+
+/// Checks that a contribution only changed `delta` and the queries divided by it.
+fn ensure_same_circuit(base: &ProvingKey<Bn254>, pk: &ProvingKey<Bn254>) -> Result<()> {
+    ensure!(
+        pk.vk.alpha_g1 == base.vk.alpha_g1
+            && pk.vk.beta_g2 == base.vk.beta_g2
+            && pk.vk.gamma_g2 == base.vk.gamma_g2
+            && pk.vk.gamma_abc_g1 == base.vk.gamma_abc_g1
+            && pk.beta_g1 == base.beta_g1
+            && pk.a_query == base.a_query
+            && pk.b_g1_query == base.b_g1_query
+            && pk.b_g2_query == base.b_g2_query
+            && pk.h_query.len() == base.h_query.len()
+            && pk.l_query.len() == base.l_query.len(),
+        "the contributions changed more than delta"
+    );
+    Ok(())
+}
+
+/// A `.ptau` file, as written by snarkjs: a list of sections of points in little-endian Montgomery
+/// form.
+struct Ptau {
+    reader: BufReader<File>,
+    /// The offset and size of each section.
+    sections: HashMap<u32, (u64, u64)>,
+    /// The log2 of the number of powers of tau.
+    power: u32,
+}
+
+impl Ptau {
+    fn open(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        let mut reader = BufReader::new(file);
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        ensure!(&magic == b"ptau", "{} is not a ptau file", path.display());
+        let _version = read_u32(&mut reader)?;
+        let num_sections = read_u32(&mut reader)?;
+
+        let mut sections = HashMap::new();
+        for _ in 0..num_sections {
+            let section = read_u32(&mut reader)?;
+            let mut size = [0; 8];
+            reader.read_exact(&mut size)?;
+            let size = u64::from_le_bytes(size);
+            sections.insert(section, (reader.stream_position()?, size));
+            reader.seek(SeekFrom::Current(size as i64))?;
+        }
+
+        let mut ptau = Self { reader, sections, power: 0 };
+        ptau.seek(PTAU_HEADER, 0, 4 + 32 + 4)?;
+        ensure!(read_u32(&mut ptau.reader)? == 32, "the ptau file is not over BN254");
+        let mut modulus = [0; 32];
+        ptau.reader.read_exact(&mut modulus)?;
+        ensure!(modulus[..] == Fq::MODULUS.to_bytes_le()[..], "the ptau file is not over BN254");
+        ptau.power = read_u32(&mut ptau.reader)?;
+        Ok(ptau)
+    }
+
+    /// Seeks to a position in a section, checking that the section has `len` bytes from there.
+    fn seek(&mut self, section: u32, position: u64, len: u64) -> Result<()> {
+        let (offset, size) = *self
+            .sections
+            .get(&section)
+            .with_context(|| format!("the ptau file has no section {section}"))?;
+        ensure!(position + len <= size, "section {section} of the ptau file is too short");
+        self.reader.seek(SeekFrom::Start(offset + position))?;
+        Ok(())
+    }
+
+    fn read_points(
+        &mut self,
+        section: u32,
+        start: usize,
+        count: usize,
+        size: usize,
+    ) -> Result<Vec<u8>> {
+        self.seek(section, (start * size) as u64, (count * size) as u64)?;
+        let mut bytes = vec![0; count * size];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn g1_points(&mut self, section: u32, start: usize, count: usize) -> Result<Vec<G1Affine>> {
+        let bytes = self.read_points(section, start, count, 64)?;
+        bytes
+            .chunks_exact(64)
+            .map(|bytes| {
+                if bytes.iter().all(|byte| *byte == 0) {
+                    return Ok(G1Affine::zero());
+                }
+                let point = G1Affine::new_unchecked(ptau_fq(&bytes[..32])?, ptau_fq(&bytes[32..])?);
+                ensure!(
+                    point.is_on_curve(),
+                    "invalid G1 point in section {section} of the ptau file"
+                );
+                Ok(point)
+            })
+            .collect()
+    }
+
+    fn g2_points(&mut self, section: u32, start: usize, count: usize) -> Result<Vec<G2Affine>> {
+        let bytes = self.read_points(section, start, count, 128)?;
+        bytes
+            .chunks_exact(128)
+            .map(|bytes| {
+                if bytes.iter().all(|byte| *byte == 0) {
+                    return Ok(G2Affine::zero());
+                }
+                let x = Fq2::new(ptau_fq(&bytes[..32])?, ptau_fq(&bytes[32..64])?);
+                let y = Fq2::new(ptau_fq(&bytes[64..96])?, ptau_fq(&bytes[96..])?);
+                let point = G2Affine::new_unchecked(x, y);
+                ensure!(
+                    point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve(),
+                    "invalid G2 point in section {section} of the ptau file"
+                );
+                Ok(point)
+            })
+            .collect()
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Decodes a base field element in little-endian Montgomery form.
+fn ptau_fq(bytes: &[u8]) -> Result<Fq> {
+    let limbs =
+        core::array::from_fn(|i| u64::from_le_bytes(bytes[8 * i..8 * i + 8].try_into().unwrap()));
+    let montgomery = BigInt(limbs);
+    ensure!(montgomery < Fq::MODULUS, "invalid field element in the ptau file");
+    Ok(Fq::new_unchecked(montgomery))
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_ff::{FftField, One};
+
+    use super::*;
+    use crate::ark::tests::{prove_and_verify, test_circuit};
+
+    fn fq_bytes(x: &Fq) -> Vec<u8> {
+        x.0 .0.iter().flat_map(|limb| limb.to_le_bytes()).collect()
+    }
+
+    fn g1_bytes(point: &G1Affine) -> Vec<u8> {
+        match point.xy() {
+            Some((x, y)) => [fq_bytes(x), fq_bytes(y)].concat(),
+            None => vec![0; 64],
+        }
+    }
+
+    fn g2_bytes(point: &G2Affine) -> Vec<u8> {
+        match point.xy() {
+            Some((x, y)) => {
+                [fq_bytes(&x.c0), fq_bytes(&x.c1), fq_bytes(&y.c0), fq_bytes(&y.c1)].concat()
+            }
+            None => vec![0; 128],
+        }
+    }
+
+    fn lagrange_coefficients(size: usize, tau: Fr) -> Vec<Fr> {
+        let omega = Fr::get_root_of_unity(size as u64).unwrap();
+        let z = (tau.pow([size as u64]) - Fr::one()) / Fr::from(size as u64);
+        let mut omega_i = Fr::one();
+        (0..size)
+            .map(|_| {
+                let coeff = z * omega_i / (tau - omega_i);
+                omega_i *= omega;
+                coeff
+            })
+            .collect()
+    }
+
+    /// Writes a prepared phase 1 for the secrets `tau`, `alpha` and `beta`.
+    fn write_ptau(path: &Path, power: u32, tau: Fr, alpha: Fr, beta: Fr) {
+        let n = 1usize << power;
+        let g1 = |xs: &[Fr], k: Fr| {
+            xs.iter()
+                .flat_map(|x| g1_bytes(&(G1Affine::generator() * (k * x)).into_affine()))
+                .collect::<Vec<_>>()
+        };
+        let g2 = |xs: &[Fr], k: Fr| {
+            xs.iter()
+                .flat_map(|x| g2_bytes(&(G2Affine::generator() * (k * x)).into_affine()))
+                .collect::<Vec<_>>()
+        };
+        let powers = (0..2 * n - 1).map(|i| tau.pow([i as u64])).collect::<Vec<_>>();
+        let lagrange =
+            (0..=power).flat_map(|p| lagrange_coefficients(1 << p, tau)).collect::<Vec<_>>();
+        let header = [
+            32u32.to_le_bytes().to_vec(),
+            Fq::MODULUS.to_bytes_le(),
+            power.to_le_bytes().to_vec(),
+            power.to_le_bytes().to_vec(),
+        ]
+        .concat();
+        let one = Fr::one();
+        let sections = [
+            (PTAU_HEADER, header),
+            (PTAU_TAU_G1, g1(&powers, one)),
+            // tauG2
+            (3, g2(&powers[..n], one)),
+            (PTAU_ALPHA_TAU_G1, g1(&powers[..n], alpha)),
+            (PTAU_BETA_TAU_G1, g1(&powers[..n], beta)),
+            (PTAU_BETA_G2, g2(&[beta], one)),
+            (PTAU_LAGRANGE_TAU_G1, g1(&lagrange, one)),
+            (PTAU_LAGRANGE_TAU_G2, g2(&lagrange, one)),
+            (PTAU_LAGRANGE_ALPHA_TAU_G1, g1(&lagrange, alpha)),
+            (PTAU_LAGRANGE_BETA_TAU_G1, g1(&lagrange, beta)),
+        ];
+
+        let mut bytes = b"ptau".to_vec();
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend((sections.len() as u32).to_le_bytes());
+        for (section, data) in sections {
+            bytes.extend(section.to_le_bytes());
+            bytes.extend((data.len() as u64).to_le_bytes());
+            bytes.extend(data);
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn test_ceremony() {
+        let (constraints, witness) = test_circuit();
+        let dir = tempfile::tempdir().unwrap();
+        let ptau = dir.path().join("phase1.ptau");
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        write_ptau(&ptau, 5, Fr::rand(&mut rng), Fr::rand(&mut rng), Fr::rand(&mut rng));
+
+        let initial = Phase2Parameters::new(&constraints, witness.clone(), &ptau).unwrap();
+        let mut params = initial.clone();
+        let first = params.contribute(&mut rng);
+        let after_first = params.clone();
+        let second = params.contribute(&mut rng);
+        assert_eq!(after_first.verify(&initial).unwrap(), vec![first]);
+        assert_eq!(params.verify(&after_first).unwrap(), vec![second]);
+
+        // The parameters round trip through their encoding.
+        let mut bytes = Vec::new();
+        params.write(&mut bytes).unwrap();
+        let params = Phase2Parameters::read(bytes.as_slice()).unwrap();
+        assert_eq!(params.verify(&initial).unwrap(), vec![first, second]);
+
+        // Changing delta without a proof of knowledge of the factor is rejected.
+        let mut forged = params.clone();
+        let delta = (forged.pk.delta_g1 * Fr::from(2u64)).into_affine();
+        forged.pk.delta_g1 = delta;
+        forged.contributions.last_mut().unwrap().delta_after = delta;
+        assert!(forged.verify(&initial).is_err());
+
+        // So are changes to the queries, and rewrites of the history.
+        let mut forged = params.clone();
+        forged.pk.h_query[0] = G1Affine::generator();
+        assert!(forged.verify(&initial).is_err());
+        let mut forged = params.clone();
+        forged.contributions.remove(0);
+        assert!(forged.verify(&initial).is_err());
+        assert!(initial.verify(&params).is_err());
+
+        // A ceremony needs a contribution, on top of initial parameters that match their hash.
+        assert!(initial.verify(&initial).is_err());
+        let mut forged_initial = initial.clone();
+        forged_initial.cs_hash[0] ^= 1;
+        let mut forged = forged_initial.clone();
+        forged.contribute(&mut rng);
+        assert!(forged.verify(&forged_initial).is_err());
+
+        // The keys of the ceremony prove the circuit.
+        let build_dir = tempfile::tempdir().unwrap();
+        assert!(finalize(&initial, &constraints, build_dir.path()).is_err());
+        finalize(&params, &constraints, build_dir.path()).unwrap();
+        prove_and_verify(witness, build_dir.path());
+    }
+}
@@ -18,6 +18,47 @@ use sp1_recursion_compiler::{
     ir::{Config, Witness},
 };
 
+/// The backend that builds and proves the Groth16 circuit.
+///
+/// The release artifacts are built with gnark. Keys built with [Groth16Backend::Ark] come from a
+/// different setup, so their proofs are not accepted by the `GROTH16_VK_BYTES` of `sp1-verifier` or
+/// by the deployed Solidity verifiers, and the release artifacts cannot be proven with arkworks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Groth16Backend {
+    /// gnark, through the native bindings or Docker.
+    #[default]
+    Gnark,
+    /// arkworks, in pure Rust, with the `ark` feature.
+    #[cfg(feature = "ark")]
+    Ark,
+}
+
+impl Groth16Backend {
+    /// The backend selected by the `SP1_GROTH16_BACKEND` environment variable, `gnark` by default.
+    pub fn from_env() -> Self {
+        match std::env::var("SP1_GROTH16_BACKEND").as_deref() {
+            Err(_) | Ok("gnark") => Self::Gnark,
+            #[cfg(feature = "ark")]
+            Ok("ark") => Self::Ark,
+            #[cfg(not(feature = "ark"))]
+            Ok("ark") => panic!("SP1_GROTH16_BACKEND=ark requires the `ark` feature"),
+            Ok(backend) => panic!("unknown SP1_GROTH16_BACKEND: {backend}"),
+        }
+    }
+
+    /// The backend that built the artifacts in a build directory: arkworks if it holds an arkworks
+    /// proving key, and gnark otherwise, as for the release artifacts.
+    pub fn of_build_dir(build_dir: &Path) -> Self {
+        #[cfg(feature = "ark")]
+        if build_dir.join(crate::ark::ARK_PK_FILE).is_file() {
+            return Self::Ark;
+        }
+        #[cfg(not(feature = "ark"))]
+        let _ = build_dir;
+        Self::Gnark
+    }
+}
+
 /// A prover that can generate proofs with the PLONK protocol using bindings to Gnark.
 #[derive(Debug, Clone)]
 pub struct Groth16Bn254Prover;
@@ -37,22 +78,39 @@ impl Groth16Bn254Prover {
 
     /// Executes the prover in testing mode with a circuit definition and witness.
     pub fn test<C: Config>(constraints: Vec<Constraint>, witness: Witness<C>) {
-        let serialized = serde_json::to_string(&constraints).unwrap();
-
-        // Write constraints.
-        let mut constraints_file = tempfile::NamedTempFile::new().unwrap();
-        constraints_file.write_all(serialized.as_bytes()).unwrap();
-
-        // Write witness.
-        let mut witness_file = tempfile::NamedTempFile::new().unwrap();
-        let gnark_witness = GnarkWitness::new(witness);
-        let serialized = serde_json::to_string(&gnark_witness).unwrap();
-        witness_file.write_all(serialized.as_bytes()).unwrap();
-
-        test_groth16_bn254(
-            witness_file.path().to_str().unwrap(),
-            constraints_file.path().to_str().unwrap(),
-        )
+        Self::test_with_backend(Groth16Backend::default(), constraints, witness)
+    }
+
+    /// Like [Self::test], with the given backend.
+    pub fn test_with_backend<C: Config>(
+        backend: Groth16Backend,
+        constraints: Vec<Constraint>,
+        witness: Witness<C>,
+    ) {
+        match backend {
+            #[cfg(feature = "ark")]
+            Groth16Backend::Ark => {
+                crate::ark::test(&constraints, witness).expect("failed to test the circuit")
+            }
+            Groth16Backend::Gnark => {
+                let serialized = serde_json::to_string(&constraints).unwrap();
+
+                // Write constraints.
+                let mut constraints_file = tempfile::NamedTempFile::new().unwrap();
+                constraints_file.write_all(serialized.as_bytes()).unwrap();
+
+                // Write witness.
+                let mut witness_file = tempfile::NamedTempFile::new().unwrap();
+                let gnark_witness = GnarkWitness::new(witness);
+                let serialized = serde_json::to_string(&gnark_witness).unwrap();
+                witness_file.write_all(serialized.as_bytes()).unwrap();
+
+                test_groth16_bn254(
+                    witness_file.path().to_str().unwrap(),
+                    constraints_file.path().to_str().unwrap(),
+                )
+            }
+        }
     }
 
     pub fn build_contracts(build_dir: PathBuf) {
@@ -72,39 +130,72 @@ impl Groth16Bn254Prover {
 
     /// Builds the Groth16 circuit locally.
     pub fn build<C: Config>(constraints: Vec<Constraint>, witness: Witness<C>, build_dir: PathBuf) {
-        let serialized = serde_json::to_string(&constraints).unwrap();
-
-        // Write constraints.
-        let constraints_path = build_dir.join("constraints.json");
-        let mut file = File::create(constraints_path).unwrap();
-        file.write_all(serialized.as_bytes()).unwrap();
-
-        // Write witness.
-        let witness_path = build_dir.join("groth16_witness.json");
-        let gnark_witness = GnarkWitness::new(witness);
-        let mut file = File::create(witness_path).unwrap();
-        let serialized = serde_json::to_string(&gnark_witness).unwrap();
-        file.write_all(serialized.as_bytes()).unwrap();
-
-        // Build the circuit.
-        build_groth16_bn254(build_dir.to_str().unwrap());
+        Self::build_with_backend(Groth16Backend::default(), constraints, witness, build_dir)
+    }
 
-        // Build the contracts.
-        Self::build_contracts(build_dir);
+    /// Like [Self::build], with the given backend.
+    ///
+    /// With [Groth16Backend::Ark], the Solidity verifier is not generated, as it is exported by
+    /// gnark.
+    pub fn build_with_backend<C: Config>(
+        backend: Groth16Backend,
+        constraints: Vec<Constraint>,
+        witness: Witness<C>,
+        build_dir: PathBuf,
+    ) {
+        match backend {
+            #[cfg(feature = "ark")]
+            Groth16Backend::Ark => crate::ark::build(&constraints, witness, &build_dir)
+                .expect("failed to build the circuit"),
+            Groth16Backend::Gnark => {
+                // Remove the arkworks keys of an earlier build, so the directory is proven with
+                // gnark.
+                #[cfg(feature = "ark")]
+                let _ = std::fs::remove_file(build_dir.join(crate::ark::ARK_PK_FILE));
+
+                let serialized = serde_json::to_string(&constraints).unwrap();
+
+                // Write constraints.
+                let constraints_path = build_dir.join("constraints.json");
+                let mut file = File::create(constraints_path).unwrap();
+                file.write_all(serialized.as_bytes()).unwrap();
+
+                // Write witness.
+                let witness_path = build_dir.join("groth16_witness.json");
+                let gnark_witness = GnarkWitness::new(witness);
+                let mut file = File::create(witness_path).unwrap();
+                let serialized = serde_json::to_string(&gnark_witness).unwrap();
+                file.write_all(serialized.as_bytes()).unwrap();
+
+                // Build the circuit.
+                build_groth16_bn254(build_dir.to_str().unwrap());
+
+                // Build the contracts.
+                Self::build_contracts(build_dir);
+            }
+        }
     }
 
-    /// Generates a Groth16 proof given a witness.
+    /// Generates a Groth16 proof given a witness, with the backend that built the artifacts.
     pub fn prove<C: Config>(&self, witness: Witness<C>, build_dir: PathBuf) -> Groth16Bn254Proof {
-        // Write witness.
-        let mut witness_file = tempfile::NamedTempFile::new().unwrap();
-        let gnark_witness = GnarkWitness::new(witness);
-        let serialized = serde_json::to_string(&gnark_witness).unwrap();
-        witness_file.write_all(serialized.as_bytes()).unwrap();
-
-        let mut proof =
-            prove_groth16_bn254(build_dir.to_str().unwrap(), witness_file.path().to_str().unwrap());
-        proof.groth16_vkey_hash = Self::get_vkey_hash(&build_dir);
-        proof
+        match Groth16Backend::of_build_dir(&build_dir) {
+            #[cfg(feature = "ark")]
+            Groth16Backend::Ark => crate::ark::prove(witness, &build_dir).expect("failed to prove"),
+            Groth16Backend::Gnark => {
+                // Write witness.
+                let mut witness_file = tempfile::NamedTempFile::new().unwrap();
+                let gnark_witness = GnarkWitness::new(witness);
+                let serialized = serde_json::to_string(&gnark_witness).unwrap();
+                witness_file.write_all(serialized.as_bytes()).unwrap();
+
+                let mut proof = prove_groth16_bn254(
+                    build_dir.to_str().unwrap(),
+                    witness_file.path().to_str().unwrap(),
+                );
+                proof.groth16_vkey_hash = Self::get_vkey_hash(&build_dir);
+                proof
+            }
+        }
     }
 
     /// Verify a Groth16proof and verify that the supplied vkey_hash and committed_values_digest
@@ -121,13 +212,22 @@ impl Groth16Bn254Prover {
                 "Proof vkey hash does not match circuit vkey hash, it was generated with a different circuit."
             );
         }
-        verify_groth16_bn254(
-            build_dir.to_str().unwrap(),
-            &proof.raw_proof,
-            &vkey_hash.to_string(),
-            &committed_values_digest.to_string(),
-        )
-        .expect("failed to verify proof")
+        match Groth16Backend::of_build_dir(build_dir) {
+            #[cfg(feature = "ark")]
+            Groth16Backend::Ark => crate::ark::verify(
+                &proof.raw_proof,
+                &[&vkey_hash.to_string(), &committed_values_digest.to_string()],
+                build_dir,
+            )
+            .expect("failed to verify proof"),
+            Groth16Backend::Gnark => verify_groth16_bn254(
+                build_dir.to_str().unwrap(),
+                &proof.raw_proof,
+                &vkey_hash.to_string(),
+                &committed_values_digest.to_string(),
+            )
+            .expect("failed to verify proof"),
+        }
     }
 
     /// Modify the Groth16Verifier so that it works with the SP1Verifier.
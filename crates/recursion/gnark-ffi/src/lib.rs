@@ -1,5 +1,8 @@
 mod babybear;
 
+#[cfg(feature = "ark")]
+pub mod ark;
+
 pub mod ffi;
 pub mod groth16_bn254;
 pub mod plonk_bn254;
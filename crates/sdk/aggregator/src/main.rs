@@ -0,0 +1,25 @@
+//! The aggregator program used by `ProverClient::aggregate`.
+//!
+//! Verifies a list of compressed SP1 proofs and commits to the list of their
+//! (vkey hash, public values digest) pairs.
+//!
+//! Rebuild the ELF with `cargo prove build --output-directory elf` after changing this program.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use sp1_zkvm::lib::verify::verify_sp1_proof;
+
+pub fn main() {
+    // Read the (vkey hash, public values digest) pairs.
+    let pairs = sp1_zkvm::io::read::<Vec<([u32; 8], [u8; 32])>>();
+
+    // Verify the proofs. The proofs themselves are witnessed by the prover and verified during
+    // the recursive compression of this program.
+    for (vkey, public_values_digest) in pairs.iter() {
+        verify_sp1_proof(vkey, public_values_digest);
+    }
+
+    // Commit to the verified pairs.
+    sp1_zkvm::io::commit(&pairs);
+}
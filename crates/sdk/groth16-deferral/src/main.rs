@@ -0,0 +1,31 @@
+//! The Groth16 deferral program used by `ProverClient::defer_groth16`.
+//!
+//! Verifies a gnark Groth16 proof over BN254 with arbitrary public inputs, and commits to the
+//! SHA-256 digest of the verifying key followed by the public inputs. Its compressed proofs are
+//! checked in other programs with `sp1_zkvm::lib::verify::verify_groth16_proof`.
+//!
+//! Rebuild the ELF with `cargo prove build --output-directory elf` after changing this program, and
+//! update `GROTH16_DEFERRAL_VK_DIGEST` in `sp1-lib` to the digest of its new verification key.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use sp1_verifier::{Groth16Verifier, PreparedGroth16Vk};
+
+pub fn main() {
+    // Read the verifying key, the proof and its public inputs.
+    let groth16_vk = sp1_zkvm::io::read_vec();
+    let proof = sp1_zkvm::io::read_vec();
+    let public_inputs = sp1_zkvm::io::read::<Vec<[u8; 32]>>();
+
+    // Verify the proof.
+    let groth16_vk = PreparedGroth16Vk::new(&groth16_vk).expect("invalid Groth16 verifying key");
+    Groth16Verifier::verify_gnark_proof_prepared(&proof, &public_inputs, &groth16_vk)
+        .expect("invalid Groth16 proof");
+
+    // Commit to the verifying key digest and the public inputs.
+    sp1_zkvm::io::commit_slice(&groth16_vk.digest());
+    for input in public_inputs.iter() {
+        sp1_zkvm::io::commit_slice(input);
+    }
+}
@@ -1,13 +1,17 @@
 use sp1_core_executor::{ExecutionReport, HookEnv, SP1ContextBuilder};
 use sp1_core_machine::io::SP1Stdin;
 use sp1_primitives::io::SP1PublicValues;
-use sp1_prover::{components::DefaultProverComponents, SP1ProvingKey};
+use sp1_prover::{components::DefaultProverComponents, SP1ProvingKey, SP1VerifyingKey};
 
 use anyhow::{Ok, Result};
-use sp1_stark::{SP1CoreOpts, SP1ProverOpts};
-use std::time::Duration;
+use sp1_stark::{CancellationToken, ProgressSink, SP1CoreOpts, SP1ProverOpts};
+use std::{sync::Arc, time::Duration};
 
-use crate::{provers::ProofOpts, Prover, SP1ProofKind, SP1ProofWithPublicValues};
+use crate::{
+    aggregation::{aggregation_stdin, AGGREGATOR_ELF},
+    provers::ProofOpts,
+    Prover, SP1ProofKind, SP1ProofWithPublicValues,
+};
 
 /// Builder to prepare and configure execution of a program on an input.
 /// May be run with [Self::run].
@@ -210,6 +214,111 @@ impl<'a> Prove<'a> {
         self
     }
 
+    /// Report the stage, completed proofs and estimated time remaining to the given sink.
+    ///
+    /// This parameter is only used when the prover is run in local mode.
+    pub fn progress(mut self, sink: Arc<dyn ProgressSink>) -> Self {
+        self.context_builder.progress(sink);
+        self
+    }
+
+    /// Abort the proof's generation when the given token is cancelled, returning an error.
+    ///
+    /// This parameter is only used when the prover is run in local mode.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.context_builder.cancellation(token);
+        self
+    }
+
+    /// Set the timeout for the proof's generation.
+    ///
+    /// This parameter is only used when the prover is run in network mode.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+/// Builder to prepare and configure the aggregation of many compressed proofs into one proof.
+/// May be run with [Self::run].
+pub struct Aggregate<'a> {
+    prover: &'a dyn Prover<DefaultProverComponents>,
+    kind: SP1ProofKind,
+    context_builder: SP1ContextBuilder<'a>,
+    proofs: Vec<SP1ProofWithPublicValues>,
+    vks: Vec<SP1VerifyingKey>,
+    core_opts: SP1CoreOpts,
+    recursion_opts: SP1CoreOpts,
+    timeout: Option<Duration>,
+}
+
+impl<'a> Aggregate<'a> {
+    /// Prepare to aggregate the given proofs, each verified against the matching verifying key.
+    ///
+    /// Prefer using [ProverClient::aggregate](super::ProverClient::aggregate).
+    /// See there for more documentation.
+    pub fn new(
+        prover: &'a dyn Prover<DefaultProverComponents>,
+        proofs: Vec<SP1ProofWithPublicValues>,
+        vks: Vec<SP1VerifyingKey>,
+    ) -> Self {
+        Self {
+            prover,
+            kind: SP1ProofKind::Compressed,
+            context_builder: Default::default(),
+            proofs,
+            vks,
+            core_opts: SP1CoreOpts::default(),
+            recursion_opts: SP1CoreOpts::recursion(),
+            timeout: None,
+        }
+    }
+
+    /// Aggregate the proofs, consuming the built action `self`.
+    pub fn run(self) -> Result<SP1ProofWithPublicValues> {
+        let Self {
+            prover,
+            kind,
+            mut context_builder,
+            proofs,
+            vks,
+            core_opts,
+            recursion_opts,
+            timeout,
+        } = self;
+        let opts = SP1ProverOpts { core_opts, recursion_opts };
+        let proof_opts = ProofOpts { sp1_prover_opts: opts, timeout };
+        let context = context_builder.build();
+
+        let stdin = aggregation_stdin(proofs, &vks)?;
+        let (pk, _) = prover.setup(AGGREGATOR_ELF);
+        prover.prove(&pk, stdin, proof_opts, context, kind)
+    }
+
+    /// Set the proof kind to the compressed mode. This is the default.
+    pub fn compressed(mut self) -> Self {
+        self.kind = SP1ProofKind::Compressed;
+        self
+    }
+
+    /// Set the proof mode to the plonk bn254 mode.
+    pub fn plonk(mut self) -> Self {
+        self.kind = SP1ProofKind::Plonk;
+        self
+    }
+
+    /// Set the proof mode to the groth16 bn254 mode.
+    pub fn groth16(mut self) -> Self {
+        self.kind = SP1ProofKind::Groth16;
+        self
+    }
+
+    /// Set the shard size for proving.
+    pub fn shard_size(mut self, value: usize) -> Self {
+        self.core_opts.shard_size = value;
+        self
+    }
+
     /// Set the timeout for the proof's generation.
     ///
     /// This parameter is only used when the prover is run in network mode.
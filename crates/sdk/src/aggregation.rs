@@ -0,0 +1,75 @@
+//! # SP1 Proof Aggregation
+//!
+//! Aggregates many compressed SP1 proofs into a single proof with a built-in aggregator program.
+//! The aggregator verifies each proof as a deferred proof and commits to the list of
+//! (vkey hash, public values digest) pairs, which can be read back with [read_aggregated_pairs].
+
+use sp1_core_machine::io::SP1Stdin;
+use sp1_primitives::io::SP1PublicValues;
+use sp1_prover::{HashableKey, SP1VerifyingKey};
+use thiserror::Error;
+
+use crate::{SP1Proof, SP1ProofKind, SP1ProofWithPublicValues};
+
+/// The ELF of the aggregator program in `crates/sdk/aggregator`, built reproducibly with
+/// `make elfs` in `crates/sdk`.
+pub const AGGREGATOR_ELF: &[u8] = include_bytes!("../aggregator/elf/riscv32im-succinct-zkvm-elf");
+
+/// The (vkey hash, public values digest) pair the aggregator commits to for each proof.
+pub type AggregatedPair = ([u32; 8], [u8; 32]);
+
+#[derive(Error, Debug)]
+pub enum AggregationError {
+    #[error("No proofs to aggregate")]
+    Empty,
+    #[error("Got {proofs} proofs but {vks} verifying keys")]
+    LengthMismatch { proofs: usize, vks: usize },
+    #[error("Proof {index} is a {kind:?} proof, only compressed proofs can be aggregated")]
+    NotCompressed { index: usize, kind: SP1ProofKind },
+}
+
+/// Build the input of the aggregator program for the given proofs and their verifying keys.
+///
+/// The proofs are written as deferred proofs, so they are verified when the aggregator's proof is
+/// compressed.
+pub fn aggregation_stdin(
+    proofs: Vec<SP1ProofWithPublicValues>,
+    vks: &[SP1VerifyingKey],
+) -> Result<SP1Stdin, AggregationError> {
+    if proofs.is_empty() {
+        return Err(AggregationError::Empty);
+    }
+    if proofs.len() != vks.len() {
+        return Err(AggregationError::LengthMismatch { proofs: proofs.len(), vks: vks.len() });
+    }
+
+    let mut stdin = SP1Stdin::new();
+    let pairs = proofs
+        .iter()
+        .zip(vks.iter())
+        .map(|(proof, vk)| {
+            let digest: [u8; 32] = proof.public_values.hash().try_into().unwrap();
+            (vk.hash_u32(), digest)
+        })
+        .collect::<Vec<AggregatedPair>>();
+    stdin.write(&pairs);
+
+    for (index, (proof, vk)) in proofs.into_iter().zip(vks.iter()).enumerate() {
+        match proof.proof {
+            SP1Proof::Compressed(proof) => stdin.write_proof(*proof, vk.vk.clone()),
+            proof => {
+                return Err(AggregationError::NotCompressed {
+                    index,
+                    kind: SP1ProofKind::from(&proof),
+                })
+            }
+        }
+    }
+
+    Ok(stdin)
+}
+
+/// Read the (vkey hash, public values digest) pairs committed by the aggregator program.
+pub fn read_aggregated_pairs(public_values: &SP1PublicValues) -> Vec<AggregatedPair> {
+    public_values.clone().read::<Vec<AggregatedPair>>()
+}
@@ -0,0 +1,72 @@
+//! # Deferred Groth16 Proofs
+//!
+//! Defers the verification of Groth16 proofs over BN254, such as the proofs of other proving
+//! systems, to a built-in deferral program. The deferral program verifies a proof and commits to
+//! the SHA-256 digest of its verifying key followed by its public inputs. A compressed proof of it
+//! is then written to the input of another program with [DeferredGroth16Proof::write_to], and
+//! checked in the guest with `sp1_zkvm::lib::verify::verify_groth16_proof`, so that the pairing
+//! check is never executed by the guest itself.
+
+use sp1_core_executor::SP1ReduceProof;
+use sp1_core_machine::io::SP1Stdin;
+use sp1_primitives::io::SP1PublicValues;
+use sp1_prover::{HashableKey, InnerSC, SP1VerifyingKey};
+
+/// The ELF of the Groth16 deferral program in `crates/sdk/groth16-deferral`, built reproducibly
+/// with `make elfs` in `crates/sdk`.
+pub const GROTH16_DEFERRAL_ELF: &[u8] =
+    include_bytes!("../groth16-deferral/elf/riscv32im-succinct-zkvm-elf");
+
+/// A compressed proof of the Groth16 deferral program, attesting that a Groth16 proof is valid.
+#[derive(Clone)]
+pub struct DeferredGroth16Proof {
+    /// The compressed proof of the deferral program.
+    pub proof: SP1ReduceProof<InnerSC>,
+    /// The public values of the deferral program.
+    pub public_values: SP1PublicValues,
+    /// The verifying key of the deferral program.
+    pub vk: SP1VerifyingKey,
+}
+
+impl DeferredGroth16Proof {
+    /// The verification key digest of the deferral program, which `verify_groth16_proof` checks
+    /// against `GROTH16_DEFERRAL_VK_DIGEST`.
+    pub fn vk_digest(&self) -> [u32; 8] {
+        self.vk.hash_u32()
+    }
+
+    /// The SHA-256 digest of the Groth16 verifying key the proof was verified against.
+    pub fn groth16_vk_digest(&self) -> [u8; 32] {
+        self.public_values.as_slice()[..32].try_into().unwrap()
+    }
+
+    /// The public inputs of the Groth16 proof, each the big-endian encoding of a BN254 scalar.
+    pub fn public_inputs(&self) -> Vec<[u8; 32]> {
+        self.public_values.as_slice()[32..]
+            .chunks_exact(32)
+            .map(|input| input.try_into().unwrap())
+            .collect()
+    }
+
+    /// Write the proof to the proof stream of `stdin`.
+    pub fn write_to(self, stdin: &mut SP1Stdin) {
+        stdin.write_proof(self.proof, self.vk.vk);
+    }
+}
+
+/// Build the input of the deferral program for the given Groth16 verifying key, proof and public
+/// inputs.
+///
+/// The proof is the raw gnark encoding of the proof, and each public input is the big-endian
+/// encoding of a BN254 scalar.
+pub fn groth16_deferral_stdin(
+    groth16_vk: &[u8],
+    proof: &[u8],
+    public_inputs: &[[u8; 32]],
+) -> SP1Stdin {
+    let mut stdin = SP1Stdin::new();
+    stdin.write_slice(groth16_vk);
+    stdin.write_slice(proof);
+    stdin.write(&public_inputs.to_vec());
+    stdin
+}
@@ -1,4 +1,3 @@
-use cfg_if::cfg_if;
 use std::path::PathBuf;
 
 #[cfg(any(feature = "network", feature = "network-v2"))]
@@ -12,6 +11,10 @@ use {
 
 use crate::SP1_CIRCUIT_VERSION;
 
+pub mod manager;
+
+use manager::{ArtifactError, ArtifactManager, CircuitArtifactsKind};
+
 /// The base URL for the S3 bucket containing the circuit artifacts.
 pub const CIRCUIT_ARTIFACTS_URL_BASE: &str = "https://sp1-circuits.s3-us-east-2.amazonaws.com";
 
@@ -25,36 +28,14 @@ pub fn plonk_circuit_artifacts_dir() -> PathBuf {
     dirs::home_dir().unwrap().join(".sp1").join("circuits/plonk").join(SP1_CIRCUIT_VERSION)
 }
 
-/// Tries to install the groth16 circuit artifacts if they are not already installed.
-pub fn try_install_circuit_artifacts(artifacts_type: &str) -> PathBuf {
-    let build_dir = if artifacts_type == "groth16" {
-        groth16_circuit_artifacts_dir()
-    } else if artifacts_type == "plonk" {
-        plonk_circuit_artifacts_dir()
-    } else {
-        unimplemented!("unsupported artifacts type: {}", artifacts_type);
-    };
-
-    if build_dir.exists() {
-        println!(
-            "[sp1] {} circuit artifacts already seem to exist at {}. if you want to re-download them, delete the directory",
-            artifacts_type,
-            build_dir.display()
-        );
-    } else {
-        cfg_if! {
-            if #[cfg(any(feature = "network", feature = "network-v2"))] {
-                println!(
-                    "[sp1] {} circuit artifacts for version {} do not exist at {}. downloading...",
-                    artifacts_type,
-                    SP1_CIRCUIT_VERSION,
-                    build_dir.display()
-                );
-                install_circuit_artifacts(build_dir.clone(), artifacts_type);
-            }
-        }
-    }
-    build_dir
+/// Tries to install the circuit artifacts if they are not already installed, and returns their
+/// directory.
+///
+/// The artifacts are verified against the builtin manifest and reinstalled if they fail
+/// verification. Use [manager::ArtifactManager] to install them from a local tarball or directory.
+pub fn try_install_circuit_artifacts(artifacts_type: &str) -> Result<PathBuf, ArtifactError> {
+    let kind: CircuitArtifactsKind = artifacts_type.parse()?;
+    ArtifactManager::default().install(kind)
 }
 
 /// Install the latest circuit artifacts.
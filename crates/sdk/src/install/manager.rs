@@ -0,0 +1,505 @@
+//! # Circuit Artifact Manager
+//!
+//! Installs the Plonk and Groth16 circuit artifacts into `~/.sp1/circuits/<kind>/<version>`, from
+//! the artifacts bucket, a local tarball or a local directory. Every installation is checked
+//! against the SHA-256 digests of [ArtifactManifest::builtin], a manifest compiled into the crate,
+//! before it is moved into place, so a partial or tampered installation is never used.
+//!
+//! An installation is checked against the files the manifest pins, and files the manifest does not
+//! pin are left alone, as the v3.0.0 entry pins the verifying keys only. The entry of a new version
+//! is generated with [ArtifactManifest::pin] from the published artifacts, which pins every file of
+//! the installation, including the proving keys and the Solidity verifiers.
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::SP1_CIRCUIT_VERSION;
+
+/// The manifest of the circuit artifacts, as a JSON map from version to kind to file digests.
+pub const CIRCUIT_ARTIFACTS_MANIFEST: &str = include_str!("../../circuit-artifacts.json");
+
+/// The kind of circuit artifacts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CircuitArtifactsKind {
+    Plonk,
+    Groth16,
+}
+
+impl CircuitArtifactsKind {
+    /// All kinds of circuit artifacts.
+    pub const ALL: [Self; 2] = [Self::Plonk, Self::Groth16];
+
+    /// The name of the kind, as used in directory and tarball names.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Plonk => "plonk",
+            Self::Groth16 => "groth16",
+        }
+    }
+}
+
+impl fmt::Display for CircuitArtifactsKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CircuitArtifactsKind {
+    type Err = ArtifactError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| ArtifactError::UnknownKind(s.to_string()))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ArtifactError {
+    #[error("I/O error at {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Unknown circuit artifacts kind: {0}")]
+    UnknownKind(String),
+    #[error("The manifest has no {kind} artifacts for version {version}")]
+    UnknownVersion { kind: CircuitArtifactsKind, version: String },
+    #[error("The {kind} artifacts for version {version} are not installed")]
+    NotInstalled { kind: CircuitArtifactsKind, version: String },
+    #[error("Missing artifact file {0}")]
+    MissingFile(PathBuf),
+    #[error("Checksum mismatch for {path}: expected {expected}, got {actual}")]
+    ChecksumMismatch { path: PathBuf, expected: String, actual: String },
+    #[error("Failed to download {url}: {reason}")]
+    Download { url: String, reason: String },
+    #[error("Downloading circuit artifacts requires the `network` or `network-v2` feature")]
+    NetworkDisabled,
+    #[error("Failed to extract {path}: {reason}")]
+    Extract { path: PathBuf, reason: String },
+}
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> ArtifactError + '_ {
+    move |source| ArtifactError::Io { path: path.to_path_buf(), source }
+}
+
+/// The SHA-256 digests of the artifact files, by version and kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactManifest(
+    pub BTreeMap<String, BTreeMap<CircuitArtifactsKind, BTreeMap<String, String>>>,
+);
+
+impl ArtifactManifest {
+    /// The manifest compiled into the crate.
+    pub fn builtin() -> Self {
+        serde_json::from_str(CIRCUIT_ARTIFACTS_MANIFEST).expect("invalid builtin manifest")
+    }
+
+    /// The hex-encoded digests of the files of the given artifacts, by file name.
+    pub fn files(
+        &self,
+        kind: CircuitArtifactsKind,
+        version: &str,
+    ) -> Result<&BTreeMap<String, String>, ArtifactError> {
+        self.0
+            .get(version)
+            .and_then(|kinds| kinds.get(&kind))
+            .ok_or_else(|| ArtifactError::UnknownVersion { kind, version: version.to_string() })
+    }
+
+    /// Pin every file of an artifacts directory as the given version of the artifacts, replacing
+    /// the files pinned for it before.
+    pub fn pin(
+        &mut self,
+        kind: CircuitArtifactsKind,
+        version: &str,
+        dir: &Path,
+    ) -> Result<(), ArtifactError> {
+        let files = Self::digest_dir(dir)?;
+        self.0.entry(version.to_string()).or_default().insert(kind, files);
+        Ok(())
+    }
+
+    /// The manifest as pretty-printed JSON, the format of `circuit-artifacts.json`.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("failed to serialize the manifest")
+    }
+
+    /// Digest every file of an artifacts directory, to add a new version to the manifest.
+    pub fn digest_dir(dir: &Path) -> Result<BTreeMap<String, String>, ArtifactError> {
+        let mut files = BTreeMap::new();
+        for entry in fs::read_dir(dir).map_err(io_error(dir))? {
+            let path = entry.map_err(io_error(dir))?.path();
+            if path.is_file() {
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                files.insert(name, digest_file(&path)?);
+            }
+        }
+        Ok(files)
+    }
+}
+
+fn digest_file(path: &Path) -> Result<String, ArtifactError> {
+    let mut file = fs::File::open(path).map_err(io_error(path))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(io_error(path))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// An installed version of the circuit artifacts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledArtifacts {
+    pub kind: CircuitArtifactsKind,
+    pub version: String,
+    pub path: PathBuf,
+}
+
+/// Installs and verifies circuit artifacts in a root directory, `~/.sp1/circuits` by default.
+#[derive(Debug, Clone)]
+pub struct ArtifactManager {
+    root: PathBuf,
+    manifest: ArtifactManifest,
+}
+
+impl Default for ArtifactManager {
+    fn default() -> Self {
+        Self::new(dirs::home_dir().unwrap().join(".sp1").join("circuits"))
+    }
+}
+
+impl ArtifactManager {
+    /// A manager for the artifacts in the given root directory, checked against the builtin
+    /// manifest.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into(), manifest: ArtifactManifest::builtin() }
+    }
+
+    /// Check the artifacts against the given manifest instead of the builtin one.
+    pub fn with_manifest(mut self, manifest: ArtifactManifest) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    /// The root directory of the artifacts.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The directory of the given version of the artifacts.
+    pub fn dir(&self, kind: CircuitArtifactsKind, version: &str) -> PathBuf {
+        self.root.join(kind.as_str()).join(version)
+    }
+
+    /// Verify the installed artifacts against the manifest.
+    pub fn verify(&self, kind: CircuitArtifactsKind, version: &str) -> Result<(), ArtifactError> {
+        let dir = self.dir(kind, version);
+        if !dir.is_dir() {
+            return Err(ArtifactError::NotInstalled { kind, version: version.to_string() });
+        }
+        self.verify_dir(kind, version, &dir)
+    }
+
+    fn verify_dir(
+        &self,
+        kind: CircuitArtifactsKind,
+        version: &str,
+        dir: &Path,
+    ) -> Result<(), ArtifactError> {
+        let files = self.manifest.files(kind, version)?;
+        for (name, expected) in files {
+            let path = dir.join(name);
+            if !path.is_file() {
+                return Err(ArtifactError::MissingFile(path));
+            }
+            let actual = digest_file(&path)?;
+            if actual != *expected {
+                return Err(ArtifactError::ChecksumMismatch {
+                    path,
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Install the artifacts for the current circuit version, downloading them if they are not
+    /// installed yet, and return their directory.
+    ///
+    /// An installation that fails verification is replaced by a fresh download.
+    pub fn install(&self, kind: CircuitArtifactsKind) -> Result<PathBuf, ArtifactError> {
+        match self.verify(kind, SP1_CIRCUIT_VERSION) {
+            Ok(()) => return Ok(self.dir(kind, SP1_CIRCUIT_VERSION)),
+            Err(ArtifactError::NotInstalled { .. }) => {}
+            Err(e @ (ArtifactError::MissingFile(_) | ArtifactError::ChecksumMismatch { .. })) => {
+                tracing::warn!("reinstalling the {} circuit artifacts: {}", kind, e);
+            }
+            Err(e) => return Err(e),
+        }
+        self.download(kind)
+    }
+
+    #[cfg(any(feature = "network", feature = "network-v2"))]
+    fn download(&self, kind: CircuitArtifactsKind) -> Result<PathBuf, ArtifactError> {
+        let url = format!(
+            "{}/{}-{}.tar.gz",
+            super::CIRCUIT_ARTIFACTS_URL_BASE,
+            SP1_CIRCUIT_VERSION,
+            kind.as_str()
+        );
+        println!("[sp1] downloading the {} circuit artifacts from {}", kind, url);
+        let mut tarball = tempfile::NamedTempFile::new().map_err(io_error(&self.root))?;
+        let download_error = |reason: String| ArtifactError::Download { url: url.clone(), reason };
+        let client =
+            reqwest::Client::builder().build().map_err(|e| download_error(e.to_string()))?;
+        crate::block_on(super::download_file(&client, &url, &mut tarball))
+            .map_err(download_error)?;
+        self.install_from_tarball(kind, tarball.path())
+    }
+
+    #[cfg(not(any(feature = "network", feature = "network-v2")))]
+    fn download(&self, _kind: CircuitArtifactsKind) -> Result<PathBuf, ArtifactError> {
+        Err(ArtifactError::NetworkDisabled)
+    }
+
+    /// Install the artifacts for the current circuit version from a `.tar.gz` tarball, as
+    /// published in the artifacts bucket, and return their directory.
+    pub fn install_from_tarball(
+        &self,
+        kind: CircuitArtifactsKind,
+        tarball: &Path,
+    ) -> Result<PathBuf, ArtifactError> {
+        self.install_with(kind, |staging| {
+            let output = Command::new("tar")
+                .arg("-xzf")
+                .arg(tarball)
+                .arg("-C")
+                .arg(staging)
+                .output()
+                .map_err(|e| ArtifactError::Extract {
+                    path: tarball.to_path_buf(),
+                    reason: e.to_string(),
+                })?;
+            if !output.status.success() {
+                return Err(ArtifactError::Extract {
+                    path: tarball.to_path_buf(),
+                    reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                });
+            }
+            Ok(())
+        })
+    }
+
+    /// Install the artifacts for the current circuit version by copying the files of a local
+    /// directory, and return their directory.
+    pub fn install_from_dir(
+        &self,
+        kind: CircuitArtifactsKind,
+        dir: &Path,
+    ) -> Result<PathBuf, ArtifactError> {
+        self.install_with(kind, |staging| {
+            for entry in fs::read_dir(dir).map_err(io_error(dir))? {
+                let path = entry.map_err(io_error(dir))?.path();
+                if path.is_file() {
+                    let target = staging.join(path.file_name().unwrap());
+                    fs::copy(&path, &target).map_err(io_error(&path))?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Fill a staging directory next to the target, verify it and move it into place.
+    fn install_with(
+        &self,
+        kind: CircuitArtifactsKind,
+        fill: impl FnOnce(&Path) -> Result<(), ArtifactError>,
+    ) -> Result<PathBuf, ArtifactError> {
+        let kind_dir = self.root.join(kind.as_str());
+        fs::create_dir_all(&kind_dir).map_err(io_error(&kind_dir))?;
+        let staging = tempfile::Builder::new()
+            .prefix(".staging-")
+            .tempdir_in(&kind_dir)
+            .map_err(io_error(&kind_dir))?;
+        fill(staging.path())?;
+        self.verify_dir(kind, SP1_CIRCUIT_VERSION, staging.path())?;
+
+        let target = self.dir(kind, SP1_CIRCUIT_VERSION);
+        if target.exists() {
+            fs::remove_dir_all(&target).map_err(io_error(&target))?;
+        }
+        fs::rename(staging.path(), &target).map_err(io_error(&target))?;
+        println!("[sp1] installed the {} circuit artifacts at {}", kind, target.display());
+        Ok(target)
+    }
+
+    /// List the installed versions of the artifacts, sorted by kind and version.
+    pub fn installed(&self) -> Result<Vec<InstalledArtifacts>, ArtifactError> {
+        let mut installed = Vec::new();
+        for kind in CircuitArtifactsKind::ALL {
+            let kind_dir = self.root.join(kind.as_str());
+            if !kind_dir.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&kind_dir).map_err(io_error(&kind_dir))? {
+                let path = entry.map_err(io_error(&kind_dir))?.path();
+                let version = path.file_name().unwrap().to_string_lossy().into_owned();
+                if path.is_dir() && !version.starts_with('.') {
+                    installed.push(InstalledArtifacts { kind, version, path });
+                }
+            }
+        }
+        installed.sort_by(|a, b| (a.kind, &a.version).cmp(&(b.kind, &b.version)));
+        Ok(installed)
+    }
+
+    /// Remove every installed version of the artifacts except the current circuit version, along
+    /// with leftover staging directories, and return the removed versions.
+    pub fn gc(&self) -> Result<Vec<InstalledArtifacts>, ArtifactError> {
+        let mut removed = Vec::new();
+        for artifacts in self.installed()? {
+            if artifacts.version != SP1_CIRCUIT_VERSION {
+                fs::remove_dir_all(&artifacts.path).map_err(io_error(&artifacts.path))?;
+                removed.push(artifacts);
+            }
+        }
+        for kind in CircuitArtifactsKind::ALL {
+            let kind_dir = self.root.join(kind.as_str());
+            if !kind_dir.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&kind_dir).map_err(io_error(&kind_dir))? {
+                let path = entry.map_err(io_error(&kind_dir))?.path();
+                if path.file_name().unwrap().to_string_lossy().starts_with(".staging-") {
+                    fs::remove_dir_all(&path).map_err(io_error(&path))?;
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROTH16_VK: &[u8] = include_bytes!("../../../verifier/bn254-vk/groth16_vk.bin");
+
+    #[test]
+    fn test_builtin_manifest() {
+        let manifest = ArtifactManifest::builtin();
+        for kind in CircuitArtifactsKind::ALL {
+            assert!(!manifest.files(kind, SP1_CIRCUIT_VERSION).unwrap().is_empty());
+        }
+        assert_eq!(
+            "groth16".parse::<CircuitArtifactsKind>().unwrap(),
+            CircuitArtifactsKind::Groth16
+        );
+        assert!("stark".parse::<CircuitArtifactsKind>().is_err());
+    }
+
+    #[test]
+    fn test_artifact_manager() {
+        let root = tempfile::tempdir().unwrap();
+        let source = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("groth16_vk.bin"), GROTH16_VK).unwrap();
+        fs::write(source.path().join("groth16_pk.bin"), b"pk").unwrap();
+        let kind = CircuitArtifactsKind::Groth16;
+
+        // Every file is pinned.
+        let mut manifest = ArtifactManifest::default();
+        manifest.pin(kind, SP1_CIRCUIT_VERSION, source.path()).unwrap();
+        assert_eq!(
+            manifest.files(kind, SP1_CIRCUIT_VERSION).unwrap().keys().collect::<Vec<_>>(),
+            ["groth16_pk.bin", "groth16_vk.bin"]
+        );
+        assert_eq!(
+            serde_json::from_str::<ArtifactManifest>(&manifest.to_json()).unwrap(),
+            manifest
+        );
+        let manager = ArtifactManager::new(root.path()).with_manifest(manifest);
+
+        // Install from a directory.
+        assert!(matches!(
+            manager.verify(kind, SP1_CIRCUIT_VERSION),
+            Err(ArtifactError::NotInstalled { .. })
+        ));
+        let dir = manager.install_from_dir(kind, source.path()).unwrap();
+        assert_eq!(dir, manager.dir(kind, SP1_CIRCUIT_VERSION));
+        assert!(dir.join("groth16_pk.bin").is_file());
+        manager.verify(kind, SP1_CIRCUIT_VERSION).unwrap();
+        assert_eq!(manager.install(kind).unwrap(), dir);
+
+        // Install from a tarball.
+        let tarball = source.path().join("artifacts.tar.gz");
+        let status = Command::new("tar")
+            .arg("-czf")
+            .arg(&tarball)
+            .arg("-C")
+            .arg(source.path())
+            .args(["groth16_vk.bin", "groth16_pk.bin"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(manager.install_from_tarball(kind, &tarball).unwrap(), dir);
+        manager.verify(kind, SP1_CIRCUIT_VERSION).unwrap();
+
+        // Tampered or missing files are detected, and never installed.
+        fs::write(dir.join("groth16_pk.bin"), b"tampered").unwrap();
+        assert!(matches!(
+            manager.verify(kind, SP1_CIRCUIT_VERSION),
+            Err(ArtifactError::ChecksumMismatch { .. })
+        ));
+        fs::write(dir.join("groth16_pk.bin"), b"pk").unwrap();
+        fs::write(dir.join("extra.bin"), b"extra").unwrap();
+        manager.verify(kind, SP1_CIRCUIT_VERSION).unwrap();
+        fs::write(dir.join("groth16_vk.bin"), b"tampered").unwrap();
+        assert!(matches!(
+            manager.verify(kind, SP1_CIRCUIT_VERSION),
+            Err(ArtifactError::ChecksumMismatch { .. })
+        ));
+        fs::remove_file(dir.join("groth16_vk.bin")).unwrap();
+        assert!(matches!(
+            manager.verify(kind, SP1_CIRCUIT_VERSION),
+            Err(ArtifactError::MissingFile(_))
+        ));
+        fs::write(source.path().join("groth16_vk.bin"), b"tampered").unwrap();
+        assert!(matches!(
+            manager.install_from_dir(kind, source.path()),
+            Err(ArtifactError::ChecksumMismatch { .. })
+        ));
+        assert!(!dir.join("groth16_vk.bin").exists());
+
+        // Old versions are listed and garbage collected.
+        fs::create_dir_all(manager.dir(kind, "v1.0.0")).unwrap();
+        fs::create_dir_all(manager.dir(CircuitArtifactsKind::Plonk, "v2.0.0")).unwrap();
+        let versions = manager
+            .installed()
+            .unwrap()
+            .into_iter()
+            .map(|artifacts| (artifacts.kind, artifacts.version))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            versions,
+            vec![
+                (CircuitArtifactsKind::Plonk, "v2.0.0".to_string()),
+                (kind, "v1.0.0".to_string()),
+                (kind, SP1_CIRCUIT_VERSION.to_string()),
+            ]
+        );
+        assert_eq!(manager.gc().unwrap().len(), 2);
+        assert_eq!(manager.installed().unwrap().len(), 1);
+    }
+}
@@ -5,8 +5,13 @@
 //! Visit the [Getting Started](https://succinctlabs.github.io/sp1/getting-started.html) section
 //! in the official SP1 documentation for a quick start guide.
 
+// Lets the `sp1_test` attribute refer to `::sp1_sdk` from within this crate's tests.
+extern crate self as sp1_sdk;
+
 pub mod action;
+pub mod aggregation;
 pub mod artifacts;
+pub mod deferral;
 pub mod install;
 #[cfg(feature = "network")]
 pub mod network;
@@ -21,13 +26,16 @@ pub use crate::network_v2::prover::NetworkProver as NetworkProverV2;
 pub use crate::provers::CudaProver;
 
 pub mod proof;
+pub mod proof_file;
 pub mod provers;
+pub mod testing;
 pub mod utils {
     pub use sp1_core_machine::utils::setup_logger;
 }
 
 use cfg_if::cfg_if;
 pub use proof::*;
+pub use proof_file::{ProofCompression, ProofFileError, ProofFileHeader};
 pub use provers::SP1VerificationError;
 use sp1_prover::components::DefaultProverComponents;
 
@@ -45,6 +53,7 @@ pub use sp1_prover::{
     CoreSC, HashableKey, InnerSC, OuterSC, PlonkBn254Proof, SP1Prover, SP1ProvingKey,
     SP1VerifyingKey,
 };
+pub use sp1_stark::{CancellationToken, ProgressSink, ProgressUpdate, ProvingStage};
 
 /// A client for interacting with SP1.
 pub struct ProverClient {
@@ -225,6 +234,81 @@ impl ProverClient {
         action::Prove::new(self.prover.as_ref(), pk, stdin)
     }
 
+    /// Prepare to aggregate many compressed proofs into a single proof. The returned
+    /// [action::Aggregate] may be configured via its methods before running.
+    ///
+    /// Each proof is verified against the verifying key at the same index by a built-in aggregator
+    /// program, whose public values commit to the list of (vkey hash, public values digest) pairs.
+    /// These can be decoded with [aggregation::read_aggregated_pairs]. The aggregated proof is
+    /// verified against the verifying key of [aggregation::AGGREGATOR_ELF].
+    ///
+    /// ### Examples
+    /// ```no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin};
+    ///
+    /// let elf = include_bytes!("../../../examples/fibonacci/program/elf/riscv32im-succinct-zkvm-elf");
+    /// let client = ProverClient::new();
+    /// let (pk, vk) = client.setup(elf);
+    /// let proofs = (0..3)
+    ///     .map(|n| {
+    ///         let mut stdin = SP1Stdin::new();
+    ///         stdin.write(&n);
+    ///         client.prove(&pk, stdin).compressed().run().unwrap()
+    ///     })
+    ///     .collect::<Vec<_>>();
+    /// let aggregated = client.aggregate(proofs, vec![vk; 3]).plonk().run().unwrap();
+    /// ```
+    pub fn aggregate<'a>(
+        &'a self,
+        proofs: Vec<SP1ProofWithPublicValues>,
+        vks: Vec<SP1VerifyingKey>,
+    ) -> action::Aggregate<'a> {
+        action::Aggregate::new(self.prover.as_ref(), proofs, vks)
+    }
+
+    /// Verifies a Groth16 proof over BN254 with the built-in deferral program, and proves it as a
+    /// compressed proof.
+    ///
+    /// The returned [deferral::DeferredGroth16Proof] can be written to the input of another
+    /// program with [deferral::DeferredGroth16Proof::write_to], where it is checked with
+    /// `sp1_zkvm::lib::verify::verify_groth16_proof` without verifying the pairing in the guest.
+    /// The proof is the raw gnark encoding of the proof, and each public input is the big-endian
+    /// encoding of a BN254 scalar.
+    ///
+    /// ### Examples
+    /// ```no_run
+    /// use sp1_sdk::{ProverClient, SP1Stdin};
+    ///
+    /// let groth16_vk = std::fs::read("groth16_vk.bin").unwrap();
+    /// let proof = std::fs::read("proof.bin").unwrap();
+    /// let public_inputs = vec![[0u8; 32]; 2];
+    /// let client = ProverClient::new();
+    /// let deferred = client.defer_groth16(&groth16_vk, &proof, &public_inputs).unwrap();
+    ///
+    /// let mut stdin = SP1Stdin::new();
+    /// stdin.write(&deferred.groth16_vk_digest());
+    /// stdin.write(&deferred.public_inputs());
+    /// deferred.write_to(&mut stdin);
+    /// ```
+    pub fn defer_groth16(
+        &self,
+        groth16_vk: &[u8],
+        proof: &[u8],
+        public_inputs: &[[u8; 32]],
+    ) -> anyhow::Result<deferral::DeferredGroth16Proof> {
+        let stdin = deferral::groth16_deferral_stdin(groth16_vk, proof, public_inputs);
+        let (pk, vk) = self.setup(deferral::GROTH16_DEFERRAL_ELF);
+        let proof = self.prove(&pk, stdin).compressed().run()?;
+        let SP1Proof::Compressed(reduce_proof) = proof.proof else {
+            anyhow::bail!("expected a compressed proof of the deferral program");
+        };
+        Ok(deferral::DeferredGroth16Proof {
+            proof: *reduce_proof,
+            public_values: proof.public_values,
+            vk,
+        })
+    }
+
     /// Verifies that the given proof is valid and matches the given verification key produced by
     /// [Self::setup].
     ///
@@ -317,9 +401,18 @@ macro_rules! include_elf {
 #[cfg(test)]
 mod tests {
 
+    use std::sync::{Arc, Mutex};
+
+    use num_bigint::BigUint;
+    use sp1_lib::verify::GROTH16_DEFERRAL_VK_DIGEST;
     use sp1_primitives::io::SP1PublicValues;
 
-    use crate::{utils, CostEstimator, ProverClient, SP1Stdin};
+    use crate::{
+        aggregation::{read_aggregated_pairs, AGGREGATOR_ELF},
+        deferral::{groth16_deferral_stdin, GROTH16_DEFERRAL_ELF},
+        utils, CancellationToken, CostEstimator, HashableKey, ProgressUpdate, ProverClient,
+        ProvingStage, SP1Proof, SP1ProofWithPublicValues, SP1Stdin,
+    };
 
     #[test]
     fn test_execute() {
@@ -375,6 +468,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_e2e_cancelled() {
+        utils::setup_logger();
+        let client = ProverClient::local();
+        let elf = include_bytes!("../../../tests/fibonacci/elf/riscv32im-succinct-zkvm-elf");
+        let (pk, _) = client.setup(elf);
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&10usize);
+
+        // A cancelled proof stops before proving any shard.
+        let token = CancellationToken::new();
+        token.cancel();
+        let stages = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let stages = stages.clone();
+            move |update: ProgressUpdate| stages.lock().unwrap().push(update.stage)
+        };
+        let result = client
+            .prove(&pk, stdin)
+            .compressed()
+            .progress(Arc::new(sink))
+            .cancellation(token)
+            .run();
+        assert!(result.is_err());
+        assert_eq!(*stages.lock().unwrap(), vec![ProvingStage::Core]);
+    }
+
     #[test]
     fn test_e2e_compressed() {
         utils::setup_logger();
@@ -415,6 +535,85 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_e2e_aggregate_compressed() {
+        utils::setup_logger();
+        let client = ProverClient::local();
+        let elf = include_bytes!("../../../tests/fibonacci/elf/riscv32im-succinct-zkvm-elf");
+        let (pk, vk) = client.setup(elf);
+        let proofs = [10usize, 20]
+            .iter()
+            .map(|n| {
+                let mut stdin = SP1Stdin::new();
+                stdin.write(n);
+                client.prove(&pk, stdin).compressed().run().unwrap()
+            })
+            .collect::<Vec<_>>();
+        let digests = proofs.iter().map(|proof| proof.public_values.hash()).collect::<Vec<_>>();
+
+        // Aggregate the proofs & verify.
+        let proof = client.aggregate(proofs, vec![vk.clone(); 2]).run().unwrap();
+        let (_, aggregator_vk) = client.setup(AGGREGATOR_ELF);
+        client.verify(&proof, &aggregator_vk).unwrap();
+
+        // Check the committed pairs.
+        let pairs = read_aggregated_pairs(&proof.public_values);
+        assert_eq!(pairs.len(), 2);
+        for ((vk_hash, digest), expected) in pairs.iter().zip(digests) {
+            assert_eq!(*vk_hash, vk.hash_u32());
+            assert_eq!(digest.to_vec(), expected);
+        }
+    }
+
+    #[test]
+    fn test_groth16_deferral_vk_digest() {
+        let client = ProverClient::local();
+        let (_, vk) = client.setup(GROTH16_DEFERRAL_ELF);
+        assert_eq!(
+            vk.hash_u32(),
+            GROTH16_DEFERRAL_VK_DIGEST,
+            "update GROTH16_DEFERRAL_VK_DIGEST in sp1-lib to {:?}",
+            vk.hash_u32()
+        );
+    }
+
+    #[test]
+    fn test_e2e_defer_groth16() {
+        utils::setup_logger();
+        let client = ProverClient::local();
+
+        // Defer an SP1 Groth16 proof, whose raw public inputs are its vkey hash and committed
+        // values digest.
+        let groth16 =
+            SP1ProofWithPublicValues::load("../verifier/test_binaries/fibonacci-groth16.bin")
+                .unwrap();
+        let SP1Proof::Groth16(groth16_proof) = &groth16.proof else { panic!() };
+        let groth16_vk = include_bytes!("../../verifier/bn254-vk/groth16_vk.bin");
+        let proof = hex::decode(&groth16_proof.encoded_proof).unwrap();
+        let public_inputs = groth16_proof
+            .public_inputs
+            .iter()
+            .map(|input| {
+                let bytes = input.parse::<BigUint>().unwrap().to_bytes_be();
+                let mut input = [0u8; 32];
+                input[32 - bytes.len()..].copy_from_slice(&bytes);
+                input
+            })
+            .collect::<Vec<_>>();
+        let deferred = client.defer_groth16(groth16_vk, &proof, &public_inputs).unwrap();
+
+        // Check the committed values.
+        assert_eq!(deferred.vk_digest(), GROTH16_DEFERRAL_VK_DIGEST);
+        assert_eq!(deferred.groth16_vk_digest(), groth16_proof.groth16_vkey_hash);
+        assert_eq!(deferred.public_inputs(), public_inputs);
+
+        // The deferral program rejects the proof for other public inputs.
+        let mut tampered_inputs = public_inputs.clone();
+        tampered_inputs[1][31] ^= 1;
+        let stdin = groth16_deferral_stdin(groth16_vk, &proof, &tampered_inputs);
+        assert!(client.execute(GROTH16_DEFERRAL_ELF, stdin).run().is_err());
+    }
+
     #[test]
     fn test_e2e_prove_plonk_mock() {
         utils::setup_logger();
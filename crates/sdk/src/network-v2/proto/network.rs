@@ -387,6 +387,7 @@ pub enum ProofStatus {
     Requested = 1,
     Assigned = 2,
     Fulfilled = 3,
+    Unfulfillable = 4,
 }
 impl ProofStatus {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -399,6 +400,7 @@ impl ProofStatus {
             Self::Requested => "REQUESTED",
             Self::Assigned => "ASSIGNED",
             Self::Fulfilled => "FULFILLED",
+            Self::Unfulfillable => "UNFULFILLABLE",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -408,6 +410,7 @@ impl ProofStatus {
             "REQUESTED" => Some(Self::Requested),
             "ASSIGNED" => Some(Self::Assigned),
             "FULFILLED" => Some(Self::Fulfilled),
+            "UNFULFILLABLE" => Some(Self::Unfulfillable),
             _ => None,
         }
     }
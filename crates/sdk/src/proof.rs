@@ -1,4 +1,4 @@
-use std::{fmt::Debug, fs::File, path::Path};
+use std::{fmt::Debug, fs, path::Path};
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -10,6 +10,10 @@ use strum_macros::{EnumDiscriminants, EnumTryAs};
 use sp1_prover::{CoreSC, Groth16Bn254Proof, InnerSC, PlonkBn254Proof};
 use sp1_stark::{MachineVerificationError, ShardProof};
 
+use crate::proof_file::{
+    decode_proof_file, encode_proof_file, proof_json, vkey_hash, ProofCompression, ProofFileHeader,
+};
+
 /// A proof generated with SP1 of a particular proof mode.
 #[derive(Debug, Clone, Serialize, Deserialize, EnumDiscriminants, EnumTryAs)]
 #[strum_discriminants(derive(Default, Hash, PartialOrd, Ord))]
@@ -32,16 +36,44 @@ pub struct SP1ProofWithPublicValues {
 }
 
 impl SP1ProofWithPublicValues {
-    /// Saves the proof to a path.
+    /// Saves the proof to a path, in the [proof file format](crate::proof_file).
     pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
-        bincode::serialize_into(File::create(path).expect("failed to open file"), self)
-            .map_err(Into::into)
+        self.save_with_compression(path, ProofCompression::None)
+    }
+
+    /// Saves the proof to a path, compressing it with the given compression.
+    pub fn save_with_compression(
+        &self,
+        path: impl AsRef<Path>,
+        compression: ProofCompression,
+    ) -> Result<()> {
+        fs::write(path, encode_proof_file(self, compression)?).map_err(Into::into)
     }
 
     /// Loads a proof from a path.
+    ///
+    /// Proofs saved by older SDKs, without a header, are loaded as well. Errors are
+    /// [ProofFileError](crate::proof_file::ProofFileError)s, which tell apart corrupted files from
+    /// proofs of other SP1 versions.
     pub fn load(path: impl AsRef<Path>) -> Result<Self> {
-        bincode::deserialize_from(File::open(path).expect("failed to open file"))
-            .map_err(Into::into)
+        decode_proof_file(&fs::read(path)?).map_err(Into::into)
+    }
+
+    /// Reads the header of a proof file, without decoding the proof.
+    pub fn load_header(path: impl AsRef<Path>) -> Result<ProofFileHeader> {
+        ProofFileHeader::read(&fs::read(path)?).map_err(Into::into)
+    }
+
+    /// Saves a Plonk or Groth16 proof as JSON, with its hex encoded proof bytes, public values and
+    /// vkey hash, for verifiers outside of Rust.
+    pub fn save_json(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, proof_json(self)?).map_err(Into::into)
+    }
+
+    /// The `bytes32` vkey hash of the program the proof commits to, for compressed, Plonk and
+    /// Groth16 proofs.
+    pub fn vkey_hash(&self) -> Option<[u8; 32]> {
+        vkey_hash(&self.proof)
     }
 
     /// Returns the raw proof as a string.
@@ -56,6 +88,9 @@ impl SP1ProofWithPublicValues {
     /// For Plonk or Groth16 proofs, returns the proof in a byte encoding the onchain verifier
     /// accepts. The bytes consist of the first four bytes of Plonk vkey hash followed by the
     /// encoded proof, in a form optimized for onchain verification.
+    ///
+    /// For compressed proofs, returns the bincode encoding of the reduce proof, as accepted by
+    /// `CompressedVerifier` in `sp1-verifier`.
     pub fn bytes(&self) -> Vec<u8> {
         match &self.proof {
             SP1Proof::Plonk(plonk_proof) => {
@@ -74,7 +109,10 @@ impl SP1ProofWithPublicValues {
                     hex::decode(&groth16_proof.encoded_proof).expect("Invalid Groth16 proof");
                 [groth16_proof.groth16_vkey_hash[..4].to_vec(), proof_bytes].concat()
             }
-            _ => unimplemented!("only Plonk and Groth16 proofs are verifiable onchain"),
+            SP1Proof::Compressed(reduce_proof) => {
+                bincode::serialize(reduce_proof).expect("failed to serialize the compressed proof")
+            }
+            _ => unimplemented!("only Plonk, Groth16 and compressed proofs have a byte encoding"),
         }
     }
 }
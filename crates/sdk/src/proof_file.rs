@@ -0,0 +1,421 @@
+//! # SP1 Proof Files
+//!
+//! The file format written by [SP1ProofWithPublicValues::save]: a header describing the proof,
+//! followed by its bincode encoding, optionally compressed with zstd. The header has a fixed
+//! layout that doesn't depend on the SP1 version, so a proof generated by another version is
+//! reported with its circuit version instead of failing with a bincode error.
+//!
+//! | Field             | Size           | Description                                         |
+//! |-------------------|----------------|-----------------------------------------------------|
+//! | magic             | 8              | `SP1PROOF`                                          |
+//! | format version    | 2              | Little-endian, [PROOF_FILE_VERSION]                 |
+//! | proof kind        | 1              | Core, Compressed, Plonk or Groth16                  |
+//! | compression       | 1              | None or zstd                                        |
+//! | circuit version   | 2 + len        | Little-endian length, then the UTF-8 version string |
+//! | vkey hash         | 1 + 32         | Presence flag, then the `bytes32` of the program    |
+//! | payload length    | 8              | Little-endian                                       |
+//! | payload checksum  | 32             | SHA-256 of the payload                              |
+//! | payload           | payload length | The (compressed) bincode encoding of the proof      |
+//!
+//! Files written before this format, which only contain the bincode encoding, are still loaded.
+//! Loading a proof file checks its header against the proof: the circuit version must be the one
+//! of this SDK, and the kind and vkey hash must be those of the decoded proof.
+
+use std::{borrow::Borrow, io::Read, str::FromStr};
+
+use num_bigint::BigUint;
+use p3_baby_bear::BabyBear;
+use p3_field::PrimeField;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sp1_core_machine::SP1_CIRCUIT_VERSION;
+use sp1_prover::utils::babybears_to_bn254;
+use sp1_recursion_core::air::{RecursionPublicValues, RECURSIVE_PROOF_NUM_PV_ELTS};
+use thiserror::Error;
+
+use crate::{SP1Proof, SP1ProofKind, SP1ProofWithPublicValues};
+
+/// The magic bytes every proof file starts with.
+pub const PROOF_FILE_MAGIC: [u8; 8] = *b"SP1PROOF";
+
+/// The version of the proof file format written by this SDK.
+pub const PROOF_FILE_VERSION: u16 = 1;
+
+/// The zstd level used to compress proofs.
+const ZSTD_LEVEL: i32 = 3;
+
+/// The maximum size of a decompressed payload, so that a small file can't exhaust the memory.
+pub const MAX_DECOMPRESSED_SIZE: u64 = 1 << 30;
+
+/// How the payload of a proof file is compressed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProofCompression {
+    #[default]
+    None,
+    Zstd,
+}
+
+/// The header of a proof file, which can be read without decoding the proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofFileHeader {
+    pub format_version: u16,
+    /// The SP1 circuit version the proof was generated with.
+    pub circuit_version: String,
+    pub kind: SP1ProofKind,
+    pub compression: ProofCompression,
+    /// The `bytes32` vkey hash of the program, if the proof commits to it. Core proofs don't.
+    pub vkey_hash: Option<[u8; 32]>,
+}
+
+#[derive(Error, Debug)]
+pub enum ProofFileError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Not an SP1 proof file")]
+    InvalidMagic,
+    #[error("Unsupported proof file format version {found}, this SDK reads version {supported}")]
+    UnsupportedFormatVersion { found: u16, supported: u16 },
+    #[error("Invalid proof file header: {0}")]
+    InvalidHeader(&'static str),
+    #[error("Unknown proof kind {0} in the proof file header")]
+    UnknownProofKind(u8),
+    #[error("Unknown compression {0} in the proof file header")]
+    UnknownCompression(u8),
+    #[error("The proof file is corrupted, its checksum doesn't match")]
+    ChecksumMismatch,
+    #[error(
+        "The proof was generated with SP1 circuit version {found}, but this SDK uses {expected}"
+    )]
+    CircuitVersionMismatch { found: String, expected: String },
+    #[error("The header describes a {header:?} proof, but the file contains a {proof:?} proof")]
+    KindMismatch { header: SP1ProofKind, proof: SP1ProofKind },
+    #[error("The vkey hash in the header doesn't match the one of the proof")]
+    VkeyHashMismatch,
+    #[error("The decompressed proof is larger than {0} bytes")]
+    PayloadTooLarge(u64),
+    #[error("Failed to decode the proof: {0}")]
+    Decode(bincode::Error),
+    #[error("Not an SP1 proof file, and failed to decode it as a legacy proof: {0}")]
+    Legacy(bincode::Error),
+    #[error("Only Plonk and Groth16 proofs can be exported to JSON, got a {0:?} proof")]
+    UnsupportedJsonExport(SP1ProofKind),
+    #[error("Failed to encode the proof as JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl ProofFileHeader {
+    /// Reads the header at the start of a proof file.
+    pub fn read(bytes: &[u8]) -> Result<Self, ProofFileError> {
+        Self::decode(&mut Cursor(bytes))
+    }
+
+    fn new(proof: &SP1ProofWithPublicValues, compression: ProofCompression) -> Self {
+        Self {
+            format_version: PROOF_FILE_VERSION,
+            circuit_version: proof.sp1_version.clone(),
+            kind: SP1ProofKind::from(&proof.proof),
+            compression,
+            vkey_hash: vkey_hash(&proof.proof),
+        }
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&PROOF_FILE_MAGIC);
+        out.extend_from_slice(&self.format_version.to_le_bytes());
+        out.push(match self.kind {
+            SP1ProofKind::Core => 0,
+            SP1ProofKind::Compressed => 1,
+            SP1ProofKind::Plonk => 2,
+            SP1ProofKind::Groth16 => 3,
+        });
+        out.push(match self.compression {
+            ProofCompression::None => 0,
+            ProofCompression::Zstd => 1,
+        });
+        out.extend_from_slice(&(self.circuit_version.len() as u16).to_le_bytes());
+        out.extend_from_slice(self.circuit_version.as_bytes());
+        match self.vkey_hash {
+            Some(vkey_hash) => {
+                out.push(1);
+                out.extend_from_slice(&vkey_hash);
+            }
+            None => {
+                out.push(0);
+                out.extend_from_slice(&[0; 32]);
+            }
+        }
+    }
+
+    fn decode(cursor: &mut Cursor) -> Result<Self, ProofFileError> {
+        if cursor.0.len() < PROOF_FILE_MAGIC.len() || cursor.take(8)? != PROOF_FILE_MAGIC {
+            return Err(ProofFileError::InvalidMagic);
+        }
+        let format_version = u16::from_le_bytes(cursor.array()?);
+        if format_version != PROOF_FILE_VERSION {
+            return Err(ProofFileError::UnsupportedFormatVersion {
+                found: format_version,
+                supported: PROOF_FILE_VERSION,
+            });
+        }
+        let kind = match cursor.array::<1>()?[0] {
+            0 => SP1ProofKind::Core,
+            1 => SP1ProofKind::Compressed,
+            2 => SP1ProofKind::Plonk,
+            3 => SP1ProofKind::Groth16,
+            kind => return Err(ProofFileError::UnknownProofKind(kind)),
+        };
+        let compression = match cursor.array::<1>()?[0] {
+            0 => ProofCompression::None,
+            1 => ProofCompression::Zstd,
+            compression => return Err(ProofFileError::UnknownCompression(compression)),
+        };
+        let len = u16::from_le_bytes(cursor.array()?) as usize;
+        let circuit_version = String::from_utf8(cursor.take(len)?.to_vec())
+            .map_err(|_| ProofFileError::InvalidHeader("the circuit version is not UTF-8"))?;
+        let has_vkey_hash = cursor.array::<1>()?[0] == 1;
+        let vkey_hash = cursor.array::<32>()?;
+        Ok(Self {
+            format_version,
+            circuit_version,
+            kind,
+            compression,
+            vkey_hash: has_vkey_hash.then_some(vkey_hash),
+        })
+    }
+}
+
+/// Encodes a proof in the proof file format.
+pub(crate) fn encode_proof_file(
+    proof: &SP1ProofWithPublicValues,
+    compression: ProofCompression,
+) -> Result<Vec<u8>, ProofFileError> {
+    let encoded = bincode::serialize(proof).expect("failed to serialize the proof");
+    let payload = match compression {
+        ProofCompression::None => encoded,
+        ProofCompression::Zstd => zstd::encode_all(encoded.as_slice(), ZSTD_LEVEL)?,
+    };
+
+    let mut out = Vec::with_capacity(payload.len() + 128);
+    ProofFileHeader::new(proof, compression).encode(&mut out);
+    out.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    out.extend_from_slice(&Sha256::digest(&payload));
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+/// Decodes a proof file, or the plain bincode encoding of a proof written by older SDKs.
+pub(crate) fn decode_proof_file(bytes: &[u8]) -> Result<SP1ProofWithPublicValues, ProofFileError> {
+    if !bytes.starts_with(&PROOF_FILE_MAGIC) {
+        return bincode::deserialize(bytes).map_err(ProofFileError::Legacy);
+    }
+
+    let mut cursor = Cursor(bytes);
+    let header = ProofFileHeader::decode(&mut cursor)?;
+    let len = u64::from_le_bytes(cursor.array()?) as usize;
+    let checksum = cursor.array::<32>()?;
+    let payload = cursor.take(len)?;
+    if !cursor.0.is_empty() {
+        return Err(ProofFileError::InvalidHeader("trailing bytes after the payload"));
+    }
+    if Sha256::digest(payload).as_slice() != checksum {
+        return Err(ProofFileError::ChecksumMismatch);
+    }
+
+    // The encoding of the proof types changes between SP1 versions.
+    if header.circuit_version != SP1_CIRCUIT_VERSION {
+        return Err(ProofFileError::CircuitVersionMismatch {
+            found: header.circuit_version,
+            expected: SP1_CIRCUIT_VERSION.to_string(),
+        });
+    }
+
+    let encoded = match header.compression {
+        ProofCompression::None => payload.to_vec(),
+        ProofCompression::Zstd => decompress(payload, MAX_DECOMPRESSED_SIZE)?,
+    };
+    let proof: SP1ProofWithPublicValues =
+        bincode::deserialize(&encoded).map_err(ProofFileError::Decode)?;
+
+    let kind = SP1ProofKind::from(&proof.proof);
+    if kind != header.kind {
+        return Err(ProofFileError::KindMismatch { header: header.kind, proof: kind });
+    }
+    if vkey_hash(&proof.proof) != header.vkey_hash {
+        return Err(ProofFileError::VkeyHashMismatch);
+    }
+    Ok(proof)
+}
+
+/// Decompresses a zstd payload, failing if it is larger than `limit` bytes.
+fn decompress(payload: &[u8], limit: u64) -> Result<Vec<u8>, ProofFileError> {
+    let mut decoded = Vec::new();
+    zstd::Decoder::new(payload)?.take(limit + 1).read_to_end(&mut decoded)?;
+    if decoded.len() as u64 > limit {
+        return Err(ProofFileError::PayloadTooLarge(limit));
+    }
+    Ok(decoded)
+}
+
+/// The JSON export of a Plonk or Groth16 proof, for verifiers outside of Rust.
+#[derive(Serialize)]
+struct ProofJson<'a> {
+    sp1_version: &'a str,
+    kind: &'static str,
+    /// The `bytes32` vkey hash of the program.
+    vkey_hash: String,
+    /// The hex encoded public values of the program.
+    public_values: String,
+    /// The hex encoded proof, as accepted by the onchain verifiers.
+    proof: String,
+    /// The public inputs of the circuit, as decimal strings.
+    public_inputs: &'a [String; 2],
+    /// The hash of the Plonk or Groth16 verifying key the proof was generated with.
+    verifier_vkey_hash: String,
+}
+
+/// Exports a Plonk or Groth16 proof as JSON.
+pub(crate) fn proof_json(proof: &SP1ProofWithPublicValues) -> Result<String, ProofFileError> {
+    let (kind, public_inputs, verifier_vkey_hash) = match &proof.proof {
+        SP1Proof::Plonk(plonk) => ("plonk", &plonk.public_inputs, plonk.plonk_vkey_hash),
+        SP1Proof::Groth16(groth16) => {
+            ("groth16", &groth16.public_inputs, groth16.groth16_vkey_hash)
+        }
+        proof => return Err(ProofFileError::UnsupportedJsonExport(SP1ProofKind::from(proof))),
+    };
+    let json = ProofJson {
+        sp1_version: &proof.sp1_version,
+        kind,
+        vkey_hash: format!("0x{}", hex::encode(vkey_hash(&proof.proof).unwrap_or_default())),
+        public_values: format!("0x{}", hex::encode(proof.public_values.as_slice())),
+        proof: format!("0x{}", hex::encode(proof.bytes())),
+        public_inputs,
+        verifier_vkey_hash: format!("0x{}", hex::encode(verifier_vkey_hash)),
+    };
+    Ok(serde_json::to_string_pretty(&json)?)
+}
+
+/// The `bytes32` vkey hash of the program a proof commits to.
+pub(crate) fn vkey_hash(proof: &SP1Proof) -> Option<[u8; 32]> {
+    let digest = match proof {
+        SP1Proof::Core(_) => return None,
+        SP1Proof::Compressed(proof) => {
+            let public_values = &proof.proof.public_values;
+            if public_values.len() < RECURSIVE_PROOF_NUM_PV_ELTS {
+                return None;
+            }
+            let public_values: &RecursionPublicValues<BabyBear> =
+                public_values[..RECURSIVE_PROOF_NUM_PV_ELTS].borrow();
+            babybears_to_bn254(&public_values.sp1_vk_digest).as_canonical_biguint()
+        }
+        SP1Proof::Plonk(proof) => BigUint::from_str(&proof.public_inputs[0]).ok()?,
+        SP1Proof::Groth16(proof) => BigUint::from_str(&proof.public_inputs[0]).ok()?,
+    };
+    let digest = digest.to_bytes_be();
+    let mut bytes = [0; 32];
+    bytes.get_mut(32usize.checked_sub(digest.len())?..)?.copy_from_slice(&digest);
+    Some(bytes)
+}
+
+/// Reads the fields of a proof file, failing on truncated files.
+struct Cursor<'a>(&'a [u8]);
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ProofFileError> {
+        if self.0.len() < len {
+            return Err(ProofFileError::InvalidHeader("the proof file is truncated"));
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ProofFileError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROTH16_PROOF: &[u8] =
+        include_bytes!("../../verifier/test_binaries/fibonacci-groth16.bin");
+
+    #[test]
+    fn test_proof_file_roundtrip() {
+        // The verifier's test binaries predate the proof file format.
+        let proof = decode_proof_file(GROTH16_PROOF).unwrap();
+
+        for compression in [ProofCompression::None, ProofCompression::Zstd] {
+            let bytes = encode_proof_file(&proof, compression).unwrap();
+            let header = ProofFileHeader::read(&bytes).unwrap();
+            assert_eq!(header.kind, SP1ProofKind::Groth16);
+            assert_eq!(header.compression, compression);
+            assert_eq!(header.circuit_version, proof.sp1_version);
+            assert_eq!(
+                hex::encode(header.vkey_hash.unwrap()),
+                "00e60860c07bfc6e4c480286c0ddbb879674eb47f84b4ef041cf858b17aa0ed1"
+            );
+
+            let decoded = decode_proof_file(&bytes).unwrap();
+            assert_eq!(decoded.bytes(), proof.bytes());
+            assert_eq!(decoded.public_values.as_slice(), proof.public_values.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_proof_file_errors() {
+        let proof = decode_proof_file(GROTH16_PROOF).unwrap();
+        let bytes = encode_proof_file(&proof, ProofCompression::None).unwrap();
+
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(matches!(decode_proof_file(&corrupted), Err(ProofFileError::ChecksumMismatch)));
+
+        let mut future = bytes.clone();
+        future[8] = 2;
+        assert!(matches!(
+            decode_proof_file(&future),
+            Err(ProofFileError::UnsupportedFormatVersion { found: 2, supported: 1 })
+        ));
+
+        assert!(matches!(
+            decode_proof_file(&bytes[..bytes.len() - 1]),
+            Err(ProofFileError::InvalidHeader(_))
+        ));
+        assert!(matches!(decode_proof_file(b"not a proof"), Err(ProofFileError::Legacy(_))));
+
+        // The header is checked against the proof, which the checksum doesn't cover.
+        let mut tampered = bytes.clone();
+        let vkey_hash_offset = 8 + 2 + 1 + 1 + 2 + proof.sp1_version.len() + 1;
+        tampered[vkey_hash_offset] ^= 1;
+        assert!(matches!(decode_proof_file(&tampered), Err(ProofFileError::VkeyHashMismatch)));
+
+        let mut old = proof.clone();
+        old.sp1_version = "v2.0.0".to_string();
+        let old = encode_proof_file(&old, ProofCompression::Zstd).unwrap();
+        assert!(matches!(
+            decode_proof_file(&old),
+            Err(ProofFileError::CircuitVersionMismatch { found, .. }) if found == "v2.0.0"
+        ));
+    }
+
+    #[test]
+    fn test_decompress_limit() {
+        let payload = zstd::encode_all([0u8; 1024].as_slice(), ZSTD_LEVEL).unwrap();
+        assert_eq!(decompress(&payload, 1024).unwrap().len(), 1024);
+        assert!(matches!(decompress(&payload, 1023), Err(ProofFileError::PayloadTooLarge(1023))));
+    }
+
+    #[test]
+    fn test_proof_json() {
+        let proof = decode_proof_file(GROTH16_PROOF).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&proof_json(&proof).unwrap()).unwrap();
+        assert_eq!(json["kind"], "groth16");
+        assert_eq!(json["proof"], format!("0x{}", hex::encode(proof.bytes())));
+        assert_eq!(
+            json["vkey_hash"],
+            "0x00e60860c07bfc6e4c480286c0ddbb879674eb47f84b4ef041cf858b17aa0ed1"
+        );
+    }
+}
@@ -50,6 +50,9 @@ impl Prover<DefaultProverComponents> for CpuProver {
         context: SP1Context<'a>,
         kind: SP1ProofKind,
     ) -> Result<SP1ProofWithPublicValues> {
+        // Keep the monitor to report and cancel the stages after the core proof.
+        let monitor = context.monitor.clone();
+
         // Generate the core proof.
         let proof: sp1_prover::SP1ProofWithMetadata<sp1_prover::SP1CoreProofData> =
             self.prover.prove_core(pk, &stdin, opts.sp1_prover_opts, context)?;
@@ -67,8 +70,13 @@ impl Prover<DefaultProverComponents> for CpuProver {
         let public_values = proof.public_values.clone();
 
         // Generate the compressed proof.
-        let reduce_proof =
-            self.prover.compress(&pk.vk, proof, deferred_proofs, opts.sp1_prover_opts)?;
+        let reduce_proof = self.prover.compress_with_monitor(
+            &pk.vk,
+            proof,
+            deferred_proofs,
+            opts.sp1_prover_opts,
+            &monitor,
+        )?;
         if kind == SP1ProofKind::Compressed {
             return Ok(SP1ProofWithPublicValues {
                 proof: SP1Proof::Compressed(Box::new(reduce_proof)),
@@ -79,10 +87,12 @@ impl Prover<DefaultProverComponents> for CpuProver {
         }
 
         // Generate the shrink proof.
-        let compress_proof = self.prover.shrink(reduce_proof, opts.sp1_prover_opts)?;
+        let compress_proof =
+            self.prover.shrink_with_monitor(reduce_proof, opts.sp1_prover_opts, &monitor)?;
 
         // Genenerate the wrap proof.
-        let outer_proof = self.prover.wrap_bn254(compress_proof, opts.sp1_prover_opts)?;
+        let outer_proof =
+            self.prover.wrap_bn254_with_monitor(compress_proof, opts.sp1_prover_opts, &monitor)?;
 
         if kind == SP1ProofKind::Plonk {
             let plonk_bn254_artifacts = if sp1_prover::build::sp1_dev_mode() {
@@ -91,9 +101,13 @@ impl Prover<DefaultProverComponents> for CpuProver {
                     &outer_proof.proof,
                 )
             } else {
-                try_install_circuit_artifacts("plonk")
+                try_install_circuit_artifacts("plonk")?
             };
-            let proof = self.prover.wrap_plonk_bn254(outer_proof, &plonk_bn254_artifacts);
+            let proof = self.prover.wrap_plonk_bn254_with_monitor(
+                outer_proof,
+                &plonk_bn254_artifacts,
+                &monitor,
+            )?;
 
             return Ok(SP1ProofWithPublicValues {
                 proof: SP1Proof::Plonk(proof),
@@ -108,10 +122,14 @@ impl Prover<DefaultProverComponents> for CpuProver {
                     &outer_proof.proof,
                 )
             } else {
-                try_install_circuit_artifacts("groth16")
+                try_install_circuit_artifacts("groth16")?
             };
 
-            let proof = self.prover.wrap_groth16_bn254(outer_proof, &groth16_bn254_artifacts);
+            let proof = self.prover.wrap_groth16_bn254_with_monitor(
+                outer_proof,
+                &groth16_bn254_artifacts,
+                &monitor,
+            )?;
             return Ok(SP1ProofWithPublicValues {
                 proof: SP1Proof::Groth16(proof),
                 stdin,
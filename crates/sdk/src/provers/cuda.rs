@@ -1,7 +1,8 @@
 use anyhow::Result;
 use sp1_core_machine::io::SP1Stdin;
 use sp1_cuda::SP1CudaProver;
-use sp1_prover::{components::DefaultProverComponents, SP1Prover};
+use sp1_prover::{components::DefaultProverComponents, SP1Prover, SP1RecursionProverError};
+use sp1_stark::{ProverMonitor, ProvingStage};
 
 use super::ProverType;
 use crate::install::try_install_circuit_artifacts;
@@ -42,13 +43,15 @@ impl Prover<DefaultProverComponents> for CudaProver {
         pk: &SP1ProvingKey,
         stdin: SP1Stdin,
         _opts: ProofOpts,
-        _context: SP1Context<'a>,
+        context: SP1Context<'a>,
         kind: SP1ProofKind,
     ) -> Result<SP1ProofWithPublicValues> {
-        tracing::warn!("opts and context are ignored for the cuda prover");
+        tracing::warn!("opts and context, except for its monitor, are ignored for the cuda prover");
+        let monitor = context.monitor;
 
         // Generate the core proof.
-        let proof = self.cuda_prover.prove_core(pk, &stdin)?;
+        let proof =
+            remote_stage(&monitor, ProvingStage::Core, || self.cuda_prover.prove_core(pk, &stdin))?;
         if kind == SP1ProofKind::Core {
             return Ok(SP1ProofWithPublicValues {
                 proof: SP1Proof::Core(proof.proof.0),
@@ -63,7 +66,9 @@ impl Prover<DefaultProverComponents> for CudaProver {
         let public_values = proof.public_values.clone();
 
         // Generate the compressed proof.
-        let reduce_proof = self.cuda_prover.compress(&pk.vk, proof, deferred_proofs)?;
+        let reduce_proof = remote_stage(&monitor, ProvingStage::Compress, || {
+            self.cuda_prover.compress(&pk.vk, proof, deferred_proofs)
+        })?;
         if kind == SP1ProofKind::Compressed {
             return Ok(SP1ProofWithPublicValues {
                 proof: SP1Proof::Compressed(Box::new(reduce_proof)),
@@ -74,10 +79,13 @@ impl Prover<DefaultProverComponents> for CudaProver {
         }
 
         // Generate the shrink proof.
-        let compress_proof = self.cuda_prover.shrink(reduce_proof)?;
+        let compress_proof =
+            remote_stage(&monitor, ProvingStage::Shrink, || self.cuda_prover.shrink(reduce_proof))?;
 
         // Genenerate the wrap proof.
-        let outer_proof = self.cuda_prover.wrap_bn254(compress_proof)?;
+        let outer_proof = remote_stage(&monitor, ProvingStage::Wrap, || {
+            self.cuda_prover.wrap_bn254(compress_proof)
+        })?;
 
         if kind == SP1ProofKind::Plonk {
             let plonk_bn254_artifacts = if sp1_prover::build::sp1_dev_mode() {
@@ -86,9 +94,13 @@ impl Prover<DefaultProverComponents> for CudaProver {
                     &outer_proof.proof,
                 )
             } else {
-                try_install_circuit_artifacts("plonk")
+                try_install_circuit_artifacts("plonk")?
             };
-            let proof = self.prover.wrap_plonk_bn254(outer_proof, &plonk_bn254_artifacts);
+            let proof = self.prover.wrap_plonk_bn254_with_monitor(
+                outer_proof,
+                &plonk_bn254_artifacts,
+                &monitor,
+            )?;
             return Ok(SP1ProofWithPublicValues {
                 proof: SP1Proof::Plonk(proof),
                 stdin,
@@ -102,10 +114,14 @@ impl Prover<DefaultProverComponents> for CudaProver {
                     &outer_proof.proof,
                 )
             } else {
-                try_install_circuit_artifacts("groth16")
+                try_install_circuit_artifacts("groth16")?
             };
 
-            let proof = self.prover.wrap_groth16_bn254(outer_proof, &groth16_bn254_artifacts);
+            let proof = self.prover.wrap_groth16_bn254_with_monitor(
+                outer_proof,
+                &groth16_bn254_artifacts,
+                &monitor,
+            )?;
             return Ok(SP1ProofWithPublicValues {
                 proof: SP1Proof::Groth16(proof),
                 stdin,
@@ -118,6 +134,22 @@ impl Prover<DefaultProverComponents> for CudaProver {
     }
 }
 
+/// Runs a stage on the CUDA prover, which proves it as a whole: the monitor is only checked for
+/// cancellation before the stage starts, and only the start and end of the stage are reported.
+fn remote_stage<T, E: Into<anyhow::Error>>(
+    monitor: &ProverMonitor,
+    stage: ProvingStage,
+    prove: impl FnOnce() -> Result<T, E>,
+) -> Result<T> {
+    if monitor.is_cancelled() {
+        return Err(SP1RecursionProverError::Cancelled.into());
+    }
+    let progress = monitor.stage(stage, Some(1));
+    let result = prove().map_err(Into::into)?;
+    progress.advance(1);
+    Ok(result)
+}
+
 impl Default for CudaProver {
     fn default() -> Self {
         Self::new(SP1Prover::new())
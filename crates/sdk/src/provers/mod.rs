@@ -144,32 +144,28 @@ pub trait Prover<C: SP1ProverComponents>: Send + Sync {
                     .verify_compressed(proof, vkey)
                     .map_err(SP1VerificationError::Recursion)
             }
-            SP1Proof::Plonk(proof) => self
-                .sp1_prover()
-                .verify_plonk_bn254(
-                    proof,
-                    vkey,
-                    &bundle.public_values,
-                    &if sp1_prover::build::sp1_dev_mode() {
-                        sp1_prover::build::plonk_bn254_artifacts_dev_dir()
-                    } else {
-                        try_install_circuit_artifacts("plonk")
-                    },
-                )
-                .map_err(SP1VerificationError::Plonk),
-            SP1Proof::Groth16(proof) => self
-                .sp1_prover()
-                .verify_groth16_bn254(
-                    proof,
-                    vkey,
-                    &bundle.public_values,
-                    &if sp1_prover::build::sp1_dev_mode() {
-                        sp1_prover::build::groth16_bn254_artifacts_dev_dir()
-                    } else {
-                        try_install_circuit_artifacts("groth16")
-                    },
-                )
-                .map_err(SP1VerificationError::Groth16),
+            SP1Proof::Plonk(proof) => {
+                let artifacts_dir = if sp1_prover::build::sp1_dev_mode() {
+                    sp1_prover::build::plonk_bn254_artifacts_dev_dir()
+                } else {
+                    try_install_circuit_artifacts("plonk")
+                        .map_err(|e| SP1VerificationError::Plonk(e.into()))?
+                };
+                self.sp1_prover()
+                    .verify_plonk_bn254(proof, vkey, &bundle.public_values, &artifacts_dir)
+                    .map_err(SP1VerificationError::Plonk)
+            }
+            SP1Proof::Groth16(proof) => {
+                let artifacts_dir = if sp1_prover::build::sp1_dev_mode() {
+                    sp1_prover::build::groth16_bn254_artifacts_dev_dir()
+                } else {
+                    try_install_circuit_artifacts("groth16")
+                        .map_err(|e| SP1VerificationError::Groth16(e.into()))?
+                };
+                self.sp1_prover()
+                    .verify_groth16_bn254(proof, vkey, &bundle.public_values, &artifacts_dir)
+                    .map_err(SP1VerificationError::Groth16)
+            }
         }
     }
 }
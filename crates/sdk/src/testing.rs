@@ -0,0 +1,292 @@
+//! # Guest Program Tests
+//!
+//! A harness for testing SP1 programs from Rust tests, usually through the [sp1_test] attribute.
+//! A test declares the program's inputs, the values it is expected to commit and a cycle budget.
+//! It executes the program by default, or proves it with the [MockProver](crate::MockProver) or
+//! the [CpuProver](crate::CpuProver), and compares the cycle count against a stored baseline.
+//!
+//! ```ignore
+//! use sp1_sdk::testing::{sp1_test, GuestTest};
+//!
+//! #[sp1_test(elf = "FIBONACCI_ELF", max_cycles = 100_000)]
+//! fn fibonacci(test: &mut GuestTest) {
+//!     test.input(&10u32);
+//!     test.expect_output(&10u32).expect_output(&55u32).expect_output(&89u32);
+//! }
+//! ```
+//!
+//! The mode can be overridden for every test with the `SP1_TEST_MODE` environment variable, set
+//! to `execute`, `mock` or `prove`. Setting `SP1_UPDATE_BASELINE=true` records the cycle counts of
+//! the tests that run as the new baseline.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{ProverClient, SP1PublicValues, SP1Stdin, SP1VerificationError};
+
+pub use sp1_derive::sp1_test;
+
+/// The file the [sp1_test] attribute stores the cycle baseline in, in the crate's directory.
+pub const BASELINE_FILE: &str = "sp1-test-baseline.json";
+
+/// The default increase of the cycle count over the baseline that is reported as a regression.
+pub const DEFAULT_REGRESSION_TOLERANCE: f64 = 0.05;
+
+/// Serializes the updates of the baseline files by the tests running in parallel.
+static BASELINE_LOCK: Mutex<()> = Mutex::new(());
+
+/// How a guest test runs the program.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TestMode {
+    /// Only execute the program.
+    #[default]
+    Execute,
+    /// Execute the program and generate a mock proof.
+    Mock,
+    /// Generate and verify a core proof with the CPU prover.
+    Prove,
+}
+
+impl FromStr for TestMode {
+    type Err = GuestTestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "execute" => Ok(TestMode::Execute),
+            "mock" => Ok(TestMode::Mock),
+            "prove" => Ok(TestMode::Prove),
+            _ => Err(GuestTestError::InvalidMode(s.to_string())),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum GuestTestError {
+    #[error("Execution failed: {0}")]
+    Execution(anyhow::Error),
+    #[error("Proving failed: {0}")]
+    Proving(anyhow::Error),
+    #[error("Verification failed: {0}")]
+    Verification(#[from] SP1VerificationError),
+    #[error("Expected the public values 0x{expected}, got 0x{actual}")]
+    OutputMismatch { expected: String, actual: String },
+    #[error("Used {cycles} cycles, over the budget of {budget}")]
+    CycleBudgetExceeded { cycles: u64, budget: u64 },
+    #[error("Used {cycles} cycles, {increase:.1}% more than the baseline of {baseline}")]
+    CycleRegression { cycles: u64, baseline: u64, increase: f64 },
+    #[error("Failed to access the cycle baseline {path}: {error}")]
+    Baseline { path: PathBuf, error: String },
+    #[error("Invalid test mode {0}, expected execute, mock or prove")]
+    InvalidMode(String),
+}
+
+/// The outcome of a successful guest test.
+#[derive(Debug, Clone)]
+pub struct TestReport {
+    /// The number of cycles the program ran for.
+    pub cycles: u64,
+    /// The public values the program committed.
+    pub public_values: SP1PublicValues,
+    /// The cycle count of the test in the baseline, if it has one.
+    pub baseline: Option<u64>,
+}
+
+/// A test of a program, with its inputs and expectations.
+pub struct GuestTest<'a> {
+    name: String,
+    elf: &'a [u8],
+    stdin: SP1Stdin,
+    expected_output: Option<Vec<u8>>,
+    max_cycles: Option<u64>,
+    mode: TestMode,
+    baseline: Option<PathBuf>,
+    regression_tolerance: f64,
+}
+
+impl<'a> GuestTest<'a> {
+    /// Create a test of the program with the given ELF. The name identifies the test in the
+    /// baseline.
+    pub fn new(name: impl Into<String>, elf: &'a [u8]) -> Self {
+        Self {
+            name: name.into(),
+            elf,
+            stdin: SP1Stdin::new(),
+            expected_output: None,
+            max_cycles: None,
+            mode: TestMode::default(),
+            baseline: None,
+            regression_tolerance: DEFAULT_REGRESSION_TOLERANCE,
+        }
+    }
+
+    /// Write a value to the program's stdin, to be read with `sp1_zkvm::io::read`.
+    pub fn input<T: Serialize>(&mut self, value: &T) -> &mut Self {
+        self.stdin.write(value);
+        self
+    }
+
+    /// Write bytes to the program's stdin, to be read with `sp1_zkvm::io::read_vec`.
+    pub fn input_slice(&mut self, bytes: &[u8]) -> &mut Self {
+        self.stdin.write_slice(bytes);
+        self
+    }
+
+    /// Expect the program to commit the given value next, with `sp1_zkvm::io::commit`.
+    pub fn expect_output<T: Serialize>(&mut self, value: &T) -> &mut Self {
+        let bytes = bincode::serialize(value).expect("failed to serialize the expected output");
+        self.expected_output.get_or_insert_with(Vec::new).extend(bytes);
+        self
+    }
+
+    /// Expect the program to commit the given bytes next, with `sp1_zkvm::io::commit_slice`.
+    pub fn expect_output_slice(&mut self, bytes: &[u8]) -> &mut Self {
+        self.expected_output.get_or_insert_with(Vec::new).extend_from_slice(bytes);
+        self
+    }
+
+    /// Fail the test if the program runs for more than `max_cycles` cycles.
+    pub fn max_cycles(&mut self, max_cycles: u64) -> &mut Self {
+        self.max_cycles = Some(max_cycles);
+        self
+    }
+
+    /// Set how the program is run, unless overridden by the `SP1_TEST_MODE` environment variable.
+    pub fn mode(&mut self, mode: TestMode) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Compare the cycle count against the baseline stored in the given JSON file.
+    pub fn baseline(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.baseline = Some(path.into());
+        self
+    }
+
+    /// Set the relative increase over the baseline that is reported as a regression.
+    pub fn regression_tolerance(&mut self, tolerance: f64) -> &mut Self {
+        self.regression_tolerance = tolerance;
+        self
+    }
+
+    /// Run the test.
+    pub fn run(&self) -> Result<TestReport, GuestTestError> {
+        let mode = match std::env::var("SP1_TEST_MODE") {
+            Ok(mode) => mode.parse()?,
+            Err(_) => self.mode,
+        };
+
+        let client = match mode {
+            TestMode::Mock => ProverClient::mock(),
+            TestMode::Execute | TestMode::Prove => ProverClient::local(),
+        };
+        let (public_values, report) = client
+            .execute(self.elf, self.stdin.clone())
+            .run()
+            .map_err(GuestTestError::Execution)?;
+        let cycles = report.total_instruction_count();
+
+        if let Some(expected) = &self.expected_output {
+            if public_values.as_slice() != expected.as_slice() {
+                return Err(GuestTestError::OutputMismatch {
+                    expected: hex::encode(expected),
+                    actual: hex::encode(public_values.as_slice()),
+                });
+            }
+        }
+        if let Some(budget) = self.max_cycles {
+            if cycles > budget {
+                return Err(GuestTestError::CycleBudgetExceeded { cycles, budget });
+            }
+        }
+
+        if mode != TestMode::Execute {
+            let (pk, vk) = client.setup(self.elf);
+            let proof =
+                client.prove(&pk, self.stdin.clone()).run().map_err(GuestTestError::Proving)?;
+            client.verify(&proof, &vk)?;
+        }
+
+        let baseline = match &self.baseline {
+            Some(path) => self.check_baseline(path, cycles)?,
+            None => None,
+        };
+        match baseline {
+            Some(baseline) => println!("{}: {cycles} cycles (baseline {baseline})", self.name),
+            None => println!("{}: {cycles} cycles", self.name),
+        }
+
+        Ok(TestReport { cycles, public_values, baseline })
+    }
+
+    /// Compare the cycle count against the baseline, or record it if `SP1_UPDATE_BASELINE` is set.
+    fn check_baseline(&self, path: &Path, cycles: u64) -> Result<Option<u64>, GuestTestError> {
+        let baseline_error = |error: String| GuestTestError::Baseline { path: path.into(), error };
+        let _guard = BASELINE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut entries: BTreeMap<String, u64> = match fs::read(path) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).map_err(|e| baseline_error(e.to_string()))?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(baseline_error(e.to_string())),
+        };
+        let baseline = entries.get(&self.name).copied();
+
+        let update = std::env::var("SP1_UPDATE_BASELINE")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        if update {
+            entries.insert(self.name.clone(), cycles);
+            let json = serde_json::to_string_pretty(&entries).unwrap();
+            fs::write(path, json + "\n").map_err(|e| baseline_error(e.to_string()))?;
+            return Ok(baseline);
+        }
+
+        if let Some(baseline) = baseline {
+            let increase = (cycles as f64 / baseline as f64 - 1.0) * 100.0;
+            if cycles as f64 > baseline as f64 * (1.0 + self.regression_tolerance) {
+                return Err(GuestTestError::CycleRegression { cycles, baseline, increase });
+            }
+        }
+        Ok(baseline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIBONACCI_ELF: &[u8] =
+        include_bytes!("../../../examples/fibonacci/program/elf/riscv32im-succinct-zkvm-elf");
+
+    #[sp1_test(elf = "FIBONACCI_ELF", max_cycles = 100_000)]
+    fn fibonacci(test: &mut GuestTest) {
+        test.input(&10u32);
+        test.expect_output(&10u32).expect_output(&55u32).expect_output(&89u32);
+    }
+
+    #[test]
+    fn test_guest_test_failures() {
+        let mut test = GuestTest::new("fibonacci", FIBONACCI_ELF);
+        test.input(&10u32).expect_output(&10u32).expect_output(&54u32);
+        assert!(matches!(test.run(), Err(GuestTestError::OutputMismatch { .. })));
+
+        let mut test = GuestTest::new("fibonacci", FIBONACCI_ELF);
+        test.input(&10u32).max_cycles(10);
+        assert!(matches!(test.run(), Err(GuestTestError::CycleBudgetExceeded { .. })));
+
+        let baseline = tempfile::NamedTempFile::new().unwrap();
+        fs::write(baseline.path(), r#"{ "fibonacci": 100 }"#).unwrap();
+        let mut test = GuestTest::new("fibonacci", FIBONACCI_ELF);
+        test.input(&10u32).baseline(baseline.path());
+        assert!(matches!(test.run(), Err(GuestTestError::CycleRegression { baseline: 100, .. })));
+    }
+}
@@ -1,6 +1,6 @@
 #![allow(missing_docs)]
 
-use crate::{Com, StarkGenericConfig, ZeroCommitment};
+use crate::{Com, CommitmentHash, StarkGenericConfig, ZeroCommitment};
 use p3_baby_bear::{BabyBear, DiffusionMatrixBabyBear};
 use p3_challenger::DuplexChallenger;
 use p3_commit::ExtensionMmcs;
@@ -135,6 +135,8 @@ impl StarkGenericConfig for BabyBearPoseidon2Inner {
     type Challenge = InnerChallenge;
     type Challenger = InnerChallenger;
 
+    const COMMITMENT_HASH: CommitmentHash = CommitmentHash::BabyBearPoseidon2;
+
     fn pcs(&self) -> &Self::Pcs {
         &self.pcs
     }
@@ -164,7 +166,7 @@ pub mod baby_bear_poseidon2 {
     use serde::{Deserialize, Serialize};
     use sp1_primitives::RC_16_30;
 
-    use crate::{Com, StarkGenericConfig, ZeroCommitment, DIGEST_SIZE};
+    use crate::{Com, CommitmentHash, StarkGenericConfig, ZeroCommitment, DIGEST_SIZE};
 
     pub type Val = BabyBear;
     pub type Challenge = BinomialExtensionField<Val, 4>;
@@ -246,9 +248,25 @@ pub mod baby_bear_poseidon2 {
         FriConfig { log_blowup: 3, num_queries, proof_of_work_bits: 16, mmcs: challenge_mmcs }
     }
 
+    /// A FRI config with an explicit blowup, number of queries, and proof of work bits.
+    #[must_use]
+    pub fn custom_fri_config(
+        log_blowup: usize,
+        num_queries: usize,
+        proof_of_work_bits: usize,
+    ) -> FriConfig<ChallengeMmcs> {
+        let perm = my_perm();
+        let hash = MyHash::new(perm.clone());
+        let compress = MyCompress::new(perm.clone());
+        let challenge_mmcs = ChallengeMmcs::new(ValMmcs::new(hash, compress));
+        FriConfig { log_blowup, num_queries, proof_of_work_bits, mmcs: challenge_mmcs }
+    }
+
+    #[derive(Clone, Copy)]
     enum BabyBearPoseidon2Type {
         Default,
         Compressed,
+        Custom { log_blowup: usize, num_queries: usize, proof_of_work_bits: usize },
     }
 
     #[derive(Deserialize)]
@@ -295,6 +313,34 @@ pub mod baby_bear_poseidon2 {
             let pcs = Pcs::new(27, dft, val_mmcs, fri_config);
             Self { pcs, perm, config_type: BabyBearPoseidon2Type::Compressed }
         }
+
+        /// A config whose FRI parameters are chosen by the caller.
+        ///
+        /// The conjectured security of the resulting config is roughly
+        /// `log_blowup * num_queries + proof_of_work_bits` bits.
+        #[must_use]
+        pub fn with_fri_params(
+            log_blowup: usize,
+            num_queries: usize,
+            proof_of_work_bits: usize,
+        ) -> Self {
+            let perm = my_perm();
+            let hash = MyHash::new(perm.clone());
+            let compress = MyCompress::new(perm.clone());
+            let val_mmcs = ValMmcs::new(hash, compress);
+            let dft = Dft {};
+            let fri_config = custom_fri_config(log_blowup, num_queries, proof_of_work_bits);
+            let pcs = Pcs::new(27, dft, val_mmcs, fri_config);
+            Self {
+                pcs,
+                perm,
+                config_type: BabyBearPoseidon2Type::Custom {
+                    log_blowup,
+                    num_queries,
+                    proof_of_work_bits,
+                },
+            }
+        }
     }
 
     impl Clone for BabyBearPoseidon2 {
@@ -302,6 +348,9 @@ pub mod baby_bear_poseidon2 {
             match self.config_type {
                 BabyBearPoseidon2Type::Default => Self::new(),
                 BabyBearPoseidon2Type::Compressed => Self::compressed(),
+                BabyBearPoseidon2Type::Custom { log_blowup, num_queries, proof_of_work_bits } => {
+                    Self::with_fri_params(log_blowup, num_queries, proof_of_work_bits)
+                }
             }
         }
     }
@@ -335,6 +384,8 @@ pub mod baby_bear_poseidon2 {
         type Challenge = Challenge;
         type Challenger = Challenger;
 
+        const COMMITMENT_HASH: CommitmentHash = CommitmentHash::BabyBearPoseidon2;
+
         fn pcs(&self) -> &Self::Pcs {
             &self.pcs
         }
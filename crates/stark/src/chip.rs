@@ -43,6 +43,11 @@ impl<F: Field, A> Chip<F, A> {
         self.log_quotient_degree
     }
 
+    /// The underlying air of the chip.
+    pub const fn air(&self) -> &A {
+        &self.air
+    }
+
     /// Consumes the chip and returns the underlying air.
     pub fn into_inner(self) -> A {
         self.air
@@ -3,7 +3,7 @@
 use p3_challenger::{CanObserve, CanSample, FieldChallenger};
 use p3_commit::{Pcs, PolynomialSpace};
 use p3_field::{ExtensionField, Field, PrimeField};
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 pub type Domain<SC> = <<SC as StarkGenericConfig>::Pcs as Pcs<
     <SC as StarkGenericConfig>::Challenge,
@@ -68,6 +68,9 @@ pub trait StarkGenericConfig: 'static + Send + Sync + Serialize + DeserializeOwn
         + Serialize
         + DeserializeOwned;
 
+    /// The hash of the Merkle commitments of the PCS.
+    const COMMITMENT_HASH: CommitmentHash;
+
     /// Get the PCS used by this configuration.
     fn pcs(&self) -> &Self::Pcs;
 
@@ -75,6 +78,19 @@ pub trait StarkGenericConfig: 'static + Send + Sync + Serialize + DeserializeOwn
     fn challenger(&self) -> Self::Challenger;
 }
 
+/// The hash function a config uses for its Merkle commitments.
+///
+/// The hash decides where proofs are cheap to verify: BabyBear Poseidon2 commitments are verified
+/// efficiently by recursion programs, and BN254 Poseidon2 commitments by BN254 circuits and onchain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum CommitmentHash {
+    /// Poseidon2 over BabyBear, with a digest of eight BabyBear elements.
+    #[default]
+    BabyBearPoseidon2,
+    /// Poseidon2 over BN254, with a digest of one BN254 element.
+    Bn254Poseidon2,
+}
+
 pub trait ZeroCommitment<SC: StarkGenericConfig> {
     fn zero_commitment(&self) -> Com<SC>;
 }
@@ -30,6 +30,7 @@ mod lookup;
 mod machine;
 mod opts;
 mod permutation;
+mod progress;
 mod prover;
 mod quotient;
 mod record;
@@ -47,6 +48,7 @@ pub use lookup::*;
 pub use machine::*;
 pub use opts::*;
 pub use permutation::*;
+pub use progress::*;
 pub use prover::*;
 pub use quotient::*;
 pub use record::*;
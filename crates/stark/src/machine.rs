@@ -288,19 +288,14 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
         &self.config
     }
 
-    /// Verify that a proof is complete and valid given a verifying key and a claimed digest.
-    #[instrument("verify", level = "info", skip_all)]
-    #[allow(clippy::match_bool)]
-    pub fn verify(
+    /// Observe the verifying key and the global commitments of a proof, and sample the challenges
+    /// of the global permutation argument.
+    pub fn global_permutation_challenges(
         &self,
         vk: &StarkVerifyingKey<SC>,
         proof: &MachineProof<SC>,
         challenger: &mut SC::Challenger,
-    ) -> Result<(), MachineVerificationError<SC>>
-    where
-        SC::Challenger: Clone,
-        A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
-    {
+    ) -> [SC::Challenge; 2] {
         let contains_global_bus = self.contains_global_bus();
 
         // Observe the preprocessed commitment.
@@ -314,19 +309,35 @@ impl<SC: StarkGenericConfig, A: MachineAir<Val<SC>>> StarkMachine<SC, A> {
             });
         });
 
-        // Verify the shard proofs.
-        if proof.shard_proofs.is_empty() {
-            return Err(MachineVerificationError::EmptyProof);
-        }
-
-        // Obtain the challenges used for the global permutation argument.
-        let global_permutation_challenges: [SC::Challenge; 2] = array::from_fn(|_| {
+        array::from_fn(|_| {
             if contains_global_bus {
                 challenger.sample_ext_element()
             } else {
                 SC::Challenge::zero()
             }
-        });
+        })
+    }
+
+    /// Verify that a proof is complete and valid given a verifying key and a claimed digest.
+    #[instrument("verify", level = "info", skip_all)]
+    #[allow(clippy::match_bool)]
+    pub fn verify(
+        &self,
+        vk: &StarkVerifyingKey<SC>,
+        proof: &MachineProof<SC>,
+        challenger: &mut SC::Challenger,
+    ) -> Result<(), MachineVerificationError<SC>>
+    where
+        SC::Challenger: Clone,
+        A: for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    {
+        let global_permutation_challenges =
+            self.global_permutation_challenges(vk, proof, challenger);
+
+        // Verify the shard proofs.
+        if proof.shard_proofs.is_empty() {
+            return Err(MachineVerificationError::EmptyProof);
+        }
 
         tracing::debug_span!("verify shard proofs").in_scope(|| {
             for (i, shard_proof) in proof.shard_proofs.iter().enumerate() {
@@ -0,0 +1,157 @@
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// A stage of the SP1 proving pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ProvingStage {
+    /// Proving the shards of the execution.
+    Core,
+    /// Recursively compressing the shard proofs into a single proof.
+    Compress,
+    /// Shrinking the compressed proof.
+    Shrink,
+    /// Wrapping the shrunk proof into a STARK over BN254.
+    Wrap,
+    /// Wrapping the BN254 STARK into a PLONK proof.
+    Plonk,
+    /// Wrapping the BN254 STARK into a Groth16 proof.
+    Groth16,
+}
+
+/// A progress report of a proving stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressUpdate {
+    /// The stage being proven.
+    pub stage: ProvingStage,
+    /// The number of proofs completed in this stage.
+    pub completed: usize,
+    /// The total number of proofs in this stage, if known.
+    pub total: Option<usize>,
+    /// The time spent in this stage so far.
+    pub elapsed: Duration,
+    /// The estimated time remaining in this stage, if the total is known.
+    pub remaining: Option<Duration>,
+}
+
+/// A receiver of progress reports from the prover.
+///
+/// Reports may be sent concurrently from the prover's worker threads.
+pub trait ProgressSink: Send + Sync {
+    /// Called whenever a stage starts or completes a proof.
+    fn report(&self, update: ProgressUpdate);
+}
+
+impl<F: Fn(ProgressUpdate) + Send + Sync> ProgressSink for F {
+    fn report(&self, update: ProgressUpdate) {
+        self(update);
+    }
+}
+
+/// A token to cancel proving from another thread.
+///
+/// Cancellation is checked between proofs, so the prover stops after finishing the proofs it
+/// already started.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a new token that is not cancelled.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel all proving using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the token has been cancelled.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The progress sink and cancellation token of a proving request.
+#[derive(Clone, Default)]
+pub struct ProverMonitor {
+    /// The sink receiving progress reports.
+    pub progress: Option<Arc<dyn ProgressSink>>,
+    /// The token cancelling the request.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Debug for ProverMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProverMonitor")
+            .field("progress", &self.progress.is_some())
+            .field("cancellation", &self.cancellation)
+            .finish()
+    }
+}
+
+impl ProverMonitor {
+    /// Whether the request has been cancelled.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Start reporting the progress of a stage.
+    #[must_use]
+    pub fn stage(&self, stage: ProvingStage, total: Option<usize>) -> StageProgress {
+        let progress = StageProgress {
+            sink: self.progress.clone(),
+            stage,
+            start: Instant::now(),
+            completed: AtomicUsize::new(0),
+            total: AtomicUsize::new(total.unwrap_or(0)),
+        };
+        progress.report(0);
+        progress
+    }
+}
+
+/// The progress of a single stage, shared by the workers proving it.
+pub struct StageProgress {
+    sink: Option<Arc<dyn ProgressSink>>,
+    stage: ProvingStage,
+    start: Instant,
+    completed: AtomicUsize,
+    /// The total number of proofs, or zero if unknown.
+    total: AtomicUsize,
+}
+
+impl StageProgress {
+    /// Set the total number of proofs once it is known.
+    pub fn set_total(&self, total: usize) {
+        self.total.store(total, Ordering::Relaxed);
+        self.report(self.completed.load(Ordering::Relaxed));
+    }
+
+    /// Record that `n` more proofs have been completed.
+    pub fn advance(&self, n: usize) {
+        let completed = self.completed.fetch_add(n, Ordering::Relaxed) + n;
+        self.report(completed);
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn report(&self, completed: usize) {
+        let Some(sink) = &self.sink else { return };
+        let elapsed = self.start.elapsed();
+        let total = Some(self.total.load(Ordering::Relaxed)).filter(|&total| total > 0);
+        let remaining = total.filter(|_| completed > 0).map(|total| {
+            elapsed.mul_f64(total.saturating_sub(completed) as f64 / completed as f64)
+        });
+        sink.report(ProgressUpdate { stage: self.stage, completed, total, elapsed, remaining });
+    }
+}
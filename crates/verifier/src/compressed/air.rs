@@ -0,0 +1,325 @@
+//! A serializable description of the constraints of the compress machine.
+//!
+//! The description is exported from the recursion AIRs by `sp1-prover`, so that the constraints
+//! can be evaluated at the out-of-domain point without depending on the recursion crates.
+
+use alloc::{string::String, vec::Vec};
+
+use p3_field::{AbstractExtensionField, AbstractField};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    config::{Challenge, Val},
+    error::CompressedError,
+};
+
+/// The constraints and interactions of every chip of the compress machine, in machine order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressConstraints {
+    /// The chips of the machine.
+    pub chips: Vec<ChipConstraints>,
+    /// The number of public values observed by the challenger.
+    pub num_public_values: usize,
+    /// Whether the machine has a global interaction bus.
+    pub contains_global_bus: bool,
+}
+
+/// The constraints and interactions of a single chip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChipConstraints {
+    /// The name of the chip, as it appears in the chip ordering of a proof.
+    pub name: String,
+    /// The width of the preprocessed trace.
+    pub preprocessed_width: usize,
+    /// The width of the main trace.
+    pub width: usize,
+    /// The log2 of the number of quotient chunks.
+    pub log_quotient_degree: usize,
+    /// The scope of the commitment to the main trace.
+    pub commit_scope: Scope,
+    /// Whether the chip only opens its traces at the current row.
+    pub local_only: bool,
+    /// The nodes of the constraint expressions. Operands always refer to earlier nodes.
+    pub nodes: Vec<AirExpr>,
+    /// The indices of the constrained nodes, in the order the AIR asserts them.
+    pub constraints: Vec<usize>,
+    /// The interactions sent by the chip.
+    pub sends: Vec<AirInteraction>,
+    /// The interactions received by the chip.
+    pub receives: Vec<AirInteraction>,
+}
+
+/// The scope of an interaction or a trace commitment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scope {
+    /// Shared between all shards of a proof.
+    Global,
+    /// Contained in a single shard.
+    Local,
+}
+
+/// A node of a constraint expression over the base field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AirExpr {
+    /// A field element, given by its canonical representation.
+    Constant(u32),
+    /// A column of the preprocessed trace, on the current or the next row.
+    Preprocessed { next: bool, column: usize },
+    /// A column of the main trace, on the current or the next row.
+    Main { next: bool, column: usize },
+    /// A public value of the shard.
+    Public(usize),
+    /// The selector of the first row.
+    IsFirstRow,
+    /// The selector of the last row.
+    IsLastRow,
+    /// The selector of every row except the last.
+    IsTransition,
+    /// The sum of two nodes.
+    Add(usize, usize),
+    /// The difference of two nodes.
+    Sub(usize, usize),
+    /// The negation of a node.
+    Neg(usize),
+    /// The product of two nodes.
+    Mul(usize, usize),
+}
+
+/// A lookup interaction of a chip.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AirInteraction {
+    /// The values of the interaction.
+    pub values: Vec<LinearColumn>,
+    /// The multiplicity of the interaction.
+    pub multiplicity: LinearColumn,
+    /// The index of the argument in the lookup table, i.e. the interaction kind.
+    pub argument_index: usize,
+    /// The scope of the interaction.
+    pub scope: Scope,
+}
+
+/// A linear combination of the columns of the current row, plus a constant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinearColumn {
+    /// The weighted columns, with weights given by their canonical representation.
+    pub terms: Vec<(Column, u32)>,
+    /// The constant term, given by its canonical representation.
+    pub constant: u32,
+}
+
+/// A column of the preprocessed or the main trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Column {
+    /// A preprocessed column.
+    Preprocessed(usize),
+    /// A main column.
+    Main(usize),
+}
+
+/// The opened values and challenges a chip's constraints are evaluated against.
+pub(crate) struct ChipEvaluation<'a> {
+    pub preprocessed_local: &'a [Challenge],
+    pub preprocessed_next: &'a [Challenge],
+    pub main_local: &'a [Challenge],
+    pub main_next: &'a [Challenge],
+    pub perm_local: &'a [Challenge],
+    pub perm_next: &'a [Challenge],
+    pub perm_challenges: &'a [Challenge; 4],
+    pub cumulative_sums: [Challenge; 2],
+    pub public_values: &'a [Val],
+    pub is_first_row: Challenge,
+    pub is_last_row: Challenge,
+    pub is_transition: Challenge,
+    pub alpha: Challenge,
+}
+
+impl ChipConstraints {
+    /// Whether the chip has an interaction of the given scope.
+    pub(crate) fn has_interactions(&self, scope: Scope) -> bool {
+        self.sends.iter().chain(&self.receives).any(|i| i.scope == scope)
+    }
+
+    /// The number of sends to the byte lookup table.
+    pub(crate) fn num_sent_byte_lookups(&self) -> usize {
+        /// The argument index of `InteractionKind::Byte`.
+        const BYTE_ARGUMENT_INDEX: usize = 5;
+        self.sends.iter().filter(|i| i.argument_index == BYTE_ARGUMENT_INDEX).count()
+    }
+
+    /// The number of interactions per column of the permutation trace.
+    pub(crate) const fn logup_batch_size(&self) -> usize {
+        1 << self.log_quotient_degree
+    }
+
+    /// The number of quotient chunks.
+    pub(crate) const fn quotient_width(&self) -> usize {
+        1 << self.log_quotient_degree
+    }
+
+    /// The width of the permutation trace of the given scope, in extension field elements.
+    pub(crate) fn scope_permutation_width(&self, scope: Scope) -> usize {
+        let num_interactions =
+            self.sends.iter().chain(&self.receives).filter(|i| i.scope == scope).count();
+        if num_interactions == 0 {
+            0
+        } else {
+            num_interactions.div_ceil(self.logup_batch_size()) + 1
+        }
+    }
+
+    /// The width of the permutation trace, in extension field elements.
+    pub(crate) fn permutation_width(&self) -> usize {
+        self.scope_permutation_width(Scope::Global) + self.scope_permutation_width(Scope::Local)
+    }
+
+    /// Folds the AIR constraints and then the permutation constraints with powers of `alpha`, in
+    /// the same order as the prover's constraint folder.
+    pub(crate) fn eval(&self, values: &ChipEvaluation<'_>) -> Result<Challenge, CompressedError> {
+        let invalid = || CompressedError::InvalidConstraints(self.name.clone());
+        let mut accumulator = Challenge::zero();
+
+        let mut nodes: Vec<Challenge> = Vec::with_capacity(self.nodes.len());
+        for (i, node) in self.nodes.iter().enumerate() {
+            let operand = |j: usize| if j < i { Some(nodes[j]) } else { None };
+            let value = match *node {
+                AirExpr::Constant(c) => Some(Challenge::from_canonical_u32(c)),
+                AirExpr::Preprocessed { next, column } => {
+                    let row =
+                        if next { values.preprocessed_next } else { values.preprocessed_local };
+                    row.get(column).copied()
+                }
+                AirExpr::Main { next, column } => {
+                    let row = if next { values.main_next } else { values.main_local };
+                    row.get(column).copied()
+                }
+                AirExpr::Public(index) => {
+                    values.public_values.get(index).map(|&v| Challenge::from_base(v))
+                }
+                AirExpr::IsFirstRow => Some(values.is_first_row),
+                AirExpr::IsLastRow => Some(values.is_last_row),
+                AirExpr::IsTransition => Some(values.is_transition),
+                AirExpr::Add(a, b) => operand(a).zip(operand(b)).map(|(a, b)| a + b),
+                AirExpr::Sub(a, b) => operand(a).zip(operand(b)).map(|(a, b)| a - b),
+                AirExpr::Neg(a) => operand(a).map(|a| -a),
+                AirExpr::Mul(a, b) => operand(a).zip(operand(b)).map(|(a, b)| a * b),
+            };
+            nodes.push(value.ok_or_else(invalid)?);
+        }
+        for &constraint in &self.constraints {
+            let value = nodes.get(constraint).ok_or_else(invalid)?;
+            accumulator = accumulator * values.alpha + *value;
+        }
+
+        for constraint in self.permutation_constraints(values)? {
+            accumulator = accumulator * values.alpha + constraint;
+        }
+        Ok(accumulator)
+    }
+
+    /// Evaluates the constraints of the logUp permutation argument, as asserted by
+    /// `eval_permutation_constraints` in `sp1-stark`.
+    fn permutation_constraints(
+        &self,
+        values: &ChipEvaluation<'_>,
+    ) -> Result<Vec<Challenge>, CompressedError> {
+        let invalid = || CompressedError::InvalidConstraints(self.name.clone());
+        let batch_size = self.logup_batch_size();
+        let global_width = self.scope_permutation_width(Scope::Global);
+        let mut constraints = Vec::new();
+
+        for (scope, offset) in [(Scope::Global, 0), (Scope::Local, global_width)] {
+            let interactions = self
+                .sends
+                .iter()
+                .filter(|i| i.scope == scope)
+                .map(|i| (i, true))
+                .chain(self.receives.iter().filter(|i| i.scope == scope).map(|i| (i, false)))
+                .collect::<Vec<_>>();
+            if interactions.is_empty() {
+                continue;
+            }
+
+            let (alpha, beta) = match scope {
+                Scope::Global => (values.perm_challenges[0], values.perm_challenges[1]),
+                Scope::Local => (values.perm_challenges[2], values.perm_challenges[3]),
+            };
+            let width = self.scope_permutation_width(scope);
+            let perm_local = values.perm_local.get(offset..offset + width).ok_or_else(invalid)?;
+            let perm_next = values.perm_next.get(offset..offset + width).ok_or_else(invalid)?;
+
+            // Each entry times the product of the batch's fingerprints equals the sum of the
+            // multiplicities times the product of the other fingerprints.
+            for (entry, chunk) in perm_local.iter().zip(interactions.chunks(batch_size)) {
+                let mut rlcs = Vec::with_capacity(chunk.len());
+                let mut multiplicities = Vec::with_capacity(chunk.len());
+                for (interaction, is_send) in chunk {
+                    let mut betas = beta.powers();
+                    let mut rlc = alpha
+                        + betas.next().unwrap()
+                            * Challenge::from_canonical_usize(interaction.argument_index);
+                    for (column, beta) in interaction.values.iter().zip(betas) {
+                        rlc += beta * column.apply(values).ok_or_else(invalid)?;
+                    }
+                    rlcs.push(rlc);
+
+                    let multiplicity =
+                        interaction.multiplicity.apply(values).ok_or_else(invalid)?;
+                    multiplicities.push(if *is_send { multiplicity } else { -multiplicity });
+                }
+
+                let mut product = Challenge::one();
+                let mut numerator = Challenge::zero();
+                for (i, (m, rlc)) in multiplicities.into_iter().zip(&rlcs).enumerate() {
+                    product *= *rlc;
+                    let all_but_current = rlcs
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| i != *j)
+                        .map(|(_, rlc)| *rlc)
+                        .product::<Challenge>();
+                    numerator += m * all_but_current;
+                }
+                constraints.push(product * *entry - numerator);
+            }
+
+            // The last column accumulates the row sums, ending at the cumulative sum of the scope.
+            let sum_local = perm_local[..width - 1].iter().copied().sum::<Challenge>();
+            let sum_next = perm_next[..width - 1].iter().copied().sum::<Challenge>();
+            let phi_local = perm_local[width - 1];
+            let phi_next = perm_next[width - 1];
+            let cumulative_sum = match scope {
+                Scope::Global => values.cumulative_sums[0],
+                Scope::Local => values.cumulative_sums[1],
+            };
+            constraints.push(values.is_first_row * (phi_local - sum_local));
+            constraints.push(values.is_transition * (phi_next - phi_local - sum_next));
+            constraints.push(values.is_last_row * (phi_local - cumulative_sum));
+        }
+        Ok(constraints)
+    }
+}
+
+impl LinearColumn {
+    /// Evaluates the combination on the current row, or returns `None` if a column is out of range.
+    fn apply(&self, values: &ChipEvaluation<'_>) -> Option<Challenge> {
+        let mut result = Challenge::from_canonical_u32(self.constant);
+        for &(column, weight) in &self.terms {
+            let value = match column {
+                Column::Preprocessed(i) => values.preprocessed_local.get(i)?,
+                Column::Main(i) => values.main_local.get(i)?,
+            };
+            result += *value * Val::from_canonical_u32(weight);
+        }
+        Some(result)
+    }
+}
+
+/// Reconstructs extension field elements from their flattened base field coordinates.
+pub(crate) fn unflatten(values: &[Challenge]) -> Vec<Challenge> {
+    values
+        .chunks_exact(<Challenge as AbstractExtensionField<Val>>::D)
+        .map(|chunk| {
+            chunk.iter().enumerate().map(|(i, &x)| Challenge::monomial(i) * x).sum::<Challenge>()
+        })
+        .collect()
+}
@@ -0,0 +1,35 @@
+use alloc::string::String;
+
+use thiserror_no_std::Error;
+
+#[derive(Debug, Error)]
+pub enum CompressedError {
+    #[error("Failed to decode the compressed proof")]
+    InvalidProof,
+    #[error("Failed to decode the compress machine description")]
+    InvalidConstraintsBytes,
+    #[error("The compress machine description is not the one of this SP1 version")]
+    ConstraintsMismatch,
+    #[error("Invalid compress machine description for chip {0}")]
+    InvalidConstraints(String),
+    #[error("Chip {0} is not part of the compress machine")]
+    UnknownChip(String),
+    #[error("Chip opening length mismatch")]
+    ChipOpeningLengthMismatch,
+    #[error("Opening shape mismatch for chip {0}: {1}")]
+    OpeningShapeMismatch(String, &'static str),
+    #[error("Byte multiplicities overflow")]
+    ByteMultiplicityOverflow,
+    #[error("Cumulative sums error: {0}")]
+    CumulativeSumsError(&'static str),
+    #[error("Invalid opening argument")]
+    InvalidOpeningArgument,
+    #[error("Out-of-domain evaluation mismatch for chip {0}")]
+    OodEvaluationMismatch(String),
+    #[error("The proof is not of the compress program of this SP1 version")]
+    InvalidVerificationKey,
+    #[error("Invalid public values: {0}")]
+    InvalidPublicValues(&'static str),
+    #[error("General error")]
+    GeneralError(#[from] crate::error::Error),
+}
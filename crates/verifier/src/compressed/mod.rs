@@ -0,0 +1,75 @@
+mod air;
+mod config;
+pub mod error;
+mod pinned;
+mod proof;
+mod public_values;
+mod verify;
+
+pub use air::{
+    AirExpr, AirInteraction, ChipConstraints, Column, CompressConstraints, LinearColumn, Scope,
+};
+pub use pinned::{COMPRESS_CONSTRAINTS_DIGEST, COMPRESS_VK_DIGEST, RECURSION_VK_ROOT};
+pub use public_values::{
+    ChallengerPublicValues, RecursionPublicValues, RECURSIVE_PROOF_NUM_PV_ELTS,
+};
+
+use error::CompressedError;
+use proof::ReduceProof;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use verify::{decode_vkey_digest, verify_machine, verify_public_values};
+
+use crate::decode_sp1_vkey_hash;
+
+/// A verifier for compressed SP1 proofs, i.e. the BabyBear STARK proofs of the compress machine.
+#[derive(Debug)]
+pub struct CompressedVerifier;
+
+impl CompressedVerifier {
+    /// Verifies a compressed proof.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The proof bytes, as returned by `SP1ProofWithPublicValues::bytes` for a
+    ///   compressed proof.
+    /// * `sp1_public_inputs` - The SP1 public inputs.
+    /// * `sp1_vkey_hash` - The SP1 vkey hash, as returned by `vk.bytes32()`.
+    /// * `compress_constraints` - The serialized [`CompressConstraints`] of the current SP1 version,
+    ///   usually [`crate::COMPRESS_CONSTRAINTS_BYTES`].
+    ///
+    /// The constraints must hash to [`COMPRESS_CONSTRAINTS_DIGEST`], the proof must carry the
+    /// verifying key of the compress program with digest [`COMPRESS_VK_DIGEST`], and its public
+    /// values must commit to the recursion verifying keys with root [`RECURSION_VK_ROOT`].
+    ///
+    /// # Returns
+    ///
+    /// A success [`Result`] if verification succeeds, or a [`CompressedError`] if verification
+    /// fails.
+    pub fn verify(
+        proof: &[u8],
+        sp1_public_inputs: &[u8],
+        sp1_vkey_hash: &str,
+        compress_constraints: &[u8],
+    ) -> Result<(), CompressedError> {
+        let proof: ReduceProof = decode(proof).ok_or(CompressedError::InvalidProof)?;
+        if Sha256::digest(compress_constraints)[..]
+            != decode_sp1_vkey_hash(COMPRESS_CONSTRAINTS_DIGEST)?
+        {
+            return Err(CompressedError::ConstraintsMismatch);
+        }
+        let air: CompressConstraints =
+            decode(compress_constraints).ok_or(CompressedError::InvalidConstraintsBytes)?;
+        let sp1_vkey_hash = decode_vkey_digest(&decode_sp1_vkey_hash(sp1_vkey_hash)?)?;
+        let public_inputs_hash = Sha256::digest(sp1_public_inputs).into();
+
+        verify_machine(&proof, &air)?;
+        verify_public_values(&proof.proof.public_values, &sp1_vkey_hash, &public_inputs_hash)
+    }
+}
+
+/// Decodes a value encoded with `bincode::serialize`, rejecting trailing bytes.
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    let (value, read) = bincode::serde::decode_from_slice(bytes, bincode::config::legacy()).ok()?;
+    (read == bytes.len()).then_some(value)
+}
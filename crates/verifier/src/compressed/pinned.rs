@@ -0,0 +1,16 @@
+//! The compress machine accepted by `CompressedVerifier`.
+//!
+//! This file is generated by `cargo run --release --bin build_compress_constraints` in
+//! `sp1-prover`. Regenerate it together with the constraints for every SP1 version.
+
+/// The digest of the verifying key of the compress program, as returned by `vk.bytes32()`.
+pub const COMPRESS_VK_DIGEST: &str =
+    "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+/// The root of the merkle tree of the recursion verifying keys, packed like `vk.bytes32()`.
+pub const RECURSION_VK_ROOT: &str =
+    "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+/// The SHA-256 digest of the serialized compress constraints.
+pub const COMPRESS_CONSTRAINTS_DIGEST: &str =
+    "0x0000000000000000000000000000000000000000000000000000000000000000";
@@ -0,0 +1,64 @@
+//! Mirrors of the `sp1-stark` proof types for the compress machine, with the same serde layout so
+//! that the bincode encoding of an `SP1ReduceProof` can be decoded without the prover crates.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use p3_commit::TwoAdicMultiplicativeCoset;
+use p3_matrix::Dimensions;
+use serde::Deserialize;
+
+use super::config::{Challenge, Com, PcsProof, Val};
+
+/// A compressed proof together with the verifying key of the program that produced it.
+#[derive(Deserialize)]
+pub(crate) struct ReduceProof {
+    pub vk: VerifyingKey,
+    pub proof: ShardProof,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct VerifyingKey {
+    pub commit: Com,
+    pub pc_start: Val,
+    pub chip_information: Vec<(String, TwoAdicMultiplicativeCoset<Val>, Dimensions)>,
+    pub chip_ordering: BTreeMap<String, usize>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ShardProof {
+    pub commitment: ShardCommitment,
+    pub opened_values: ShardOpenedValues,
+    pub opening_proof: PcsProof,
+    pub chip_ordering: BTreeMap<String, usize>,
+    pub public_values: Vec<Val>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ShardCommitment {
+    pub global_main_commit: Com,
+    pub local_main_commit: Com,
+    pub permutation_commit: Com,
+    pub quotient_commit: Com,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ShardOpenedValues {
+    pub chips: Vec<ChipOpenedValues>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ChipOpenedValues {
+    pub preprocessed: AirOpenedValues,
+    pub main: AirOpenedValues,
+    pub permutation: AirOpenedValues,
+    pub quotient: Vec<Vec<Challenge>>,
+    pub global_cumulative_sum: Challenge,
+    pub local_cumulative_sum: Challenge,
+    pub log_degree: usize,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AirOpenedValues {
+    pub local: Vec<Challenge>,
+    pub next: Vec<Challenge>,
+}
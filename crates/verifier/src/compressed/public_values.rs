@@ -0,0 +1,80 @@
+//! The layout of the public values of a compressed proof.
+//!
+//! These mirror `ChallengerPublicValues` and `RecursionPublicValues` of `sp1-recursion-core`, so
+//! that the offsets of the checked fields follow the recursion machine instead of being written
+//! out by hand. `sp1-prover` tests that both layouts agree.
+
+use core::{
+    mem::{offset_of, size_of},
+    ops::Range,
+};
+
+use super::config::DIGEST_SIZE;
+
+/// The width of the Poseidon2 permutation of the recursion challenger.
+const PERMUTATION_WIDTH: usize = 16;
+
+/// The number of 32-bit words in the committed value digest.
+const PV_DIGEST_NUM_WORDS: usize = 8;
+
+/// The number of elements in the digest of the deferred proofs.
+const POSEIDON_NUM_WORDS: usize = 8;
+
+/// The state of a challenger, as stored in the public values of a recursive proof.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct ChallengerPublicValues<T> {
+    pub sponge_state: [T; PERMUTATION_WIDTH],
+    pub num_inputs: T,
+    pub input_buffer: [T; PERMUTATION_WIDTH],
+    pub num_outputs: T,
+    pub output_buffer: [T; PERMUTATION_WIDTH],
+}
+
+/// The public values of a recursive proof, with the layout of `RecursionPublicValues`.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct RecursionPublicValues<T> {
+    pub committed_value_digest: [[T; 4]; PV_DIGEST_NUM_WORDS],
+    pub deferred_proofs_digest: [T; POSEIDON_NUM_WORDS],
+    pub start_pc: T,
+    pub next_pc: T,
+    pub start_shard: T,
+    pub next_shard: T,
+    pub start_execution_shard: T,
+    pub next_execution_shard: T,
+    pub previous_init_addr_bits: [T; 32],
+    pub last_init_addr_bits: [T; 32],
+    pub previous_finalize_addr_bits: [T; 32],
+    pub last_finalize_addr_bits: [T; 32],
+    pub start_reconstruct_challenger: ChallengerPublicValues<T>,
+    pub end_reconstruct_challenger: ChallengerPublicValues<T>,
+    pub start_reconstruct_deferred_digest: [T; POSEIDON_NUM_WORDS],
+    pub end_reconstruct_deferred_digest: [T; POSEIDON_NUM_WORDS],
+    pub sp1_vk_digest: [T; DIGEST_SIZE],
+    pub vk_root: [T; DIGEST_SIZE],
+    pub leaf_challenger: ChallengerPublicValues<T>,
+    pub cumulative_sum: [T; 4],
+    pub is_complete: T,
+    pub contains_execution_shard: T,
+    pub exit_code: T,
+    pub digest: [T; DIGEST_SIZE],
+}
+
+/// The number of public values of a recursive proof.
+pub const RECURSIVE_PROOF_NUM_PV_ELTS: usize = size_of::<RecursionPublicValues<u8>>();
+
+/// The range of the public values holding a field, given its offset and number of elements.
+const fn field(offset: usize, len: usize) -> Range<usize> {
+    offset..offset + len
+}
+
+pub(crate) const COMMITTED_VALUE_DIGEST: Range<usize> =
+    field(offset_of!(RecursionPublicValues<u8>, committed_value_digest), PV_DIGEST_NUM_WORDS * 4);
+pub(crate) const SP1_VK_DIGEST: Range<usize> =
+    field(offset_of!(RecursionPublicValues<u8>, sp1_vk_digest), DIGEST_SIZE);
+pub(crate) const VK_ROOT: Range<usize> =
+    field(offset_of!(RecursionPublicValues<u8>, vk_root), DIGEST_SIZE);
+pub(crate) const IS_COMPLETE: usize = offset_of!(RecursionPublicValues<u8>, is_complete);
+pub(crate) const DIGEST: Range<usize> =
+    field(offset_of!(RecursionPublicValues<u8>, digest), DIGEST_SIZE);
@@ -0,0 +1,435 @@
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+
+use p3_challenger::{CanObserve, FieldChallenger};
+use p3_commit::{PolynomialSpace, TwoAdicMultiplicativeCoset};
+use p3_field::{AbstractExtensionField, AbstractField, Field, PrimeField32, TwoAdicField};
+use p3_symmetric::CryptographicHasher;
+
+use super::{
+    air::{unflatten, ChipConstraints, ChipEvaluation, CompressConstraints, Scope},
+    config::{inner_pcs, inner_perm, Challenge, Challenger, Com, InnerHash, Pcs, Val, DIGEST_SIZE},
+    error::CompressedError,
+    pinned::{COMPRESS_VK_DIGEST, RECURSION_VK_ROOT},
+    proof::{ChipOpenedValues, ReduceProof, ShardProof, VerifyingKey},
+    public_values::{
+        COMMITTED_VALUE_DIGEST, DIGEST, IS_COMPLETE, RECURSIVE_PROOF_NUM_PV_ELTS, SP1_VK_DIGEST,
+        VK_ROOT,
+    },
+};
+
+use crate::decode_sp1_vkey_hash;
+
+type Domain = TwoAdicMultiplicativeCoset<Val>;
+
+/// The opening points and values of the matrices committed in one round.
+type Round = (Com, Vec<(Domain, Vec<(Challenge, Vec<Challenge>)>)>);
+
+/// Verifies the compress machine proof, mirroring `StarkMachine::verify` for a single shard.
+pub(crate) fn verify_machine(
+    proof: &ReduceProof,
+    air: &CompressConstraints,
+) -> Result<(), CompressedError> {
+    let ReduceProof { vk, proof } = proof;
+
+    // The proof must be of the compress program of this SP1 version.
+    if vk_digest(vk) != decode_vkey_digest(&decode_sp1_vkey_hash(COMPRESS_VK_DIGEST)?)? {
+        return Err(CompressedError::InvalidVerificationKey);
+    }
+
+    let perm = inner_perm();
+    let pcs = inner_pcs(&perm);
+    let mut challenger = Challenger::new(perm);
+
+    // Observe the preprocessed commitment and the shard's public values.
+    challenger.observe(vk.commit);
+    challenger.observe(vk.pc_start);
+    for _ in 0..7 {
+        challenger.observe(Val::zero());
+    }
+    if air.contains_global_bus {
+        challenger.observe(proof.commitment.global_main_commit);
+    }
+    let public_values = proof
+        .public_values
+        .get(..air.num_public_values)
+        .ok_or(CompressedError::InvalidPublicValues("too few public values"))?;
+    challenger.observe_slice(public_values);
+
+    let global_permutation_challenges: [Challenge; 2] = core::array::from_fn(|_| {
+        if air.contains_global_bus {
+            challenger.sample_ext_element()
+        } else {
+            Challenge::zero()
+        }
+    });
+
+    let chips = shard_chips(air, &proof.chip_ordering)?;
+    verify_shard(&pcs, vk, &chips, &mut challenger, proof, global_permutation_challenges)?;
+
+    // With a single shard, the global cumulative sum must vanish on its own.
+    let global_cumulative_sum =
+        proof.opened_values.chips.iter().map(|c| c.global_cumulative_sum).sum::<Challenge>();
+    if !global_cumulative_sum.is_zero() {
+        return Err(CompressedError::CumulativeSumsError("global cumulative sum is not zero"));
+    }
+    Ok(())
+}
+
+/// Checks the recursion public values of a verified compressed proof against the program and its
+/// public inputs, mirroring `SP1Prover::verify_compressed`.
+pub(crate) fn verify_public_values(
+    public_values: &[Val],
+    sp1_vkey_hash: &[Val; DIGEST_SIZE],
+    public_inputs_hash: &[u8; 32],
+) -> Result<(), CompressedError> {
+    if public_values.len() < RECURSIVE_PROOF_NUM_PV_ELTS {
+        return Err(CompressedError::InvalidPublicValues("too few public values"));
+    }
+
+    // The digest commits to all the public values before it.
+    let digest = InnerHash::new(inner_perm()).hash_slice(&public_values[..DIGEST.start]);
+    if digest[..] != public_values[DIGEST] {
+        return Err(CompressedError::InvalidPublicValues("public values digest mismatch"));
+    }
+
+    // `is_complete` should be 1. In the reduce program, this ensures that the proof is fully
+    // reduced.
+    if public_values[IS_COMPLETE] != Val::one() {
+        return Err(CompressedError::InvalidPublicValues("is_complete is not 1"));
+    }
+
+    // The recursion programs that produced the proof must be those of this SP1 version.
+    let vk_root = decode_vkey_digest(&decode_sp1_vkey_hash(RECURSION_VK_ROOT)?)?;
+    if vk_root[..] != public_values[VK_ROOT] {
+        return Err(CompressedError::InvalidPublicValues("vk root mismatch"));
+    }
+
+    if sp1_vkey_hash[..] != public_values[SP1_VK_DIGEST] {
+        return Err(CompressedError::InvalidPublicValues("sp1 vk hash mismatch"));
+    }
+
+    let committed_value_digest = &public_values[COMMITTED_VALUE_DIGEST];
+    if committed_value_digest
+        .iter()
+        .zip(public_inputs_hash)
+        .any(|(byte, expected)| byte.as_canonical_u32() != u32::from(*expected))
+    {
+        return Err(CompressedError::InvalidPublicValues("committed value digest mismatch"));
+    }
+
+    Ok(())
+}
+
+/// Hashes a verifying key, mirroring `HashableKey::hash_babybear` in `sp1-prover`.
+pub(crate) fn vk_digest(vk: &VerifyingKey) -> [Val; DIGEST_SIZE] {
+    let commit: [Val; DIGEST_SIZE] = vk.commit.into();
+    let mut inputs = commit.to_vec();
+    inputs.push(vk.pc_start);
+    for (_, domain, _) in &vk.chip_information {
+        inputs.push(Val::from_canonical_usize(domain.log_n));
+        inputs.push(Val::from_canonical_usize(1 << domain.log_n));
+        inputs.push(domain.shift);
+        inputs.push(Val::two_adic_generator(domain.log_n));
+    }
+    InnerHash::new(inner_perm()).hash_iter(inputs)
+}
+
+/// Decodes the digest packed by `vk.bytes32()`, where each of the eight 31-bit elements occupies
+/// 31 bits of a big-endian integer, the first element being the most significant.
+pub(crate) fn decode_vkey_digest(bytes: &[u8; 32]) -> Result<[Val; DIGEST_SIZE], CompressedError> {
+    let invalid = || CompressedError::GeneralError(crate::error::Error::InvalidProgramVkeyHash);
+    if bytes[0] != 0 {
+        return Err(invalid());
+    }
+    let bit = |k: usize| u32::from(bytes[31 - k / 8] >> (k % 8)) & 1;
+    let mut digest = [Val::zero(); DIGEST_SIZE];
+    for (i, element) in digest.iter_mut().rev().enumerate() {
+        let word = (0..31).fold(0u32, |word, j| word | (bit(31 * i + j) << j));
+        if word >= Val::ORDER_U32 {
+            return Err(invalid());
+        }
+        *element = Val::from_canonical_u32(word);
+    }
+    Ok(digest)
+}
+
+/// The chips of the machine included in the shard, sorted by the shard's chip ordering.
+fn shard_chips<'a>(
+    air: &'a CompressConstraints,
+    chip_ordering: &BTreeMap<String, usize>,
+) -> Result<Vec<&'a ChipConstraints>, CompressedError> {
+    if let Some(name) =
+        chip_ordering.keys().find(|name| !air.chips.iter().any(|chip| &chip.name == *name))
+    {
+        return Err(CompressedError::UnknownChip(name.clone()));
+    }
+    let mut chips =
+        air.chips.iter().filter(|c| chip_ordering.contains_key(&c.name)).collect::<Vec<_>>();
+    chips.sort_by_key(|chip| chip_ordering[&chip.name]);
+    if chips.iter().enumerate().any(|(i, chip)| chip_ordering[&chip.name] != i) {
+        return Err(CompressedError::ChipOpeningLengthMismatch);
+    }
+    Ok(chips)
+}
+
+/// Verifies a shard proof, mirroring `Verifier::verify_shard`.
+#[allow(clippy::too_many_lines)]
+fn verify_shard(
+    pcs: &Pcs,
+    vk: &VerifyingKey,
+    chips: &[&ChipConstraints],
+    challenger: &mut Challenger,
+    proof: &ShardProof,
+    global_permutation_challenges: [Challenge; 2],
+) -> Result<(), CompressedError> {
+    let ShardProof { commitment, opened_values, opening_proof, public_values, .. } = proof;
+
+    if chips.len() != opened_values.chips.len() {
+        return Err(CompressedError::ChipOpeningLengthMismatch);
+    }
+    for (chip, values) in chips.iter().zip(&opened_values.chips) {
+        verify_opening_shape(chip, values)
+            .map_err(|e| CompressedError::OpeningShapeMismatch(chip.name.clone(), e))?;
+    }
+
+    // Assert that the byte multiplicities don't overflow.
+    let mut max_byte_lookup_mult = 0u64;
+    for (chip, values) in chips.iter().zip(&opened_values.chips) {
+        max_byte_lookup_mult = 1u64
+            .checked_shl(values.log_degree as u32)
+            .and_then(|height| height.checked_mul(chip.num_sent_byte_lookups() as u64))
+            .and_then(|mult| mult.checked_add(max_byte_lookup_mult))
+            .ok_or(CompressedError::ByteMultiplicityOverflow)?;
+    }
+    if max_byte_lookup_mult > u64::from(Val::ORDER_U32) {
+        return Err(CompressedError::ByteMultiplicityOverflow);
+    }
+
+    let trace_domains = opened_values
+        .chips
+        .iter()
+        .map(|values| natural_domain(pcs, values.log_degree))
+        .collect::<Vec<_>>();
+
+    challenger.observe(commitment.local_main_commit);
+    let local_permutation_challenges: [Challenge; 2] =
+        core::array::from_fn(|_| challenger.sample_ext_element());
+
+    challenger.observe(commitment.permutation_commit);
+    // Observe the cumulative sums and constrain any sum without a corresponding scope to be zero.
+    for (values, chip) in opened_values.chips.iter().zip(chips) {
+        let global_sum = values.global_cumulative_sum;
+        let local_sum = values.local_cumulative_sum;
+        challenger.observe_slice(global_sum.as_base_slice());
+        challenger.observe_slice(local_sum.as_base_slice());
+
+        if !chip.has_interactions(Scope::Global) && !global_sum.is_zero() {
+            return Err(CompressedError::CumulativeSumsError(
+                "global cumulative sum is non-zero, but no global interactions",
+            ));
+        }
+        if !chip.has_interactions(Scope::Local) && !local_sum.is_zero() {
+            return Err(CompressedError::CumulativeSumsError(
+                "local cumulative sum is non-zero, but no local interactions",
+            ));
+        }
+    }
+
+    let alpha: Challenge = challenger.sample_ext_element();
+    challenger.observe(commitment.quotient_commit);
+    let zeta: Challenge = challenger.sample_ext_element();
+
+    let opened_at = |domain: &Domain, local: &[Challenge], next: &[Challenge], local_only: bool| {
+        let mut points = vec![(zeta, local.to_vec())];
+        if !local_only {
+            points.push((domain.next_point(zeta).unwrap(), next.to_vec()));
+        }
+        (*domain, points)
+    };
+
+    let mut preprocessed_domains_points_and_opens = Vec::new();
+    for (name, domain, _) in &vk.chip_information {
+        let i = *proof
+            .chip_ordering
+            .get(name)
+            .ok_or_else(|| CompressedError::UnknownChip(name.clone()))?;
+        let values = &opened_values.chips[i].preprocessed;
+        preprocessed_domains_points_and_opens.push(opened_at(
+            domain,
+            &values.local,
+            &values.next,
+            chips[i].local_only,
+        ));
+    }
+
+    let mut global_main_domains_points_and_opens = Vec::new();
+    let mut local_main_domains_points_and_opens = Vec::new();
+    for ((domain, values), chip) in trace_domains.iter().zip(&opened_values.chips).zip(chips) {
+        let opening = opened_at(domain, &values.main.local, &values.main.next, chip.local_only);
+        match chip.commit_scope {
+            Scope::Global => global_main_domains_points_and_opens.push(opening),
+            Scope::Local => local_main_domains_points_and_opens.push(opening),
+        }
+    }
+
+    let perm_domains_points_and_opens = trace_domains
+        .iter()
+        .zip(&opened_values.chips)
+        .map(|(domain, values)| {
+            opened_at(domain, &values.permutation.local, &values.permutation.next, false)
+        })
+        .collect::<Vec<_>>();
+
+    let quotient_chunk_domains = trace_domains
+        .iter()
+        .zip(&opened_values.chips)
+        .zip(chips)
+        .map(|((domain, values), chip)| {
+            let quotient_domain =
+                domain.create_disjoint_domain(1 << (values.log_degree + chip.log_quotient_degree));
+            quotient_domain.split_domains(chip.quotient_width())
+        })
+        .collect::<Vec<_>>();
+
+    let quotient_domains_points_and_opens = opened_values
+        .chips
+        .iter()
+        .zip(&quotient_chunk_domains)
+        .flat_map(|(values, qc_domains)| {
+            values
+                .quotient
+                .iter()
+                .zip(qc_domains)
+                .map(move |(values, q_domain)| (*q_domain, vec![(zeta, values.clone())]))
+        })
+        .collect::<Vec<_>>();
+
+    let mut rounds: Vec<Round> = vec![(vk.commit, preprocessed_domains_points_and_opens)];
+    if !global_main_domains_points_and_opens.is_empty() {
+        rounds.push((commitment.global_main_commit, global_main_domains_points_and_opens));
+    }
+    rounds.extend([
+        (commitment.local_main_commit, local_main_domains_points_and_opens),
+        (commitment.permutation_commit, perm_domains_points_and_opens),
+        (commitment.quotient_commit, quotient_domains_points_and_opens),
+    ]);
+
+    <Pcs as p3_commit::Pcs<Challenge, Challenger>>::verify(pcs, rounds, opening_proof, challenger)
+        .map_err(|_| CompressedError::InvalidOpeningArgument)?;
+
+    let [global_alpha, global_beta] = global_permutation_challenges;
+    let [local_alpha, local_beta] = local_permutation_challenges;
+    let permutation_challenges = [global_alpha, global_beta, local_alpha, local_beta];
+
+    // Verify the constraint evaluations.
+    for (((chip, trace_domain), qc_domains), values) in
+        chips.iter().zip(&trace_domains).zip(&quotient_chunk_domains).zip(&opened_values.chips)
+    {
+        let selectors = trace_domain.selectors_at_point(zeta);
+        let perm_local = unflatten(&values.permutation.local);
+        let perm_next = unflatten(&values.permutation.next);
+        let folded_constraints = chip.eval(&ChipEvaluation {
+            preprocessed_local: &values.preprocessed.local,
+            preprocessed_next: &values.preprocessed.next,
+            main_local: &values.main.local,
+            main_next: &values.main.next,
+            perm_local: &perm_local,
+            perm_next: &perm_next,
+            perm_challenges: &permutation_challenges,
+            cumulative_sums: [values.global_cumulative_sum, values.local_cumulative_sum],
+            public_values,
+            is_first_row: selectors.is_first_row,
+            is_last_row: selectors.is_last_row,
+            is_transition: selectors.is_transition,
+            alpha,
+        })?;
+
+        // Check that the constraints match the quotient, i.e.
+        //     folded_constraints(zeta) / Z_H(zeta) = quotient(zeta)
+        let quotient = recompute_quotient(values, qc_domains, zeta);
+        if folded_constraints * selectors.inv_zeroifier != quotient {
+            return Err(CompressedError::OodEvaluationMismatch(chip.name.clone()));
+        }
+    }
+
+    // Verify that the local cumulative sum is zero.
+    let local_cumulative_sum =
+        opened_values.chips.iter().map(|c| c.local_cumulative_sum).sum::<Challenge>();
+    if !local_cumulative_sum.is_zero() {
+        return Err(CompressedError::CumulativeSumsError("local cumulative sum is not zero"));
+    }
+
+    Ok(())
+}
+
+fn verify_opening_shape(
+    chip: &ChipConstraints,
+    opening: &ChipOpenedValues,
+) -> Result<(), &'static str> {
+    let d = <Challenge as AbstractExtensionField<Val>>::D;
+    if opening.preprocessed.local.len() != chip.preprocessed_width
+        || opening.preprocessed.next.len() != chip.preprocessed_width
+    {
+        return Err("preprocessed width mismatch");
+    }
+    if opening.main.local.len() != chip.width || opening.main.next.len() != chip.width {
+        return Err("main width mismatch");
+    }
+    if opening.permutation.local.len() != chip.permutation_width() * d
+        || opening.permutation.next.len() != chip.permutation_width() * d
+    {
+        return Err("permutation width mismatch");
+    }
+    if opening.quotient.len() != chip.quotient_width() {
+        return Err("quotient width mismatch");
+    }
+    if opening.quotient.iter().any(|chunk| chunk.len() != d) {
+        return Err("quotient chunk size mismatch");
+    }
+    // The quotient domain must fit in the two-adic subgroup.
+    if opening.log_degree + chip.log_quotient_degree > Val::TWO_ADICITY {
+        return Err("trace height too large");
+    }
+    Ok(())
+}
+
+/// The trace domain of a chip, as chosen by the prover's polynomial commitment scheme.
+fn natural_domain(pcs: &Pcs, log_degree: usize) -> Domain {
+    <Pcs as p3_commit::Pcs<Challenge, Challenger>>::natural_domain_for_degree(pcs, 1 << log_degree)
+}
+
+/// Recomputes the quotient at `zeta` from its chunks.
+fn recompute_quotient(
+    opening: &ChipOpenedValues,
+    qc_domains: &[Domain],
+    zeta: Challenge,
+) -> Challenge {
+    let zps = qc_domains
+        .iter()
+        .enumerate()
+        .map(|(i, domain)| {
+            qc_domains
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, other_domain)| {
+                    other_domain.zp_at_point(zeta)
+                        * other_domain.zp_at_point(domain.first_point()).inverse()
+                })
+                .product::<Challenge>()
+        })
+        .collect::<Vec<_>>();
+
+    opening
+        .quotient
+        .iter()
+        .zip(zps)
+        .map(|(chunk, zp)| {
+            chunk
+                .iter()
+                .enumerate()
+                .map(|(e_i, &c)| zp * Challenge::monomial(e_i) * c)
+                .sum::<Challenge>()
+        })
+        .sum()
+}
@@ -1,14 +1,22 @@
 mod converter;
 pub mod error;
+mod prepared;
 mod verify;
 
 pub(crate) use converter::{load_groth16_proof_from_bytes, load_groth16_verifying_key_from_bytes};
+pub use prepared::PreparedGroth16Vk;
 use sha2::{Digest, Sha256};
 pub(crate) use verify::*;
 
+use alloc::vec::Vec;
+use bn::Fr;
 use error::Groth16Error;
 
-use crate::{bn254_public_values, decode_sp1_vkey_hash, error::Error};
+use crate::{bn254_public_values, decode_sp1_vkey_hash, error::Error, hash_public_inputs};
+
+/// The length of a raw gnark Groth16 proof: two uncompressed G1 points and one uncompressed G2
+/// point.
+const GROTH16_PROOF_LEN: usize = 256;
 
 /// A verifier for Groth16 zero-knowledge proofs.
 #[derive(Debug)]
@@ -42,17 +50,29 @@ impl Groth16Verifier {
         sp1_vkey_hash: &str,
         groth16_vk: &[u8],
     ) -> Result<(), Groth16Error> {
-        // Hash the vk and get the first 4 bytes.
-        let groth16_vk_hash: [u8; 4] = Sha256::digest(groth16_vk)[..4]
-            .try_into()
-            .map_err(|_| Groth16Error::GeneralError(Error::InvalidData))?;
+        Self::verify_prepared(
+            proof,
+            sp1_public_inputs,
+            sp1_vkey_hash,
+            &PreparedGroth16Vk::new(groth16_vk)?,
+        )
+    }
 
+    /// Verifies a Groth16 proof against a [`PreparedGroth16Vk`].
+    ///
+    /// This is the same as [`Groth16Verifier::verify`], without parsing the verifying key again.
+    pub fn verify_prepared(
+        proof: &[u8],
+        sp1_public_inputs: &[u8],
+        sp1_vkey_hash: &str,
+        groth16_vk: &PreparedGroth16Vk,
+    ) -> Result<(), Groth16Error> {
         // Check to make sure that this proof was generated by the groth16 proving key corresponding to
         // the given groth16_vk.
         //
         // SP1 prepends the raw Groth16 proof with the first 4 bytes of the groth16 vkey to
         // facilitate this check.
-        if groth16_vk_hash != proof[..4] {
+        if proof.len() < 4 || groth16_vk.hash() != proof[..4] {
             return Err(Groth16Error::Groth16VkeyHashMismatch);
         }
 
@@ -60,8 +80,134 @@ impl Groth16Verifier {
         let public_inputs = bn254_public_values(&sp1_vkey_hash, sp1_public_inputs);
 
         let proof = load_groth16_proof_from_bytes(&proof[4..])?;
-        let groth16_vk = load_groth16_verifying_key_from_bytes(groth16_vk)?;
 
-        verify_groth16_raw(&groth16_vk, &proof, &public_inputs)
+        verify_groth16_raw(groth16_vk, &proof, &public_inputs)
+    }
+
+    /// Verifies a raw gnark Groth16 proof over arbitrary public inputs.
+    ///
+    /// Unlike [`Groth16Verifier::verify`], the proof doesn't need to be an SP1 proof: this checks
+    /// any Groth16 proof over BN254 made with gnark, such as the proofs of other proving systems
+    /// aggregated by the Groth16 deferral program.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The proof bytes, two uncompressed G1 points and one uncompressed G2 point as
+    ///   outputted by gnark. Unlike SP1 proofs, they are not prefixed with the vkey hash.
+    /// * `public_inputs` - The public inputs, each the big-endian encoding of a BN254 scalar.
+    /// * `groth16_vk` - The Groth16 verifying key bytes.
+    ///
+    /// # Returns
+    ///
+    /// A success [`Result`] if verification succeeds, or a [`Groth16Error`] if verification fails.
+    pub fn verify_gnark_proof(
+        proof: &[u8],
+        public_inputs: &[[u8; 32]],
+        groth16_vk: &[u8],
+    ) -> Result<(), Groth16Error> {
+        Self::verify_gnark_proof_prepared(
+            proof,
+            public_inputs,
+            &PreparedGroth16Vk::new(groth16_vk)?,
+        )
+    }
+
+    /// Verifies a raw gnark Groth16 proof against a [`PreparedGroth16Vk`].
+    ///
+    /// This is the same as [`Groth16Verifier::verify_gnark_proof`], without parsing the verifying
+    /// key again.
+    pub fn verify_gnark_proof_prepared(
+        proof: &[u8],
+        public_inputs: &[[u8; 32]],
+        groth16_vk: &PreparedGroth16Vk,
+    ) -> Result<(), Groth16Error> {
+        if proof.len() != GROTH16_PROOF_LEN {
+            return Err(Groth16Error::GeneralError(Error::InvalidData));
+        }
+
+        let public_inputs = public_inputs
+            .iter()
+            .map(|input| {
+                Fr::from_slice(input).map_err(|e| Groth16Error::GeneralError(Error::Field(e)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let proof = load_groth16_proof_from_bytes(proof)?;
+
+        verify_groth16_raw(groth16_vk, &proof, &public_inputs)
+    }
+
+    /// Verifies a batch of Groth16 proofs generated with the same Groth16 verifying key.
+    ///
+    /// The verifying key is parsed once, and the proofs are checked with a single multi-pairing
+    /// on a random linear combination of their pairing equations. The coefficients are derived by
+    /// hashing the verifying key and every proof with its public inputs, so that they can't be
+    /// chosen before the proofs are fixed.
+    ///
+    /// # Arguments
+    ///
+    /// * `proofs` - The proof bytes, SP1 public inputs and SP1 vkey hash of every proof, as passed
+    ///   to [`Groth16Verifier::verify`].
+    /// * `groth16_vk` - The Groth16 verifying key bytes.
+    ///
+    /// # Returns
+    ///
+    /// A success [`Result`] if every proof is valid, or a [`Groth16Error`] if any of them is not.
+    pub fn verify_batch(
+        proofs: &[(&[u8], &[u8], &str)],
+        groth16_vk: &[u8],
+    ) -> Result<(), Groth16Error> {
+        Self::verify_batch_prepared(proofs, &PreparedGroth16Vk::new(groth16_vk)?)
+    }
+
+    /// Verifies a batch of Groth16 proofs against a [`PreparedGroth16Vk`].
+    ///
+    /// This is the same as [`Groth16Verifier::verify_batch`], without parsing the verifying key
+    /// again.
+    pub fn verify_batch_prepared(
+        proofs: &[(&[u8], &[u8], &str)],
+        groth16_vk: &PreparedGroth16Vk,
+    ) -> Result<(), Groth16Error> {
+        if proofs.is_empty() {
+            return Ok(());
+        }
+
+        let mut transcript = Sha256::new();
+        transcript.update(groth16_vk.digest);
+
+        let mut batch = Vec::with_capacity(proofs.len());
+        for (proof, sp1_public_inputs, sp1_vkey_hash) in proofs {
+            if proof.len() < 4 || groth16_vk.hash() != proof[..4] {
+                return Err(Groth16Error::Groth16VkeyHashMismatch);
+            }
+
+            let sp1_vkey_hash = decode_sp1_vkey_hash(sp1_vkey_hash)?;
+            transcript.update(sp1_vkey_hash);
+            transcript.update(hash_public_inputs(sp1_public_inputs));
+            transcript.update((proof.len() as u64).to_be_bytes());
+            transcript.update(proof);
+
+            let public_inputs = bn254_public_values(&sp1_vkey_hash, sp1_public_inputs);
+            batch.push((load_groth16_proof_from_bytes(&proof[4..])?, public_inputs.to_vec()));
+        }
+        let seed = transcript.finalize();
+
+        // The first coefficient can be one without loss of soundness.
+        let challenges = (0..batch.len() as u32)
+            .map(|i| {
+                if i == 0 {
+                    return Ok(Fr::one());
+                }
+                let mut r: [u8; 32] = Sha256::new()
+                    .chain_update(seed)
+                    .chain_update(i.to_be_bytes())
+                    .finalize()
+                    .into();
+                // Clear the top bits so that the coefficient is below the BN254 scalar field modulus.
+                r[0] &= 0x1F;
+                Fr::from_slice(&r).map_err(|e| Groth16Error::GeneralError(Error::Field(e)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        verify_groth16_batch_raw(&groth16_vk.vk, &batch, &challenges)
     }
 }
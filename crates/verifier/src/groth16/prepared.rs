@@ -0,0 +1,41 @@
+use bn::{pairing, Gt, G2};
+use sha2::{Digest, Sha256};
+
+use super::{error::Groth16Error, load_groth16_verifying_key_from_bytes, Groth16VerifyingKey};
+
+/// A Groth16 verifying key that is parsed once and reused to verify many proofs.
+///
+/// Besides the curve points of the key, it caches the SHA-256 digest of the key bytes, whose first
+/// 4 bytes prefix every proof, and the pairing `e(alpha, beta)`, the only term of the pairing
+/// equation that doesn't depend on the proof. Verifying a proof then takes three Miller loops
+/// instead of four.
+#[derive(Clone)]
+pub struct PreparedGroth16Vk {
+    pub(crate) vk: Groth16VerifyingKey,
+    pub(crate) digest: [u8; 32],
+    pub(crate) alpha_beta: Gt,
+}
+
+impl PreparedGroth16Vk {
+    /// Parses and prepares a Groth16 verifying key.
+    ///
+    /// Usually this will be the [`static@crate::GROTH16_VK_BYTES`] constant, which is the Groth16
+    /// verifying key for the current SP1 version.
+    pub fn new(groth16_vk: &[u8]) -> Result<Self, Groth16Error> {
+        let digest = Sha256::digest(groth16_vk).into();
+        let vk = load_groth16_verifying_key_from_bytes(groth16_vk)?;
+        let alpha_beta = pairing(vk.g1.alpha.into(), -Into::<G2>::into(vk.g2.beta));
+        Ok(Self { vk, digest, alpha_beta })
+    }
+
+    /// The first 4 bytes of the SHA-256 digest of the verifying key, which SP1 prepends to the
+    /// proofs generated with it.
+    pub fn hash(&self) -> [u8; 4] {
+        self.digest[..4].try_into().unwrap()
+    }
+
+    /// The SHA-256 digest of the verifying key bytes.
+    pub fn digest(&self) -> [u8; 32] {
+        self.digest
+    }
+}
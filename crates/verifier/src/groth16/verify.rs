@@ -1,7 +1,7 @@
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
 use bn::{pairing_batch, AffineG1, AffineG2, Fr, Gt, G1, G2};
 
-use super::error::Groth16Error;
+use super::{error::Groth16Error, PreparedGroth16Vk};
 
 /// G1 elements of the verification key.
 #[derive(Clone, PartialEq)]
@@ -34,7 +34,7 @@ pub(crate) struct Groth16Proof {
 
 /// Prepare the inputs for the Groth16 verification by combining the public inputs with the
 /// corresponding elements of the verification key.
-fn prepare_inputs(vk: Groth16VerifyingKey, public_inputs: &[Fr]) -> Result<G1, Groth16Error> {
+fn prepare_inputs(vk: &Groth16VerifyingKey, public_inputs: &[Fr]) -> Result<G1, Groth16Error> {
     if (public_inputs.len() + 1) != vk.g1.k.len() {
         return Err(Groth16Error::PrepareInputsFailed);
     }
@@ -49,23 +49,71 @@ fn prepare_inputs(vk: Groth16VerifyingKey, public_inputs: &[Fr]) -> Result<G1, G
 /// Verify the Groth16 proof
 ///
 /// First, prepare the public inputs by folding them with the verification key.
-/// Then, verify the proof by checking the pairing equation.
+/// Then, verify the proof by checking the pairing equation, where `e(alpha, beta)` is taken from
+/// the prepared verification key.
 pub(crate) fn verify_groth16_raw(
-    vk: &Groth16VerifyingKey,
+    vk: &PreparedGroth16Vk,
     proof: &Groth16Proof,
     public_inputs: &[Fr],
 ) -> Result<(), Groth16Error> {
-    let prepared_inputs = prepare_inputs(vk.clone(), public_inputs)?;
+    let prepared_inputs = prepare_inputs(&vk.vk, public_inputs)?;
 
     if pairing_batch(&[
         (-Into::<G1>::into(proof.ar), proof.bs.into()),
-        (prepared_inputs, vk.g2.gamma.into()),
-        (proof.krs.into(), vk.g2.delta.into()),
-        (vk.g1.alpha.into(), -Into::<G2>::into(vk.g2.beta)),
-    ]) == Gt::one()
+        (prepared_inputs, vk.vk.g2.gamma.into()),
+        (proof.krs.into(), vk.vk.g2.delta.into()),
+    ]) * vk.alpha_beta
+        == Gt::one()
     {
         Ok(())
     } else {
         Err(Groth16Error::ProofVerificationFailed)
     }
 }
+
+/// Verify a batch of Groth16 proofs for the same verification key with a single multi-pairing.
+///
+/// The pairing equations of the proofs are combined with the random `challenges`, one per proof,
+/// so that the batch passes only if every proof does, except with negligible probability:
+///
+/// ```text
+/// Π e(-r_i * A_i, B_i) * e(Σ r_i * L_i, gamma) * e(Σ r_i * C_i, delta) * e(Σ r_i * alpha, -beta) = 1
+/// ```
+///
+/// where `L_i` are the prepared public inputs of the `i`-th proof. Since `L_i` is linear in the
+/// public inputs, `Σ r_i * L_i` is computed with a single MSM over the verification key.
+pub(crate) fn verify_groth16_batch_raw(
+    vk: &Groth16VerifyingKey,
+    proofs: &[(Groth16Proof, Vec<Fr>)],
+    challenges: &[Fr],
+) -> Result<(), Groth16Error> {
+    if proofs.len() != challenges.len() {
+        return Err(Groth16Error::PrepareInputsFailed);
+    }
+
+    let mut input_scalars = vec![Fr::zero(); vk.g1.k.len()];
+    let mut pairs = Vec::with_capacity(proofs.len() + 3);
+    for ((proof, public_inputs), r) in proofs.iter().zip(challenges) {
+        if (public_inputs.len() + 1) != vk.g1.k.len() {
+            return Err(Groth16Error::PrepareInputsFailed);
+        }
+        input_scalars[0] += *r;
+        for (scalar, input) in input_scalars.iter_mut().skip(1).zip(public_inputs) {
+            *scalar += *r * *input;
+        }
+        pairs.push((-(Into::<G1>::into(proof.ar) * *r), proof.bs.into()));
+    }
+
+    let krs: Vec<AffineG1> = proofs.iter().map(|(proof, _)| proof.krs).collect();
+    let prepared_inputs = AffineG1::msm(&vk.g1.k, &input_scalars);
+    let folded_krs = AffineG1::msm(&krs, challenges);
+    pairs.push((prepared_inputs.into(), vk.g2.gamma.into()));
+    pairs.push((folded_krs.into(), vk.g2.delta.into()));
+    pairs.push((Into::<G1>::into(vk.g1.alpha) * input_scalars[0], -Into::<G2>::into(vk.g2.beta)));
+
+    if pairing_batch(&pairs) == Gt::one() {
+        Ok(())
+    } else {
+        Err(Groth16Error::ProofVerificationFailed)
+    }
+}
@@ -1,5 +1,8 @@
 //! This crate provides verifiers for SP1 Groth16 and Plonk BN254 proofs in a no-std environment.
 //! It is patched for efficient verification within the SP1 ZKVM context.
+//!
+//! With the `compressed` feature, it also verifies compressed SP1 proofs, the BabyBear STARK proofs
+//! of the recursion machine. With the `wasm` feature, the verifiers are exported to JavaScript.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 extern crate alloc;
@@ -16,6 +19,13 @@ lazy_static! {
     pub static ref GROTH16_VK_BYTES: &'static [u8] = include_bytes!("../bn254-vk/groth16_vk.bin");
 }
 
+#[cfg(feature = "compressed")]
+lazy_static! {
+    /// The serialized constraints of the compress machine for this SP1 version.
+    pub static ref COMPRESS_CONSTRAINTS_BYTES: &'static [u8] =
+        include_bytes!("../compressed/compress_constraints.bin");
+}
+
 mod constants;
 mod converter;
 mod error;
@@ -24,12 +34,20 @@ mod utils;
 pub use utils::*;
 
 pub use groth16::error::Groth16Error;
-pub use groth16::Groth16Verifier;
+pub use groth16::{Groth16Verifier, PreparedGroth16Vk};
 mod groth16;
 
 pub use plonk::error::PlonkError;
-pub use plonk::PlonkVerifier;
+pub use plonk::{PlonkVerifier, PreparedPlonkVk};
 mod plonk;
 
-#[cfg(test)]
+#[cfg(feature = "compressed")]
+pub use compressed::{error::CompressedError, CompressedVerifier};
+#[cfg(feature = "compressed")]
+pub mod compressed;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests;
@@ -7,6 +7,7 @@ pub(crate) const U: &str = "u";
 mod converter;
 mod hash_to_field;
 mod kzg;
+mod prepared;
 mod proof;
 mod transcript;
 mod verify;
@@ -14,13 +15,13 @@ mod verify;
 pub(crate) mod error;
 
 pub(crate) use converter::{load_plonk_proof_from_bytes, load_plonk_verifying_key_from_bytes};
+pub use prepared::PreparedPlonkVk;
 pub(crate) use proof::PlonkProof;
 pub(crate) use verify::verify_plonk_raw;
 
 use error::PlonkError;
-use sha2::{Digest, Sha256};
 
-use crate::{bn254_public_values, decode_sp1_vkey_hash, error::Error};
+use crate::{bn254_public_values, decode_sp1_vkey_hash};
 /// A verifier for Plonk zero-knowledge proofs.
 #[derive(Debug)]
 pub struct PlonkVerifier;
@@ -51,26 +52,37 @@ impl PlonkVerifier {
         sp1_vkey_hash: &str,
         plonk_vk: &[u8],
     ) -> Result<(), PlonkError> {
-        // Hash the vk and get the first 4 bytes.
-        let plonk_vk_hash: [u8; 4] = Sha256::digest(plonk_vk)[..4]
-            .try_into()
-            .map_err(|_| PlonkError::GeneralError(Error::InvalidData))?;
+        Self::verify_prepared(
+            proof,
+            sp1_public_inputs,
+            sp1_vkey_hash,
+            &PreparedPlonkVk::new(plonk_vk)?,
+        )
+    }
 
+    /// Verifies a Plonk proof against a [`PreparedPlonkVk`].
+    ///
+    /// This is the same as [`PlonkVerifier::verify`], without parsing the verifying key again.
+    pub fn verify_prepared(
+        proof: &[u8],
+        sp1_public_inputs: &[u8],
+        sp1_vkey_hash: &str,
+        plonk_vk: &PreparedPlonkVk,
+    ) -> Result<(), PlonkError> {
         // Check to make sure that this proof was generated by the plonk proving key corresponding to
         // the given plonk vk.
         //
         // SP1 prepends the raw Plonk proof with the first 4 bytes of the plonk vkey to
         // facilitate this check.
-        if plonk_vk_hash != proof[..4] {
+        if proof.len() < 4 || plonk_vk.hash != proof[..4] {
             return Err(PlonkError::PlonkVkeyHashMismatch);
         }
 
         let sp1_vkey_hash = decode_sp1_vkey_hash(sp1_vkey_hash)?;
         let public_inputs = bn254_public_values(&sp1_vkey_hash, sp1_public_inputs);
 
-        let plonk_vk = load_plonk_verifying_key_from_bytes(plonk_vk)?;
-        let proof = load_plonk_proof_from_bytes(&proof[4..], plonk_vk.qcp.len())?;
+        let proof = load_plonk_proof_from_bytes(&proof[4..], plonk_vk.vk.qcp.len())?;
 
-        verify_plonk_raw(&plonk_vk, &proof, &public_inputs)
+        verify_plonk_raw(&plonk_vk.vk, &proof, &public_inputs)
     }
 }
@@ -0,0 +1,32 @@
+use sha2::{Digest, Sha256};
+
+use super::{error::PlonkError, load_plonk_verifying_key_from_bytes, verify::PlonkVerifyingKey};
+
+/// A Plonk verifying key that is parsed once and reused to verify many proofs.
+///
+/// It caches the curve points and domain of the key together with the first 4 bytes of the
+/// SHA-256 digest of the key bytes, which prefix every proof. The gnark key also serializes the
+/// precomputed lines of its two G2 points, but the pairing of `bn` computes them itself, so they
+/// are not kept.
+#[derive(Clone, Debug)]
+pub struct PreparedPlonkVk {
+    pub(crate) vk: PlonkVerifyingKey,
+    pub(crate) hash: [u8; 4],
+}
+
+impl PreparedPlonkVk {
+    /// Parses and prepares a Plonk verifying key.
+    ///
+    /// Usually this will be the [`static@crate::PLONK_VK_BYTES`] constant.
+    pub fn new(plonk_vk: &[u8]) -> Result<Self, PlonkError> {
+        let hash = Sha256::digest(plonk_vk)[..4].try_into().unwrap();
+        let vk = load_plonk_verifying_key_from_bytes(plonk_vk)?;
+        Ok(Self { vk, hash })
+    }
+
+    /// The first 4 bytes of the SHA-256 digest of the verifying key, which SP1 prepends to the
+    /// proofs generated with it.
+    pub fn hash(&self) -> [u8; 4] {
+        self.hash
+    }
+}
@@ -10,7 +10,7 @@ use crate::{
 use super::{
     converter::g1_to_bytes, error::PlonkError, kzg, PlonkProof, ALPHA, BETA, GAMMA, U, ZETA,
 };
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) struct PlonkVerifyingKey {
     pub(crate) size: usize,
     pub(crate) size_inv: Fr,
@@ -20,6 +20,84 @@ fn test_verify_groth16() {
         .expect("Groth16 proof is invalid");
 }
 
+#[test]
+fn test_verify_groth16_batch() {
+    let sp1_proof_with_public_values =
+        SP1ProofWithPublicValues::load("test_binaries/fibonacci-groth16.bin").unwrap();
+    let proof = sp1_proof_with_public_values.bytes();
+    let public_inputs = sp1_proof_with_public_values.public_values.to_vec();
+    let vkey_hash = "0x00e60860c07bfc6e4c480286c0ddbb879674eb47f84b4ef041cf858b17aa0ed1";
+
+    let batch = vec![(proof.as_slice(), public_inputs.as_slice(), vkey_hash); 4];
+    crate::Groth16Verifier::verify_batch(&batch, &crate::GROTH16_VK_BYTES)
+        .expect("Groth16 proofs are invalid");
+
+    // A single proof with the wrong public inputs invalidates the whole batch.
+    let mut tampered_inputs = public_inputs.clone();
+    tampered_inputs[0] ^= 1;
+    let mut tampered_batch = batch.clone();
+    tampered_batch[2] = (proof.as_slice(), tampered_inputs.as_slice(), vkey_hash);
+    assert!(matches!(
+        crate::Groth16Verifier::verify_batch(&tampered_batch, &crate::GROTH16_VK_BYTES),
+        Err(crate::Groth16Error::ProofVerificationFailed)
+    ));
+
+    // So does a single proof whose `A` and `C` points are swapped.
+    let mut tampered_proof = proof.clone();
+    tampered_proof[4..68].copy_from_slice(&proof[196..260]);
+    tampered_proof[196..260].copy_from_slice(&proof[4..68]);
+    let mut tampered_batch = batch.clone();
+    tampered_batch[3] = (tampered_proof.as_slice(), public_inputs.as_slice(), vkey_hash);
+    assert!(
+        crate::Groth16Verifier::verify_batch(&tampered_batch, &crate::GROTH16_VK_BYTES).is_err()
+    );
+}
+
+#[test]
+fn test_verify_gnark_proof() {
+    let sp1_proof_with_public_values =
+        SP1ProofWithPublicValues::load("test_binaries/fibonacci-groth16.bin").unwrap();
+    let proof = sp1_proof_with_public_values.bytes();
+    let vkey_hash = "0x00e60860c07bfc6e4c480286c0ddbb879674eb47f84b4ef041cf858b17aa0ed1";
+
+    // The raw public inputs of an SP1 proof are its vkey hash and committed values digest.
+    let sp1_vkey_hash = crate::decode_sp1_vkey_hash(vkey_hash).unwrap();
+    let public_inputs = crate::bn254_public_values(
+        &sp1_vkey_hash,
+        &sp1_proof_with_public_values.public_values.to_vec(),
+    )
+    .map(|input| {
+        let mut bytes = [0u8; 32];
+        input.to_big_endian(&mut bytes).unwrap();
+        bytes
+    });
+
+    crate::Groth16Verifier::verify_gnark_proof(
+        &proof[4..],
+        &public_inputs,
+        &crate::GROTH16_VK_BYTES,
+    )
+    .expect("Groth16 proof is invalid");
+
+    // The proof is rejected for other public inputs, or with the vkey hash prefix.
+    let mut tampered_inputs = public_inputs;
+    tampered_inputs[1][31] ^= 1;
+    assert!(matches!(
+        crate::Groth16Verifier::verify_gnark_proof(
+            &proof[4..],
+            &tampered_inputs,
+            &crate::GROTH16_VK_BYTES
+        ),
+        Err(crate::Groth16Error::ProofVerificationFailed)
+    ));
+    assert!(crate::Groth16Verifier::verify_gnark_proof(
+        &proof,
+        &public_inputs,
+        &crate::GROTH16_VK_BYTES
+    )
+    .is_err());
+}
+
 #[test]
 fn test_verify_plonk() {
     // Location of the serialized SP1ProofWithPublicValues. See README.md for more information.
@@ -38,14 +116,54 @@ fn test_verify_plonk() {
         .expect("Plonk proof is invalid");
 }
 
+#[test]
+fn test_verify_prepared() {
+    let vkey_hash = "0x00e60860c07bfc6e4c480286c0ddbb879674eb47f84b4ef041cf858b17aa0ed1";
+
+    let groth16 = SP1ProofWithPublicValues::load("test_binaries/fibonacci-groth16.bin").unwrap();
+    let groth16_vk = crate::PreparedGroth16Vk::new(&crate::GROTH16_VK_BYTES).unwrap();
+    let plonk = SP1ProofWithPublicValues::load("test_binaries/fibonacci-plonk.bin").unwrap();
+    let plonk_vk = crate::PreparedPlonkVk::new(&crate::PLONK_VK_BYTES).unwrap();
+
+    // The prepared keys are reused across verifications.
+    for _ in 0..2 {
+        crate::Groth16Verifier::verify_prepared(
+            &groth16.bytes(),
+            &groth16.public_values.to_vec(),
+            vkey_hash,
+            &groth16_vk,
+        )
+        .expect("Groth16 proof is invalid");
+        crate::PlonkVerifier::verify_prepared(
+            &plonk.bytes(),
+            &plonk.public_values.to_vec(),
+            vkey_hash,
+            &plonk_vk,
+        )
+        .expect("Plonk proof is invalid");
+    }
+
+    // Each prepared key only accepts the proofs of its own system.
+    assert!(matches!(
+        crate::Groth16Verifier::verify_prepared(
+            &plonk.bytes(),
+            &plonk.public_values.to_vec(),
+            vkey_hash,
+            &groth16_vk,
+        ),
+        Err(crate::Groth16Error::Groth16VkeyHashMismatch)
+    ));
+    assert_eq!(plonk_vk.hash(), plonk.bytes()[..4]);
+}
+
 #[test]
 fn test_vkeys() {
-    let groth16_path = try_install_circuit_artifacts("groth16");
+    let groth16_path = try_install_circuit_artifacts("groth16").unwrap();
     let s3_vkey_path = groth16_path.join("groth16_vk.bin");
     let s3_vkey_bytes = std::fs::read(s3_vkey_path).unwrap();
     assert_eq!(s3_vkey_bytes, *crate::GROTH16_VK_BYTES);
 
-    let plonk_path = try_install_circuit_artifacts("plonk");
+    let plonk_path = try_install_circuit_artifacts("plonk").unwrap();
     let s3_vkey_path = plonk_path.join("plonk_vk.bin");
     let s3_vkey_bytes = std::fs::read(s3_vkey_path).unwrap();
     assert_eq!(s3_vkey_bytes, *crate::PLONK_VK_BYTES);
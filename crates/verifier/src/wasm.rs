@@ -0,0 +1,50 @@
+//! JavaScript bindings for the verifiers, built with the `wasm` feature.
+//!
+//! Every function takes the proof bytes returned by `SP1ProofWithPublicValues::bytes`, the SP1
+//! public values and the SP1 vkey hash as a hex string, and throws an `Error` carrying the reason
+//! verification failed.
+
+use alloc::string::ToString;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Groth16Verifier, PlonkVerifier, GROTH16_VK_BYTES, PLONK_VK_BYTES};
+
+/// Verifies a Groth16 proof against the Groth16 verifying key of this SP1 version.
+#[wasm_bindgen(js_name = verifyGroth16)]
+pub fn verify_groth16(
+    proof: &[u8],
+    public_values: &[u8],
+    sp1_vkey_hash: &str,
+) -> Result<(), JsError> {
+    Groth16Verifier::verify(proof, public_values, sp1_vkey_hash, &GROTH16_VK_BYTES)
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Verifies a Plonk proof against the Plonk verifying key of this SP1 version.
+#[wasm_bindgen(js_name = verifyPlonk)]
+pub fn verify_plonk(
+    proof: &[u8],
+    public_values: &[u8],
+    sp1_vkey_hash: &str,
+) -> Result<(), JsError> {
+    PlonkVerifier::verify(proof, public_values, sp1_vkey_hash, &PLONK_VK_BYTES)
+        .map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Verifies a compressed proof.
+#[cfg(feature = "compressed")]
+#[wasm_bindgen(js_name = verifyCompressed)]
+pub fn verify_compressed(
+    proof: &[u8],
+    public_values: &[u8],
+    sp1_vkey_hash: &str,
+) -> Result<(), JsError> {
+    crate::CompressedVerifier::verify(
+        proof,
+        public_values,
+        sp1_vkey_hash,
+        &crate::COMPRESS_CONSTRAINTS_BYTES,
+    )
+    .map_err(|e| JsError::new(&e.to_string()))
+}
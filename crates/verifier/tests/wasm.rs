@@ -0,0 +1,38 @@
+//! Tests of the JavaScript bindings, run under Node with `wasm-bindgen-test-runner`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use sp1_verifier::wasm::{verify_groth16, verify_plonk};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+// The proofs and public values of the fibonacci proofs in `test_binaries`, extracted with
+// `SP1ProofWithPublicValues::bytes` since the SDK is not available on wasm.
+const GROTH16_PROOF: &[u8] = include_bytes!("../test_binaries/fibonacci-groth16-proof.bin");
+const GROTH16_PUBLIC_VALUES: &[u8] =
+    include_bytes!("../test_binaries/fibonacci-groth16-public-values.bin");
+const PLONK_PROOF: &[u8] = include_bytes!("../test_binaries/fibonacci-plonk-proof.bin");
+const PLONK_PUBLIC_VALUES: &[u8] =
+    include_bytes!("../test_binaries/fibonacci-plonk-public-values.bin");
+
+const VKEY_HASH: &str = "0x00e60860c07bfc6e4c480286c0ddbb879674eb47f84b4ef041cf858b17aa0ed1";
+
+#[wasm_bindgen_test]
+fn test_verify_groth16() {
+    verify_groth16(GROTH16_PROOF, GROTH16_PUBLIC_VALUES, VKEY_HASH)
+        .expect("Groth16 proof is invalid");
+}
+
+#[wasm_bindgen_test]
+fn test_verify_plonk() {
+    verify_plonk(PLONK_PROOF, PLONK_PUBLIC_VALUES, VKEY_HASH).expect("Plonk proof is invalid");
+}
+
+#[wasm_bindgen_test]
+fn test_verify_tampered_public_values() {
+    let mut public_values = GROTH16_PUBLIC_VALUES.to_vec();
+    public_values[0] ^= 1;
+    assert!(verify_groth16(GROTH16_PROOF, &public_values, VKEY_HASH).is_err());
+
+    let mut public_values = PLONK_PUBLIC_VALUES.to_vec();
+    public_values[0] ^= 1;
+    assert!(verify_plonk(PLONK_PROOF, &public_values, VKEY_HASH).is_err());
+}
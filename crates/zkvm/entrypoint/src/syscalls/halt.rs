@@ -9,7 +9,7 @@ cfg_if::cfg_if! {
 
 cfg_if::cfg_if! {
     if #[cfg(all(target_os = "zkvm", feature = "verify"))] {
-        use p3_field::PrimeField32;
+        use p3_field::{AbstractField, PrimeField32};
     }
 }
 
@@ -20,36 +20,7 @@ cfg_if::cfg_if! {
 pub extern "C" fn syscall_halt(exit_code: u8) -> ! {
     #[cfg(target_os = "zkvm")]
     unsafe {
-        // When we halt, we retrieve the public values finalized digest.  This is the hash of all
-        // the bytes written to the public values fd.
-        let pv_digest_bytes =
-            core::mem::take(&mut *core::ptr::addr_of_mut!(zkvm::PUBLIC_VALUES_HASHER))
-                .unwrap()
-                .finalize();
-
-        // For each digest word, call COMMIT ecall.  In the runtime, this will store the digest
-        // words into the runtime's execution record's public values digest.  In the AIR, it
-        // will be used to verify that the provided public values digest matches the one
-        // computed by the program.
-        for i in 0..PV_DIGEST_NUM_WORDS {
-            let word = u32::from_le_bytes(pv_digest_bytes[i * 4..(i + 1) * 4].try_into().unwrap());
-            asm!("ecall", in("t0") crate::syscalls::COMMIT, in("a0") i, in("a1") word);
-        }
-
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "verify")] {
-                let deferred_proofs_digest = zkvm::DEFERRED_PROOFS_DIGEST.as_mut().unwrap();
-
-                for i in 0..POSEIDON_NUM_WORDS {
-                    let word = deferred_proofs_digest[i].as_canonical_u32();
-                    asm!("ecall", in("t0") crate::syscalls::COMMIT_DEFERRED_PROOFS, in("a0") i, in("a1") word);
-                }
-            } else {
-                for i in 0..POSEIDON_NUM_WORDS {
-                    asm!("ecall", in("t0") crate::syscalls::COMMIT_DEFERRED_PROOFS, in("a0") i, in("a1") 0);
-                }
-            }
-        }
+        commit_digests();
 
         asm!(
             "ecall",
@@ -62,3 +33,68 @@ pub extern "C" fn syscall_halt(exit_code: u8) -> ! {
     #[cfg(not(target_os = "zkvm"))]
     unreachable!()
 }
+
+/// Suspends the program so that it can be resumed in a new segment.
+///
+/// Like [syscall_halt], the public values and deferred proofs digests are committed before halting
+/// with exit code zero. The address of the next instruction is passed in `a1`, so that a program
+/// started there with the final memory of this segment returns from this call. The digests are
+/// then reset for the new segment.
+pub extern "C" fn syscall_suspend() {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        commit_digests();
+
+        asm!(
+            "la a1, 2f",
+            "ecall",
+            "2:",
+            in("t0") crate::syscalls::HALT,
+            in("a0") 0,
+            out("a1") _,
+            clobber_abi("C"),
+        );
+
+        *core::ptr::addr_of_mut!(zkvm::PUBLIC_VALUES_HASHER) = Some(sha2::Sha256::new());
+        #[cfg(feature = "verify")]
+        {
+            *core::ptr::addr_of_mut!(zkvm::DEFERRED_PROOFS_DIGEST) =
+                Some([p3_baby_bear::BabyBear::zero(); POSEIDON_NUM_WORDS]);
+        }
+    }
+}
+
+/// Commits the public values digest and the deferred proofs digest of the program.
+#[cfg(target_os = "zkvm")]
+unsafe fn commit_digests() {
+    // When we halt, we retrieve the public values finalized digest.  This is the hash of all
+    // the bytes written to the public values fd.
+    let pv_digest_bytes =
+        core::mem::take(&mut *core::ptr::addr_of_mut!(zkvm::PUBLIC_VALUES_HASHER))
+            .unwrap()
+            .finalize();
+
+    // For each digest word, call COMMIT ecall.  In the runtime, this will store the digest
+    // words into the runtime's execution record's public values digest.  In the AIR, it
+    // will be used to verify that the provided public values digest matches the one
+    // computed by the program.
+    for i in 0..PV_DIGEST_NUM_WORDS {
+        let word = u32::from_le_bytes(pv_digest_bytes[i * 4..(i + 1) * 4].try_into().unwrap());
+        asm!("ecall", in("t0") crate::syscalls::COMMIT, in("a0") i, in("a1") word);
+    }
+
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "verify")] {
+            let deferred_proofs_digest = zkvm::DEFERRED_PROOFS_DIGEST.as_mut().unwrap();
+
+            for i in 0..POSEIDON_NUM_WORDS {
+                let word = deferred_proofs_digest[i].as_canonical_u32();
+                asm!("ecall", in("t0") crate::syscalls::COMMIT_DEFERRED_PROOFS, in("a0") i, in("a1") word);
+            }
+        } else {
+            for i in 0..POSEIDON_NUM_WORDS {
+                asm!("ecall", in("t0") crate::syscalls::COMMIT_DEFERRED_PROOFS, in("a0") i, in("a1") 0);
+            }
+        }
+    }
+}
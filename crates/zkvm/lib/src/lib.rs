@@ -18,6 +18,9 @@ extern "C" {
     /// Halts the program with the given exit code.
     pub fn syscall_halt(exit_code: u8) -> !;
 
+    /// Suspends the program so that it can be resumed in a new segment.
+    pub fn syscall_suspend();
+
     /// Writes the bytes in the given buffer to the given file descriptor.
     pub fn syscall_write(fd: u32, write_buf: *const u8, nbytes: usize);
 
@@ -1,5 +1,13 @@
+use sha2::{Digest, Sha256};
+
 use crate::syscall_verify_sp1_proof;
 
+/// The verification key digest of the Groth16 deferral program in `crates/sdk/groth16-deferral`.
+///
+/// It changes with the committed ELF of the deferral program, built with `make elfs` in
+/// `crates/sdk`, and `sp1-sdk` tests that it is up to date.
+pub const GROTH16_DEFERRAL_VK_DIGEST: [u32; 8] = [0, 0, 0, 0, 0, 0, 0, 0];
+
 /// Verifies the next proof in the proof input stream given a verification key digest and public
 /// values digest. If the proof is invalid, the function will panic.
 ///
@@ -9,3 +17,23 @@ pub fn verify_sp1_proof(vk_digest: &[u32; 8], pv_digest: &[u8; 32]) {
         syscall_verify_sp1_proof(vk_digest, pv_digest);
     }
 }
+
+/// Verifies the next proof in the proof input stream as a deferred Groth16 proof, given the
+/// SHA-256 digest of the Groth16 verifying key and the public inputs of the proof, each the
+/// big-endian encoding of a BN254 scalar. If the proof is invalid, the function will panic.
+///
+/// The Groth16 proof itself is not verified in the guest. Instead, the next proof in the stream
+/// must be a compressed proof of the Groth16 deferral program, which verifies it and commits to
+/// the digest of the verifying key followed by the public inputs. The proof is checked against
+/// [GROTH16_DEFERRAL_VK_DIGEST], so it cannot come from any other program.
+///
+/// Enable this function by adding the `verify` feature to both the `sp1-lib` AND `sp1-zkvm` crates.
+pub fn verify_groth16_proof(groth16_vk_digest: &[u8; 32], public_inputs: &[[u8; 32]]) {
+    let mut hasher = Sha256::new();
+    hasher.update(groth16_vk_digest);
+    for input in public_inputs {
+        hasher.update(input);
+    }
+    let pv_digest: [u8; 32] = hasher.finalize().into();
+    verify_sp1_proof(&GROTH16_DEFERRAL_VK_DIGEST, &pv_digest);
+}